@@ -0,0 +1,413 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Machine architectures of Apple platforms.
+
+use {
+    crate::{Error, Platform},
+    std::{
+        fmt::{Display, Formatter},
+        str::FromStr,
+    },
+};
+
+/// A machine architecture targeted by Apple platforms.
+///
+/// Apple tools and LLVM name architectures identically. e.g. `arm64` or
+/// `x86_64h`. Rust names differ for some architectures. e.g. Rust's `aarch64`
+/// is Apple's `arm64`. This type converts between the naming schemes.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Architecture {
+    /// `arm64`. Rust's `aarch64`.
+    Arm64,
+    /// `arm64e`. `arm64` with pointer authentication.
+    Arm64e,
+    /// `arm64_32`. `arm64` with 32-bit pointers, used by Apple Watch.
+    Arm64_32,
+    /// `armv7`.
+    Armv7,
+    /// `armv7s`.
+    Armv7s,
+    /// `armv7k`. Used by older Apple Watch devices.
+    Armv7k,
+    /// `x86_64`.
+    X86_64,
+    /// `x86_64h`. `x86_64` for Haswell and newer CPUs.
+    X86_64h,
+    /// `i386`. Rust's `i686` on macOS.
+    I386,
+}
+
+impl Display for Architecture {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.apple_name())
+    }
+}
+
+impl FromStr for Architecture {
+    type Err = Error;
+
+    /// Parse an Apple, LLVM, or Rust architecture name.
+    ///
+    /// e.g. `arm64` and `aarch64` both resolve to [Self::Arm64].
+    ///
+    /// Will return [Error::UnknownArchitecture] for unknown names.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "arm64" | "aarch64" => Self::Arm64,
+            "arm64e" => Self::Arm64e,
+            "arm64_32" => Self::Arm64_32,
+            "armv7" => Self::Armv7,
+            "armv7s" => Self::Armv7s,
+            "armv7k" => Self::Armv7k,
+            "x86_64" => Self::X86_64,
+            "x86_64h" => Self::X86_64h,
+            "i386" | "i686" => Self::I386,
+            _ => return Err(Error::UnknownArchitecture(s.to_string())),
+        })
+    }
+}
+
+impl TryFrom<&str> for Architecture {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Self::from_str(s)
+    }
+}
+
+impl Architecture {
+    /// All known architectures.
+    pub const ALL: &'static [Self] = &[
+        Self::Arm64,
+        Self::Arm64e,
+        Self::Arm64_32,
+        Self::Armv7,
+        Self::Armv7s,
+        Self::Armv7k,
+        Self::X86_64,
+        Self::X86_64h,
+        Self::I386,
+    ];
+
+    /// Obtain an iterator over all known architectures.
+    ///
+    /// This yields the members of [Self::ALL].
+    pub fn all() -> impl Iterator<Item = Self> {
+        Self::ALL.iter().cloned()
+    }
+
+    /// Attempt to construct an instance from a Rust target triple.
+    ///
+    /// e.g. `aarch64-apple-ios-sim` resolves to [Self::Arm64].
+    ///
+    /// Will return [Error::UnknownArchitecture] if the architecture component
+    /// isn't known.
+    pub fn from_rust_target_triple(target: &str) -> Result<Self, Error> {
+        let arch = target
+            .split_once('-')
+            .map(|(arch, _)| arch)
+            .unwrap_or(target);
+
+        Self::from_str(arch).map_err(|_| Error::UnknownArchitecture(target.to_string()))
+    }
+
+    /// The Apple name of this architecture. e.g. `arm64`.
+    ///
+    /// This is the name used by `-arch` compiler flags, `lipo`, and
+    /// `SDKSettings.json` files.
+    pub fn apple_name(&self) -> &'static str {
+        match self {
+            Self::Arm64 => "arm64",
+            Self::Arm64e => "arm64e",
+            Self::Arm64_32 => "arm64_32",
+            Self::Armv7 => "armv7",
+            Self::Armv7s => "armv7s",
+            Self::Armv7k => "armv7k",
+            Self::X86_64 => "x86_64",
+            Self::X86_64h => "x86_64h",
+            Self::I386 => "i386",
+        }
+    }
+
+    /// The name of this architecture in LLVM target triples. e.g. `arm64`.
+    ///
+    /// LLVM accepts the Apple names, so this is the same as [Self::apple_name()].
+    /// It can be fed to [Platform::llvm_target_triple()].
+    pub fn llvm_name(&self) -> &'static str {
+        self.apple_name()
+    }
+
+    /// The architecture component of Rust target triples for a platform.
+    ///
+    /// e.g. `aarch64` for [Self::Arm64] or `i686` for [Self::I386] on macOS.
+    ///
+    /// Returns [None] if Rust has no target for this architecture on the platform.
+    pub fn rust_triple_arch(&self, platform: &Platform) -> Option<&'static str> {
+        self.rust_target_triple(platform)
+            .and_then(|triple| triple.split_once('-').map(|(arch, _)| arch))
+    }
+
+    /// The value of `cfg(target_arch)` in Rust code built for this architecture.
+    ///
+    /// e.g. `aarch64` for [Self::Arm64], [Self::Arm64e], and [Self::Arm64_32].
+    /// This is also the value of `CARGO_CFG_TARGET_ARCH` in build scripts.
+    pub fn rust_target_arch(&self) -> &'static str {
+        match self {
+            Self::Arm64 | Self::Arm64e | Self::Arm64_32 => "aarch64",
+            Self::Armv7 | Self::Armv7s | Self::Armv7k => "arm",
+            Self::X86_64 | Self::X86_64h => "x86_64",
+            Self::I386 => "x86",
+        }
+    }
+
+    /// Whether this architecture has 64-bit pointers.
+    pub fn is_64bit(&self) -> bool {
+        matches!(
+            self,
+            Self::Arm64 | Self::Arm64e | Self::X86_64 | Self::X86_64h
+        )
+    }
+
+    /// The Rust target triple for this architecture on a platform.
+    ///
+    /// e.g. `aarch64-apple-ios-sim` for [Self::Arm64] on
+    /// [Platform::IPhoneSimulator]. Mac Catalyst (`*-apple-ios-macabi`) triples are
+    /// never returned.
+    ///
+    /// Returns [None] if Rust has no target for this combination.
+    pub fn rust_target_triple(&self, platform: &Platform) -> Option<&'static str> {
+        Some(match (platform, self) {
+            (Platform::MacOsX, Self::Arm64) => "aarch64-apple-darwin",
+            (Platform::MacOsX, Self::Arm64e) => "arm64e-apple-darwin",
+            (Platform::MacOsX, Self::X86_64) => "x86_64-apple-darwin",
+            (Platform::MacOsX, Self::X86_64h) => "x86_64h-apple-darwin",
+            (Platform::MacOsX, Self::I386) => "i686-apple-darwin",
+            (Platform::IPhoneOs, Self::Arm64) => "aarch64-apple-ios",
+            (Platform::IPhoneOs, Self::Arm64e) => "arm64e-apple-ios",
+            (Platform::IPhoneOs, Self::Armv7s) => "armv7s-apple-ios",
+            (Platform::IPhoneSimulator, Self::Arm64) => "aarch64-apple-ios-sim",
+            (Platform::IPhoneSimulator, Self::X86_64) => "x86_64-apple-ios",
+            (Platform::IPhoneSimulator, Self::I386) => "i386-apple-ios",
+            (Platform::AppleTvOs, Self::Arm64) => "aarch64-apple-tvos",
+            (Platform::AppleTvOs, Self::Arm64e) => "arm64e-apple-tvos",
+            (Platform::AppleTvSimulator, Self::Arm64) => "aarch64-apple-tvos-sim",
+            (Platform::AppleTvSimulator, Self::X86_64) => "x86_64-apple-tvos",
+            (Platform::WatchOs, Self::Arm64) => "aarch64-apple-watchos",
+            (Platform::WatchOs, Self::Arm64_32) => "arm64_32-apple-watchos",
+            (Platform::WatchOs, Self::Armv7k) => "armv7k-apple-watchos",
+            (Platform::WatchSimulator, Self::Arm64) => "aarch64-apple-watchos-sim",
+            (Platform::WatchSimulator, Self::X86_64) => "x86_64-apple-watchos-sim",
+            (Platform::XrOs, Self::Arm64) => "aarch64-apple-visionos",
+            (Platform::XrOsSimulator, Self::Arm64) => "aarch64-apple-visionos-sim",
+            _ => return None,
+        })
+    }
+
+    /// The platforms that run binaries of this architecture.
+    ///
+    /// This includes architectures that current SDKs no longer support. e.g.
+    /// [Self::I386] on macOS. Use [crate::ParsedSdk::supported_architectures()]
+    /// to find the architectures of a specific SDK.
+    pub fn platforms(&self) -> &'static [Platform] {
+        match self {
+            Self::Arm64 => &[
+                Platform::AppleTvOs,
+                Platform::AppleTvSimulator,
+                Platform::DriverKit,
+                Platform::IPhoneOs,
+                Platform::IPhoneSimulator,
+                Platform::MacOsX,
+                Platform::WatchOs,
+                Platform::WatchSimulator,
+                Platform::XrOs,
+                Platform::XrOsSimulator,
+            ],
+            Self::Arm64e => &[
+                Platform::AppleTvOs,
+                Platform::DriverKit,
+                Platform::IPhoneOs,
+                Platform::MacOsX,
+            ],
+            Self::Arm64_32 => &[Platform::WatchOs],
+            Self::Armv7 | Self::Armv7s => &[Platform::IPhoneOs],
+            Self::Armv7k => &[Platform::WatchOs],
+            Self::X86_64 => &[
+                Platform::AppleTvSimulator,
+                Platform::DriverKit,
+                Platform::IPhoneSimulator,
+                Platform::MacOsX,
+                Platform::WatchSimulator,
+            ],
+            Self::X86_64h => &[Platform::MacOsX],
+            Self::I386 => &[
+                Platform::IPhoneSimulator,
+                Platform::MacOsX,
+                Platform::WatchSimulator,
+            ],
+        }
+    }
+
+    /// Whether this architecture is used by a platform.
+    pub fn is_used_by(&self, platform: &Platform) -> bool {
+        self.platforms().contains(platform)
+    }
+
+    /// The native architecture of the running Mac.
+    ///
+    /// This is [Self::Arm64] on Apple silicon, even if the current process is an
+    /// `x86_64` binary running under Rosetta. See [is_translated_process()]. Use it
+    /// to pick a default target architecture or the architecture of simulator
+    /// binaries.
+    ///
+    /// Returns [None] when not running on macOS.
+    pub fn host() -> Option<Self> {
+        if !cfg!(target_os = "macos") {
+            return None;
+        }
+
+        if sysctl_int("hw.optional.arm64") == Some(1) {
+            Some(Self::Arm64)
+        } else {
+            Some(Self::X86_64)
+        }
+    }
+}
+
+/// Whether the current process runs under Rosetta translation.
+///
+/// This queries the `sysctl.proc_translated` sysctl. Translated processes are
+/// `x86_64` binaries running on Apple silicon. Tools they spawn may run
+/// translated too. So the architecture Rust was compiled for doesn't reflect the
+/// host. Use [Architecture::host()] to obtain the native architecture.
+///
+/// Returns `false` when not running on macOS or on Intel Macs, where the sysctl
+/// doesn't exist.
+pub fn is_translated_process() -> bool {
+    sysctl_int("sysctl.proc_translated") == Some(1)
+}
+
+/// Read an integer sysctl by name.
+#[cfg(target_os = "macos")]
+fn sysctl_int(name: &str) -> Option<i32> {
+    use std::ffi::{c_char, c_int, c_void, CString};
+
+    extern "C" {
+        fn sysctlbyname(
+            name: *const c_char,
+            oldp: *mut c_void,
+            oldlenp: *mut usize,
+            newp: *mut c_void,
+            newlen: usize,
+        ) -> c_int;
+    }
+
+    let name = CString::new(name).ok()?;
+    let mut value: c_int = 0;
+    let mut size = std::mem::size_of::<c_int>();
+
+    // SAFETY: the output buffer is a valid c_int and `size` holds its length.
+    let res = unsafe {
+        sysctlbyname(
+            name.as_ptr(),
+            &mut value as *mut c_int as *mut c_void,
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+
+    if res == 0 && size == std::mem::size_of::<c_int>() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn sysctl_int(_name: &str) -> Option<i32> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn names() -> Result<(), Error> {
+        for arch in Architecture::all() {
+            assert_eq!(Architecture::from_str(arch.apple_name())?, arch);
+            assert_eq!(Architecture::from_str(arch.llvm_name())?, arch);
+            assert_eq!(arch.to_string(), arch.apple_name());
+        }
+
+        assert_eq!(Architecture::from_str("aarch64")?, Architecture::Arm64);
+        assert_eq!(Architecture::from_str("i686")?, Architecture::I386);
+        assert!(matches!(
+            Architecture::from_str("ppc"),
+            Err(Error::UnknownArchitecture(_))
+        ));
+
+        assert_eq!(Architecture::Arm64_32.rust_target_arch(), "aarch64");
+        assert_eq!(Architecture::Armv7k.rust_target_arch(), "arm");
+        assert_eq!(Architecture::X86_64h.rust_target_arch(), "x86_64");
+        assert!(!Architecture::Arm64_32.is_64bit());
+
+        Ok(())
+    }
+
+    #[test]
+    fn rust_target_triples() -> Result<(), Error> {
+        for arch in Architecture::all() {
+            for platform in Platform::all() {
+                let Some(triple) = arch.rust_target_triple(&platform) else {
+                    continue;
+                };
+
+                assert!(arch.is_used_by(&platform), "{triple}");
+                assert_eq!(Architecture::from_rust_target_triple(triple)?, arch);
+                assert_eq!(Platform::from_target_triple(triple)?, platform);
+            }
+        }
+
+        assert_eq!(
+            Architecture::I386.rust_triple_arch(&Platform::MacOsX),
+            Some("i686")
+        );
+        assert_eq!(
+            Architecture::I386.rust_triple_arch(&Platform::IPhoneSimulator),
+            Some("i386")
+        );
+        assert_eq!(
+            Architecture::Arm64.rust_triple_arch(&Platform::XrOs),
+            Some("aarch64")
+        );
+        assert_eq!(
+            Architecture::Armv7k.rust_target_triple(&Platform::IPhoneOs),
+            None
+        );
+        assert!(Architecture::from_rust_target_triple("powerpc-apple-darwin").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn host() {
+        if cfg!(target_os = "macos") {
+            let host = Architecture::host().unwrap();
+            assert!(matches!(host, Architecture::Arm64 | Architecture::X86_64));
+
+            if is_translated_process() {
+                assert_eq!(host, Architecture::Arm64);
+            }
+        } else {
+            assert_eq!(Architecture::host(), None);
+            assert!(!is_translated_process());
+        }
+    }
+}
@@ -0,0 +1,797 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! SDK archives.
+//!
+//! Cross-compilation setups commonly distribute SDKs as tarballs or zip files.
+//! This module can look inside such archives without extracting them and can
+//! extract them on demand.
+
+use {
+    crate::{os_str_to_bytes, os_string_from_bytes, Error},
+    std::{
+        ffi::OsStr,
+        fs::File,
+        io::{BufReader, Read},
+        path::{Component, Path, PathBuf},
+    },
+};
+
+#[cfg(feature = "parse")]
+use crate::SdkSettingsJson;
+
+/// Name of the file written to extracted cache directories to mark them complete.
+const EXTRACTED_MARKER: &str = ".apple-sdk-extracted";
+
+/// The format of an SDK archive.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SdkArchiveFormat {
+    /// An uncompressed tar archive.
+    Tar,
+    /// A gzip compressed tar archive.
+    TarGz,
+    /// An xz compressed tar archive.
+    TarXz,
+    /// A zip archive.
+    Zip,
+}
+
+impl SdkArchiveFormat {
+    /// Resolve the format of an archive from its filename.
+    ///
+    /// Recognizes `.tar`, `.tar.gz`, `.tgz`, `.tar.xz`, `.txz`, and `.zip`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+
+        if name.ends_with(".tar.xz") || name.ends_with(".txz") {
+            Some(Self::TarXz)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// The type of an entry in an archive.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum EntryKind {
+    File,
+    Directory,
+    Symlink(PathBuf),
+    Hardlink(PathBuf),
+    Other,
+}
+
+/// An entry in an archive.
+#[derive(Clone, Debug)]
+struct Entry {
+    /// Normalized relative path of the entry.
+    path: PathBuf,
+    kind: EntryKind,
+    /// Unix permission bits, if known.
+    mode: Option<u32>,
+}
+
+impl Entry {
+    /// The relative path of the SDK directory this entry defines settings for.
+    fn sdk_directory(&self) -> Option<&Path> {
+        if self.kind != EntryKind::File {
+            return None;
+        }
+
+        let filename = self.path.file_name()?;
+        if filename != "SDKSettings.json" && filename != "SDKSettings.plist" {
+            return None;
+        }
+
+        let parent = self.path.parent()?;
+        if parent.extension() == Some(OsStr::new("sdk")) {
+            Some(parent)
+        } else {
+            None
+        }
+    }
+}
+
+/// An archive file containing one or more SDKs.
+///
+/// SDKs are directories named `*.sdk` within the archive holding an
+/// `SDKSettings.json` or `SDKSettings.plist` file. They can be at any depth.
+///
+/// Tar archives are read sequentially. Each inspection operation therefore
+/// reads (and decompresses) the archive until it has what it needs.
+#[derive(Clone, Debug)]
+pub struct SdkArchive {
+    path: PathBuf,
+    format: SdkArchiveFormat,
+}
+
+impl SdkArchive {
+    /// Construct an instance from a path to an archive file.
+    ///
+    /// The format is derived from the filename. See [SdkArchiveFormat::from_path()].
+    ///
+    /// Returns [Error::PathNotSdkArchive] if the path isn't a file with a
+    /// recognized archive extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+
+        match SdkArchiveFormat::from_path(&path) {
+            Some(format) if path.is_file() => Ok(Self { path, format }),
+            _ => Err(Error::PathNotSdkArchive(path)),
+        }
+    }
+
+    /// The filesystem path of this archive.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The format of this archive.
+    pub fn format(&self) -> SdkArchiveFormat {
+        self.format
+    }
+
+    /// Find the SDK directories in this archive.
+    ///
+    /// Returns relative paths within the archive. e.g. `MacOSX14.0.sdk` or
+    /// `SDKs/MacOSX14.0.sdk`. Symlinked SDK directories are not reported.
+    pub fn sdk_directories(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut res = vec![];
+
+        self.for_each_entry(|entry, _| {
+            if let Some(dir) = entry.sdk_directory() {
+                if !res.iter().any(|p| p == dir) {
+                    res.push(dir.to_path_buf());
+                }
+            }
+
+            Ok(true)
+        })?;
+
+        // Make deterministic.
+        res.sort();
+
+        Ok(res)
+    }
+
+    /// Read the content of a file in this archive.
+    ///
+    /// `path` is relative to the archive root. Returns [None] if no regular file
+    /// exists at that path.
+    pub fn read_file(&self, path: impl AsRef<Path>) -> Result<Option<Vec<u8>>, Error> {
+        let wanted = normalize_path(path.as_ref())?;
+        let mut res = None;
+
+        self.for_each_entry(|entry, reader| {
+            if entry.kind == EntryKind::File && entry.path == wanted {
+                let mut data = vec![];
+                reader
+                    .read_to_end(&mut data)
+                    .map_err(Error::io_path(&self.path))?;
+                res = Some(data);
+
+                Ok(false)
+            } else {
+                Ok(true)
+            }
+        })?;
+
+        Ok(res)
+    }
+
+    /// Parse the `SDKSettings.json` files of SDKs in this archive.
+    ///
+    /// Returns the relative path of each SDK directory and its parsed settings.
+    /// SDKs that only have an `SDKSettings.plist` are not reported.
+    ///
+    /// Requires the `parse` crate feature.
+    #[cfg(feature = "parse")]
+    pub fn sdk_settings(&self) -> Result<Vec<(PathBuf, SdkSettingsJson)>, Error> {
+        let mut res = vec![];
+
+        self.for_each_entry(|entry, reader| {
+            if let Some(dir) = entry.sdk_directory() {
+                if entry.path.file_name() == Some(OsStr::new("SDKSettings.json")) {
+                    let mut data = vec![];
+                    reader
+                        .read_to_end(&mut data)
+                        .map_err(Error::io_path(&self.path))?;
+
+                    res.push((dir.to_path_buf(), serde_json::from_slice(&data)?));
+                }
+            }
+
+            Ok(true)
+        })?;
+
+        res.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Ok(res)
+    }
+
+    /// Extract this archive into a directory.
+    ///
+    /// Returns the paths of extracted SDK directories.
+    ///
+    /// Entries with absolute paths or paths escaping the destination directory
+    /// are rejected with [Error::ArchiveMalformed].
+    pub fn extract(&self, dest: &Path) -> Result<Vec<PathBuf>, Error> {
+        std::fs::create_dir_all(dest).map_err(Error::io_path(dest))?;
+
+        let mut sdks = vec![];
+
+        self.for_each_entry(|entry, reader| {
+            extract_entry(dest, entry, reader)?;
+
+            if let Some(dir) = entry.sdk_directory() {
+                sdks.push(dest.join(dir));
+            }
+
+            Ok(true)
+        })?;
+
+        // Make deterministic.
+        sdks.sort();
+        sdks.dedup();
+
+        Ok(sdks)
+    }
+
+    /// Extract this archive into a cache directory if not already extracted.
+    ///
+    /// The archive is extracted into a subdirectory of `cache_dir` derived from
+    /// the archive's filename, size, and modification time. If that subdirectory
+    /// was previously fully extracted, extraction is skipped.
+    ///
+    /// Returns the paths of extracted SDK directories.
+    pub fn extract_cached(&self, cache_dir: &Path) -> Result<Vec<PathBuf>, Error> {
+        let metadata = std::fs::metadata(&self.path).map_err(Error::io_path(&self.path))?;
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        let stem = self
+            .path
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let dest = cache_dir.join(format!("{stem}-{:x}-{mtime:x}", metadata.len()));
+        let marker = dest.join(EXTRACTED_MARKER);
+
+        if let Ok(data) = std::fs::read(&marker) {
+            return Ok(data
+                .split(|b| *b == b'\n')
+                .filter(|l| !l.is_empty())
+                .map(|l| dest.join(os_string_from_bytes(l.to_vec())))
+                .collect());
+        }
+
+        // Extract to a temporary directory so interrupted extractions aren't reused.
+        let temp = cache_dir.join(format!(
+            ".{stem}-{:x}-{mtime:x}.{}",
+            metadata.len(),
+            std::process::id()
+        ));
+        if temp.exists() {
+            std::fs::remove_dir_all(&temp).map_err(Error::io_path(&temp))?;
+        }
+
+        let sdks = self.extract(&temp)?;
+
+        let relative = sdks
+            .iter()
+            .filter_map(|p| p.strip_prefix(&temp).ok())
+            .map(|p| p.to_path_buf())
+            .collect::<Vec<_>>();
+        let marker_data = relative
+            .iter()
+            .map(|p| os_str_to_bytes(p.as_os_str()))
+            .collect::<Vec<_>>()
+            .join(&b'\n');
+        std::fs::write(temp.join(EXTRACTED_MARKER), marker_data).map_err(Error::io_path(&temp))?;
+
+        if dest.exists() {
+            std::fs::remove_dir_all(&dest).map_err(Error::io_path(&dest))?;
+        }
+        std::fs::rename(&temp, &dest).map_err(Error::io_path(&dest))?;
+
+        Ok(relative.iter().map(|l| dest.join(l)).collect())
+    }
+
+    /// Call a function for each entry in the archive.
+    ///
+    /// Iteration stops when the function returns `false`.
+    fn for_each_entry(
+        &self,
+        mut f: impl FnMut(&Entry, &mut dyn Read) -> Result<bool, Error>,
+    ) -> Result<(), Error> {
+        let fh = File::open(&self.path).map_err(Error::io_path(&self.path))?;
+
+        match self.format {
+            SdkArchiveFormat::Tar => for_each_tar_entry(&self.path, BufReader::new(fh), f),
+            SdkArchiveFormat::TarGz => for_each_tar_entry(
+                &self.path,
+                flate2::read::GzDecoder::new(BufReader::new(fh)),
+                f,
+            ),
+            SdkArchiveFormat::TarXz => {
+                for_each_tar_entry(&self.path, xz2::read::XzDecoder::new(BufReader::new(fh)), f)
+            }
+            SdkArchiveFormat::Zip => {
+                let mut archive = zip::ZipArchive::new(BufReader::new(fh))?;
+
+                for i in 0..archive.len() {
+                    let mut file = archive.by_index(i)?;
+
+                    let path = normalize_path(Path::new(file.name()))?;
+                    let mode = file.unix_mode();
+
+                    let kind = if file.is_dir() {
+                        EntryKind::Directory
+                    } else if mode.map(|m| m & 0o170000 == 0o120000).unwrap_or_default() {
+                        let mut target = String::new();
+                        file.read_to_string(&mut target)
+                            .map_err(Error::io_path(&self.path))?;
+
+                        EntryKind::Symlink(PathBuf::from(target))
+                    } else {
+                        EntryKind::File
+                    };
+
+                    let entry = Entry { path, kind, mode };
+
+                    if !f(&entry, &mut file)? {
+                        break;
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Normalize a relative path in an archive.
+///
+/// Current directory components are removed. Absolute paths and parent directory
+/// components are rejected.
+fn normalize_path(path: &Path) -> Result<PathBuf, Error> {
+    let mut res = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(c) => res.push(c),
+            Component::CurDir => {}
+            _ => {
+                return Err(Error::ArchiveMalformed(format!(
+                    "unsafe path in archive: {}",
+                    path.display()
+                )))
+            }
+        }
+    }
+
+    Ok(res)
+}
+
+/// Ensure a path within a destination directory doesn't traverse symlinks.
+///
+/// Every component of `path`, including the final one, is checked. Symlinks
+/// created by earlier entries could otherwise redirect writes or reads outside
+/// the destination.
+fn ensure_no_symlinks(dest: &Path, path: &Path) -> Result<(), Error> {
+    let mut current = dest.to_path_buf();
+
+    for component in path.components() {
+        current.push(component);
+
+        if let Ok(metadata) = std::fs::symlink_metadata(&current) {
+            if metadata.file_type().is_symlink() {
+                return Err(Error::ArchiveMalformed(format!(
+                    "archive entry traverses a symlink: {}",
+                    path.display()
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Ensure a symlink target stays within the destination directory.
+///
+/// `path` is the relative path of the symlink and `target` its content.
+/// Absolute targets and relative targets resolving outside the destination are
+/// rejected.
+fn ensure_symlink_contained(path: &Path, target: &Path) -> Result<(), Error> {
+    let mut depth = path.components().count().saturating_sub(1);
+
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => depth -= 1,
+            _ => {
+                return Err(Error::ArchiveMalformed(format!(
+                    "archive symlink escapes destination: {} -> {}",
+                    path.display(),
+                    target.display()
+                )))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Write an archive entry into a destination directory.
+fn extract_entry(dest: &Path, entry: &Entry, reader: &mut dyn Read) -> Result<(), Error> {
+    if entry.path.as_os_str().is_empty() {
+        return Ok(());
+    }
+
+    ensure_no_symlinks(dest, &entry.path)?;
+
+    let path = dest.join(&entry.path);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(Error::io_path(parent))?;
+    }
+
+    match &entry.kind {
+        EntryKind::Directory => {
+            std::fs::create_dir_all(&path).map_err(Error::io_path(&path))?;
+        }
+        EntryKind::File => {
+            let mut fh = File::create(&path).map_err(Error::io_path(&path))?;
+            std::io::copy(reader, &mut fh).map_err(Error::io_path(&path))?;
+
+            #[cfg(unix)]
+            if let Some(mode) = entry.mode {
+                use std::os::unix::fs::PermissionsExt;
+
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode & 0o777))
+                    .map_err(Error::io_path(&path))?;
+            }
+        }
+        EntryKind::Symlink(target) => {
+            ensure_symlink_contained(&entry.path, target)?;
+
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(target, &path).map_err(Error::io_path(&path))?;
+
+            // Symlinks are only found within SDKs for aliases. So skipping them
+            // doesn't lose content.
+            #[cfg(not(unix))]
+            let _ = target;
+        }
+        EntryKind::Hardlink(target) => {
+            let target = normalize_path(target)?;
+            ensure_no_symlinks(dest, &target)?;
+            let source = dest.join(target);
+
+            if std::fs::hard_link(&source, &path).is_err() {
+                std::fs::copy(&source, &path).map_err(Error::io_path(&path))?;
+            }
+        }
+        EntryKind::Other => {}
+    }
+
+    Ok(())
+}
+
+/// Call a function for each entry in a tar stream.
+///
+/// The [tar] crate handles pax and GNU long name headers. Path and symlink
+/// safety checks are applied on top of it.
+fn for_each_tar_entry(
+    archive_path: &Path,
+    reader: impl Read,
+    mut f: impl FnMut(&Entry, &mut dyn Read) -> Result<bool, Error>,
+) -> Result<(), Error> {
+    let mut archive = tar::Archive::new(reader);
+
+    for tar_entry in archive.entries().map_err(Error::io_path(archive_path))? {
+        let mut tar_entry = tar_entry.map_err(Error::io_path(archive_path))?;
+
+        let link = || -> Result<PathBuf, Error> {
+            Ok(tar_entry
+                .link_name()
+                .map_err(Error::io_path(archive_path))?
+                .map(|p| p.into_owned())
+                .unwrap_or_default())
+        };
+
+        let kind = match tar_entry.header().entry_type() {
+            tar::EntryType::Regular | tar::EntryType::Continuous => EntryKind::File,
+            tar::EntryType::Link => EntryKind::Hardlink(link()?),
+            tar::EntryType::Symlink => EntryKind::Symlink(link()?),
+            tar::EntryType::Directory => EntryKind::Directory,
+            _ => EntryKind::Other,
+        };
+
+        let entry = Entry {
+            path: normalize_path(&tar_entry.path().map_err(Error::io_path(archive_path))?)?,
+            kind,
+            mode: tar_entry.header().mode().ok(),
+        };
+
+        if !f(&entry, &mut tar_entry)? {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, std::io::Write};
+
+    /// Construct a tar archive from `(path, typeflag, data, link)` entries.
+    ///
+    /// Paths and link targets are written as given, so unsafe archives can be
+    /// constructed.
+    fn tar_file(entries: &[(&str, u8, &[u8], &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(vec![]);
+
+        for (name, typeflag, data, link) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::new(*typeflag));
+            header.set_mode(0o644);
+            header.set_size(data.len() as u64);
+
+            if link.is_empty() {
+                builder.append_data(&mut header, name, *data).unwrap();
+            } else {
+                builder.append_link(&mut header, name, link).unwrap();
+            }
+        }
+
+        builder.into_inner().unwrap()
+    }
+
+    const SDK_SETTINGS: &[u8] = br#"{"CanonicalName": "macosx14.0", "DefaultDeploymentTarget": "14.0", "DefaultProperties": {"PLATFORM_NAME": "macosx"}, "DisplayName": "macOS 14.0", "MaximumDeploymentTarget": "14.0.99", "MinimalDisplayName": "14.0", "SupportedTargets": {}, "Version": "14.0"}"#;
+
+    #[test]
+    fn format() {
+        for (name, format) in [
+            ("sdk.tar", Some(SdkArchiveFormat::Tar)),
+            ("sdk.tar.gz", Some(SdkArchiveFormat::TarGz)),
+            ("sdk.TGZ", Some(SdkArchiveFormat::TarGz)),
+            ("MacOSX14.0.sdk.tar.xz", Some(SdkArchiveFormat::TarXz)),
+            ("sdk.zip", Some(SdkArchiveFormat::Zip)),
+            ("MacOSX14.0.sdk", None),
+        ] {
+            assert_eq!(SdkArchiveFormat::from_path(Path::new(name)), format);
+        }
+    }
+
+    #[test]
+    fn tar_archive() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let archive_path = td.join("sdk.tar.xz");
+
+        let tar = tar_file(&[
+            ("./MacOSX14.0.sdk/", b'5', b"", ""),
+            ("./MacOSX14.0.sdk/SDKSettings.json", b'0', SDK_SETTINGS, ""),
+            ("./MacOSX14.0.sdk/usr/include/zlib.h", b'0', b"zlib", ""),
+            ("./MacOSX.sdk", b'2', b"", "MacOSX14.0.sdk"),
+        ]);
+        let mut encoder = xz2::write::XzEncoder::new(File::create(&archive_path)?, 6);
+        encoder.write_all(&tar)?;
+        encoder.finish()?;
+
+        let archive = SdkArchive::from_path(&archive_path)?;
+        assert_eq!(archive.format(), SdkArchiveFormat::TarXz);
+        assert_eq!(
+            archive.sdk_directories()?,
+            vec![PathBuf::from("MacOSX14.0.sdk")]
+        );
+        assert_eq!(
+            archive.read_file("MacOSX14.0.sdk/usr/include/zlib.h")?,
+            Some(b"zlib".to_vec())
+        );
+        assert_eq!(archive.read_file("missing")?, None);
+
+        #[cfg(feature = "parse")]
+        {
+            let settings = archive.sdk_settings()?;
+            assert_eq!(settings.len(), 1);
+            assert_eq!(settings[0].1.canonical_name, "macosx14.0");
+        }
+
+        let cache = td.join("cache");
+        let sdks = archive.extract_cached(&cache)?;
+        assert_eq!(sdks.len(), 1);
+        assert!(sdks[0].join("usr/include/zlib.h").is_file());
+        #[cfg(unix)]
+        assert!(sdks[0].with_file_name("MacOSX.sdk").is_symlink());
+        assert_eq!(archive.extract_cached(&cache)?, sdks);
+
+        Ok(())
+    }
+
+    #[test]
+    fn zip_archive() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let archive_path = td.join("sdk.zip");
+
+        let mut writer = zip::ZipWriter::new(File::create(&archive_path)?);
+        writer.add_directory("SDKs/MacOSX14.0.sdk/", Default::default())?;
+        writer.start_file("SDKs/MacOSX14.0.sdk/SDKSettings.json", Default::default())?;
+        writer.write_all(SDK_SETTINGS)?;
+        writer.finish()?;
+
+        let archive = SdkArchive::from_path(&archive_path)?;
+        assert_eq!(
+            archive.sdk_directories()?,
+            vec![PathBuf::from("SDKs/MacOSX14.0.sdk")]
+        );
+
+        let sdks = archive.extract(&td.join("extracted"))?;
+        assert_eq!(sdks, vec![td.join("extracted/SDKs/MacOSX14.0.sdk")]);
+        assert!(sdks[0].join("SDKSettings.json").is_file());
+
+        Ok(())
+    }
+
+    #[test]
+    fn unsafe_paths() {
+        assert!(normalize_path(Path::new("./a/b")).is_ok());
+        assert!(matches!(
+            normalize_path(Path::new("../a")),
+            Err(Error::ArchiveMalformed(_))
+        ));
+        assert!(matches!(
+            normalize_path(Path::new("/etc/passwd")),
+            Err(Error::ArchiveMalformed(_))
+        ));
+    }
+
+    /// Extract a tar archive made of the given entries into a fresh directory.
+    fn extract_tar(td: &Path, entries: &[(&str, u8, &[u8], &str)]) -> Result<Vec<PathBuf>, Error> {
+        let archive_path = td.join("sdk.tar");
+        std::fs::write(&archive_path, tar_file(entries))?;
+
+        SdkArchive::from_path(&archive_path)?.extract(&td.join("extracted"))
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_escapes() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let outside = td.join("outside");
+        let outside_str = outside.to_string_lossy().to_string();
+
+        // A symlink followed by a file of the same name writes through the link.
+        assert!(matches!(
+            extract_tar(
+                td,
+                &[("a", b'2', b"", &outside_str), ("a", b'0', b"evil", "")]
+            ),
+            Err(Error::ArchiveMalformed(_))
+        ));
+        assert!(!outside.exists());
+
+        std::fs::remove_dir_all(td.join("extracted"))?;
+        assert!(matches!(
+            extract_tar(td, &[("a", b'2', b"", "b"), ("a", b'0', b"evil", "")]),
+            Err(Error::ArchiveMalformed(_))
+        ));
+        assert!(!td.join("extracted/b").exists());
+
+        std::fs::remove_dir_all(td.join("extracted"))?;
+        assert!(matches!(
+            extract_tar(td, &[("x/a", b'2', b"", "../../outside")]),
+            Err(Error::ArchiveMalformed(_))
+        ));
+
+        std::fs::remove_dir_all(td.join("extracted"))?;
+        extract_tar(td, &[("x/a", b'2', b"", "../b")])?;
+        assert!(td.join("extracted/x/a").is_symlink());
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hardlink_escapes() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let outside = td.join("outside");
+        std::fs::create_dir_all(&outside)?;
+        std::fs::write(outside.join("secret"), b"secret")?;
+
+        // A symlink already in the destination must not be followed by hardlinks.
+        std::fs::create_dir_all(td.join("extracted"))?;
+        std::os::unix::fs::symlink(&outside, td.join("extracted/evil"))?;
+
+        assert!(matches!(
+            extract_tar(td, &[("copy", b'1', b"", "evil/secret")]),
+            Err(Error::ArchiveMalformed(_))
+        ));
+        assert!(!td.join("extracted/copy").exists());
+
+        assert!(matches!(
+            extract_tar(td, &[("copy", b'1', b"", "../outside/secret")]),
+            Err(Error::ArchiveMalformed(_))
+        ));
+        assert!(matches!(
+            extract_tar(td, &[("evil/new", b'0', b"evil", "")]),
+            Err(Error::ArchiveMalformed(_))
+        ));
+        assert!(!outside.join("new").exists());
+
+        extract_tar(
+            td,
+            &[("file", b'0', b"data", ""), ("copy", b'1', b"", "file")],
+        )?;
+        assert_eq!(std::fs::read(td.join("extracted/copy"))?, b"data");
+
+        Ok(())
+    }
+
+    #[test]
+    fn tar_checksum() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+
+        let mut tar = tar_file(&[("MacOSX14.0.sdk/SDKSettings.json", b'0', SDK_SETTINGS, "")]);
+        let archive_path = td.join("sdk.tar");
+        std::fs::write(&archive_path, &tar)?;
+        assert_eq!(
+            SdkArchive::from_path(&archive_path)?.sdk_directories()?,
+            vec![PathBuf::from("MacOSX14.0.sdk")]
+        );
+
+        tar[0] = b'N';
+        std::fs::write(&archive_path, &tar)?;
+        assert!(matches!(
+            SdkArchive::from_path(&archive_path)?.sdk_directories(),
+            Err(Error::IoPath(path, _)) if path == archive_path
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn tar_long_paths() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+
+        let dir = format!("SDKs/{}/MacOSX14.0.sdk", "nested/".repeat(20));
+        let settings = format!("{dir}/SDKSettings.json");
+        let link = format!("{dir}/usr/include/{}", "header/".repeat(20));
+        std::fs::write(
+            td.join("sdk.tar"),
+            tar_file(&[
+                (&settings, b'0', SDK_SETTINGS, ""),
+                (&format!("{dir}/alias"), b'2', b"", &link),
+            ]),
+        )?;
+
+        let archive = SdkArchive::from_path(td.join("sdk.tar"))?;
+        assert_eq!(archive.sdk_directories()?, vec![PathBuf::from(&dir)]);
+        assert_eq!(archive.read_file(&settings)?, Some(SDK_SETTINGS.to_vec()));
+
+        Ok(())
+    }
+}
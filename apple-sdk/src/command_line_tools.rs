@@ -0,0 +1,248 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Xcode Command Line Tools installation.
+//!
+//! macOS installs the Command Line Tools on demand via `softwareupdate`. The
+//! install is only offered while a trigger file exists. This module wraps that
+//! process so provisioning tools can bootstrap a machine.
+
+use {
+    crate::{Error, SdkVersion, COMMAND_LINE_TOOLS_DEFAULT_PATH},
+    std::{
+        path::{Path, PathBuf},
+        process::{Command, Stdio},
+    },
+};
+
+/// Path of the file that makes `softwareupdate` offer the Command Line Tools.
+pub const COMMAND_LINE_TOOLS_INSTALL_TRIGGER_PATH: &str =
+    "/tmp/.com.apple.dt.CommandLineTools.installondemand.in-progress";
+
+/// Whether the Xcode Command Line Tools are installed.
+///
+/// This checks for the `usr/bin` directory of [COMMAND_LINE_TOOLS_DEFAULT_PATH].
+/// A Command Line Tools directory without tools, as left behind by an interrupted
+/// install, isn't considered installed.
+pub fn command_line_tools_installed() -> bool {
+    Path::new(COMMAND_LINE_TOOLS_DEFAULT_PATH)
+        .join("usr")
+        .join("bin")
+        .is_dir()
+}
+
+/// Create the trigger file making `softwareupdate` offer the Command Line Tools.
+///
+/// The file is created exclusively and symlinks aren't followed, as this
+/// typically runs as root on a predictable path in a world writable directory.
+///
+/// Returns the path of the trigger file. Remove it via
+/// [remove_command_line_tools_install_trigger()] once the install completes.
+///
+/// Errors with [Error::CommandLineToolsInstallTriggerExists] if anything exists
+/// at the path already.
+pub fn create_command_line_tools_install_trigger() -> Result<PathBuf, Error> {
+    let path = PathBuf::from(COMMAND_LINE_TOOLS_INSTALL_TRIGGER_PATH);
+
+    create_install_trigger(&path)?;
+
+    Ok(path)
+}
+
+/// Exclusively create an empty file without following symlinks.
+fn create_install_trigger(path: &Path) -> Result<(), Error> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    // `create_new` already refuses existing symlinks. Be explicit where the flag
+    // value is known.
+    #[cfg(target_os = "macos")]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        const O_NOFOLLOW: i32 = 0x0100;
+        options.custom_flags(O_NOFOLLOW);
+    }
+
+    match options.open(path) {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Err(
+            Error::CommandLineToolsInstallTriggerExists(path.to_path_buf()),
+        ),
+        Err(e) => Err(Error::IoPath(path.to_path_buf(), e)),
+    }
+}
+
+/// Remove the trigger file created by [create_command_line_tools_install_trigger()].
+///
+/// A missing trigger file is not an error.
+pub fn remove_command_line_tools_install_trigger() -> Result<(), Error> {
+    let path = Path::new(COMMAND_LINE_TOOLS_INSTALL_TRIGGER_PATH);
+
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(Error::IoPath(path.to_path_buf(), e)),
+    }
+}
+
+/// Find the `softwareupdate` label of the newest Command Line Tools on offer.
+///
+/// This runs `softwareupdate --list`. The Command Line Tools are only listed while
+/// the trigger file from [create_command_line_tools_install_trigger()] exists.
+///
+/// Returns [None] if no Command Line Tools are on offer.
+pub fn find_command_line_tools_update_label() -> Result<Option<String>, Error> {
+    let output = run_softwareupdate(&["--list"])?;
+
+    Ok(parse_command_line_tools_label(&String::from_utf8_lossy(
+        &output,
+    )))
+}
+
+/// Install the newest Xcode Command Line Tools via `softwareupdate`.
+///
+/// This creates the install trigger file, finds the label of the newest Command
+/// Line Tools via [find_command_line_tools_update_label()], runs
+/// `softwareupdate --install` for it, and removes the trigger file. An existing
+/// trigger file is used as is and left in place. Installing requires root
+/// privileges and network access and can take several minutes.
+///
+/// Returns the installed label. Returns [None] without installing anything if no
+/// Command Line Tools are on offer.
+pub fn install_command_line_tools() -> Result<Option<String>, Error> {
+    let created = match create_command_line_tools_install_trigger() {
+        Ok(_) => true,
+        Err(Error::CommandLineToolsInstallTriggerExists(_)) => false,
+        Err(e) => return Err(e),
+    };
+
+    let res = find_command_line_tools_update_label().and_then(|label| {
+        if let Some(label) = &label {
+            run_softwareupdate(&["--install", label, "--verbose"])?;
+        }
+
+        Ok(label)
+    });
+
+    if created {
+        remove_command_line_tools_install_trigger()?;
+    }
+
+    res
+}
+
+/// Run `softwareupdate`, yielding its stdout.
+fn run_softwareupdate(args: &[&str]) -> Result<Vec<u8>, Error> {
+    let output = Command::new("softwareupdate")
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(Error::SoftwareupdateRun)?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(Error::SoftwareupdateBadStatus(output.status))
+    }
+}
+
+/// Extract the label of the newest Command Line Tools from `softwareupdate --list` output.
+///
+/// Current macOS versions print `* Label: <label>` lines. Older versions print
+/// `* <label>` lines.
+fn parse_command_line_tools_label(output: &str) -> Option<String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("* "))
+        .map(|label| label.strip_prefix("Label: ").unwrap_or(label).trim())
+        .filter(|label| label.starts_with("Command Line Tools"))
+        .max_by(|a, b| label_version(a).cmp(&label_version(b)))
+        .map(|label| label.to_string())
+}
+
+/// The Xcode version at the end of a label. e.g. `15.1` for `Command Line Tools for Xcode-15.1`.
+fn label_version(label: &str) -> SdkVersion {
+    SdkVersion::from(label.rsplit_once('-').map(|(_, v)| v).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn install_trigger() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        std::fs::create_dir_all(td)?;
+
+        let path = td.join("trigger");
+        create_install_trigger(&path)?;
+        assert!(path.is_file());
+        assert!(matches!(
+            create_install_trigger(&path),
+            Err(Error::CommandLineToolsInstallTriggerExists(_))
+        ));
+
+        // Symlinks aren't followed and their targets aren't truncated.
+        #[cfg(unix)]
+        {
+            let victim = td.join("victim");
+            std::fs::write(&victim, b"content")?;
+            let link = td.join("link");
+            std::os::unix::fs::symlink(&victim, &link)?;
+            assert!(matches!(
+                create_install_trigger(&link),
+                Err(Error::CommandLineToolsInstallTriggerExists(_))
+            ));
+            assert_eq!(std::fs::read(&victim)?, b"content");
+
+            let dangling = td.join("dangling");
+            std::os::unix::fs::symlink(td.join("missing"), &dangling)?;
+            assert!(create_install_trigger(&dangling).is_err());
+            assert!(!td.join("missing").exists());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_label() {
+        const OUTPUT: &str = "Software Update Tool
+
+Finding available software
+Software Update found the following new or updated software:
+* Label: Command Line Tools for Xcode-14.3
+\tTitle: Command Line Tools for Xcode, Version: 14.3, Size: 711123KiB, Recommended: YES,
+* Label: Command Line Tools for Xcode-15.1
+\tTitle: Command Line Tools for Xcode, Version: 15.1, Size: 735439KiB, Recommended: YES,
+* Label: macOS Sonoma 14.2.1-23C71
+\tTitle: macOS Sonoma 14.2.1, Version: 14.2.1, Size: 1234567KiB, Recommended: YES, Action: restart,
+";
+
+        assert_eq!(
+            parse_command_line_tools_label(OUTPUT),
+            Some("Command Line Tools for Xcode-15.1".to_string())
+        );
+
+        const LEGACY: &str = "Software Update found the following new or updated software:
+   * Command Line Tools (macOS Mojave version 10.14) for Xcode-10.3
+\tCommand Line Tools (macOS Mojave version 10.14) for Xcode (10.3), 199140K [recommended]
+";
+        assert_eq!(
+            parse_command_line_tools_label(LEGACY),
+            Some("Command Line Tools (macOS Mojave version 10.14) for Xcode-10.3".to_string())
+        );
+
+        assert_eq!(
+            parse_command_line_tools_label("No new software available.\n"),
+            None
+        );
+    }
+}
@@ -0,0 +1,551 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! SDK search policies defined in config files.
+//!
+//! A config file lets a repository pin how SDKs are found, so every tool
+//! built on this crate resolves the same SDKs. Config files are JSON
+//! (`.apple-sdk.json`) or, with the `toml` crate feature, TOML
+//! (`.apple-sdk.toml`). Keys are kebab-case. e.g.
+//!
+//! ```toml
+//! locations = ["sdkroot-env", "developer-dir-env", { sdks = "vendor/sdks" }]
+//! platforms = ["macosx"]
+//! minimum-version = "13.0"
+//! sorting = "version-descending"
+//! beta-preference = "prefer-release"
+//! ```
+
+use {
+    crate::{
+        DeveloperDirectory, Error, Platform, SdkSearch, SdkSearchLocation, SdkSorting, SdkVersion,
+        SdkVersionReq, XcodeBetaPreference,
+    },
+    serde::{Deserialize, Serialize},
+    std::path::{Path, PathBuf},
+};
+
+/// File name of a JSON SDK search config file.
+pub const SDK_SEARCH_CONFIG_JSON_FILE_NAME: &str = ".apple-sdk.json";
+
+/// File name of a TOML SDK search config file.
+///
+/// Loading these files requires the `toml` crate feature.
+pub const SDK_SEARCH_CONFIG_TOML_FILE_NAME: &str = ".apple-sdk.toml";
+
+/// Environment variable opting [SdkSearch::from_env()] into a config file.
+///
+/// The value is the path to a config file or to a directory. For a directory, the
+/// config file applying to it is found via [SdkSearchConfig::find()].
+pub const SDK_SEARCH_CONFIG_ENV: &str = "APPLE_SDK_CONFIG";
+
+/// Entries marking the root of a repository, where config file discovery stops.
+const REPOSITORY_ROOT_MARKERS: &[&str] = &[".git", ".hg", ".jj"];
+
+/// A search location in a [SdkSearchConfig].
+///
+/// Locations without a path are written as a string. e.g. `"system-xcode"`.
+/// Locations with a path are written as a single key map. e.g.
+/// `{ "sdks": "vendor/sdks" }`. Relative paths are resolved against the
+/// directory holding the config file.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SdkSearchConfigLocation {
+    /// [SdkSearchLocation::SdkRootEnv].
+    #[serde(rename = "sdkroot-env")]
+    SdkRootEnv,
+    /// [SdkSearchLocation::DeveloperDirEnv].
+    DeveloperDirEnv,
+    /// [SdkSearchLocation::SystemXcode].
+    SystemXcode,
+    /// [SdkSearchLocation::CommandLineTools].
+    CommandLineTools,
+    /// [SdkSearchLocation::XcodeSelect].
+    XcodeSelect,
+    /// [SdkSearchLocation::SystemXcodes].
+    SystemXcodes,
+    /// [SdkSearchLocation::UserXcodes].
+    UserXcodes,
+    /// [SdkSearchLocation::OsxcrossEnv].
+    OsxcrossEnv,
+    /// [SdkSearchLocation::Xcodebuild].
+    Xcodebuild,
+    /// [SdkSearchLocation::Developer].
+    Developer(PathBuf),
+    /// [SdkSearchLocation::Sdks].
+    Sdks(PathBuf),
+    /// [SdkSearchLocation::Sdk].
+    Sdk(PathBuf),
+    /// [SdkSearchLocation::Osxcross].
+    Osxcross(PathBuf),
+}
+
+impl SdkSearchConfigLocation {
+    /// Resolve relative paths against a directory.
+    fn resolve_paths(&mut self, base: &Path) {
+        match self {
+            Self::Developer(path) | Self::Sdks(path) | Self::Sdk(path) | Self::Osxcross(path)
+                if path.is_relative() =>
+            {
+                *path = base.join(&path);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl From<&SdkSearchConfigLocation> for SdkSearchLocation {
+    fn from(location: &SdkSearchConfigLocation) -> Self {
+        match location {
+            SdkSearchConfigLocation::SdkRootEnv => Self::SdkRootEnv,
+            SdkSearchConfigLocation::DeveloperDirEnv => Self::DeveloperDirEnv,
+            SdkSearchConfigLocation::SystemXcode => Self::SystemXcode,
+            SdkSearchConfigLocation::CommandLineTools => Self::CommandLineTools,
+            SdkSearchConfigLocation::XcodeSelect => Self::XcodeSelect,
+            SdkSearchConfigLocation::SystemXcodes => Self::SystemXcodes,
+            SdkSearchConfigLocation::UserXcodes => Self::UserXcodes,
+            SdkSearchConfigLocation::OsxcrossEnv => Self::OsxcrossEnv,
+            SdkSearchConfigLocation::Xcodebuild => Self::Xcodebuild,
+            SdkSearchConfigLocation::Developer(path) => {
+                Self::Developer(DeveloperDirectory::from(path))
+            }
+            SdkSearchConfigLocation::Sdks(path) => Self::Sdks(path.clone()),
+            SdkSearchConfigLocation::Sdk(path) => Self::Sdk(path.clone()),
+            SdkSearchConfigLocation::Osxcross(path) => Self::Osxcross(path.clone()),
+        }
+    }
+}
+
+/// A deployment target in a [SdkSearchConfig].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SdkSearchConfigDeploymentTarget {
+    /// The name of the target. e.g. `macosx`.
+    pub target: String,
+
+    /// The version to target. e.g. `11.0`.
+    pub version: SdkVersion,
+}
+
+/// [SdkSearch] parameters loaded from a config file.
+///
+/// Every field is optional. Fields that are not set leave the corresponding
+/// [SdkSearch] setting alone when applied via [Self::apply()]. Unknown keys are
+/// rejected so typos don't silently change SDK resolution.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SdkSearchConfig {
+    /// Replaces the search locations. See [SdkSearch::locations()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locations: Option<Vec<SdkSearchConfigLocation>>,
+
+    /// Platforms to search for. See [SdkSearch::platforms()].
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub platforms: Vec<Platform>,
+
+    /// Replaces the platform fallbacks. See [SdkSearch::platform_fallbacks()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform_fallbacks: Option<Vec<Platform>>,
+
+    /// See [SdkSearch::minimum_version()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum_version: Option<SdkVersion>,
+
+    /// See [SdkSearch::maximum_version()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum_version: Option<SdkVersion>,
+
+    /// See [SdkSearch::version_req()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_req: Option<SdkVersionReq>,
+
+    /// See [SdkSearch::deployment_target()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployment_target: Option<SdkSearchConfigDeploymentTarget>,
+
+    /// See [SdkSearch::deployment_target_from_env()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployment_target_from_env: Option<bool>,
+
+    /// See [SdkSearch::sorting()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sorting: Option<SdkSorting>,
+
+    /// See [SdkSearch::beta_preference()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub beta_preference: Option<XcodeBetaPreference>,
+
+    /// See [SdkSearch::minimum_xcode_version()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum_xcode_version: Option<SdkVersion>,
+
+    /// See [SdkSearch::deduplicate()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deduplicate: Option<bool>,
+
+    /// See [SdkSearch::prefer_versioned()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefer_versioned: Option<bool>,
+
+    /// See [SdkSearch::exclude_symlinks()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclude_symlinks: Option<bool>,
+
+    /// See [SdkSearch::user_applications()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_applications: Option<bool>,
+
+    /// See [SdkSearch::extra_sdks_dirs_from_env()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_sdks_dirs_from_env: Option<bool>,
+
+    /// See [SdkSearch::limit()].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+impl SdkSearchConfig {
+    /// Parse a config from JSON data.
+    pub fn from_json_slice(data: &[u8]) -> Result<Self, Error> {
+        Ok(serde_json::from_slice(data)?)
+    }
+
+    /// Parse a config from TOML data.
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(data: &str) -> Result<Self, Error> {
+        Ok(toml::from_str(data)?)
+    }
+
+    /// Load a config file.
+    ///
+    /// Files with a `.toml` extension are parsed as TOML, which requires the
+    /// `toml` crate feature. Otherwise [Error::FunctionalityNotSupported] is
+    /// returned. All other files are parsed as JSON.
+    ///
+    /// Relative paths in locations are resolved against the directory holding
+    /// the file.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read(path).map_err(Error::io_path(path))?;
+
+        let mut config = if path.extension().and_then(|x| x.to_str()) == Some("toml") {
+            Self::from_toml_data(path, &data)?
+        } else {
+            Self::from_json_slice(&data)?
+        };
+
+        if let Some(base) = path.parent() {
+            for location in config.locations.iter_mut().flatten() {
+                location.resolve_paths(base);
+            }
+        }
+
+        Ok(config)
+    }
+
+    #[cfg(feature = "toml")]
+    fn from_toml_data(path: &Path, data: &[u8]) -> Result<Self, Error> {
+        let data = std::str::from_utf8(data).map_err(|e| {
+            Error::IoPath(
+                path.to_path_buf(),
+                std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+            )
+        })?;
+
+        Self::from_toml_str(data)
+    }
+
+    #[cfg(not(feature = "toml"))]
+    fn from_toml_data(_path: &Path, _data: &[u8]) -> Result<Self, Error> {
+        Err(Error::FunctionalityNotSupported(
+            "loading TOML SDK search config requires the toml crate feature",
+        ))
+    }
+
+    /// Find the config file applying to a directory.
+    ///
+    /// The directory and each of its ancestors are checked for a
+    /// [SDK_SEARCH_CONFIG_TOML_FILE_NAME] or [SDK_SEARCH_CONFIG_JSON_FILE_NAME]
+    /// file, in that order. The first file found is returned.
+    ///
+    /// The search stops at the root of the repository holding the directory,
+    /// identified by a `.git`, `.hg`, or `.jj` entry. So config files outside the
+    /// repository don't apply.
+    ///
+    /// TOML files are found even if the `toml` crate feature is disabled, so
+    /// a repository's policy is never silently ignored.
+    pub fn find(dir: impl AsRef<Path>) -> Option<PathBuf> {
+        for dir in dir.as_ref().ancestors() {
+            let found = [
+                SDK_SEARCH_CONFIG_TOML_FILE_NAME,
+                SDK_SEARCH_CONFIG_JSON_FILE_NAME,
+            ]
+            .into_iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file());
+
+            if found.is_some() {
+                return found;
+            }
+
+            if REPOSITORY_ROOT_MARKERS
+                .iter()
+                .any(|marker| dir.join(marker).exists())
+            {
+                break;
+            }
+        }
+
+        None
+    }
+
+    /// Find and load the config file applying to the current directory.
+    ///
+    /// Returns `Ok(None)` if there is no config file. See [Self::find()].
+    pub fn discover() -> Result<Option<Self>, Error> {
+        let cwd = std::env::current_dir()?;
+
+        Self::find(cwd).map(Self::from_path).transpose()
+    }
+
+    /// Load the config designated by a [SDK_SEARCH_CONFIG_ENV] value.
+    ///
+    /// A file is loaded via [Self::from_path()], failing if it is invalid. For a
+    /// directory, the config file is found via [Self::find()]. A discovered file
+    /// that can't be loaded is ignored, so a broken config in an ancestor directory
+    /// doesn't fail searches.
+    pub(crate) fn from_env_value(path: &Path) -> Result<Option<Self>, Error> {
+        if !path.is_dir() {
+            return Self::from_path(path).map(Some);
+        }
+
+        let Some(path) = Self::find(path) else {
+            return Ok(None);
+        };
+
+        match Self::from_path(&path) {
+            Ok(config) => Ok(Some(config)),
+            Err(e) => {
+                warn!("ignoring SDK search config {}: {e}", path.display());
+
+                Ok(None)
+            }
+        }
+    }
+
+    /// Apply this config to a search.
+    ///
+    /// Each field that is set is passed to the corresponding [SdkSearch] method.
+    /// Platforms are added to the search's existing platforms.
+    pub fn apply(&self, mut search: SdkSearch) -> SdkSearch {
+        if let Some(locations) = &self.locations {
+            search = search.locations(locations.iter().map(SdkSearchLocation::from));
+        }
+        if let Some(enabled) = self.user_applications {
+            search = search.user_applications(enabled);
+        }
+
+        for platform in &self.platforms {
+            search = search.add_platform(platform.clone());
+        }
+
+        if let Some(platforms) = &self.platform_fallbacks {
+            search = search.platform_fallbacks(platforms.iter().cloned());
+        }
+        if let Some(version) = &self.minimum_version {
+            search = search.minimum_version(version.clone());
+        }
+        if let Some(version) = &self.maximum_version {
+            search = search.maximum_version(version.clone());
+        }
+        if let Some(req) = &self.version_req {
+            search = search.version_req(req.clone());
+        }
+        if let Some(target) = &self.deployment_target {
+            search = search.deployment_target(&target.target, target.version.clone());
+        }
+        if let Some(enabled) = self.deployment_target_from_env {
+            search = search.deployment_target_from_env(enabled);
+        }
+        if let Some(sorting) = self.sorting {
+            search = search.sorting(sorting);
+        }
+        if let Some(preference) = self.beta_preference {
+            search = search.beta_preference(preference);
+        }
+        if let Some(version) = &self.minimum_xcode_version {
+            search = search.minimum_xcode_version(version.clone());
+        }
+        if let Some(enabled) = self.deduplicate {
+            search = search.deduplicate(enabled);
+        }
+        if let Some(enabled) = self.prefer_versioned {
+            search = search.prefer_versioned(enabled);
+        }
+        if let Some(enabled) = self.exclude_symlinks {
+            search = search.exclude_symlinks(enabled);
+        }
+        if let Some(enabled) = self.extra_sdks_dirs_from_env {
+            search = search.extra_sdks_dirs_from_env(enabled);
+        }
+        if let Some(limit) = self.limit {
+            search = search.limit(limit);
+        }
+
+        search
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG_JSON: &str = r#"{
+  "locations": ["sdkroot-env", "system-xcode", { "sdks": "vendor/sdks" }],
+  "platforms": ["macosx"],
+  "minimum-version": "13.0",
+  "version-req": ">=13, <15",
+  "deployment-target": { "target": "macosx", "version": "11.0" },
+  "sorting": "version-descending",
+  "beta-preference": "prefer-release",
+  "deduplicate": true,
+  "prefer-versioned": true
+}"#;
+
+    #[test]
+    fn parse_json() -> Result<(), Error> {
+        let config = SdkSearchConfig::from_json_slice(CONFIG_JSON.as_bytes())?;
+
+        assert_eq!(
+            config.locations,
+            Some(vec![
+                SdkSearchConfigLocation::SdkRootEnv,
+                SdkSearchConfigLocation::SystemXcode,
+                SdkSearchConfigLocation::Sdks("vendor/sdks".into()),
+            ])
+        );
+        assert_eq!(config.platforms, vec![Platform::MacOsX]);
+        assert_eq!(config.minimum_version, Some(SdkVersion::from("13.0")));
+        assert_eq!(config.version_req, Some(">=13, <15".parse()?));
+        assert_eq!(config.sorting, Some(SdkSorting::VersionDescending));
+        assert_eq!(
+            config.beta_preference,
+            Some(XcodeBetaPreference::PreferRelease)
+        );
+        assert_eq!(config.prefer_versioned, Some(true));
+        assert_eq!(config.exclude_symlinks, None);
+
+        assert!(SdkSearchConfig::from_json_slice(br#"{"platform": "macosx"}"#).is_err());
+
+        let value = serde_json::to_string(&config)?;
+        assert_eq!(SdkSearchConfig::from_json_slice(value.as_bytes())?, config);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn parse_toml() -> Result<(), Error> {
+        let config = SdkSearchConfig::from_toml_str(
+            r#"
+locations = ["developer-dir-env", { osxcross = "/opt/osxcross" }]
+platforms = ["iphoneos", "iphonesimulator"]
+exclude-symlinks = true
+"#,
+        )?;
+
+        assert_eq!(
+            config.locations,
+            Some(vec![
+                SdkSearchConfigLocation::DeveloperDirEnv,
+                SdkSearchConfigLocation::Osxcross("/opt/osxcross".into()),
+            ])
+        );
+        assert_eq!(
+            config.platforms,
+            vec![Platform::IPhoneOs, Platform::IPhoneSimulator]
+        );
+        assert_eq!(config.exclude_symlinks, Some(true));
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_and_load() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let nested = td.join("a").join("b");
+        std::fs::create_dir_all(&nested)?;
+
+        let path = td.join(SDK_SEARCH_CONFIG_JSON_FILE_NAME);
+        std::fs::write(&path, CONFIG_JSON)?;
+
+        assert_eq!(SdkSearchConfig::find(&nested), Some(path.clone()));
+
+        // Discovery stops at the repository root.
+        std::fs::create_dir(td.join("a").join(".git"))?;
+        assert_eq!(SdkSearchConfig::find(&nested), None);
+        std::fs::remove_dir(td.join("a").join(".git"))?;
+
+        let config = SdkSearchConfig::from_path(&path)?;
+        assert_eq!(
+            config.locations.as_ref().unwrap()[2],
+            SdkSearchConfigLocation::Sdks(td.join("vendor/sdks"))
+        );
+
+        let search = config.apply(SdkSearch::default());
+        assert_eq!(
+            search.search_locations(),
+            &[
+                SdkSearchLocation::SdkRootEnv,
+                SdkSearchLocation::SystemXcode,
+                SdkSearchLocation::Sdks(td.join("vendor/sdks")),
+            ]
+        );
+
+        Ok(())
+    }
+    #[test]
+    fn from_env() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path().to_path_buf();
+        let path = td.join(SDK_SEARCH_CONFIG_JSON_FILE_NAME);
+        std::fs::write(&path, CONFIG_JSON)?;
+
+        let from_env = |value: Option<PathBuf>| {
+            SdkSearch::from_env_with_lookup(move |name| {
+                value
+                    .clone()
+                    .filter(|_| name == SDK_SEARCH_CONFIG_ENV)
+                    .map(|path| path.into_os_string())
+            })
+        };
+
+        // Config files only apply when opted into.
+        assert_eq!(
+            from_env(None)?.search_locations(),
+            SdkSearchLocation::default_locations()
+        );
+
+        for value in [td.clone(), path.clone()] {
+            assert_eq!(
+                from_env(Some(value))?.search_locations()[2],
+                SdkSearchLocation::Sdks(td.join("vendor/sdks"))
+            );
+        }
+
+        // Malformed discovered configs are ignored. Malformed explicit ones fail.
+        std::fs::write(&path, b"{")?;
+        assert_eq!(
+            from_env(Some(td.clone()))?.search_locations(),
+            SdkSearchLocation::default_locations()
+        );
+        assert!(from_env(Some(path)).is_err());
+
+        Ok(())
+    }
+}
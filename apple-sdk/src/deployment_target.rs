@@ -0,0 +1,381 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Deployment targets.
+
+use {
+    crate::{AppleSdk, Error, Platform, SdkVersion},
+    std::{
+        cmp::Ordering,
+        ffi::OsString,
+        fmt::{Display, Formatter},
+    },
+};
+
+/// The minimum OS version a binary is built to run on.
+///
+/// Deployment targets are distinct from SDK versions. An SDK version describes
+/// the OS release headers and libraries came from. A deployment target describes
+/// the oldest OS release the built binary supports. An SDK can typically target a
+/// range of OS versions older than itself.
+///
+/// Deployment targets are associated with a [Platform] and must be dot delimited
+/// integers with at most 3 components. e.g. `11`, `10.15`, or `14.5.1`.
+///
+/// Deployment targets are only ordered relative to other deployment targets of
+/// the same platform. Comparing targets of different platforms yields [None].
+/// Equality agrees with this ordering. So `14` and `14.0` are equal.
+#[derive(Clone, Debug)]
+pub struct DeploymentTarget {
+    platform: Platform,
+    version: SdkVersion,
+}
+
+impl DeploymentTarget {
+    /// Construct an instance from a platform and version.
+    ///
+    /// Errors with [Error::VersionParse] if the version isn't a valid deployment
+    /// target version.
+    pub fn new(platform: Platform, version: impl Into<SdkVersion>) -> Result<Self, Error> {
+        let version = version.into();
+
+        if version.components().len() > 3 {
+            return Err(Error::VersionParse(version.to_string()));
+        }
+
+        // Validates the version consists of integer components.
+        version.normalized_version()?;
+
+        Ok(Self { platform, version })
+    }
+
+    /// Resolve the deployment target for a platform from its environment variable.
+    ///
+    /// The environment variable is [Platform::deployment_target_environment_variable()].
+    ///
+    /// Returns [None] if the variable isn't set. Errors if the value isn't a valid
+    /// deployment target version.
+    pub fn from_env(platform: Platform) -> Result<Option<Self>, Error> {
+        Self::from_env_with_lookup(platform, |name| std::env::var_os(name))
+    }
+
+    /// Resolve the deployment target for a platform from an environment lookup.
+    ///
+    /// This is [Self::from_env()] with variables read through `lookup` instead of
+    /// the process environment. `lookup` receives a variable name and returns its
+    /// value, if set.
+    pub fn from_env_with_lookup(
+        platform: Platform,
+        lookup: impl Fn(&str) -> Option<OsString>,
+    ) -> Result<Option<Self>, Error> {
+        match platform.deployment_target_from_lookup(&lookup) {
+            Some(version) => Ok(Some(Self::new(platform, version)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The platform being targeted.
+    pub fn platform(&self) -> &Platform {
+        &self.platform
+    }
+
+    /// The minimum OS version being targeted.
+    pub fn version(&self) -> &SdkVersion {
+        &self.version
+    }
+
+    /// The name of the target in SDK settings files.
+    ///
+    /// This is the key in `SupportedTargets`. e.g. `macosx` or `iphonesimulator`.
+    pub fn target_name(&self) -> String {
+        self.platform.filesystem_name().to_ascii_lowercase()
+    }
+
+    /// The environment variable used to communicate this deployment target to build tools.
+    ///
+    /// e.g. `MACOSX_DEPLOYMENT_TARGET`.
+    pub fn environment_variable(&self) -> Option<&'static str> {
+        self.platform.deployment_target_environment_variable()
+    }
+
+    /// Obtain an LLVM target triple for an architecture targeting this deployment target.
+    ///
+    /// See [Platform::llvm_target_triple()].
+    pub fn llvm_target_triple(&self, arch: &str) -> Option<String> {
+        self.platform.llvm_target_triple(arch, &self.version)
+    }
+
+    /// Obtain an LLVM target triple for Mac Catalyst targeting this deployment target.
+    ///
+    /// Mac Catalyst deployment targets are iOS versions. e.g. an iOS 15.0 target
+    /// yields `x86_64-apple-ios15.0-macabi` for `x86_64`. Binaries for these triples
+    /// are built against the macOS SDK.
+    ///
+    /// Returns [None] if this isn't an iOS deployment target.
+    pub fn mac_catalyst_llvm_target_triple(&self, arch: &str) -> Option<String> {
+        if self.platform == Platform::IPhoneOs {
+            Some(format!("{arch}-apple-ios{}-macabi", self.version))
+        } else {
+            None
+        }
+    }
+
+    /// Whether an SDK supports this deployment target.
+    ///
+    /// This is a convenience method for [AppleSdk::supports_deployment_target()]
+    /// using [Self::target_name()]. SDKs of a different platform never support
+    /// the target.
+    pub fn supported_by<SDK: AppleSdk>(&self, sdk: &SDK) -> Result<bool, Error> {
+        if sdk.platform() != &self.platform {
+            return Ok(false);
+        }
+
+        sdk.supports_deployment_target(&self.target_name(), &self.version)
+    }
+
+    /// Obtain the DriverKit deployment target equivalent to this macOS one.
+    ///
+    /// DriverKit versions don't track macOS versions. DriverKit 19.0 shipped with
+    /// macOS 10.15 and DriverKit minor versions often differ from macOS ones. e.g.
+    /// macOS 13.3 is DriverKit 22.4. The conversion uses a table of known releases.
+    /// A macOS version between releases maps to the DriverKit version of the
+    /// preceding release of the same major version. Patch versions are ignored.
+    ///
+    /// Returns [None] if this isn't a macOS target or if it is a macOS version
+    /// outside the table.
+    pub fn to_driverkit(&self) -> Option<Self> {
+        if self.platform != Platform::MacOsX {
+            return None;
+        }
+
+        let (_, driverkit) = find_driverkit_release(&self.version, |(macos, _)| *macos)?;
+
+        Some(Self {
+            platform: Platform::DriverKit,
+            version: driverkit.into(),
+        })
+    }
+
+    /// Obtain the macOS deployment target equivalent to this DriverKit one.
+    ///
+    /// This is the inverse of [Self::to_driverkit()].
+    ///
+    /// Returns [None] if this isn't a DriverKit target or if it is a DriverKit
+    /// version outside the table of known releases.
+    pub fn to_macos(&self) -> Option<Self> {
+        if self.platform != Platform::DriverKit {
+            return None;
+        }
+
+        let (macos, _) = find_driverkit_release(&self.version, |(_, driverkit)| *driverkit)?;
+
+        Some(Self {
+            platform: Platform::MacOsX,
+            version: macos.into(),
+        })
+    }
+}
+
+/// macOS releases and the DriverKit release shipped alongside them.
+const DRIVERKIT_RELEASES: &[(&str, &str)] = &[
+    ("10.15", "19.0"),
+    ("11.0", "20.0"),
+    ("11.1", "20.2"),
+    ("11.3", "20.4"),
+    ("12.0", "21.0"),
+    ("12.1", "21.2"),
+    ("12.3", "21.4"),
+    ("13.0", "22.0"),
+    ("13.1", "22.2"),
+    ("13.3", "22.4"),
+    ("14.0", "23.0"),
+    ("14.2", "23.2"),
+    ("14.4", "23.4"),
+    ("14.5", "23.5"),
+    ("15.0", "24.0"),
+    ("15.2", "24.2"),
+    ("15.4", "24.4"),
+    ("15.5", "24.5"),
+];
+
+/// Find the newest entry in [DRIVERKIT_RELEASES] not newer than a version.
+///
+/// `key` selects the version of an entry to compare against. Only entries with the
+/// same major version (the first 2 components for macOS 10) are considered.
+fn find_driverkit_release(
+    version: &SdkVersion,
+    key: impl Fn(&(&'static str, &'static str)) -> &'static str,
+) -> Option<(&'static str, &'static str)> {
+    let components = version.components();
+    let major_len = if components.first() == Some(&10) {
+        2
+    } else {
+        1
+    };
+
+    DRIVERKIT_RELEASES
+        .iter()
+        .filter(|release| {
+            let release_version = SdkVersion::from(key(release));
+            let release_components = release_version.components();
+
+            components.get(..major_len) == release_components.get(..major_len)
+                && &release_version <= version
+        })
+        .last()
+        .copied()
+}
+
+impl Display for DeploymentTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}{}", self.target_name(), self.version))
+    }
+}
+
+impl PartialEq for DeploymentTarget {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl Eq for DeploymentTarget {}
+
+impl PartialOrd for DeploymentTarget {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.platform == other.platform {
+            Some(self.version.cmp(&other.version))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn validation() -> Result<(), Error> {
+        for v in ["11", "10.15", "14.5.1"] {
+            DeploymentTarget::new(Platform::MacOsX, v)?;
+        }
+
+        for v in ["", "foo", "13.0b1", "10.15.7.1", "300.0"] {
+            assert!(matches!(
+                DeploymentTarget::new(Platform::MacOsX, v),
+                Err(Error::VersionParse(_))
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn ordering() -> Result<(), Error> {
+        let a = DeploymentTarget::new(Platform::MacOsX, "10.15")?;
+        let b = DeploymentTarget::new(Platform::MacOsX, "11.0")?;
+        let c = DeploymentTarget::new(Platform::IPhoneOs, "11.0")?;
+
+        assert!(a < b);
+        assert_eq!(b.partial_cmp(&c), None);
+        assert_ne!(b, c);
+
+        let d = DeploymentTarget::new(Platform::MacOsX, "11")?;
+        assert_eq!(b.partial_cmp(&d), Some(Ordering::Equal));
+        assert_eq!(b, d);
+
+        Ok(())
+    }
+
+    #[test]
+    fn driverkit() -> Result<(), Error> {
+        for (macos, driverkit) in [
+            ("10.15", "19.0"),
+            ("10.15.4", "19.0"),
+            ("11", "20.0"),
+            ("11.2", "20.2"),
+            ("12.3", "21.4"),
+            ("13.3", "22.4"),
+            ("13.3.1", "22.4"),
+            ("14.2", "23.2"),
+            ("15.0", "24.0"),
+        ] {
+            assert_eq!(
+                DeploymentTarget::new(Platform::MacOsX, macos)?.to_driverkit(),
+                Some(DeploymentTarget::new(Platform::DriverKit, driverkit)?),
+                "macOS {macos}"
+            );
+        }
+
+        for (driverkit, macos) in [
+            ("19.0", "10.15"),
+            ("20.4", "11.3"),
+            ("22.4", "13.3"),
+            ("23.2", "14.2"),
+            ("23.3", "14.2"),
+        ] {
+            assert_eq!(
+                DeploymentTarget::new(Platform::DriverKit, driverkit)?.to_macos(),
+                Some(DeploymentTarget::new(Platform::MacOsX, macos)?),
+                "DriverKit {driverkit}"
+            );
+        }
+
+        for macos in ["10.14", "10.16", "9.0", "16.0", "99.0"] {
+            assert_eq!(
+                DeploymentTarget::new(Platform::MacOsX, macos)?.to_driverkit(),
+                None,
+                "macOS {macos}"
+            );
+        }
+
+        for driverkit in ["18.0", "25.0"] {
+            assert_eq!(
+                DeploymentTarget::new(Platform::DriverKit, driverkit)?.to_macos(),
+                None,
+                "DriverKit {driverkit}"
+            );
+        }
+
+        assert_eq!(
+            DeploymentTarget::new(Platform::IPhoneOs, "15.0")?.to_driverkit(),
+            None
+        );
+        assert_eq!(
+            DeploymentTarget::new(Platform::MacOsX, "14.0")?.to_macos(),
+            None
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn metadata() -> Result<(), Error> {
+        let target = DeploymentTarget::new(Platform::IPhoneSimulator, "15.0")?;
+
+        assert_eq!(target.target_name(), "iphonesimulator");
+        assert_eq!(target.to_string(), "iphonesimulator15.0");
+        assert_eq!(
+            target.environment_variable(),
+            Some("IPHONEOS_DEPLOYMENT_TARGET")
+        );
+        assert_eq!(
+            target.llvm_target_triple("arm64").as_deref(),
+            Some("arm64-apple-ios15.0-simulator")
+        );
+        assert_eq!(target.mac_catalyst_llvm_target_triple("arm64"), None);
+        assert_eq!(
+            DeploymentTarget::new(Platform::IPhoneOs, "15.0")?
+                .mac_catalyst_llvm_target_triple("x86_64")
+                .as_deref(),
+            Some("x86_64-apple-ios15.0-macabi")
+        );
+
+        Ok(())
+    }
+}
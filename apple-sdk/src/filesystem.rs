@@ -0,0 +1,408 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Filesystem access used by SDK discovery.
+//!
+//! SDK discovery reads directories and files through the [Filesystem] trait. This
+//! allows searches to run against an in-memory tree, which makes search behavior
+//! testable on any host.
+
+use std::{
+    collections::BTreeMap,
+    io::{Error as IoError, ErrorKind},
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Metadata about a filesystem entry.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FileMetadata {
+    /// Whether the entry is a directory.
+    pub is_dir: bool,
+
+    /// Whether the entry is a symlink.
+    ///
+    /// Only ever true when obtained via [Filesystem::symlink_metadata()].
+    pub is_symlink: bool,
+
+    /// The last modification time of the entry, if known.
+    pub modified: Option<SystemTime>,
+
+    /// The size of the entry in bytes.
+    pub len: u64,
+
+    /// The inode number of the entry, if known.
+    ///
+    /// Only available on Unix.
+    pub inode: Option<u64>,
+}
+
+/// Filesystem operations needed to discover SDKs.
+pub trait Filesystem: Send + Sync {
+    /// Obtain the paths of entries in a directory.
+    ///
+    /// Returned paths are the directory path joined with the entry name.
+    fn read_dir(&self, path: &Path) -> std::io::Result<Vec<PathBuf>>;
+
+    /// Obtain metadata for a path, following symlinks.
+    fn metadata(&self, path: &Path) -> std::io::Result<FileMetadata>;
+
+    /// Obtain metadata for a path without following symlinks.
+    fn symlink_metadata(&self, path: &Path) -> std::io::Result<FileMetadata>;
+
+    /// Read the content of a file.
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>>;
+
+    /// Resolve a path to an absolute path with all symlinks resolved.
+    ///
+    /// Errors if the path doesn't exist.
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf>;
+
+    /// Whether a path exists, following symlinks.
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+
+    /// Whether a path is a directory, following symlinks.
+    fn is_dir(&self, path: &Path) -> bool {
+        self.metadata(path).map(|m| m.is_dir).unwrap_or(false)
+    }
+}
+
+/// A [Filesystem] backed by the real filesystem.
+///
+/// This is the default filesystem used by SDK discovery.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealFilesystem;
+
+impl RealFilesystem {
+    fn convert_metadata(metadata: std::fs::Metadata) -> FileMetadata {
+        #[cfg(unix)]
+        let inode = Some(std::os::unix::fs::MetadataExt::ino(&metadata));
+        #[cfg(not(unix))]
+        let inode = None;
+
+        FileMetadata {
+            is_dir: metadata.is_dir(),
+            is_symlink: metadata.file_type().is_symlink(),
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            inode,
+        }
+    }
+}
+
+impl Filesystem for RealFilesystem {
+    fn read_dir(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
+    fn metadata(&self, path: &Path) -> std::io::Result<FileMetadata> {
+        std::fs::metadata(path).map(Self::convert_metadata)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> std::io::Result<FileMetadata> {
+        std::fs::symlink_metadata(path).map(Self::convert_metadata)
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        std::fs::canonicalize(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+}
+
+#[derive(Clone, Debug)]
+enum MemoryEntry {
+    Directory,
+    File(Vec<u8>),
+    Symlink(PathBuf),
+}
+
+/// A [Filesystem] holding a tree in memory.
+///
+/// Paths should be absolute. Parent directories are created implicitly.
+/// Directory entries are returned in sorted order. Entries have no modification
+/// time.
+///
+/// ```
+/// use apple_sdk::{MemoryFilesystem, Platform, SdkSearch, SdkSearchLocation, SimpleSdk};
+///
+/// let fs = MemoryFilesystem::new()
+///     .file("/sdks/MacOSX14.2.sdk/SDKSettings.json", "{}")
+///     .symlink("/sdks/MacOSX.sdk", "MacOSX14.2.sdk");
+///
+/// let sdks = SdkSearch::empty()
+///     .filesystem(fs)
+///     .location(SdkSearchLocation::Sdks("/sdks".into()))
+///     .search::<SimpleSdk>()
+///     .unwrap();
+/// assert_eq!(sdks.len(), 2);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MemoryFilesystem {
+    entries: BTreeMap<PathBuf, MemoryEntry>,
+}
+
+impl MemoryFilesystem {
+    /// The maximum number of symlinks followed when resolving a path.
+    const MAX_SYMLINKS: usize = 40;
+
+    /// Construct an empty instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a directory.
+    pub fn directory(mut self, path: impl AsRef<Path>) -> Self {
+        self.insert(path.as_ref(), MemoryEntry::Directory);
+        self
+    }
+
+    /// Add a file with the given content.
+    pub fn file(mut self, path: impl AsRef<Path>, data: impl AsRef<[u8]>) -> Self {
+        self.insert(path.as_ref(), MemoryEntry::File(data.as_ref().to_vec()));
+        self
+    }
+
+    /// Add a symlink.
+    ///
+    /// Relative targets are resolved against the directory holding the symlink.
+    pub fn symlink(mut self, path: impl AsRef<Path>, target: impl AsRef<Path>) -> Self {
+        self.insert(
+            path.as_ref(),
+            MemoryEntry::Symlink(target.as_ref().to_path_buf()),
+        );
+        self
+    }
+
+    /// Add an SDK directory holding an empty `SDKSettings.json`.
+    #[cfg(test)]
+    pub(crate) fn sdk(self, path: impl AsRef<Path>) -> Self {
+        self.file(path.as_ref().join("SDKSettings.json"), "{}")
+    }
+
+    fn insert(&mut self, path: &Path, entry: MemoryEntry) {
+        let path = normalize(path);
+
+        for parent in path.ancestors().skip(1) {
+            self.entries
+                .entry(parent.to_path_buf())
+                .or_insert(MemoryEntry::Directory);
+        }
+
+        self.entries.insert(path, entry);
+    }
+
+    /// Resolve symlinks in a path.
+    ///
+    /// Symlinks in the final component are only resolved if `follow` is true.
+    fn resolve(&self, path: &Path, follow: bool) -> std::io::Result<PathBuf> {
+        let mut pending = normalize(path);
+        let mut followed = 0;
+
+        'restart: loop {
+            let mut resolved = PathBuf::new();
+            let components = pending.components().collect::<Vec<_>>();
+
+            for (i, component) in components.iter().enumerate() {
+                resolved.push(component);
+                let last = i == components.len() - 1;
+
+                if let Some(MemoryEntry::Symlink(target)) = self.entries.get(&resolved) {
+                    if last && !follow {
+                        break;
+                    }
+
+                    followed += 1;
+                    if followed > Self::MAX_SYMLINKS {
+                        return Err(IoError::new(ErrorKind::InvalidInput, "too many symlinks"));
+                    }
+
+                    let mut next = resolved
+                        .parent()
+                        .map(|p| p.join(target))
+                        .unwrap_or_else(|| target.clone());
+                    for rest in &components[i + 1..] {
+                        next.push(rest);
+                    }
+
+                    pending = normalize(&next);
+                    continue 'restart;
+                }
+            }
+
+            return Ok(resolved);
+        }
+    }
+
+    fn get(&self, path: &Path, follow: bool) -> std::io::Result<(&MemoryEntry, bool)> {
+        let resolved = self.resolve(path, follow)?;
+
+        self.entries
+            .get(&resolved)
+            .map(|entry| (entry, !follow && matches!(entry, MemoryEntry::Symlink(_))))
+            .ok_or_else(|| IoError::new(ErrorKind::NotFound, path.display().to_string()))
+    }
+
+    fn entry_metadata(&self, path: &Path, follow: bool) -> std::io::Result<FileMetadata> {
+        let (entry, is_symlink) = self.get(path, follow)?;
+
+        Ok(FileMetadata {
+            is_dir: matches!(entry, MemoryEntry::Directory),
+            is_symlink,
+            modified: None,
+            len: match entry {
+                MemoryEntry::File(data) => data.len() as u64,
+                _ => 0,
+            },
+            inode: None,
+        })
+    }
+}
+
+impl Filesystem for MemoryFilesystem {
+    fn read_dir(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        let resolved = self.resolve(path, true)?;
+
+        match self.entries.get(&resolved) {
+            Some(MemoryEntry::Directory) => Ok(self
+                .entries
+                .keys()
+                .filter(|p| p.parent() == Some(resolved.as_path()))
+                .filter_map(|p| p.file_name())
+                .map(|name| path.join(name))
+                .collect()),
+            Some(_) => Err(IoError::new(
+                ErrorKind::InvalidInput,
+                format!("not a directory: {}", path.display()),
+            )),
+            None => Err(IoError::new(
+                ErrorKind::NotFound,
+                path.display().to_string(),
+            )),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> std::io::Result<FileMetadata> {
+        self.entry_metadata(path, true)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> std::io::Result<FileMetadata> {
+        self.entry_metadata(path, false)
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        match self.get(path, true)?.0 {
+            MemoryEntry::File(data) => Ok(data.clone()),
+            _ => Err(IoError::new(
+                ErrorKind::InvalidInput,
+                format!("not a file: {}", path.display()),
+            )),
+        }
+    }
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        let resolved = self.resolve(path, true)?;
+
+        if self.entries.contains_key(&resolved) {
+            Ok(resolved)
+        } else {
+            Err(IoError::new(
+                ErrorKind::NotFound,
+                path.display().to_string(),
+            ))
+        }
+    }
+}
+
+/// Remove `.` and `..` components from a path without consulting a filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut res = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                res.pop();
+            }
+            c => res.push(c),
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn memory_filesystem() -> std::io::Result<()> {
+        let fs = MemoryFilesystem::new()
+            .file("/root/a/file.txt", "hello")
+            .directory("/root/b")
+            .symlink("/root/link", "a")
+            .symlink("/root/absolute", "/root/b")
+            .symlink("/root/loop", "loop");
+
+        assert_eq!(
+            fs.read_dir(Path::new("/root"))?,
+            ["a", "absolute", "b", "link", "loop"]
+                .iter()
+                .map(|name| PathBuf::from("/root").join(name))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            fs.read_dir(Path::new("/root/link"))?,
+            vec![PathBuf::from("/root/link/file.txt")]
+        );
+        assert_eq!(fs.read(Path::new("/root/link/file.txt"))?, b"hello");
+        assert_eq!(fs.read(Path::new("/root/b/../a/./file.txt"))?, b"hello");
+
+        assert!(fs.is_dir(Path::new("/root/link")));
+        assert!(fs.is_dir(Path::new("/root/absolute")));
+        assert!(!fs.is_dir(Path::new("/root/a/file.txt")));
+        assert!(fs.symlink_metadata(Path::new("/root/link"))?.is_symlink);
+        assert!(!fs.metadata(Path::new("/root/link"))?.is_symlink);
+        assert!(!fs.exists(Path::new("/root/missing")));
+        assert!(!fs.exists(Path::new("/root/loop")));
+
+        assert_eq!(
+            fs.canonicalize(Path::new("/root/link/file.txt"))?,
+            PathBuf::from("/root/a/file.txt")
+        );
+        assert_eq!(
+            fs.canonicalize(Path::new("/root/absolute/../link"))?,
+            PathBuf::from("/root/a")
+        );
+        assert!(fs.canonicalize(Path::new("/root/missing")).is_err());
+        assert!(fs.canonicalize(Path::new("/root/loop")).is_err());
+
+        assert_eq!(
+            fs.read_dir(Path::new("/missing")).unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+        assert!(fs.read_dir(Path::new("/root/a/file.txt")).is_err());
+        assert!(fs.read(Path::new("/root/a")).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,353 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Fake developer directories for testing.
+//!
+//! This allows testing SDK selection logic without an Xcode install. The
+//! created directories are recognized by [DeveloperDirectory], [SdkSearch](crate::SdkSearch),
+//! [SimpleSdk](crate::SimpleSdk), and [ParsedSdk](crate::ParsedSdk).
+
+use {
+    crate::{
+        DeveloperDirectory, Error, Platform, SdkSettingsJson, SdkSettingsJsonDefaultProperties,
+        SdkVersion, SupportedTarget,
+    },
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    },
+};
+
+/// An SDK to create in a [DeveloperDirectoryFixture].
+#[derive(Clone, Debug)]
+pub struct FixtureSdk {
+    /// The platform of the SDK.
+    pub platform: Platform,
+
+    /// The version of the SDK.
+    pub version: SdkVersion,
+
+    /// The lowest OS version the SDK can target.
+    pub minimum_deployment_target: SdkVersion,
+
+    /// The architectures the SDK can target.
+    pub archs: Vec<String>,
+}
+
+impl FixtureSdk {
+    /// Construct an instance for a platform and version.
+    ///
+    /// The SDK can target its own version only and supports `arm64` and `x86_64`.
+    pub fn new(platform: Platform, version: impl Into<SdkVersion>) -> Self {
+        let version = version.into();
+
+        Self {
+            platform,
+            minimum_deployment_target: version.clone(),
+            version,
+            archs: vec!["arm64".to_string(), "x86_64".to_string()],
+        }
+    }
+
+    /// Set the lowest OS version the SDK can target.
+    pub fn minimum_deployment_target(mut self, version: impl Into<SdkVersion>) -> Self {
+        self.minimum_deployment_target = version.into();
+        self
+    }
+
+    /// Set the architectures the SDK can target.
+    pub fn archs(mut self, archs: impl IntoIterator<Item = impl ToString>) -> Self {
+        self.archs = archs.into_iter().map(|a| a.to_string()).collect();
+        self
+    }
+
+    /// The directory name of this SDK. e.g. `MacOSX14.2.sdk`.
+    pub fn directory_name(&self) -> String {
+        format!("{}{}.sdk", self.platform.filesystem_name(), self.version)
+    }
+
+    /// Obtain the `SDKSettings.json` content describing this SDK.
+    ///
+    /// This contains the keys required by [ParsedSdk](crate::ParsedSdk).
+    pub fn settings(&self) -> SdkSettingsJson {
+        let target_name = self.platform.filesystem_name().to_ascii_lowercase();
+        let version = self.version.to_string();
+        let maximum = format!("{version}.99");
+
+        let target = SupportedTarget {
+            archs: self.archs.clone(),
+            default_deployment_target: version.clone(),
+            default_variant: None,
+            llvm_target_triple_environment: None,
+            llvm_target_triple_sys: None,
+            llvm_target_triple_vendor: None,
+            deployment_target_setting_name: self
+                .platform
+                .deployment_target_environment_variable()
+                .map(|name| name.to_string()),
+            maximum_deployment_target: Some(maximum.clone()),
+            minimum_deployment_target: self.minimum_deployment_target.to_string(),
+            platform_family_name: None,
+            valid_deployment_targets: vec![],
+        };
+
+        SdkSettingsJson {
+            canonical_name: format!("{target_name}{version}"),
+            default_deployment_target: version.clone(),
+            default_properties: SdkSettingsJsonDefaultProperties {
+                platform_name: target_name.clone(),
+                default_compiler: None,
+                deployment_target_setting_name: None,
+                deployment_target_suggested_values: vec![],
+                other: HashMap::new(),
+            },
+            default_variant: None,
+            display_name: format!("{} {version}", self.platform.display_name()),
+            maximum_deployment_target: maximum,
+            minimal_display_name: version.clone(),
+            supported_targets: HashMap::from([(target_name, target)]),
+            property_condition_fallback_names: vec![],
+            toolchains: vec![],
+            variants: vec![],
+            version,
+        }
+    }
+
+    /// Create this SDK in a directory holding SDKs.
+    ///
+    /// The SDK directory is named after [Self::directory_name()] and receives an
+    /// `SDKSettings.json` file. Existing files are overwritten.
+    ///
+    /// Returns the path of the SDK directory.
+    pub fn write_to_directory(&self, dir: &Path) -> Result<PathBuf, Error> {
+        let sdk_path = dir.join(self.directory_name());
+
+        std::fs::create_dir_all(&sdk_path).map_err(Error::io_path(&sdk_path))?;
+        self.settings().write_to_sdk_directory(&sdk_path)?;
+
+        Ok(sdk_path)
+    }
+}
+
+/// A builder of fake developer directories.
+///
+/// The created tree mirrors an Xcode install:
+/// `<root>/Platforms/<platform>.platform/Developer/SDKs/<platform><version>.sdk`,
+/// where each SDK has an `SDKSettings.json` file.
+///
+/// Requires the `test-support` crate feature.
+///
+/// ```
+/// use apple_sdk::{DeveloperDirectoryFixture, FixtureSdk, Platform, SdkSearch, SdkSearchLocation, SimpleSdk};
+///
+/// let root = tempfile::tempdir().unwrap();
+///
+/// let developer_dir = DeveloperDirectoryFixture::new(root.path())
+///     .sdk(FixtureSdk::new(Platform::MacOsX, "14.2"))
+///     .sdk(FixtureSdk::new(Platform::IPhoneOs, "17.2"))
+///     .build()
+///     .unwrap();
+///
+/// let sdks = SdkSearch::empty()
+///     .location(SdkSearchLocation::Developer(developer_dir))
+///     .platform(Platform::MacOsX)
+///     .search::<SimpleSdk>()
+///     .unwrap();
+/// assert_eq!(sdks.len(), 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DeveloperDirectoryFixture {
+    root: PathBuf,
+    sdks: Vec<FixtureSdk>,
+    unversioned_symlinks: bool,
+}
+
+impl DeveloperDirectoryFixture {
+    /// Construct an instance creating a developer directory at the given path.
+    ///
+    /// The directory is created by [Self::build()]. It is never deleted by this
+    /// type. Callers typically pass a temporary directory.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            sdks: vec![],
+            unversioned_symlinks: false,
+        }
+    }
+
+    /// Add an SDK to create.
+    pub fn sdk(mut self, sdk: FixtureSdk) -> Self {
+        self.sdks.push(sdk);
+        self
+    }
+
+    /// Whether to create unversioned symlinks to the newest SDK of each platform.
+    ///
+    /// e.g. `MacOSX.sdk` -> `MacOSX14.2.sdk`, as Xcode does. Only supported on
+    /// Unix.
+    ///
+    /// Default is `false`.
+    pub fn unversioned_symlinks(mut self, enabled: bool) -> Self {
+        self.unversioned_symlinks = enabled;
+        self
+    }
+
+    /// Create the developer directory on the filesystem.
+    ///
+    /// Existing files are overwritten.
+    pub fn build(&self) -> Result<DeveloperDirectory, Error> {
+        for sdk in &self.sdks {
+            sdk.write_to_directory(&self.sdks_directory(&sdk.platform))?;
+        }
+
+        if self.unversioned_symlinks {
+            self.create_symlinks()?;
+        }
+
+        Ok(DeveloperDirectory::from(&self.root))
+    }
+
+    /// The path to the directory holding SDKs of a platform.
+    fn sdks_directory(&self, platform: &Platform) -> PathBuf {
+        self.root
+            .join("Platforms")
+            .join(format!("{}.platform", platform.filesystem_name()))
+            .join("Developer")
+            .join("SDKs")
+    }
+
+    #[cfg(unix)]
+    fn create_symlinks(&self) -> Result<(), Error> {
+        let mut newest: Vec<&FixtureSdk> = vec![];
+
+        for sdk in &self.sdks {
+            match newest.iter_mut().find(|s| s.platform == sdk.platform) {
+                Some(existing) if existing.version < sdk.version => *existing = sdk,
+                Some(_) => {}
+                None => newest.push(sdk),
+            }
+        }
+
+        for sdk in newest {
+            let link = self
+                .sdks_directory(&sdk.platform)
+                .join(format!("{}.sdk", sdk.platform.filesystem_name()));
+
+            if link.symlink_metadata().is_ok() {
+                std::fs::remove_file(&link).map_err(Error::io_path(&link))?;
+            }
+
+            std::os::unix::fs::symlink(sdk.directory_name(), &link)
+                .map_err(Error::io_path(&link))?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn create_symlinks(&self) -> Result<(), Error> {
+        Err(Error::FunctionalityNotSupported(
+            "SDK symlinks on this platform",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{AppleSdk, SdkSearch, SdkSearchLocation, SimpleSdk},
+    };
+
+    #[test]
+    fn build() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+
+        let dir = DeveloperDirectoryFixture::new(td)
+            .sdk(FixtureSdk::new(Platform::MacOsX, "13.3"))
+            .sdk(FixtureSdk::new(Platform::MacOsX, "14.2").minimum_deployment_target("11.0"))
+            .sdk(FixtureSdk::new(Platform::IPhoneSimulator, "17.2"))
+            .build()?;
+
+        assert_eq!(dir.platforms()?.len(), 2);
+
+        let sdks = SdkSearch::empty()
+            .location(SdkSearchLocation::Developer(dir.clone()))
+            .platform(Platform::MacOsX)
+            .search::<SimpleSdk>()?;
+        assert_eq!(sdks.len(), 2);
+        assert!(sdks.iter().all(|sdk| !sdk.is_symlink()));
+
+        let sdks = SdkSearch::empty()
+            .location(SdkSearchLocation::Developer(dir))
+            .platform(Platform::MacOsX)
+            .deployment_target("macosx", "12.0")
+            .search::<crate::ParsedSdk>()?;
+        assert_eq!(sdks.len(), 1);
+        assert_eq!(sdks[0].name, "macosx14.2");
+        assert_eq!(sdks[0].display_name, "macOS 14.2");
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn build_unversioned_symlinks() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+
+        let dir = DeveloperDirectoryFixture::new(td)
+            .sdk(FixtureSdk::new(Platform::MacOsX, "13.3"))
+            .sdk(FixtureSdk::new(Platform::MacOsX, "14.2").minimum_deployment_target("11.0"))
+            .unversioned_symlinks(true)
+            .build()?;
+
+        let sdks = SdkSearch::empty()
+            .location(SdkSearchLocation::Developer(dir.clone()))
+            .platform(Platform::MacOsX)
+            .search::<SimpleSdk>()?;
+        assert_eq!(sdks.len(), 3);
+
+        let symlink = sdks.iter().find(|sdk| sdk.is_symlink()).unwrap();
+        assert_eq!(
+            std::fs::canonicalize(symlink.path())?,
+            std::fs::canonicalize(
+                td.join("Platforms/MacOSX.platform/Developer/SDKs/MacOSX14.2.sdk")
+            )?
+        );
+
+        let sdks = SdkSearch::empty()
+            .location(SdkSearchLocation::Developer(dir))
+            .platform(Platform::MacOsX)
+            .deployment_target("macosx", "12.0")
+            .exclude_symlinks(true)
+            .search::<crate::ParsedSdk>()?;
+        assert_eq!(sdks.len(), 1);
+        assert_eq!(sdks[0].name, "macosx14.2");
+
+        Ok(())
+    }
+
+    #[test]
+    fn settings_escaping() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+
+        let sdk_path = FixtureSdk::new(Platform::MacOsX, "14.2")
+            .archs(["arm\"64", "x86\\64"])
+            .write_to_directory(temp.path())?;
+
+        let sdk = crate::ParsedSdk::from_directory(&sdk_path)?;
+        assert_eq!(
+            sdk.supported_targets["macosx"].archs,
+            vec!["arm\"64".to_string(), "x86\\64".to_string()]
+        );
+
+        Ok(())
+    }
+}
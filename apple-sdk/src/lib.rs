@@ -37,6 +37,12 @@
 //! A directory containing an Apple platform is represented by the
 //! [PlatformDirectory] struct.
 //!
+//! # Apple Toolchains
+//!
+//! Toolchains (`*.xctoolchain` directories) hold compilers and other build tools.
+//! They are represented by the [AppleToolchain] struct and can be found via
+//! [DeveloperDirectory::toolchains()].
+//!
 //! # Apple SDKs
 //!
 //! We model Apple SDKs using the [SimpleSdk] and [ParsedSdk] types. The
@@ -62,6 +68,38 @@
 //! This functionality is exposed through the [SdkSearch] struct. See its
 //! documentation for more.
 //!
+//! Search parameters can also be defined by a repository via a `.apple-sdk.json`
+//! or `.apple-sdk.toml` file. See [SdkSearchConfig]. [SdkSearch::from_env()]
+//! honors these files when opted into via [SDK_SEARCH_CONFIG_ENV].
+//!
+//! SDKs don't need to live in Apple's filesystem layouts. When cross-compiling
+//! from non-Apple hosts, [SdkSearchLocation::Sdks] can point at a directory of
+//! `*.sdk` directories and [SdkSearchLocation::Osxcross] at an
+//! [osxcross](https://github.com/tpoechtrager/osxcross) install.
+//!
+//! # Crate Features
+//!
+//! * `parse` (default) - Enables parsing of SDK metadata files and the [ParsedSdk] type.
+//!   Also enables [SdkSearchLocation::Xcodebuild] for discovering SDKs via `xcodebuild`.
+//! * `cc` - Enables [AppleSdk::configure_cc_build()] to apply an SDK to a `cc::Build`.
+//! * `rayon` - Enables [SdkSearch::search_parallel()] to scan for SDKs in parallel.
+//! * `archive` - Enables [SdkArchive] for inspecting and extracting SDKs in `.tar.xz`,
+//!   `.tar.gz`, and `.zip` archives.
+//! * `xip` - Enables [XcodeXip] for enumerating the platforms and SDKs in an Xcode
+//!   `.xip` archive without installing it.
+//! * `semver` - Implements conversions between [SdkVersion] / [SdkVersionReq] and
+//!   `semver::Version` / `semver::VersionReq`.
+//! * `tbd` - Enables parsing of `.tbd` text stub libraries via [TbdFile]. Implies `parse`.
+//! * `toml` - Enables loading [SdkSearchConfig] from `.apple-sdk.toml` files. Implies
+//!   `parse`.
+//! * `test-support` - Enables [DeveloperDirectoryFixture] for creating fake developer
+//!   directories, so SDK selection logic can be tested without an Xcode install.
+//!   Implies `parse`.
+//! * `log` - Logs SDK search events and developer directory resolution via the
+//!   `log` crate.
+//! * `serde` (enabled by `parse`) - Implements `serde::Serialize` and `serde::Deserialize`
+//!   for platforms, versions, SDK paths, and SDK types.
+//!
 //! # Common Functionality
 //!
 //! To locate the default SDK to use, do something like this:
@@ -84,25 +122,128 @@
 //! }
 //! ```
 
+/// Log a warning via the `log` crate, if the `log` feature is enabled.
+///
+/// Without the feature, arguments are type checked but never evaluated.
+macro_rules! warn {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        log::warn!($($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+/// Log a debug message via the `log` crate, if the `log` feature is enabled.
+///
+/// See [warn!].
+macro_rules! debug {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        log::debug!($($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+mod architecture;
+#[cfg(feature = "archive")]
+mod archive;
+mod command_line_tools;
+#[cfg(feature = "parse")]
+mod config;
+mod deployment_target;
+mod filesystem;
+#[cfg(all(feature = "parse", any(test, feature = "test-support")))]
+mod fixture;
 #[cfg(feature = "parse")]
 mod parsed_sdk;
 mod search;
+#[cfg(feature = "semver")]
+mod semver_interop;
 mod simple_sdk;
+#[cfg(feature = "parse")]
+mod simulator;
+#[cfg(feature = "tbd")]
+mod tbd;
+mod toolchain;
+mod version_req;
+mod xcode_app;
+#[cfg(feature = "parse")]
+mod xcodebuild;
+mod xcrun;
+#[cfg(feature = "xip")]
+mod xip;
 
 use std::{
+    borrow::Cow,
     cmp::Ordering,
+    ffi::{OsStr, OsString},
     fmt::{Display, Formatter},
+    hash::{Hash, Hasher},
     ops::Deref,
     path::{Path, PathBuf},
     process::{Command, ExitStatus, Stdio},
     str::FromStr,
 };
 
-pub use crate::{search::*, simple_sdk::SimpleSdk};
+#[cfg(feature = "archive")]
+pub use crate::archive::{SdkArchive, SdkArchiveFormat};
+
+#[cfg(all(feature = "parse", any(test, feature = "test-support")))]
+pub use crate::fixture::{DeveloperDirectoryFixture, FixtureSdk};
+
+pub use crate::{
+    architecture::{is_translated_process, Architecture},
+    command_line_tools::{
+        command_line_tools_installed, create_command_line_tools_install_trigger,
+        find_command_line_tools_update_label, install_command_line_tools,
+        remove_command_line_tools_install_trigger, COMMAND_LINE_TOOLS_INSTALL_TRIGGER_PATH,
+    },
+    deployment_target::DeploymentTarget,
+    filesystem::{FileMetadata, Filesystem, MemoryFilesystem, RealFilesystem},
+    search::*,
+    simple_sdk::SimpleSdk,
+    toolchain::{
+        find_toolchains_in_developer_directory, find_toolchains_in_directory, AppleToolchain,
+        SwiftToolchain, XCODE_DEFAULT_TOOLCHAIN_IDENTIFIER,
+    },
+    version_req::{SdkVersionComparator, SdkVersionOp, SdkVersionReq},
+    xcode_app::{XcodeAppDiscovery, XcodeApplication},
+    xcrun::{
+        xcrun_developer_directory, xcrun_sdk_path, xcrun_select_sdk,
+        xcrun_select_sdk_with_filesystem, XCRUN_DEFAULT_SDK,
+    },
+};
 
 #[cfg(feature = "parse")]
 pub use crate::parsed_sdk::{
-    ParsedSdk, SdkSettingsJson, SdkSettingsJsonDefaultProperties, SupportedTarget,
+    ConditionalProperty, ParsedSdk, SdkProductVersion, SdkSettingsDocument, SdkSettingsJson,
+    SdkSettingsJsonDefaultProperties, SdkVariant, SupportedArchitecture, SupportedTarget,
+};
+
+#[cfg(feature = "parse")]
+pub use crate::xcodebuild::XcodebuildSdk;
+
+#[cfg(feature = "parse")]
+pub use crate::config::{
+    SdkSearchConfig, SdkSearchConfigDeploymentTarget, SdkSearchConfigLocation,
+    SDK_SEARCH_CONFIG_ENV, SDK_SEARCH_CONFIG_JSON_FILE_NAME, SDK_SEARCH_CONFIG_TOML_FILE_NAME,
+};
+
+#[cfg(feature = "xip")]
+pub use crate::xip::{XcodeXip, XcodeXipContents};
+
+#[cfg(feature = "tbd")]
+pub use crate::tbd::{tbd_stub_path, TbdDocument, TbdFile, TbdSymbols};
+
+#[cfg(feature = "parse")]
+pub use crate::simulator::{
+    SimulatorDevice, SimulatorDeviceSet, SimulatorDeviceState, SIMULATOR_DEVICE_SET_RELATIVE_PATH,
 };
 
 /// Default install path for the Xcode command line tools.
@@ -116,27 +257,85 @@ pub const XCODE_APP_DEFAULT_PATH: &str = "/Applications/Xcode.app";
 /// This directory contains platforms, toolchains, etc.
 pub const XCODE_APP_RELATIVE_PATH_DEVELOPER: &str = "Contents/Developer";
 
+/// Path to the symlink recording the developer directory selected via `xcode-select`.
+pub const XCODE_SELECT_LINK_PATH: &str = "/var/db/xcode_select_link";
+
+/// Legacy path to the symlink recording the `xcode-select` developer directory.
+///
+/// Older versions of macOS stored the selection here.
+pub const XCODE_SELECT_LEGACY_LINK_PATH: &str = "/usr/share/xcode-select/xcode_dir_link";
+
 /// Error type for this crate.
+///
+/// New variants may be added in minor releases.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Error occurred when running `xcode-select`.
     XcodeSelectRun(std::io::Error),
     /// `xcode-select` did not run successfully.
     XcodeSelectBadStatus(ExitStatus),
+    /// Error occurred when running `xcodebuild`.
+    XcodebuildRun(std::io::Error),
+    /// `xcodebuild` did not run successfully.
+    XcodebuildBadStatus(ExitStatus),
+    /// Error occurred when running `swiftc`.
+    SwiftcRun(std::io::Error),
+    /// `swiftc` did not run successfully.
+    SwiftcBadStatus(ExitStatus),
+    /// Error occurred when running `softwareupdate`.
+    SoftwareupdateRun(std::io::Error),
+    /// `softwareupdate` did not run successfully.
+    SoftwareupdateBadStatus(ExitStatus),
+    /// The Command Line Tools install trigger file already exists.
+    ///
+    /// Another install may be in progress. Or something else placed a file or
+    /// symlink at the path.
+    CommandLineToolsInstallTriggerExists(PathBuf),
     /// Generic I/O error.
     Io(std::io::Error),
+    /// I/O error operating on a specific path.
+    IoPath(PathBuf, std::io::Error),
     /// A developer directory could not be found.
     DeveloperDirectoryNotFound,
     /// A path is not a Developer Directory.
     PathNotDeveloper(PathBuf),
+    /// Insufficient privileges to write the `xcode-select` selection at the given path.
+    XcodeSelectPermissionDenied(PathBuf),
     /// A path is not an Apple Platform directory.
     PathNotPlatform(PathBuf),
     /// A path is not an Apple SDK.
     PathNotSdk(PathBuf),
+    /// An SDK search found no matching SDK.
+    ///
+    /// The string describes the search.
+    SdkNotFound(String),
+    /// An SDK search is configured such that it can't match anything.
+    ///
+    /// The string describes the problem.
+    SdkSearchInvalid(String),
+    /// A path is not an Apple toolchain.
+    PathNotToolchain(PathBuf),
+    /// A path is not an Xcode application.
+    PathNotXcodeApplication(PathBuf),
+    /// A path is not a simulator device directory.
+    PathNotSimulatorDevice(PathBuf),
+    /// A path is not a supported SDK archive.
+    PathNotSdkArchive(PathBuf),
+    /// An archive is malformed or has unsafe content.
+    ArchiveMalformed(String),
+    /// A path is not an Xcode `.xip` archive.
+    PathNotXip(PathBuf),
     /// A version string could not be parsed.
     VersionParse(String),
+    /// A version requirement could not be parsed.
+    VersionReqParse(String),
+    /// An SDK name could not be parsed.
+    SdkNameParse(String),
     /// Certain functionality is not supported.
     FunctionalityNotSupported(&'static str),
+    /// Running the named program was prevented by [SdkSearch::allow_subprocess()].
+    SubprocessDisabled(&'static str),
     /// A plist value is not a dictionary.
     PlistNotDictionary,
     /// An expected plist key is missing.
@@ -155,8 +354,22 @@ pub enum Error {
     SerdeJson(serde_json::Error),
     #[cfg(feature = "plist")]
     Plist(plist::Error),
+    #[cfg(feature = "tbd")]
+    SerdeYaml(serde_yaml::Error),
+    #[cfg(feature = "toml")]
+    TomlDe(toml::de::Error),
+    #[cfg(feature = "archive")]
+    Zip(zip::result::ZipError),
+    #[cfg(feature = "xip")]
+    Xar(apple_xar::Error),
+    #[cfg(feature = "xip")]
+    Cpio(cpio_archive::Error),
     /// Maybe a new target is added to rust toolchain.
     UnknownTarget(String),
+    /// A Mach-O platform identifier has no corresponding [Platform].
+    UnknownMachOPlatform(u32),
+    /// An architecture name isn't a known [Architecture].
+    UnknownArchitecture(String),
 }
 
 impl Display for Error {
@@ -168,8 +381,33 @@ impl Display for Error {
             Self::XcodeSelectBadStatus(v) => {
                 f.write_fmt(format_args!("Error running xcode-select: {v}"))
             }
+            Self::XcodebuildRun(err) => {
+                f.write_fmt(format_args!("Error running xcodebuild: {err}"))
+            }
+            Self::XcodebuildBadStatus(v) => {
+                f.write_fmt(format_args!("Error running xcodebuild: {v}"))
+            }
+            Self::SwiftcRun(err) => f.write_fmt(format_args!("Error running swiftc: {err}")),
+            Self::SwiftcBadStatus(v) => f.write_fmt(format_args!("Error running swiftc: {v}")),
+            Self::SoftwareupdateRun(err) => {
+                f.write_fmt(format_args!("Error running softwareupdate: {err}"))
+            }
+            Self::SoftwareupdateBadStatus(v) => {
+                f.write_fmt(format_args!("Error running softwareupdate: {v}"))
+            }
+            Self::CommandLineToolsInstallTriggerExists(p) => f.write_fmt(format_args!(
+                "Command Line Tools install trigger already exists: {}",
+                p.display()
+            )),
             Self::Io(err) => f.write_fmt(format_args!("I/O error: {err}")),
+            Self::IoPath(path, err) => {
+                f.write_fmt(format_args!("I/O error on {}: {err}", path.display()))
+            }
             Self::DeveloperDirectoryNotFound => f.write_str("could not find a Developer Directory"),
+            Self::XcodeSelectPermissionDenied(p) => f.write_fmt(format_args!(
+                "permission denied writing {}; selecting a developer directory requires root privileges",
+                p.display()
+            )),
             Self::PathNotDeveloper(p) => f.write_fmt(format_args!(
                 "path is not a Developer directory: {}",
                 p.display()
@@ -181,8 +419,38 @@ impl Display for Error {
             Self::PathNotSdk(p) => {
                 f.write_fmt(format_args!("path is not an Apple SDK: {}", p.display()))
             }
+            Self::SdkNotFound(s) => f.write_fmt(format_args!("no SDK found: {s}")),
+            Self::SdkSearchInvalid(s) => f.write_fmt(format_args!("invalid SDK search: {s}")),
+            Self::PathNotToolchain(p) => f.write_fmt(format_args!(
+                "path is not an Apple toolchain: {}",
+                p.display()
+            )),
+            Self::PathNotXcodeApplication(p) => f.write_fmt(format_args!(
+                "path is not an Xcode application: {}",
+                p.display()
+            )),
+            Self::PathNotSimulatorDevice(p) => f.write_fmt(format_args!(
+                "path is not a simulator device: {}",
+                p.display()
+            )),
+            Self::PathNotSdkArchive(p) => f.write_fmt(format_args!(
+                "path is not a supported SDK archive: {}",
+                p.display()
+            )),
+            Self::ArchiveMalformed(s) => f.write_fmt(format_args!("malformed archive: {s}")),
+            Self::PathNotXip(p) => f.write_fmt(format_args!(
+                "path is not an Xcode xip archive: {}",
+                p.display()
+            )),
             Self::VersionParse(s) => f.write_fmt(format_args!("malformed version string: {s}")),
+            Self::VersionReqParse(s) => {
+                f.write_fmt(format_args!("malformed version requirement: {s}"))
+            }
+            Self::SdkNameParse(s) => f.write_fmt(format_args!("malformed SDK name: {s}")),
             Self::FunctionalityNotSupported(s) => f.write_fmt(format_args!("not supported: {s}")),
+            Self::SubprocessDisabled(program) => f.write_fmt(format_args!(
+                "running {program} is disabled for this SDK search"
+            )),
             Self::PlistNotDictionary => f.write_str("plist value not a dictionary"),
             Self::PlistKeyMissing(key) => f.write_fmt(format_args!("plist key missing: {key}")),
             Self::PlistKeyNotDictionary(key) => {
@@ -195,12 +463,61 @@ impl Display for Error {
             Self::SerdeJson(err) => f.write_fmt(format_args!("JSON parsing error: {err}")),
             #[cfg(feature = "plist")]
             Self::Plist(err) => f.write_fmt(format_args!("plist error: {err}")),
+            #[cfg(feature = "tbd")]
+            Self::SerdeYaml(err) => f.write_fmt(format_args!("YAML parsing error: {err}")),
+            #[cfg(feature = "toml")]
+            Self::TomlDe(err) => f.write_fmt(format_args!("TOML parsing error: {err}")),
+            #[cfg(feature = "archive")]
+            Self::Zip(err) => f.write_fmt(format_args!("zip error: {err}")),
+            #[cfg(feature = "xip")]
+            Self::Xar(err) => f.write_fmt(format_args!("XAR error: {err}")),
+            #[cfg(feature = "xip")]
+            Self::Cpio(err) => f.write_fmt(format_args!("cpio error: {err}")),
             Self::UnknownTarget(target) => f.write_fmt(format_args!("unknown target: {target}")),
+            Self::UnknownMachOPlatform(v) => {
+                f.write_fmt(format_args!("unknown Mach-O platform: {v}"))
+            }
+            Self::UnknownArchitecture(arch) => {
+                f.write_fmt(format_args!("unknown architecture: {arch}"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::XcodeSelectRun(err)
+            | Self::XcodebuildRun(err)
+            | Self::SwiftcRun(err)
+            | Self::SoftwareupdateRun(err)
+            | Self::Io(err)
+            | Self::IoPath(_, err) => Some(err),
+            #[cfg(feature = "parse")]
+            Self::SerdeJson(err) => Some(err),
+            #[cfg(feature = "plist")]
+            Self::Plist(err) => Some(err),
+            #[cfg(feature = "tbd")]
+            Self::SerdeYaml(err) => Some(err),
+            #[cfg(feature = "toml")]
+            Self::TomlDe(err) => Some(err),
+            #[cfg(feature = "archive")]
+            Self::Zip(err) => Some(err),
+            #[cfg(feature = "xip")]
+            Self::Xar(err) => Some(err),
+            #[cfg(feature = "xip")]
+            Self::Cpio(err) => Some(err),
+            _ => None,
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl Error {
+    /// Obtain a function converting an I/O error to [Error::IoPath] for a path.
+    pub(crate) fn io_path(path: &Path) -> impl FnOnce(std::io::Error) -> Self + '_ {
+        move |e| Self::IoPath(path.to_path_buf(), e)
+    }
+}
 
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
@@ -215,6 +532,41 @@ impl From<serde_json::Error> for Error {
     }
 }
 
+#[cfg(feature = "tbd")]
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Self {
+        Self::SerdeYaml(e)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
+        Self::TomlDe(e)
+    }
+}
+
+#[cfg(feature = "archive")]
+impl From<zip::result::ZipError> for Error {
+    fn from(e: zip::result::ZipError) -> Self {
+        Self::Zip(e)
+    }
+}
+
+#[cfg(feature = "xip")]
+impl From<apple_xar::Error> for Error {
+    fn from(e: apple_xar::Error) -> Self {
+        Self::Xar(e)
+    }
+}
+
+#[cfg(feature = "xip")]
+impl From<cpio_archive::Error> for Error {
+    fn from(e: cpio_archive::Error) -> Self {
+        Self::Cpio(e)
+    }
+}
+
 #[cfg(feature = "parse")]
 impl From<plist::Error> for Error {
     fn from(e: plist::Error) -> Self {
@@ -263,6 +615,28 @@ impl FromStr for Platform {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Platform {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.filesystem_name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Platform {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+
+        Self::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.display_name())
+    }
+}
+
 impl PartialEq for Platform {
     fn eq(&self, other: &Self) -> bool {
         self.filesystem_name().eq(other.filesystem_name())
@@ -280,6 +654,29 @@ impl TryFrom<&str> for Platform {
 }
 
 impl Platform {
+    /// All known platforms.
+    ///
+    /// This excludes [Self::Unknown].
+    pub const ALL: &'static [Self] = &[
+        Self::AppleTvOs,
+        Self::AppleTvSimulator,
+        Self::DriverKit,
+        Self::IPhoneOs,
+        Self::IPhoneSimulator,
+        Self::MacOsX,
+        Self::WatchOs,
+        Self::WatchSimulator,
+        Self::XrOs,
+        Self::XrOsSimulator,
+    ];
+
+    /// Obtain an iterator over all known platforms.
+    ///
+    /// This yields the members of [Self::ALL].
+    pub fn all() -> impl Iterator<Item = Self> {
+        Self::ALL.iter().cloned()
+    }
+
     /// Attempt to construct an instance from a filesystem path to a platform directory.
     ///
     /// The argument should be the path of a `*.platform` directory. e.g.
@@ -321,14 +718,45 @@ impl Platform {
             target if target.ends_with("-apple-watchos-sim") => Self::WatchSimulator,
             target if target.ends_with("-apple-watchos") => Platform::WatchOs,
             "x86_64-apple-tvos" => Self::AppleTvSimulator,
+            target if target.ends_with("-apple-tvos-sim") => Self::AppleTvSimulator,
             target if target.ends_with("-apple-tvos") => Platform::AppleTvOs,
-            "aarch64-apple-xros-sim" => Platform::XrOsSimulator,
+            target if target.ends_with("-apple-xros-sim") => Platform::XrOsSimulator,
             target if target.ends_with("-apple-xros") => Platform::XrOs,
+            target if target.ends_with("-apple-visionos-sim") => Platform::XrOsSimulator,
+            target if target.ends_with("-apple-visionos") => Platform::XrOs,
             _ => return Err(Error::UnknownTarget(target.to_string())),
         };
         Ok(platform)
     }
 
+    /// Attempt to construct an instance from a Mach-O platform identifier.
+    ///
+    /// The argument is the `platform` field of an `LC_BUILD_VERSION` load command.
+    /// e.g. `1` (`PLATFORM_MACOS`) or `7` (`PLATFORM_IOSSIMULATOR`).
+    ///
+    /// `PLATFORM_MACCATALYST` maps to [Self::IPhoneOs], the OS Mac Catalyst binaries
+    /// target, like `*-apple-ios-macabi` triples do in [Self::from_target_triple()].
+    /// Mac Catalyst binaries are built against the macOS SDK though. Search for
+    /// [Self::MacOsX] SDKs to find an SDK for them.
+    ///
+    /// Will return [Error::UnknownMachOPlatform] for identifiers without a
+    /// corresponding platform, such as `PLATFORM_BRIDGEOS`.
+    pub fn from_mach_o_platform(value: u32) -> Result<Self, Error> {
+        Ok(match value {
+            1 => Self::MacOsX,
+            2 | 6 => Self::IPhoneOs,
+            3 => Self::AppleTvOs,
+            4 => Self::WatchOs,
+            7 => Self::IPhoneSimulator,
+            8 => Self::AppleTvSimulator,
+            9 => Self::WatchSimulator,
+            10 => Self::DriverKit,
+            11 => Self::XrOs,
+            12 => Self::XrOsSimulator,
+            _ => return Err(Error::UnknownMachOPlatform(value)),
+        })
+    }
+
     /// Obtain the name of this platform as used in filesystem paths.
     ///
     /// This is just the platform part of the name without the trailing
@@ -351,6 +779,218 @@ impl Platform {
         }
     }
 
+    /// Obtain the name of the environment variable defining the deployment target.
+    ///
+    /// e.g. `MACOSX_DEPLOYMENT_TARGET` for macOS. Simulator platforms share the
+    /// variable of their device platform.
+    ///
+    /// These environment variables are honored by Apple's compilers and by rustc to
+    /// define the minimum OS version to target.
+    ///
+    /// Returns [None] for [Self::Unknown].
+    pub fn deployment_target_environment_variable(&self) -> Option<&'static str> {
+        match self {
+            Self::AppleTvOs | Self::AppleTvSimulator => Some("TVOS_DEPLOYMENT_TARGET"),
+            Self::DriverKit => Some("DRIVERKIT_DEPLOYMENT_TARGET"),
+            Self::IPhoneOs | Self::IPhoneSimulator => Some("IPHONEOS_DEPLOYMENT_TARGET"),
+            Self::MacOsX => Some("MACOSX_DEPLOYMENT_TARGET"),
+            Self::WatchOs | Self::WatchSimulator => Some("WATCHOS_DEPLOYMENT_TARGET"),
+            Self::XrOs | Self::XrOsSimulator => Some("XROS_DEPLOYMENT_TARGET"),
+            Self::Unknown(_) => None,
+        }
+    }
+
+    /// Resolve the deployment target from this platform's environment variable.
+    ///
+    /// See [Self::deployment_target_environment_variable()] for the name of the
+    /// variable consulted.
+    ///
+    /// Returns [None] if the variable is not defined or is empty.
+    pub fn deployment_target_from_env(&self) -> Option<SdkVersion> {
+        self.deployment_target_from_lookup(&|name| std::env::var_os(name))
+    }
+
+    /// Resolve the deployment target from this platform's variable in an environment lookup.
+    ///
+    /// This is [Self::deployment_target_from_env()] reading variables through `lookup`.
+    pub(crate) fn deployment_target_from_lookup(
+        &self,
+        lookup: &dyn Fn(&str) -> Option<OsString>,
+    ) -> Option<SdkVersion> {
+        let value = lookup(self.deployment_target_environment_variable()?)?;
+        let value = value.to_str()?.trim();
+
+        if value.is_empty() {
+            None
+        } else {
+            Some(SdkVersion::from(value))
+        }
+    }
+
+    /// Obtain the OS component of LLVM target triples for this platform.
+    ///
+    /// e.g. `macosx` or `ios`. Simulator platforms share the value of their device
+    /// platform and are distinguished by [Self::llvm_target_environment()].
+    ///
+    /// Returns [None] for [Self::Unknown].
+    pub fn llvm_target_sys(&self) -> Option<&'static str> {
+        match self {
+            Self::AppleTvOs | Self::AppleTvSimulator => Some("tvos"),
+            Self::DriverKit => Some("driverkit"),
+            Self::IPhoneOs | Self::IPhoneSimulator => Some("ios"),
+            Self::MacOsX => Some("macosx"),
+            Self::WatchOs | Self::WatchSimulator => Some("watchos"),
+            Self::XrOs | Self::XrOsSimulator => Some("xros"),
+            Self::Unknown(_) => None,
+        }
+    }
+
+    /// Obtain the environment component of LLVM target triples for this platform.
+    ///
+    /// This is `simulator` for simulator platforms and [None] otherwise.
+    pub fn llvm_target_environment(&self) -> Option<&'static str> {
+        match self {
+            Self::AppleTvSimulator
+            | Self::IPhoneSimulator
+            | Self::WatchSimulator
+            | Self::XrOsSimulator => Some("simulator"),
+            _ => None,
+        }
+    }
+
+    /// Construct an LLVM target triple for this platform.
+    ///
+    /// `arch` is the Apple name of the machine architecture. e.g. `arm64` or `x86_64`.
+    /// See [Architecture::llvm_name()].
+    ///
+    /// The deployment target version is embedded in the triple. e.g.
+    /// `arm64-apple-ios15.0-simulator`. Compilers derive the minimum OS version
+    /// from this, so `-m*-version-min` flags are not necessary.
+    ///
+    /// Returns [None] for [Self::Unknown].
+    pub fn llvm_target_triple(&self, arch: &str, deployment_target: &SdkVersion) -> Option<String> {
+        let sys = self.llvm_target_sys()?;
+
+        Some(if let Some(env) = self.llvm_target_environment() {
+            format!("{arch}-apple-{sys}{deployment_target}-{env}")
+        } else {
+            format!("{arch}-apple-{sys}{deployment_target}")
+        })
+    }
+
+    /// The `clang` flag setting the minimum OS version for this platform.
+    ///
+    /// e.g. `-mios-simulator-version-min`. [None] for platforms only accepting the
+    /// version via the target triple.
+    #[cfg(feature = "cc")]
+    fn clang_version_min_flag(&self) -> Option<&'static str> {
+        match self {
+            Self::MacOsX => Some("-mmacosx-version-min"),
+            Self::IPhoneOs => Some("-miphoneos-version-min"),
+            Self::IPhoneSimulator => Some("-mios-simulator-version-min"),
+            Self::AppleTvOs => Some("-mtvos-version-min"),
+            Self::AppleTvSimulator => Some("-mtvos-simulator-version-min"),
+            Self::WatchOs => Some("-mwatchos-version-min"),
+            Self::WatchSimulator => Some("-mwatchos-simulator-version-min"),
+            _ => None,
+        }
+    }
+
+    /// The machine architectures used by this platform.
+    ///
+    /// See [Architecture::platforms()] for caveats. Empty for [Self::Unknown].
+    pub fn architectures(&self) -> Vec<Architecture> {
+        Architecture::all()
+            .filter(|arch| arch.is_used_by(self))
+            .collect()
+    }
+
+    /// Obtain the Mach-O platform identifier for this platform.
+    ///
+    /// This is the value of the `platform` field of `LC_BUILD_VERSION` load
+    /// commands in binaries built for this platform. e.g. `1` (`PLATFORM_MACOS`).
+    ///
+    /// Returns [None] for [Self::Unknown].
+    pub fn mach_o_platform(&self) -> Option<u32> {
+        match self {
+            Self::MacOsX => Some(1),
+            Self::IPhoneOs => Some(2),
+            Self::AppleTvOs => Some(3),
+            Self::WatchOs => Some(4),
+            Self::IPhoneSimulator => Some(7),
+            Self::AppleTvSimulator => Some(8),
+            Self::WatchSimulator => Some(9),
+            Self::DriverKit => Some(10),
+            Self::XrOs => Some(11),
+            Self::XrOsSimulator => Some(12),
+            Self::Unknown(_) => None,
+        }
+    }
+
+    /// Whether this is a simulator platform.
+    pub fn is_simulator(&self) -> bool {
+        self.llvm_target_environment() == Some("simulator")
+    }
+
+    /// Whether this platform targets physical hardware.
+    ///
+    /// This is true for all known platforms that aren't simulators, including
+    /// macOS and DriverKit.
+    pub fn is_device(&self) -> bool {
+        !self.is_simulator() && !matches!(self, Self::Unknown(_))
+    }
+
+    /// Obtain a human readable name for this platform.
+    ///
+    /// e.g. `macOS` or `iOS Simulator`. This is suitable for UI and log output.
+    /// The [Display] implementation also writes this name.
+    pub fn display_name(&self) -> &str {
+        match self {
+            Self::AppleTvOs => "tvOS",
+            Self::AppleTvSimulator => "tvOS Simulator",
+            Self::DriverKit => "DriverKit",
+            Self::IPhoneOs => "iOS",
+            Self::IPhoneSimulator => "iOS Simulator",
+            Self::MacOsX => "macOS",
+            Self::WatchOs => "watchOS",
+            Self::WatchSimulator => "watchOS Simulator",
+            Self::XrOs => "visionOS",
+            Self::XrOsSimulator => "visionOS Simulator",
+            Self::Unknown(v) => v,
+        }
+    }
+
+    /// Obtain the simulator platform corresponding to this device platform.
+    ///
+    /// e.g. [Self::IPhoneOs] yields [Self::IPhoneSimulator].
+    ///
+    /// Returns [None] for platforms without a simulator, such as macOS, and for
+    /// simulator platforms themselves.
+    pub fn simulator_counterpart(&self) -> Option<Self> {
+        match self {
+            Self::AppleTvOs => Some(Self::AppleTvSimulator),
+            Self::IPhoneOs => Some(Self::IPhoneSimulator),
+            Self::WatchOs => Some(Self::WatchSimulator),
+            Self::XrOs => Some(Self::XrOsSimulator),
+            _ => None,
+        }
+    }
+
+    /// Obtain the device platform corresponding to this simulator platform.
+    ///
+    /// e.g. [Self::IPhoneSimulator] yields [Self::IPhoneOs].
+    ///
+    /// Returns [None] for platforms that aren't a simulator.
+    pub fn device_counterpart(&self) -> Option<Self> {
+        match self {
+            Self::AppleTvSimulator => Some(Self::AppleTvOs),
+            Self::IPhoneSimulator => Some(Self::IPhoneOs),
+            Self::WatchSimulator => Some(Self::WatchOs),
+            Self::XrOsSimulator => Some(Self::XrOs),
+            _ => None,
+        }
+    }
+
     /// Obtain the directory name of this platform.
     ///
     /// This simply appends `.platform` to [Self::filesystem_name()].
@@ -371,6 +1011,10 @@ impl Platform {
 ///
 /// This is just a thin abstraction over a filesystem path and a [Platform] instance.
 ///
+/// If the `parse` feature is enabled, metadata is also parsed from the platform's
+/// `Info.plist` and `version.plist` files. Without it, the metadata fields are
+/// always [None].
+///
 /// Equivalence and sorting are implemented in terms of the path component
 /// only. The assumption here is the [Platform] is fully derived from the filesystem
 /// path and this derivation is deterministic.
@@ -380,15 +1024,141 @@ pub struct PlatformDirectory {
 
     /// The platform within this directory.
     platform: Platform,
+
+    /// The bundle identifier of the platform.
+    ///
+    /// e.g. `com.apple.platform.iphoneos`.
+    pub identifier: Option<String>,
+
+    /// The version of the platform.
+    ///
+    /// This typically matches the version of the newest SDK in the platform.
+    pub version: Option<SdkVersion>,
+
+    /// The build identifier of the platform.
+    ///
+    /// This is the `DTPlatformBuild` key. e.g. `21C52`.
+    pub build: Option<String>,
+
+    /// The build identifier of the SDK this platform shipped with.
+    ///
+    /// This is the `DTSDKBuild` key. e.g. `21C52`.
+    pub sdk_build: Option<String>,
+
+    /// The version of Xcode this platform shipped with.
+    ///
+    /// This is derived from the `DTXcode` key. e.g. `1520` is `15.2`.
+    pub xcode_version: Option<SdkVersion>,
+
+    /// The build identifier of the Xcode this platform shipped with.
+    ///
+    /// This is the `DTXcodeBuild` key. e.g. `15C500b`.
+    pub xcode_build: Option<String>,
 }
 
 impl PlatformDirectory {
     /// Attempt to construct an instance from a filesystem path.
+    ///
+    /// If the `parse` feature is enabled and the directory contains an
+    /// `Info.plist` or `version.plist` file, its metadata is loaded. Metadata files
+    /// that can't be read or parsed are ignored, leaving their fields [None].
     pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_path_with_filesystem(path, &RealFilesystem)
+    }
+
+    /// Attempt to construct an instance from a path on the given [Filesystem].
+    ///
+    /// This is like [Self::from_path()] except metadata files are read from `fs`.
+    #[cfg_attr(not(feature = "parse"), allow(unused_variables))]
+    pub fn from_path_with_filesystem(
+        path: impl AsRef<Path>,
+        fs: &dyn Filesystem,
+    ) -> Result<Self, Error> {
         let path = path.as_ref().to_path_buf();
         let platform = Platform::from_platform_path(&path)?;
 
-        Ok(Self { path, platform })
+        #[allow(unused_mut)]
+        let mut res = Self {
+            path,
+            platform,
+            identifier: None,
+            version: None,
+            build: None,
+            sdk_build: None,
+            xcode_version: None,
+            xcode_build: None,
+        };
+
+        #[cfg(feature = "parse")]
+        {
+            // version.plist is loaded last so its version fields take precedence.
+            for filename in ["Info.plist", "version.plist"] {
+                let plist_path = res.path.join(filename);
+
+                if !fs.exists(&plist_path) {
+                    continue;
+                }
+
+                // Metadata is optional. An unreadable plist shouldn't make the
+                // platform, and every search touching it, unusable.
+                let loaded = fs
+                    .read(&plist_path)
+                    .map_err(Error::io_path(&plist_path))
+                    .and_then(|data| Ok(plist::Value::from_reader(std::io::Cursor::new(data))?))
+                    .and_then(|value| res.load_plist(value));
+
+                if let Err(e) = loaded {
+                    warn!(
+                        "ignoring platform metadata in {}: {e}",
+                        plist_path.display()
+                    );
+                }
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Populate metadata fields from a parsed `Info.plist` or `version.plist`.
+    ///
+    /// Only keys present in the plist are applied. Existing values are retained
+    /// otherwise.
+    #[cfg(feature = "parse")]
+    pub fn load_plist(&mut self, value: plist::Value) -> Result<(), Error> {
+        let value = value.into_dictionary().ok_or(Error::PlistNotDictionary)?;
+
+        let get_string = |key: &str| -> Option<String> {
+            value
+                .get(key)
+                .and_then(|v| v.as_string())
+                .map(|s| s.to_string())
+        };
+
+        if let Some(identifier) = get_string("CFBundleIdentifier") {
+            self.identifier = Some(identifier);
+        }
+        if let Some(version) = get_string("DTPlatformVersion")
+            .or_else(|| get_string("Version"))
+            .or_else(|| get_string("CFBundleShortVersionString"))
+        {
+            self.version = Some(SdkVersion::from(version));
+        }
+        if let Some(build) =
+            get_string("DTPlatformBuild").or_else(|| get_string("ProductBuildVersion"))
+        {
+            self.build = Some(build);
+        }
+        if let Some(build) = get_string("DTSDKBuild") {
+            self.sdk_build = Some(build);
+        }
+        if let Some(version) = get_string("DTXcode").as_deref().and_then(parse_dt_xcode) {
+            self.xcode_version = Some(version);
+        }
+        if let Some(build) = get_string("DTXcodeBuild") {
+            self.xcode_build = Some(build);
+        }
+
+        Ok(())
     }
 
     /// The filesystem path of this instance.
@@ -413,6 +1183,56 @@ impl PlatformDirectory {
     pub fn find_sdks<T: AppleSdk>(&self) -> Result<Vec<T>, Error> {
         T::find_in_directory(&self.sdks_path())
     }
+
+    /// The filesystem paths to directories holding platform specific tools.
+    ///
+    /// These are `Developer/usr/bin` and `usr/bin`, in that order. Only existing
+    /// directories are returned.
+    pub fn tool_directories(&self) -> Vec<PathBuf> {
+        [
+            self.path.join("Developer").join("usr").join("bin"),
+            self.path.join("usr").join("bin"),
+        ]
+        .into_iter()
+        .filter(|p| p.is_dir())
+        .collect::<Vec<_>>()
+    }
+
+    /// Finds all tools provided by this platform directory.
+    ///
+    /// Entries of each [Self::tool_directories()] directory are returned, sorted
+    /// by path.
+    pub fn tools(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut res = vec![];
+
+        for dir in self.tool_directories() {
+            for entry in std::fs::read_dir(&dir).map_err(Error::io_path(&dir))? {
+                let entry = entry?;
+
+                if !entry.file_type()?.is_dir() {
+                    res.push(entry.path());
+                }
+            }
+        }
+
+        res.sort();
+
+        Ok(res)
+    }
+
+    /// Resolve the path to a tool provided by this platform directory.
+    ///
+    /// `name` is the filename of the tool. e.g. `ld` or `actool`. Directories are
+    /// searched in the order of [Self::tool_directories()] and the first match is
+    /// returned.
+    ///
+    /// Returns [None] if the platform doesn't provide the tool.
+    pub fn find_tool(&self, name: impl AsRef<OsStr>) -> Option<PathBuf> {
+        self.tool_directories()
+            .into_iter()
+            .map(|dir| dir.join(name.as_ref()))
+            .find(|p| p.is_file())
+    }
 }
 
 impl AsRef<Path> for PlatformDirectory {
@@ -455,10 +1275,42 @@ impl Ord for PlatformDirectory {
     }
 }
 
-/// A directory containing Apple platforms, SDKs, and other tools.
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct DeveloperDirectory {
-    path: PathBuf,
+/// The kind of install a [DeveloperDirectory] belongs to.
+///
+/// See [DeveloperDirectory::kind()].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum DeveloperDirectoryKind {
+    /// A full Xcode application.
+    ///
+    /// Tools like `xcodebuild`, `ibtool`, and `actool` are available.
+    Xcode,
+    /// An install of the Xcode Command Line Tools.
+    ///
+    /// Compilers and linkers are available. Tools requiring Xcode like `ibtool`
+    /// and `actool` are not.
+    CommandLineTools,
+    /// A directory holding platforms or SDKs without any tools.
+    ///
+    /// e.g. SDKs extracted from an Xcode install for cross-compiling.
+    SdkTree,
+}
+
+impl Display for DeveloperDirectoryKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Xcode => "Xcode",
+            Self::CommandLineTools => "Command Line Tools",
+            Self::SdkTree => "SDK tree",
+        })
+    }
+}
+
+/// A directory containing Apple platforms, SDKs, and other tools.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct DeveloperDirectory {
+    path: PathBuf,
 }
 
 impl AsRef<Path> for DeveloperDirectory {
@@ -493,21 +1345,109 @@ impl DeveloperDirectory {
     /// This environment variable is used by convention to override default search
     /// locations for the developer directory.
     ///
-    /// If `DEVELOPER_DIR` is defined, the value/path is validated for existence
-    /// and an error is returned if it doesn't exist.
+    /// If `DEVELOPER_DIR` is defined, the value/path is resolved and validated via
+    /// [Self::from_path()]. So the value can be the path to an `Xcode.app` bundle
+    /// and an error is returned if the path doesn't look like a developer directory.
     ///
     /// If `DEVELOPER_DIR` isn't defined, returns `Ok(None)`.
     pub fn from_env() -> Result<Option<Self>, Error> {
         if let Some(value) = std::env::var_os("DEVELOPER_DIR") {
-            let path = PathBuf::from(value);
+            Ok(Some(Self::from_path(PathBuf::from(value))?))
+        } else {
+            Ok(None)
+        }
+    }
 
-            if path.exists() {
-                Ok(Some(Self { path }))
-            } else {
-                Err(Error::PathNotDeveloper(path))
+    /// Resolve an instance from a path, validating that it is a developer directory.
+    ///
+    /// If the path is an Xcode application bundle (e.g. `/Applications/Xcode.app`),
+    /// the [XCODE_APP_RELATIVE_PATH_DEVELOPER] directory within is used. This mirrors
+    /// how Apple's tools interpret `DEVELOPER_DIR`.
+    ///
+    /// The directory must contain a `Platforms` or `usr/bin` directory, as Xcode
+    /// and the Xcode Command Line Tools do. Errors with [Error::PathNotDeveloper]
+    /// otherwise.
+    ///
+    /// Use the [From] implementations to construct an instance without validation.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+
+        let path = if path.join(XCODE_APP_RELATIVE_PATH_DEVELOPER).is_dir() {
+            path.join(XCODE_APP_RELATIVE_PATH_DEVELOPER)
+        } else {
+            path.to_path_buf()
+        };
+
+        if path.join("Platforms").is_dir() || path.join("usr").join("bin").is_dir() {
+            Ok(Self { path })
+        } else {
+            Err(Error::PathNotDeveloper(path))
+        }
+    }
+
+    /// Attempt to resolve an instance from the `xcode-select` selection symlink.
+    ///
+    /// `xcode-select --switch` records the selected developer directory as a symlink
+    /// at [XCODE_SELECT_LINK_PATH] (or [XCODE_SELECT_LEGACY_LINK_PATH] on older
+    /// macOS versions). This function reads that symlink directly, without
+    /// spawning a process.
+    ///
+    /// Returns `Ok(None)` if no selection has been recorded or if the selected
+    /// path no longer exists.
+    pub fn from_xcode_select_link() -> Result<Option<Self>, Error> {
+        for link in [XCODE_SELECT_LINK_PATH, XCODE_SELECT_LEGACY_LINK_PATH] {
+            if let Some(path) = read_xcode_select_link(Path::new(link))? {
+                return Ok(Some(Self { path }));
             }
+        }
+
+        Ok(None)
+    }
+
+    /// Make this the active developer directory, like `xcode-select --switch`.
+    ///
+    /// The selection is recorded by replacing the [XCODE_SELECT_LINK_PATH] symlink,
+    /// which is what `xcode-select` and [Self::from_xcode_select_link()] read.
+    /// `DEVELOPER_DIR` still takes precedence over the selection.
+    ///
+    /// The path is validated like [Self::from_path()], so an instance for an Xcode
+    /// application bundle selects its developer directory. Returns the selected
+    /// developer directory.
+    ///
+    /// Writing the selection requires root privileges. Errors with
+    /// [Error::XcodeSelectPermissionDenied] if the process lacks them. Errors with
+    /// [Error::FunctionalityNotSupported] on non-Unix platforms.
+    pub fn select(&self) -> Result<Self, Error> {
+        self.select_with_link(Path::new(XCODE_SELECT_LINK_PATH))
+    }
+
+    /// Make this the active developer directory by writing a selection symlink at `link`.
+    ///
+    /// This is [Self::select()] with a custom symlink path. The symlink is replaced
+    /// atomically so readers never observe a missing selection.
+    pub fn select_with_link(&self, link: &Path) -> Result<Self, Error> {
+        let dir = Self::from_path(&self.path)?;
+        let target = dir.path.canonicalize().map_err(Error::io_path(&dir.path))?;
+
+        write_xcode_select_link(link, &target)?;
+
+        Ok(Self { path: target })
+    }
+
+    /// Resolve the developer directory `xcode-select` would report, without running it.
+    ///
+    /// This mirrors the resolution logic of `xcode-select --print-path`:
+    ///
+    /// 1. The selection recorded by [Self::from_xcode_select_link()].
+    /// 2. The system Xcode application, via [Self::default_xcode()].
+    /// 3. The Xcode Command Line Tools, via [Self::command_line_tools()].
+    pub fn from_xcode_select_pure() -> Result<Option<Self>, Error> {
+        if let Some(v) = Self::from_xcode_select_link()? {
+            Ok(Some(v))
+        } else if let Some(v) = Self::default_xcode() {
+            Ok(Some(v))
         } else {
-            Ok(None)
+            Ok(Self::command_line_tools())
         }
     }
 
@@ -515,6 +1455,8 @@ impl DeveloperDirectory {
     ///
     /// The output from `xcode-select` is implicitly trusted and no validation
     /// of the path is performed.
+    ///
+    /// Consider [Self::from_xcode_select_pure()] to avoid spawning a process.
     pub fn from_xcode_select() -> Result<Self, Error> {
         let output = Command::new("xcode-select")
             .args(["--print-path"])
@@ -523,10 +1465,9 @@ impl DeveloperDirectory {
             .map_err(Error::XcodeSelectRun)?;
 
         if output.status.success() {
-            // We should arguably use OsString here. Keep it simple until someone
-            // complains.
-            let path = String::from_utf8_lossy(&output.stdout);
-            let path = PathBuf::from(path.trim());
+            let path = PathBuf::from(os_string_from_bytes(
+                trim_ascii_whitespace(&output.stdout).to_vec(),
+            ));
 
             Ok(Self { path })
         } else {
@@ -539,8 +1480,31 @@ impl DeveloperDirectory {
     /// This looks for a system installed `Xcode.app` and for the developer
     /// directory within. If found, returns `Some`. If not, returns `None`.
     pub fn default_xcode() -> Option<Self> {
+        Self::default_xcode_with_filesystem(&RealFilesystem)
+    }
+
+    /// Attempt to resolve an instance from the default Xcode.app location on the given [Filesystem].
+    ///
+    /// This is like [Self::default_xcode()] except the location is checked via `fs`.
+    pub fn default_xcode_with_filesystem(fs: &dyn Filesystem) -> Option<Self> {
         let path = PathBuf::from(XCODE_APP_DEFAULT_PATH).join(XCODE_APP_RELATIVE_PATH_DEVELOPER);
 
+        if fs.exists(&path) {
+            Some(Self { path })
+        } else {
+            None
+        }
+    }
+
+    /// Attempt to resolve an instance from the default Xcode Command Line Tools location.
+    ///
+    /// The Command Line Tools install is a developer directory without platforms.
+    /// SDKs are located in its `SDKs` directory.
+    ///
+    /// Returns `Some` if [COMMAND_LINE_TOOLS_DEFAULT_PATH] exists.
+    pub fn command_line_tools() -> Option<Self> {
+        let path = PathBuf::from(COMMAND_LINE_TOOLS_DEFAULT_PATH);
+
         if path.exists() {
             Some(Self { path })
         } else {
@@ -556,18 +1520,55 @@ impl DeveloperDirectory {
     /// It will return all available `Developer` directories for all Xcode installs
     /// under `/Applications`.
     pub fn find_system_xcodes() -> Result<Vec<Self>, Error> {
-        Ok(find_system_xcode_applications()?
-            .into_iter()
+        Self::find_system_xcodes_with_filesystem(&RealFilesystem)
+    }
+
+    /// Finds all `Developer` directories for system installed Xcode applications on the given [Filesystem].
+    ///
+    /// This is like [Self::find_system_xcodes()] except all reads go through `fs`.
+    pub fn find_system_xcodes_with_filesystem(fs: &dyn Filesystem) -> Result<Vec<Self>, Error> {
+        let apps =
+            XcodeAppDiscovery::default().find_with_filesystem(Path::new("/Applications"), fs)?;
+
+        Ok(Self::from_xcode_apps(apps, fs))
+    }
+
+    /// Finds all `Developer` directories for Xcode applications in the user's
+    /// `Applications` directory.
+    ///
+    /// This is like [Self::find_system_xcodes()] except it uses
+    /// [find_user_xcode_applications()]. Version managers like `xcodes` install
+    /// Xcode here. e.g. `~/Applications/Xcode-15.2.0.app`.
+    pub fn find_user_xcodes() -> Result<Vec<Self>, Error> {
+        Self::find_user_xcodes_with_filesystem(&RealFilesystem)
+    }
+
+    /// Finds all `Developer` directories for the user's Xcode applications on the given [Filesystem].
+    ///
+    /// This is like [Self::find_user_xcodes()] except all reads go through `fs`.
+    pub fn find_user_xcodes_with_filesystem(fs: &dyn Filesystem) -> Result<Vec<Self>, Error> {
+        let apps = if let Some(dir) = user_applications_directory() {
+            XcodeAppDiscovery::default().find_with_filesystem(&dir, fs)?
+        } else {
+            vec![]
+        };
+
+        Ok(Self::from_xcode_apps(apps, fs))
+    }
+
+    /// Resolve the existing `Developer` directories of Xcode applications.
+    fn from_xcode_apps(apps: Vec<PathBuf>, fs: &dyn Filesystem) -> Vec<Self> {
+        apps.into_iter()
             .filter_map(|p| {
                 let path = p.join(XCODE_APP_RELATIVE_PATH_DEVELOPER);
 
-                if path.exists() {
+                if fs.exists(&path) {
                     Some(Self { path })
                 } else {
                     None
                 }
             })
-            .collect::<Vec<_>>())
+            .collect::<Vec<_>>()
     }
 
     /// Attempt to find a Developer Directory using reasonable semantics.
@@ -581,21 +1582,34 @@ impl DeveloperDirectory {
     /// In priority order:
     ///
     /// 1. `DEVELOPER_DIR`
-    /// 2. System Xcode.app application.
-    /// 3. `xcode-select` output.
+    /// 2. The `xcode-select` selection symlink.
+    /// 3. System Xcode.app application.
+    /// 4. System Xcode Command Line Tools.
+    /// 5. `xcode-select` output.
     ///
     /// Errors only if `DEVELOPER_DIR` is defined and it points to an invalid path.
-    /// Errors from running `xcode-select` are ignored.
+    /// Errors from reading the `xcode-select` symlink or running `xcode-select` are
+    /// ignored.
     pub fn find_default() -> Result<Option<Self>, Error> {
-        if let Some(v) = Self::from_env()? {
-            Ok(Some(v))
-        } else if let Some(v) = Self::default_xcode() {
-            Ok(Some(v))
+        let (dir, source) = if let Some(v) = Self::from_env()? {
+            (v, "DEVELOPER_DIR environment variable")
+        } else if let Ok(Some(v)) = Self::from_xcode_select_pure() {
+            (v, "xcode-select symlink")
         } else if let Ok(v) = Self::from_xcode_select() {
-            Ok(Some(v))
+            (v, "running xcode-select")
         } else {
-            Ok(None)
-        }
+            debug!("could not find a default developer directory");
+
+            return Ok(None);
+        };
+
+        debug!(
+            "using developer directory {} from {}",
+            dir.path.display(),
+            source
+        );
+
+        Ok(Some(dir))
     }
 
     /// Find the Developer Directory and error if not found.
@@ -620,6 +1634,31 @@ impl DeveloperDirectory {
         self.path.join("Platforms")
     }
 
+    /// Classify the kind of install this directory belongs to.
+    ///
+    /// Directories within an `.app` bundle (`*.app/Contents/Developer`) or providing
+    /// `usr/bin/xcodebuild` are [DeveloperDirectoryKind::Xcode]. Other directories
+    /// providing `usr/bin` are [DeveloperDirectoryKind::CommandLineTools]. Everything
+    /// else is [DeveloperDirectoryKind::SdkTree].
+    pub fn kind(&self) -> DeveloperDirectoryKind {
+        let in_app_bundle = self.path.file_name() == Some(OsStr::new("Developer"))
+            && self
+                .path
+                .parent()
+                .filter(|p| p.file_name() == Some(OsStr::new("Contents")))
+                .and_then(|p| p.parent())
+                .and_then(|p| p.extension())
+                == Some(OsStr::new("app"));
+
+        if in_app_bundle || self.path.join("usr/bin/xcodebuild").is_file() {
+            DeveloperDirectoryKind::Xcode
+        } else if self.path.join("usr/bin").is_dir() {
+            DeveloperDirectoryKind::CommandLineTools
+        } else {
+            DeveloperDirectoryKind::SdkTree
+        }
+    }
+
     /// Find platform directories within this developer directory.
     ///
     /// Platforms are defined by the presence of a `Platforms` directory under
@@ -630,26 +1669,38 @@ impl DeveloperDirectory {
     ///
     /// The return order is sorted and deterministic.
     pub fn platforms(&self) -> Result<Vec<PlatformDirectory>, Error> {
+        self.platforms_with_filesystem(&RealFilesystem)
+    }
+
+    /// Find platform directories within this developer directory on the given [Filesystem].
+    ///
+    /// This is like [Self::platforms()] except all reads go through `fs`.
+    pub fn platforms_with_filesystem(
+        &self,
+        fs: &dyn Filesystem,
+    ) -> Result<Vec<PlatformDirectory>, Error> {
         let platforms_path = self.platforms_path();
 
-        let dir = match std::fs::read_dir(platforms_path) {
+        let dir = match fs.read_dir(&platforms_path) {
             Ok(v) => Ok(v),
             Err(e) => {
                 if e.kind() == std::io::ErrorKind::NotFound {
                     return Ok(vec![]);
                 } else {
-                    Err(Error::from(e))
+                    Err(Error::IoPath(platforms_path.clone(), e))
                 }
             }
         }?;
 
         let mut res = vec![];
 
-        for entry in dir {
-            let entry = entry?;
-
-            if let Ok(platform) = PlatformDirectory::from_path(entry.path()) {
-                res.push(platform);
+        for path in dir {
+            match PlatformDirectory::from_path_with_filesystem(path, fs) {
+                Ok(platform) => {
+                    res.push(platform);
+                }
+                Err(Error::PathNotPlatform(_)) => {}
+                Err(err) => return Err(err),
             }
         }
 
@@ -673,25 +1724,393 @@ impl DeveloperDirectory {
             .flatten()
             .collect::<Vec<_>>())
     }
+
+    /// The path to the directory containing toolchains.
+    pub fn toolchains_path(&self) -> PathBuf {
+        self.path.join("Toolchains")
+    }
+
+    /// Find toolchains within this developer directory.
+    ///
+    /// This is a convenience method for [find_toolchains_in_developer_directory()].
+    ///
+    /// The return order is sorted and deterministic.
+    pub fn toolchains(&self) -> Result<Vec<AppleToolchain>, Error> {
+        find_toolchains_in_directory(&self.toolchains_path())
+    }
+
+    /// Resolve the Swift toolchain within this developer directory.
+    ///
+    /// The default Xcode toolchain is preferred. Otherwise the `usr` directory of
+    /// the developer directory itself is used, which is where Command Line Tools
+    /// installs hold Swift.
+    ///
+    /// Returns [None] if no `swiftc` could be found.
+    pub fn swift_toolchain(&self) -> Result<Option<SwiftToolchain>, Error> {
+        if let Some(swift) = self
+            .toolchains()?
+            .iter()
+            .filter(|t| t.is_xcode_default())
+            .find_map(SwiftToolchain::from_toolchain)
+        {
+            return Ok(Some(swift));
+        }
+
+        Ok(SwiftToolchain::from_usr_directory(&self.path.join("usr")))
+    }
+}
+
+/// Read an `xcode-select` selection symlink.
+///
+/// Returns `Ok(None)` if the link doesn't exist, isn't a symlink, or points to a
+/// path that doesn't exist. Relative link targets are resolved against the
+/// directory containing the link.
+fn read_xcode_select_link(link: &Path) -> Result<Option<PathBuf>, Error> {
+    let target = match std::fs::read_link(link) {
+        Ok(v) => v,
+        Err(e) => {
+            return if matches!(
+                e.kind(),
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::InvalidInput
+            ) {
+                Ok(None)
+            } else {
+                Err(Error::IoPath(link.to_path_buf(), e))
+            };
+        }
+    };
+
+    let path = if target.is_relative() {
+        link.parent().map(|p| p.join(&target)).unwrap_or(target)
+    } else {
+        target
+    };
+
+    if path.exists() {
+        Ok(Some(path))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Replace an `xcode-select` selection symlink to point at `target`.
+#[cfg(unix)]
+fn write_xcode_select_link(link: &Path, target: &Path) -> Result<(), Error> {
+    let map_err = |e: std::io::Error| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            Error::XcodeSelectPermissionDenied(link.to_path_buf())
+        } else {
+            Error::IoPath(link.to_path_buf(), e)
+        }
+    };
+
+    // Create the new symlink beside the existing one and rename it into place.
+    let mut temp_name = OsString::from(".");
+    temp_name.push(link.file_name().unwrap_or_default());
+    temp_name.push(format!(".{}", std::process::id()));
+    let temp = link.with_file_name(temp_name);
+
+    if temp.symlink_metadata().is_ok() {
+        std::fs::remove_file(&temp).map_err(map_err)?;
+    }
+    std::os::unix::fs::symlink(target, &temp).map_err(map_err)?;
+
+    std::fs::rename(&temp, link).map_err(|e| {
+        let _ = std::fs::remove_file(&temp);
+        map_err(e)
+    })
+}
+
+#[cfg(not(unix))]
+fn write_xcode_select_link(_link: &Path, _target: &Path) -> Result<(), Error> {
+    Err(Error::FunctionalityNotSupported(
+        "selecting a developer directory on this platform",
+    ))
+}
+
+/// Parse a `DTXcode` plist value into a version.
+///
+/// The value is the Xcode version without delimiters, with the last 2 digits
+/// being the minor and patch versions. e.g. `1520` is `15.2` and `0941` is `9.4.1`.
+#[cfg_attr(not(feature = "parse"), allow(dead_code))]
+fn parse_dt_xcode(value: &str) -> Option<SdkVersion> {
+    if value.len() < 3 || !value.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let (major, rest) = value.split_at(value.len() - 2);
+    let (minor, patch) = rest.split_at(1);
+    let major = major.parse::<u64>().ok()?;
+
+    Some(SdkVersion::from(if patch == "0" {
+        format!("{major}.{minor}")
+    } else {
+        format!("{major}.{minor}.{patch}")
+    }))
+}
+
+/// Format an Xcode version as a `DTXcode` value.
+///
+/// This is the inverse of [parse_dt_xcode()]. e.g. `15.2` is `1520`. Returns
+/// [None] if the minor or patch version has multiple digits.
+#[cfg_attr(not(feature = "parse"), allow(dead_code))]
+fn format_dt_xcode(version: &SdkVersion) -> Option<String> {
+    let components = version.components();
+    let component = |i: usize| components.get(i).copied().unwrap_or_default();
+
+    if components.is_empty() || component(1) > 9 || component(2) > 9 {
+        return None;
+    }
+
+    Some(format!(
+        "{:02}{}{}",
+        component(0),
+        component(1),
+        component(2)
+    ))
+}
+
+/// Extract the version of libc++ from the content of its `__config` header.
+///
+/// `_LIBCPP_VERSION` encodes the LLVM version as `MMmmpp` since LLVM 16 and as
+/// `MMmpp` before. e.g. `170006` is `17.0.6` and `15000` is `15.0.0`.
+fn parse_libcpp_version(config: &str) -> Option<SdkVersion> {
+    let value = config.lines().find_map(|line| {
+        let line = line.trim_start().strip_prefix('#')?.trim_start();
+        let mut parts = line.strip_prefix("define")?.split_whitespace();
+
+        if parts.next()? == "_LIBCPP_VERSION" {
+            parts.next()?.parse::<u64>().ok()
+        } else {
+            None
+        }
+    })?;
+
+    let (major, minor, patch) = if value >= 100000 {
+        (value / 10000, value / 100 % 100, value % 100)
+    } else {
+        (value / 1000, value / 100 % 10, value % 100)
+    };
+
+    Some(SdkVersion::from(format!("{major}.{minor}.{patch}")))
 }
 
 /// Obtain the path to SDKs within an Xcode Command Line Tools installation.
 ///
 /// Returns [Some] if we found a path in the expected location or [None] otherwise.
 pub fn command_line_tools_sdks_directory() -> Option<PathBuf> {
+    command_line_tools_sdks_directory_with_filesystem(&RealFilesystem)
+}
+
+/// Obtain the path to SDKs within an Xcode Command Line Tools installation on the given [Filesystem].
+///
+/// This is like [command_line_tools_sdks_directory()] except the location is checked via `fs`.
+pub fn command_line_tools_sdks_directory_with_filesystem(fs: &dyn Filesystem) -> Option<PathBuf> {
     let sdk_path = PathBuf::from(COMMAND_LINE_TOOLS_DEFAULT_PATH).join("SDKs");
 
-    if sdk_path.exists() {
+    if fs.exists(&sdk_path) {
         Some(sdk_path)
     } else {
         None
     }
 }
 
+/// Identifier of the package installing the Xcode Command Line Tools.
+pub const COMMAND_LINE_TOOLS_PACKAGE_ID: &str = "com.apple.pkg.CLTools_Executables";
+
+/// Path to the install receipt of the Xcode Command Line Tools package.
+pub const COMMAND_LINE_TOOLS_RECEIPT_PATH: &str =
+    "/Library/Apple/System/Library/Receipts/com.apple.pkg.CLTools_Executables.plist";
+
+/// Resolve the version of the installed Xcode Command Line Tools.
+///
+/// The version is read from the package install receipt at
+/// [COMMAND_LINE_TOOLS_RECEIPT_PATH] when the `parse` feature is enabled. Otherwise,
+/// or if the receipt could not be found, `pkgutil --pkg-info` is executed.
+///
+/// Package versions have the form `15.1.0.0.1.1700200546`. The returned version
+/// consists of the leading `X.Y.Z` components, which correspond to the Xcode
+/// release the Command Line Tools were derived from.
+///
+/// Returns [None] if the Command Line Tools do not appear to be installed.
+pub fn command_line_tools_version() -> Result<Option<SdkVersion>, Error> {
+    #[cfg(feature = "parse")]
+    {
+        let receipt_path = Path::new(COMMAND_LINE_TOOLS_RECEIPT_PATH);
+
+        if receipt_path.exists() {
+            let value = plist::Value::from_file(receipt_path)?;
+            let value = value.as_dictionary().ok_or(Error::PlistNotDictionary)?;
+
+            let version = value
+                .get("PackageVersion")
+                .ok_or_else(|| Error::PlistKeyMissing("PackageVersion".to_string()))?
+                .as_string()
+                .ok_or_else(|| Error::PlistKeyNotString("PackageVersion".to_string()))?;
+
+            return Ok(Some(package_version_to_sdk_version(version)));
+        }
+    }
+
+    let output = match Command::new("pkgutil")
+        .arg(format!("--pkg-info={COMMAND_LINE_TOOLS_PACKAGE_ID}"))
+        .stderr(Stdio::null())
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(Error::Io(e)),
+    };
+
+    // pkgutil exits non-zero if the package isn't installed.
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(parse_pkgutil_version(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// Path to the plist describing the running macOS release.
+pub const SYSTEM_VERSION_PLIST_PATH: &str = "/System/Library/CoreServices/SystemVersion.plist";
+
+/// Resolve the version of the running macOS.
+///
+/// The version is read from the `ProductVersion` key of [SYSTEM_VERSION_PLIST_PATH]
+/// when the `parse` feature is enabled. Otherwise, or if the plist could not be
+/// found, `sw_vers -productVersion` is executed.
+///
+/// This enables policies like picking an SDK no newer than the host OS:
+///
+/// ```no_run
+/// use apple_sdk::{host_macos_version, Platform, SdkSearch};
+///
+/// let mut search = SdkSearch::default().platform(Platform::MacOsX);
+///
+/// if let Some(version) = host_macos_version()? {
+///     search = search.maximum_version(version);
+/// }
+/// # Ok::<(), apple_sdk::Error>(())
+/// ```
+///
+/// Returns [None] when not running on macOS.
+pub fn host_macos_version() -> Result<Option<SdkVersion>, Error> {
+    if !cfg!(target_os = "macos") {
+        return Ok(None);
+    }
+
+    #[cfg(feature = "parse")]
+    {
+        let plist_path = Path::new(SYSTEM_VERSION_PLIST_PATH);
+
+        if plist_path.exists() {
+            return parse_system_version_plist(plist::Value::from_file(plist_path)?).map(Some);
+        }
+    }
+
+    let output = match Command::new("sw_vers")
+        .arg("-productVersion")
+        .stderr(Stdio::null())
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(Error::Io(e)),
+    };
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    Ok(SdkVersion::from_str(&version).ok())
+}
+
+/// Extract the `ProductVersion` from a `SystemVersion.plist`.
+#[cfg(feature = "parse")]
+fn parse_system_version_plist(value: plist::Value) -> Result<SdkVersion, Error> {
+    let value = value.as_dictionary().ok_or(Error::PlistNotDictionary)?;
+
+    let version = value
+        .get("ProductVersion")
+        .ok_or_else(|| Error::PlistKeyMissing("ProductVersion".to_string()))?
+        .as_string()
+        .ok_or_else(|| Error::PlistKeyNotString("ProductVersion".to_string()))?;
+
+    SdkVersion::from_str(version)
+}
+
+/// Convert bytes from process output or archive headers to an [OsString].
+///
+/// On Unix, paths are arbitrary bytes and are preserved as is. Elsewhere the bytes
+/// are decoded as UTF-8, lossily.
+pub(crate) fn os_string_from_bytes(data: Vec<u8>) -> OsString {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+
+        OsString::from_vec(data)
+    }
+
+    #[cfg(not(unix))]
+    {
+        OsString::from(String::from_utf8_lossy(&data).into_owned())
+    }
+}
+
+/// Obtain the bytes of an [OsStr]. The inverse of [os_string_from_bytes()].
+pub(crate) fn os_str_to_bytes(s: &OsStr) -> Cow<'_, [u8]> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        Cow::Borrowed(s.as_bytes())
+    }
+
+    #[cfg(not(unix))]
+    {
+        match s.to_string_lossy() {
+            Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+            Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+        }
+    }
+}
+
+/// Strip leading and trailing ASCII whitespace from bytes.
+fn trim_ascii_whitespace(data: &[u8]) -> &[u8] {
+    let start = data
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(data.len());
+    let end = data
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(start, |i| i + 1);
+
+    &data[start..end]
+}
+
+/// Extract the package version from `pkgutil --pkg-info` output.
+fn parse_pkgutil_version(output: &str) -> Option<SdkVersion> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("version: "))
+        .map(|version| package_version_to_sdk_version(version.trim()))
+}
+
+/// Convert a package version to an [SdkVersion] of at most 3 components.
+fn package_version_to_sdk_version(version: &str) -> SdkVersion {
+    SdkVersion::from(version.split('.').take(3).collect::<Vec<_>>().join("."))
+}
+
 /// Attempt to resolve all available Xcode applications in an `Applications` directory.
 ///
 /// This function is a convenience method for iterating a directory
-/// and filtering for `Xcode*.app` entries.
+/// and filtering for `Xcode*.app` entries. This includes version suffixed names
+/// like `Xcode-15.2.0.app` and `Xcode_15.2.app`. Only direct children of the
+/// directory are considered. Use [XcodeAppDiscovery] to customize this.
 ///
 /// No guarantee is made about whether the directory constitutes a working
 /// Xcode application.
@@ -699,44 +2118,7 @@ pub fn command_line_tools_sdks_directory() -> Option<PathBuf> {
 /// The results are sorted according to the directory name. However, `Xcode.app` always
 /// sorts first so the default application name is always preferred.
 pub fn find_xcode_apps(applications_dir: &Path) -> Result<Vec<PathBuf>, Error> {
-    let dir = match std::fs::read_dir(applications_dir) {
-        Ok(v) => Ok(v),
-        Err(e) => {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                return Ok(vec![]);
-            } else {
-                Err(Error::from(e))
-            }
-        }
-    }?;
-
-    let mut res = dir
-        .into_iter()
-        .map(|entry| {
-            let entry = entry?;
-
-            let name = entry.file_name();
-            let file_name = name.to_string_lossy();
-
-            if file_name.starts_with("Xcode") && file_name.ends_with(".app") {
-                Ok(Some(entry.path()))
-            } else {
-                Ok(None)
-            }
-        })
-        .collect::<Result<Vec<_>, Error>>()?
-        .into_iter()
-        .flatten()
-        .collect::<Vec<_>>();
-
-    // Make deterministic.
-    res.sort_by(|a, b| match (a.file_name(), b.file_name()) {
-        (Some(x), _) if x == "Xcode.app" => Ordering::Less,
-        (_, Some(x)) if x == "Xcode.app" => Ordering::Greater,
-        (_, _) => a.cmp(b),
-    });
-
-    Ok(res)
+    XcodeAppDiscovery::default().find(applications_dir)
 }
 
 /// Find all system installed Xcode applications.
@@ -747,6 +2129,30 @@ pub fn find_system_xcode_applications() -> Result<Vec<PathBuf>, Error> {
     find_xcode_apps(&PathBuf::from("/Applications"))
 }
 
+/// Obtain the path to the current user's `Applications` directory.
+///
+/// This is `~/Applications`, as resolved from the `HOME` environment variable.
+/// The path is not validated to exist.
+///
+/// Returns [None] if `HOME` isn't defined.
+pub fn user_applications_directory() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Applications"))
+}
+
+/// Find all Xcode applications in the current user's `Applications` directory.
+///
+/// This is a convenience method for [find_xcode_apps()] looking under
+/// [user_applications_directory()]. Xcode is installed here for non-admin users
+/// and by version managers like `xcodes`, which use version suffixed names like
+/// `Xcode-15.2.0.app`.
+pub fn find_user_xcode_applications() -> Result<Vec<PathBuf>, Error> {
+    if let Some(dir) = user_applications_directory() {
+        find_xcode_apps(&dir)
+    } else {
+        Ok(vec![])
+    }
+}
+
 /// Represents an SDK version string.
 ///
 /// This type attempts to apply semantic versioning onto SDK version strings
@@ -755,12 +2161,30 @@ pub fn find_system_xcode_applications() -> Result<Vec<PathBuf>, Error> {
 /// The version string is not validated for correctness at construction time:
 /// any string can be stored.
 ///
-/// The string is interpreted as a `X.Y` or `X.Y.Z` semantic version string
-/// where each component is an integer.
+/// The string is interpreted as a dot delimited sequence of integer components.
+/// e.g. `X.Y`, `X.Y.Z`, or `X.Y.Z.W`. Any data following the integer components
+/// (e.g. the `b1` in `13.0b1`) is preserved as a suffix. See [Self::components()]
+/// and [Self::suffix()].
 ///
-/// For ordering, an invalid string is interpreted as the version `0.0.0` and
-/// therefore should always sort less than a well-formed version.
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// For ordering, components are compared numerically, with missing components
+/// treated as `0`. So `12.3` and `12.3.0` compare equal. If components are equal, a
+/// version with a suffix sorts before the same version without a suffix, as
+/// suffixes typically denote pre-releases. Suffixes are compared with runs of
+/// digits ordered numerically, so `14.0b2` sorts before `14.0b10`. Invalid
+/// strings, which don't start with an integer component, sort before all valid
+/// versions and are compared lexicographically amongst themselves.
+///
+/// Equality and hashing are consistent with ordering. So `12.3` and `12.3.0` are
+/// equal and hash the same, even though their string representations differ.
+///
+/// Instances can be constructed from any string via [From] or from a validated
+/// string via [FromStr].
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(transparent)
+)]
 pub struct SdkVersion {
     value: String,
 }
@@ -795,26 +2219,126 @@ impl From<&String> for SdkVersion {
     }
 }
 
-impl SdkVersion {
-    fn normalized_version(&self) -> Result<(u8, u8, u8), Error> {
-        let ints = self
-            .value
-            .split('.')
-            .map(|x| u8::from_str(x).map_err(|_| Error::VersionParse(self.value.to_string())))
-            .collect::<Result<Vec<_>, Error>>()?;
+impl FromStr for SdkVersion {
+    type Err = Error;
 
-        match ints.len() {
-            1 => Ok((ints[0], 0, 0)),
-            2 => Ok((ints[0], ints[1], 0)),
-            3 => Ok((ints[0], ints[1], ints[2])),
-            _ => Err(Error::VersionParse(self.value.to_string())),
-        }
-    }
+    /// Parse a version string, validating it.
+    ///
+    /// Errors with [Error::VersionParse] unless the string consists of integer
+    /// components optionally followed by an alphanumeric suffix. e.g. `12.3` and
+    /// `13.0b1` are accepted but `foo`, `12.`, and `12.3 beta` are not.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let v = Self::from(s);
+
+        if v.is_valid() && v.suffix().chars().all(|c| c.is_ascii_alphanumeric()) {
+            Ok(v)
+        } else {
+            Err(Error::VersionParse(s.to_string()))
+        }
+    }
+}
+
+impl SdkVersion {
+    /// Split the version string into leading integer components and the remainder.
+    fn parse_components(&self) -> (Vec<u64>, &str) {
+        let mut components = vec![];
+        let mut remaining = self.value.as_str();
+
+        loop {
+            let digits = remaining.len()
+                - remaining
+                    .trim_start_matches(|c: char| c.is_ascii_digit())
+                    .len();
+
+            if digits == 0 {
+                break;
+            }
+
+            match u64::from_str(&remaining[0..digits]) {
+                Ok(v) => components.push(v),
+                Err(_) => break,
+            }
+
+            remaining = &remaining[digits..];
+
+            // Only consume a separator if another component follows.
+            match remaining.strip_prefix('.') {
+                Some(rest) if rest.starts_with(|c: char| c.is_ascii_digit()) => {
+                    remaining = rest;
+                }
+                _ => break,
+            }
+        }
+
+        (components, remaining)
+    }
+
+    /// The leading integer components of this version.
+    ///
+    /// e.g. `12.3.1` yields `[12, 3, 1]` and `13.0b1` yields `[13, 0]`.
+    ///
+    /// Returns an empty vector if the version doesn't start with an integer.
+    pub fn components(&self) -> Vec<u64> {
+        self.parse_components().0
+    }
+
+    /// Data following the integer components of this version.
+    ///
+    /// e.g. `13.0b1` yields `b1`. Well-formed versions have an empty suffix.
+    pub fn suffix(&self) -> &str {
+        self.parse_components().1
+    }
+
+    /// Whether this version starts with an integer component.
+    ///
+    /// Invalid versions sort before all valid versions.
+    pub fn is_valid(&self) -> bool {
+        !self.components().is_empty()
+    }
+
+    /// Resolve the version as a `(X, Y, Z)` tuple.
+    ///
+    /// Versions with fewer than 3 components have missing components filled
+    /// with `0`. Components beyond the third are ignored. Errors if the version
+    /// has a suffix or a component doesn't fit in a `u8`.
+    pub(crate) fn normalized_version(&self) -> Result<(u8, u8, u8), Error> {
+        let (components, suffix) = self.parse_components();
+
+        if components.is_empty() || !suffix.is_empty() {
+            return Err(Error::VersionParse(self.value.to_string()));
+        }
+
+        let ints = components
+            .into_iter()
+            .take(3)
+            .map(|x| u8::try_from(x).map_err(|_| Error::VersionParse(self.value.to_string())))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok((
+            ints[0],
+            ints.get(1).copied().unwrap_or_default(),
+            ints.get(2).copied().unwrap_or_default(),
+        ))
+    }
+
+    /// Whether this version is the same as or newer than another version.
+    ///
+    /// This uses the ordering of [SdkVersion]. So `14.2.0` is at least `14.2`.
+    pub fn is_at_least(&self, other: impl Into<SdkVersion>) -> bool {
+        self >= &other.into()
+    }
+
+    /// Whether this version is the same as or older than another version.
+    ///
+    /// This uses the ordering of [SdkVersion]. So `14.2` is at most `14.2.0`.
+    pub fn is_at_most(&self, other: impl Into<SdkVersion>) -> bool {
+        self <= &other.into()
+    }
 
     /// Resolve a version string that adheres to Rust's semantic version string format.
     ///
     /// The returned string will have the form `X.Y.Z` where all components are
-    /// integers.
+    /// integers. Components beyond the third are dropped.
     pub fn semantic_version(&self) -> Result<String, Error> {
         let (x, y, z) = self.normalized_version()?;
 
@@ -822,6 +2346,32 @@ impl SdkVersion {
     }
 }
 
+impl PartialEq for SdkVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for SdkVersion {}
+
+impl Hash for SdkVersion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let (mut components, suffix) = self.parse_components();
+
+        if components.is_empty() {
+            self.value.hash(state);
+        } else {
+            // Trailing zero components don't affect ordering.
+            while components.last() == Some(&0) {
+                components.pop();
+            }
+
+            components.hash(state);
+            suffix.hash(state);
+        }
+    }
+}
+
 impl PartialOrd for SdkVersion {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -830,15 +2380,90 @@ impl PartialOrd for SdkVersion {
 
 impl Ord for SdkVersion {
     fn cmp(&self, other: &Self) -> Ordering {
-        let a = self.normalized_version().unwrap_or((0, 0, 0));
-        let b = other.normalized_version().unwrap_or((0, 0, 0));
+        let (a, a_suffix) = self.parse_components();
+        let (b, b_suffix) = other.parse_components();
+
+        match (a.is_empty(), b.is_empty()) {
+            (true, true) => return self.value.cmp(&other.value),
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            (false, false) => {}
+        }
+
+        for i in 0..a.len().max(b.len()) {
+            let a = a.get(i).copied().unwrap_or_default();
+            let b = b.get(i).copied().unwrap_or_default();
+
+            match a.cmp(&b) {
+                Ordering::Equal => {}
+                res => return res,
+            }
+        }
+
+        match (a_suffix.is_empty(), b_suffix.is_empty()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => compare_version_suffix(a_suffix, b_suffix),
+        }
+    }
+}
+
+/// Compare version suffixes, ordering runs of digits numerically.
+///
+/// So `b2` sorts before `b10`. Suffixes whose runs compare equal, like `b2` and
+/// `b02`, are ordered by their strings.
+fn compare_version_suffix(a: &str, b: &str) -> Ordering {
+    fn runs(s: &str) -> impl Iterator<Item = &str> {
+        let mut remaining = s;
+
+        std::iter::from_fn(move || {
+            let first = remaining.chars().next()?;
+            let end = remaining
+                .find(|c: char| c.is_ascii_digit() != first.is_ascii_digit())
+                .unwrap_or(remaining.len());
+            let (run, rest) = remaining.split_at(end);
+            remaining = rest;
+
+            Some(run)
+        })
+    }
+
+    let mut a_runs = runs(a);
+    let mut b_runs = runs(b);
+
+    loop {
+        let res = match (a_runs.next(), b_runs.next()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => {
+                let a_digits = a.trim_start_matches('0');
+                let b_digits = b.trim_start_matches('0');
+
+                if a.starts_with(|c: char| c.is_ascii_digit())
+                    && b.starts_with(|c: char| c.is_ascii_digit())
+                {
+                    // Numbers with more significant digits are larger.
+                    a_digits
+                        .len()
+                        .cmp(&b_digits.len())
+                        .then_with(|| a_digits.cmp(b_digits))
+                } else {
+                    a.cmp(b)
+                }
+            }
+        };
 
-        a.cmp(&b)
+        if res.is_ne() {
+            return res;
+        }
     }
 }
 
 /// Represents an SDK path with metadata parsed from the path.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct SdkPath {
     /// The filesystem path.
     pub path: PathBuf,
@@ -888,19 +2513,7 @@ impl SdkPath {
 
         // prefix can be a platform name (e.g. `MacOSX`) or a platform name + version
         // (e.g. `MacOSX12.4`).
-        let (platform_name, version) = if let Some(first_digit) = prefix
-            .chars()
-            .enumerate()
-            .find_map(|(i, c)| if c.is_numeric() { Some(i) } else { None })
-        {
-            let (name, version) = prefix.split_at(first_digit);
-
-            (name, Some(version.to_string().into()))
-        } else {
-            (prefix, None)
-        };
-
-        let platform = Platform::from_str(platform_name)?;
+        let (platform, version) = split_platform_version(prefix)?;
 
         Ok(Self {
             path,
@@ -908,6 +2521,183 @@ impl SdkPath {
             version,
         })
     }
+
+    /// The canonical SDK directory name for this platform and version.
+    ///
+    /// e.g. `MacOSX14.2.sdk`, or `MacOSX.sdk` if there is no version. This is
+    /// derived from [Self::platform] and [Self::version], not [Self::path]. Passing a
+    /// path with this name to [Self::from_path()] yields the same platform and version.
+    pub fn directory_name(&self) -> String {
+        format!(
+            "{}{}.sdk",
+            self.platform.filesystem_name(),
+            self.version
+                .as_ref()
+                .map(|v| v.to_string())
+                .unwrap_or_default()
+        )
+    }
+}
+
+/// Split a string like `MacOSX12.4` or `macosx` into a platform and optional version.
+///
+/// The version begins at the first numeric character.
+fn split_platform_version(s: &str) -> Result<(Platform, Option<SdkVersion>), Error> {
+    let (platform_name, version) = if let Some(first_digit) = s
+        .chars()
+        .enumerate()
+        .find_map(|(i, c)| if c.is_numeric() { Some(i) } else { None })
+    {
+        let (name, version) = s.split_at(first_digit);
+
+        (name, Some(version.to_string().into()))
+    } else {
+        (s, None)
+    };
+
+    Ok((Platform::from_str(platform_name)?, version))
+}
+
+/// An SDK name as accepted by `xcrun --sdk` and `xcodebuild -sdk`.
+///
+/// e.g. `macosx`, `iphoneos17.0`, or `iphonesimulator`. Names consist of a
+/// lowercase platform name optionally followed by an SDK version. Parsing is
+/// case insensitive.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SdkName {
+    /// The platform the SDK is for.
+    pub platform: Platform,
+
+    /// The SDK version, if one was present.
+    pub version: Option<SdkVersion>,
+}
+
+impl Display for SdkName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.platform.filesystem_name().to_ascii_lowercase())?;
+
+        if let Some(version) = &self.version {
+            f.write_str(version.as_ref())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for SdkName {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (platform, version) = split_platform_version(s)?;
+
+        if matches!(platform, Platform::Unknown(_)) {
+            return Err(Error::SdkNameParse(s.to_string()));
+        }
+
+        if let Some(version) = &version {
+            if !version.is_valid() {
+                return Err(Error::SdkNameParse(s.to_string()));
+            }
+        }
+
+        Ok(Self { platform, version })
+    }
+}
+
+impl SdkName {
+    /// Whether an SDK matches this name.
+    ///
+    /// Platforms must be equal. If this name has a version, the SDK's version
+    /// must be equivalent to it.
+    pub fn matches<SDK: AppleSdk>(&self, sdk: &SDK) -> bool {
+        if sdk.platform() != &self.platform {
+            return false;
+        }
+
+        match (&self.version, sdk.version()) {
+            (Some(wanted), Some(have)) => wanted.cmp(have) == std::cmp::Ordering::Equal,
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+}
+
+/// The kind of file a library in an SDK resolves to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SdkLibraryKind {
+    /// A `.tbd` text stub.
+    ///
+    /// This is how SDKs typically ship system libraries.
+    TextStub,
+    /// A Mach-O dynamic library.
+    Dylib,
+}
+
+/// A library resolved within an SDK.
+///
+/// See [AppleSdk::resolve_library()] and [AppleSdk::resolve_framework()].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SdkLibrary {
+    /// The filesystem path to the library file.
+    pub path: PathBuf,
+
+    /// The kind of library file.
+    pub kind: SdkLibraryKind,
+}
+
+impl SdkLibrary {
+    /// Resolve the first existing candidate file in a directory.
+    fn find_candidate(dir: &Path, candidates: &[(String, SdkLibraryKind)]) -> Option<Self> {
+        candidates.iter().find_map(|(filename, kind)| {
+            let path = dir.join(filename);
+
+            if path.is_file() {
+                Some(Self { path, kind: *kind })
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// A problem with the structure of an SDK directory.
+///
+/// See [AppleSdk::validate()].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SdkProblem {
+    /// Neither `SDKSettings.json` nor `SDKSettings.plist` exists.
+    MissingSdkSettings,
+    /// The SDK settings file could not be parsed.
+    ///
+    /// The string describes the parse error.
+    InvalidSdkSettings(String),
+    /// An expected directory does not exist.
+    ///
+    /// The path is relative to the SDK root.
+    MissingDirectory(PathBuf),
+    /// An expected directory exists but is empty.
+    ///
+    /// The path is relative to the SDK root.
+    EmptyDirectory(PathBuf),
+}
+
+impl Display for SdkProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingSdkSettings => {
+                f.write_str("missing SDKSettings.json or SDKSettings.plist")
+            }
+            Self::InvalidSdkSettings(err) => {
+                f.write_fmt(format_args!("invalid SDK settings: {err}"))
+            }
+            Self::MissingDirectory(p) => {
+                f.write_fmt(format_args!("missing directory: {}", p.display()))
+            }
+            Self::EmptyDirectory(p) => {
+                f.write_fmt(format_args!("empty directory: {}", p.display()))
+            }
+        }
+    }
 }
 
 /// Defines common behavior for types representing Apple SDKs.
@@ -918,6 +2708,16 @@ pub trait AppleSdk: Sized + AsRef<Path> {
     /// [Error::Io] if the input path is not an Apple SDK.
     fn from_directory(path: &Path) -> Result<Self, Error>;
 
+    /// Attempt to construct an instance from a directory on the given [Filesystem].
+    ///
+    /// This is used by [SdkSearch] so searches can run against a [MemoryFilesystem].
+    /// The default implementation ignores `fs` and calls [Self::from_directory()].
+    fn from_directory_with_filesystem(path: &Path, fs: &dyn Filesystem) -> Result<Self, Error> {
+        let _ = fs;
+
+        Self::from_directory(path)
+    }
+
     /// Find Apple SDKs in a specified directory.
     ///
     /// Directory entries are often symlinks pointing to other directories.
@@ -925,23 +2725,32 @@ pub trait AppleSdk: Sized + AsRef<Path> {
     /// the case. Callers may want to filter out symlinked SDKs to avoid
     /// duplicates.
     fn find_in_directory(root: &Path) -> Result<Vec<Self>, Error> {
-        let dir = match std::fs::read_dir(root) {
+        Self::find_in_directory_with_filesystem(root, &RealFilesystem)
+    }
+
+    /// Find Apple SDKs in a directory on the given [Filesystem].
+    ///
+    /// This is like [Self::find_in_directory()] except SDKs are loaded via
+    /// [Self::from_directory_with_filesystem()].
+    fn find_in_directory_with_filesystem(
+        root: &Path,
+        fs: &dyn Filesystem,
+    ) -> Result<Vec<Self>, Error> {
+        let dir = match fs.read_dir(root) {
             Ok(v) => Ok(v),
             Err(e) => {
                 if e.kind() == std::io::ErrorKind::NotFound {
                     return Ok(vec![]);
                 } else {
-                    Err(Error::from(e))
+                    Err(Error::IoPath(root.to_path_buf(), e))
                 }
             }
         }?;
 
         let mut res = vec![];
 
-        for entry in dir {
-            let entry = entry?;
-
-            match Self::from_directory(&entry.path()) {
+        for path in dir {
+            match Self::from_directory_with_filesystem(&path, fs) {
                 Ok(sdk) => {
                     res.push(sdk);
                 }
@@ -977,6 +2786,43 @@ pub trait AppleSdk: Sized + AsRef<Path> {
         }
     }
 
+    /// Obtain the canonical name of this SDK. e.g. `macosx14.2`.
+    ///
+    /// This is the name accepted by `xcrun --sdk` and `xcodebuild -sdk`. The
+    /// default implementation derives it from the platform and version. If the
+    /// version isn't known, only the platform name is returned. e.g. `macosx`.
+    fn canonical_name(&self) -> String {
+        SdkName {
+            platform: self.platform().clone(),
+            version: self.version().cloned(),
+        }
+        .to_string()
+    }
+
+    /// Obtain a human readable name of this SDK. e.g. `macOS 14.2`.
+    ///
+    /// This is suitable for UI and log output. The default implementation derives it
+    /// from [Platform::display_name()] and the version.
+    fn display_name(&self) -> String {
+        if let Some(version) = self.version() {
+            format!("{} {version}", self.platform().display_name())
+        } else {
+            self.platform().display_name().to_string()
+        }
+    }
+
+    /// Obtain a shortened human readable name of this SDK. e.g. `14.2`.
+    ///
+    /// The default implementation returns the version, falling back to
+    /// [Platform::display_name()] if the version isn't known.
+    fn minimal_display_name(&self) -> String {
+        if let Some(version) = self.version() {
+            version.to_string()
+        } else {
+            self.platform().display_name().to_string()
+        }
+    }
+
     #[deprecated(since = "0.1.1", note = "plase use `sdk_path` instead")]
     fn as_sdk_path(&self) -> SdkPath {
         self.sdk_path()
@@ -1006,68 +2852,1306 @@ pub trait AppleSdk: Sized + AsRef<Path> {
         target_name: &str,
         target_version: &SdkVersion,
     ) -> Result<bool, Error>;
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn find_system_xcode_applications() -> Result<(), Error> {
-        let res = crate::find_system_xcode_applications()?;
+    /// The filesystem path to the root of this SDK's headers, libraries, and frameworks.
+    ///
+    /// This is the SDK directory for most SDKs. DriverKit SDKs hold their content
+    /// under `System/DriverKit`, and that directory is returned for them.
+    fn content_path(&self) -> PathBuf {
+        if self.platform() == &Platform::DriverKit {
+            self.path().join("System").join("DriverKit")
+        } else {
+            self.path().to_path_buf()
+        }
+    }
 
-        if PathBuf::from(XCODE_APP_DEFAULT_PATH).exists() {
-            assert!(!res.is_empty());
+    /// Resolve the path to a header file within this SDK.
+    ///
+    /// `header` is the path as it would appear in an `#include` directive.
+    /// e.g. `zlib.h` or `CoreFoundation/CoreFoundation.h`.
+    ///
+    /// Paths are relative to [Self::content_path()].
+    ///
+    /// The header is first looked for in the SDK's `usr/include` directory. If
+    /// not found there and the path has a leading directory component, that
+    /// component is treated as a framework name and the header is looked for in
+    /// the `Headers` directory of that framework under `System/Library/Frameworks`.
+    /// e.g. `CoreFoundation/CoreFoundation.h` resolves to
+    /// `System/Library/Frameworks/CoreFoundation.framework/Headers/CoreFoundation.h`.
+    ///
+    /// Returns [None] if the header doesn't exist in the SDK.
+    fn find_header(&self, header: &str) -> Option<PathBuf> {
+        let include = self.content_path().join("usr").join("include").join(header);
+        if include.is_file() {
+            return Some(include);
         }
 
-        Ok(())
-    }
+        let (framework, rest) = header.split_once('/')?;
 
-    #[test]
-    fn find_system_xcode_developer_directories() -> Result<(), Error> {
-        let res = DeveloperDirectory::find_system_xcodes()?;
+        let framework_header = self
+            .content_path()
+            .join("System")
+            .join("Library")
+            .join("Frameworks")
+            .join(format!("{framework}.framework"))
+            .join("Headers")
+            .join(rest);
 
-        if PathBuf::from(XCODE_APP_DEFAULT_PATH).exists() {
-            assert!(!res.is_empty());
+        if framework_header.is_file() {
+            Some(framework_header)
+        } else {
+            None
         }
+    }
 
-        Ok(())
+    /// Resolve a library by name within this SDK.
+    ///
+    /// `name` is the library name as passed to the linker via `-l`. e.g. `System`
+    /// or `z`. The library is looked for in the SDK's `usr/lib` directory, preferring
+    /// a `lib<name>.tbd` text stub over a `lib<name>.dylib`. e.g. `System` resolves
+    /// to `usr/lib/libSystem.tbd`. Paths are relative to [Self::content_path()].
+    ///
+    /// Returns [None] if the library doesn't exist in the SDK.
+    fn resolve_library(&self, name: &str) -> Option<SdkLibrary> {
+        SdkLibrary::find_candidate(
+            &self.content_path().join("usr").join("lib"),
+            &[
+                (format!("lib{name}.tbd"), SdkLibraryKind::TextStub),
+                (format!("lib{name}.dylib"), SdkLibraryKind::Dylib),
+            ],
+        )
     }
 
-    #[test]
-    fn find_all_platform_directories() -> Result<(), Error> {
-        for dir in DeveloperDirectory::find_system_xcodes()? {
-            for platform in dir.platforms()? {
-                // Paths should agree.
-                assert_eq!(
-                    platform.path,
-                    dir.platforms_path().join(platform.directory_name())
-                );
-                assert_eq!(
-                    platform.path,
-                    platform.path_in_developer_directory(dir.path())
-                );
+    /// Resolve a framework library by name within this SDK.
+    ///
+    /// `name` is the framework name as passed to the linker via `-framework`.
+    /// e.g. `CoreFoundation`. The framework is looked for under
+    /// `System/Library/Frameworks`, preferring a `<name>.tbd` text stub over a
+    /// `<name>` dynamic library in the framework directory. Paths are relative to
+    /// [Self::content_path()].
+    ///
+    /// Returns [None] if the framework doesn't exist in the SDK.
+    fn resolve_framework(&self, name: &str) -> Option<SdkLibrary> {
+        SdkLibrary::find_candidate(
+            &self
+                .content_path()
+                .join("System")
+                .join("Library")
+                .join("Frameworks")
+                .join(format!("{name}.framework")),
+            &[
+                (format!("{name}.tbd"), SdkLibraryKind::TextStub),
+                (name.to_string(), SdkLibraryKind::Dylib),
+            ],
+        )
+    }
 
-                // Ensure we're able to parse all platform types in existence. We want
-                // this to fail when Apple introduces new platforms so we can implement
-                // support for the new platform!
-                assert!(!matches!(platform.platform, Platform::Unknown(_)));
-            }
-        }
+    /// Resolve a Swift module by name within this SDK.
+    ///
+    /// `name` is the module name as it would appear in an `import` statement.
+    /// e.g. `Foundation` or `_Concurrency`. The module is first looked for as
+    /// `usr/lib/swift/<name>.swiftmodule`. If not found there, it is looked for in
+    /// the `Modules` directory of the `<name>` framework under
+    /// `System/Library/Frameworks`. Paths are relative to [Self::content_path()].
+    ///
+    /// The returned path is the `.swiftmodule` directory. Use
+    /// [Self::find_swift_interface()] to find the interface for an architecture.
+    ///
+    /// Returns [None] if the SDK doesn't provide the module.
+    fn find_swift_module(&self, name: &str) -> Option<PathBuf> {
+        let filename = format!("{name}.swiftmodule");
+
+        [
+            self.content_path().join("usr").join("lib").join("swift"),
+            self.content_path()
+                .join("System")
+                .join("Library")
+                .join("Frameworks")
+                .join(format!("{name}.framework"))
+                .join("Modules"),
+        ]
+        .into_iter()
+        .map(|dir| dir.join(&filename))
+        .find(|path| path.exists())
+    }
 
-        Ok(())
+    /// Whether this SDK provides a Swift module.
+    ///
+    /// See [Self::find_swift_module()].
+    fn has_swift_module(&self, name: &str) -> bool {
+        self.find_swift_module(name).is_some()
     }
 
-    #[test]
-    fn apple_platform() -> Result<(), Error> {
-        assert_eq!(Platform::from_str("macosx")?, Platform::MacOsX);
-        assert_eq!(Platform::from_str("MacOSX")?, Platform::MacOsX);
+    /// Resolve the `.swiftinterface` file of a Swift module for an architecture.
+    ///
+    /// `arch` is the Apple name of the machine architecture. e.g. `arm64`.
+    /// Interfaces are named after the unversioned target triple. e.g.
+    /// `arm64-apple-macos.swiftinterface` or
+    /// `x86_64-apple-ios-simulator.swiftinterface`. Interfaces named after the
+    /// architecture only (`arm64.swiftinterface`), as found in older SDKs, are
+    /// also recognized.
+    ///
+    /// Returns [None] if the module or its interface for the architecture doesn't
+    /// exist in the SDK.
+    fn find_swift_interface(&self, name: &str, arch: &str) -> Option<PathBuf> {
+        let module = self.find_swift_module(name)?;
+
+        let sys = match self.platform().llvm_target_sys()? {
+            "macosx" => "macos",
+            sys => sys,
+        };
+        let triple = if let Some(env) = self.platform().llvm_target_environment() {
+            format!("{arch}-apple-{sys}-{env}")
+        } else {
+            format!("{arch}-apple-{sys}")
+        };
 
-        Ok(())
+        [triple, arch.to_string()]
+            .into_iter()
+            .map(|stem| module.join(format!("{stem}.swiftinterface")))
+            .find(|path| path.is_file())
     }
 
-    #[test]
-    fn target_platform() -> Result<(), Error> {
+    /// The path to the libc++ headers in this SDK, if present.
+    ///
+    /// This is the `usr/include/c++/v1` directory relative to [Self::content_path()].
+    /// It is passed to compilers via `-isystem` or found implicitly when using
+    /// `-stdlib=libc++`.
+    fn libcxx_headers_path(&self) -> Option<PathBuf> {
+        let path = self
+            .content_path()
+            .join("usr")
+            .join("include")
+            .join("c++")
+            .join("v1");
+
+        if path.is_dir() {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    /// Whether this SDK carries libc++ headers.
+    ///
+    /// See [Self::libcxx_headers_path()].
+    fn has_libcxx_headers(&self) -> bool {
+        self.libcxx_headers_path().is_some()
+    }
+
+    /// Resolve the LLVM version of the libc++ headers in this SDK.
+    ///
+    /// The version is derived from the `_LIBCPP_VERSION` define in the `__config`
+    /// header. e.g. `170006` is `17.0.6` and `15000` is `15.0.0`. Apple's libc++
+    /// tracks upstream LLVM, so this indicates which C++ standard library features
+    /// the SDK provides.
+    ///
+    /// Returns [None] if the SDK has no libc++ headers or the version isn't defined.
+    fn libcxx_version(&self) -> Result<Option<SdkVersion>, Error> {
+        let Some(headers) = self.libcxx_headers_path() else {
+            return Ok(None);
+        };
+
+        let config_path = headers.join("__config");
+        if !config_path.is_file() {
+            return Ok(None);
+        }
+
+        let data = std::fs::read(&config_path).map_err(Error::io_path(&config_path))?;
+
+        Ok(parse_libcpp_version(&String::from_utf8_lossy(&data)))
+    }
+
+    /// Verify this SDK has the expected directory structure.
+    ///
+    /// This checks for SDK settings, a non-empty `usr/include` and `usr/lib`,
+    /// and a `System/Library/Frameworks` directory. These directories are expected
+    /// under [Self::content_path()], which is `System/DriverKit` for DriverKit
+    /// SDKs. With the `parse` feature,
+    /// the SDK settings are also parsed.
+    ///
+    /// This is useful for detecting half-extracted or incompletely copied SDKs,
+    /// which otherwise fail later with confusing compiler errors.
+    ///
+    /// Returns all found problems. An empty result means the SDK looks complete.
+    fn validate(&self) -> Vec<SdkProblem> {
+        let mut problems = vec![];
+
+        let path = self.path();
+
+        if !path.join("SDKSettings.json").exists() && !path.join("SDKSettings.plist").exists() {
+            problems.push(SdkProblem::MissingSdkSettings);
+        } else {
+            #[cfg(feature = "parse")]
+            if let Err(e) = ParsedSdk::from_directory(path) {
+                problems.push(SdkProblem::InvalidSdkSettings(e.to_string()));
+            }
+        }
+
+        let root = self
+            .content_path()
+            .strip_prefix(path)
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default();
+
+        for (relative, require_entries) in [
+            ("usr/include", true),
+            ("usr/lib", true),
+            ("System/Library/Frameworks", false),
+        ] {
+            let relative = root.join(relative);
+
+            match std::fs::read_dir(path.join(&relative)) {
+                Ok(mut entries) => {
+                    if require_entries && entries.next().is_none() {
+                        problems.push(SdkProblem::EmptyDirectory(relative));
+                    }
+                }
+                Err(_) => problems.push(SdkProblem::MissingDirectory(relative)),
+            }
+        }
+
+        problems
+    }
+
+    /// Resolve the *Developer Directory* this SDK belongs to.
+    ///
+    /// This recognizes SDKs in platform directories (e.g.
+    /// `<developer>/Platforms/MacOSX.platform/Developer/SDKs/MacOSX14.2.sdk`) and in
+    /// the `SDKs` directory of Xcode Command Line Tools style installs. Only the path
+    /// is inspected for the former. The latter requires a `usr/bin` directory
+    /// next to `SDKs`.
+    ///
+    /// Returns [None] if the SDK isn't in a recognized layout.
+    fn developer_directory(&self) -> Option<DeveloperDirectory> {
+        let sdks = self.path().parent()?;
+
+        if sdks.file_name()? != "SDKs" {
+            return None;
+        }
+
+        let parent = sdks.parent()?;
+
+        if parent.file_name() == Some("Developer".as_ref()) {
+            let platform = parent.parent()?;
+            let platforms = platform.parent()?;
+
+            if platform.extension() == Some("platform".as_ref())
+                && platforms.file_name() == Some("Platforms".as_ref())
+            {
+                return platforms.parent().map(DeveloperDirectory::from);
+            }
+        }
+
+        if parent.join("usr").join("bin").is_dir() {
+            Some(DeveloperDirectory::from(parent))
+        } else {
+            None
+        }
+    }
+
+    /// Obtain environment variables conveying this SDK to Apple's tools.
+    ///
+    /// This always includes `SDKROOT`. `DEVELOPER_DIR` is included if
+    /// [Self::developer_directory()] resolves. If a deployment target is given, its
+    /// platform's variable is included. e.g. `MACOSX_DEPLOYMENT_TARGET`.
+    ///
+    /// These are the variables `xcrun` defines when running a tool.
+    fn environment_variables(
+        &self,
+        deployment_target: Option<&DeploymentTarget>,
+    ) -> Vec<(OsString, OsString)> {
+        let mut vars = vec![("SDKROOT".into(), self.path().as_os_str().to_os_string())];
+
+        if let Some(dir) = self.developer_directory() {
+            vars.push((
+                "DEVELOPER_DIR".into(),
+                dir.path().as_os_str().to_os_string(),
+            ));
+        }
+
+        if let Some(target) = deployment_target {
+            if let Some(name) = target.platform().deployment_target_environment_variable() {
+                vars.push((name.into(), target.version().to_string().into()));
+            }
+        }
+
+        vars
+    }
+
+    /// Obtain a [Command] running a program with this SDK's environment applied.
+    ///
+    /// The variables from [Self::environment_variables()] are set on the command.
+    /// The command is not spawned, so callers can customize it further.
+    ///
+    /// This is similar to running a tool via `xcrun --sdk`. However, `program` is
+    /// not resolved against the SDK's toolchains.
+    fn command(
+        &self,
+        program: impl AsRef<OsStr>,
+        deployment_target: Option<&DeploymentTarget>,
+    ) -> Command {
+        let mut command = Command::new(program);
+        command.envs(self.environment_variables(deployment_target));
+
+        command
+    }
+
+    /// Obtain `clang` arguments to compile against this SDK.
+    ///
+    /// `arch` is the Apple name of the machine architecture to target. e.g. `arm64`.
+    /// `deployment_target` is the minimum OS version to target.
+    ///
+    /// Returns `-isysroot <path>` and `-target <triple>` arguments. See
+    /// [Platform::llvm_target_triple()] for how the triple is derived.
+    ///
+    /// For DriverKit SDKs, `deployment_target` is a DriverKit version (e.g. `19.0`),
+    /// not a macOS version. See [DeploymentTarget::to_driverkit()]. The compiler
+    /// resolves headers and frameworks under `System/DriverKit` for these targets.
+    fn compiler_flags(
+        &self,
+        arch: &str,
+        deployment_target: &SdkVersion,
+    ) -> Result<Vec<OsString>, Error> {
+        let triple = self
+            .platform()
+            .llvm_target_triple(arch, deployment_target)
+            .ok_or(Error::FunctionalityNotSupported(
+                "compiler flags for unknown platforms",
+            ))?;
+
+        Ok(vec![
+            "-isysroot".into(),
+            self.path().as_os_str().to_os_string(),
+            "-target".into(),
+            triple.into(),
+        ])
+    }
+
+    /// Obtain `swiftc` arguments to compile against this SDK.
+    ///
+    /// This is like [Self::compiler_flags()] except the SDK is specified via `-sdk`.
+    ///
+    /// DriverKit doesn't support Swift. [Error::FunctionalityNotSupported] is
+    /// returned for DriverKit SDKs.
+    fn swift_compiler_flags(
+        &self,
+        arch: &str,
+        deployment_target: &SdkVersion,
+    ) -> Result<Vec<OsString>, Error> {
+        if self.platform() == &Platform::DriverKit {
+            return Err(Error::FunctionalityNotSupported("Swift for DriverKit"));
+        }
+
+        let triple = self
+            .platform()
+            .llvm_target_triple(arch, deployment_target)
+            .ok_or(Error::FunctionalityNotSupported(
+                "compiler flags for unknown platforms",
+            ))?;
+
+        Ok(vec![
+            "-sdk".into(),
+            self.path().as_os_str().to_os_string(),
+            "-target".into(),
+            triple.into(),
+        ])
+    }
+
+    /// Configure a [cc::Build] to compile against this SDK.
+    ///
+    /// The build target is set to the Rust target triple of `arch` on this SDK's
+    /// platform via [cc::Build::target()], so `cc` emits its own target flag. The
+    /// SDK is added with `-isysroot` and `deployment_target` with a
+    /// `-m*-version-min` flag. Requires the `cc` crate feature.
+    ///
+    /// Returns [Error::FunctionalityNotSupported] for platforms Rust or `clang`
+    /// flags can't target, such as DriverKit.
+    #[cfg(feature = "cc")]
+    fn configure_cc_build(
+        &self,
+        build: &mut cc::Build,
+        arch: &str,
+        deployment_target: &SdkVersion,
+    ) -> Result<(), Error> {
+        let target = Architecture::from_str(arch)?
+            .rust_target_triple(self.platform())
+            .ok_or(Error::FunctionalityNotSupported(
+                "cc builds for targets without a Rust target triple",
+            ))?;
+        let version_min =
+            self.platform()
+                .clang_version_min_flag()
+                .ok_or(Error::FunctionalityNotSupported(
+                    "cc builds for platforms without a minimum version flag",
+                ))?;
+        let sysroot = self
+            .path()
+            .to_str()
+            .ok_or(Error::FunctionalityNotSupported(
+                "non UTF-8 SDK paths with the cc crate",
+            ))?;
+
+        build
+            .target(target)
+            .flag("-isysroot")
+            .flag(sysroot)
+            .flag(&format!("{version_min}={deployment_target}"));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Write an SDK directory `name` into `dir`.
+    ///
+    /// The SDK holds an empty `SDKSettings.json` plus an empty file for each of `files`,
+    /// which are relative to the SDK directory.
+    pub(crate) fn write_sdk(dir: &Path, name: &str, files: &[&str]) -> Result<PathBuf, Error> {
+        let sdk_path = dir.join(name);
+        std::fs::create_dir_all(&sdk_path)?;
+        std::fs::write(sdk_path.join("SDKSettings.json"), b"{}")?;
+
+        for file in files {
+            let path = sdk_path.join(file);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, b"")?;
+        }
+
+        Ok(sdk_path)
+    }
+
+    #[cfg(feature = "parse")]
+    #[test]
+    fn parse_platform_plist() -> Result<(), Error> {
+        const INFO_PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>CFBundleIdentifier</key>
+	<string>com.apple.platform.iphoneos</string>
+	<key>CFBundleName</key>
+	<string>iPhoneOS</string>
+	<key>CFBundleShortVersionString</key>
+	<string>1.0</string>
+	<key>DTPlatformBuild</key>
+	<string>21C52</string>
+	<key>DTPlatformVersion</key>
+	<string>17.2</string>
+	<key>DTSDKBuild</key>
+	<string>21C52</string>
+	<key>DTXcode</key>
+	<string>1520</string>
+	<key>DTXcodeBuild</key>
+	<string>15C500b</string>
+</dict>
+</plist>"#;
+
+        let mut dir = PlatformDirectory::from_path("/does/not/exist/iPhoneOS.platform")?;
+        assert_eq!(dir.platform, Platform::IPhoneOs);
+        assert!(dir.identifier.is_none());
+
+        dir.load_plist(plist::Value::from_reader_xml(INFO_PLIST.as_bytes())?)?;
+        assert_eq!(
+            dir.identifier.as_deref(),
+            Some("com.apple.platform.iphoneos")
+        );
+        assert_eq!(dir.version, Some(SdkVersion::from("17.2")));
+        assert_eq!(dir.build.as_deref(), Some("21C52"));
+        assert_eq!(dir.sdk_build.as_deref(), Some("21C52"));
+        assert_eq!(dir.xcode_version, Some(SdkVersion::from("15.2")));
+        assert_eq!(dir.xcode_build.as_deref(), Some("15C500b"));
+
+        // Broken metadata is ignored rather than failing platform discovery.
+        let temp = tempfile::tempdir()?;
+        let platform_path = temp.path().join("Platforms/iPhoneOS.platform");
+        std::fs::create_dir_all(&platform_path)?;
+        std::fs::write(platform_path.join("Info.plist"), b"malformed")?;
+        std::fs::write(platform_path.join("version.plist"), INFO_PLIST)?;
+
+        let platforms = DeveloperDirectory::from(temp.path()).platforms()?;
+        assert_eq!(platforms.len(), 1);
+        assert_eq!(platforms[0].version, Some(SdkVersion::from("17.2")));
+
+        std::fs::write(platform_path.join("version.plist"), b"malformed")?;
+        let platforms = DeveloperDirectory::from(temp.path()).platforms()?;
+        assert_eq!(platforms.len(), 1);
+        assert_eq!(platforms[0].version, None);
+        assert_eq!(platforms[0].identifier, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn dt_xcode() {
+        assert_eq!(parse_dt_xcode("1520"), Some(SdkVersion::from("15.2")));
+        assert_eq!(parse_dt_xcode("0941"), Some(SdkVersion::from("9.4.1")));
+        assert_eq!(parse_dt_xcode("1600"), Some(SdkVersion::from("16.0")));
+
+        for value in ["1520", "0941", "1600"] {
+            assert_eq!(
+                format_dt_xcode(&parse_dt_xcode(value).unwrap()).as_deref(),
+                Some(value)
+            );
+        }
+        assert_eq!(format_dt_xcode(&SdkVersion::from("15.10")), None);
+        assert_eq!(parse_dt_xcode("15"), None);
+        assert_eq!(parse_dt_xcode("15.2"), None);
+    }
+
+    #[test]
+    fn error_source() {
+        use std::error::Error as _;
+
+        let err = Error::IoPath(
+            PathBuf::from("/foo"),
+            std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied"),
+        );
+        assert_eq!(err.to_string(), "I/O error on /foo: denied");
+        assert_eq!(err.source().unwrap().to_string(), "denied");
+
+        assert!(Error::DeveloperDirectoryNotFound.source().is_none());
+    }
+
+    #[test]
+    fn pkgutil_version() {
+        const OUTPUT: &str = "package-id: com.apple.pkg.CLTools_Executables\n\
+            version: 15.1.0.0.1.1700200546\n\
+            volume: /\n\
+            location: /\n\
+            install-time: 1700612345\n";
+
+        assert_eq!(
+            parse_pkgutil_version(OUTPUT),
+            Some(SdkVersion::from("15.1.0"))
+        );
+        assert_eq!(parse_pkgutil_version(""), None);
+    }
+
+    #[cfg(feature = "parse")]
+    #[test]
+    fn system_version_plist() -> Result<(), Error> {
+        const PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>ProductBuildVersion</key>
+	<string>23C71</string>
+	<key>ProductName</key>
+	<string>macOS</string>
+	<key>ProductVersion</key>
+	<string>14.2.1</string>
+</dict>
+</plist>"#;
+
+        let version = parse_system_version_plist(plist::Value::from_reader_xml(PLIST.as_bytes())?)?;
+        assert_eq!(version, SdkVersion::from("14.2.1"));
+        assert!(version.is_at_least("14.2"));
+        assert!(!version.is_at_least("15"));
+        assert!(version.is_at_most("14.3"));
+        assert!(!version.is_at_most("14.2"));
+
+        if cfg!(target_os = "macos") {
+            assert!(host_macos_version()?.is_some());
+        } else if !cfg!(target_os = "macos") {
+            assert_eq!(host_macos_version()?, None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn trim_bytes() {
+        assert_eq!(
+            trim_ascii_whitespace(b"  /Applications/Xcode.app/Contents/Developer\n"),
+            b"/Applications/Xcode.app/Contents/Developer"
+        );
+        assert_eq!(trim_ascii_whitespace(b" \n"), b"");
+        assert_eq!(trim_ascii_whitespace(b""), b"");
+    }
+
+    // APFS rejects filenames that aren't UTF-8.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn non_utf8_paths() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path().join(os_string_from_bytes(b"dir\xff".to_vec()));
+        let sdks = td.join("Platforms/MacOSX.platform/Developer/SDKs");
+        write_sdk(&sdks, "MacOSX14.0.sdk", &[])?;
+
+        let apps = td.join("Applications");
+        let app = apps.join(os_string_from_bytes(b"Xcode-\xfe.app".to_vec()));
+        std::fs::create_dir_all(&app)?;
+        assert_eq!(find_xcode_apps(&apps)?, vec![app.clone()]);
+        assert!(XcodeApplication::from_path(&app).is_ok());
+
+        let found = DeveloperDirectory::from(&td).sdks::<SimpleSdk>()?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path(), sdks.join("MacOSX14.0.sdk"));
+
+        let bytes = os_str_to_bytes(td.as_os_str());
+        assert_eq!(
+            os_string_from_bytes(bytes.to_vec()),
+            td.as_os_str().to_os_string()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn command_line_tools_version() -> Result<(), Error> {
+        let version = super::command_line_tools_version()?;
+
+        if command_line_tools_sdks_directory().is_some() {
+            assert!(version.is_some());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_header() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let sdk_path = write_sdk(
+            temp.path(),
+            "MacOSX14.0.sdk",
+            &[
+                "usr/include/zlib.h",
+                "System/Library/Frameworks/CoreFoundation.framework/Headers/CoreFoundation.h",
+            ],
+        )?;
+        let include = sdk_path.join("usr/include");
+        let headers = sdk_path.join("System/Library/Frameworks/CoreFoundation.framework/Headers");
+
+        let sdk = SimpleSdk::from_directory(&sdk_path)?;
+
+        assert_eq!(sdk.find_header("zlib.h"), Some(include.join("zlib.h")));
+        assert_eq!(
+            sdk.find_header("CoreFoundation/CoreFoundation.h"),
+            Some(headers.join("CoreFoundation.h"))
+        );
+        assert_eq!(sdk.find_header("missing.h"), None);
+        assert_eq!(sdk.find_header("CoreFoundation/missing.h"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_library() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let sdk_path = write_sdk(
+            temp.path(),
+            "MacOSX14.0.sdk",
+            &[
+                "usr/lib/libSystem.tbd",
+                "usr/lib/libSystem.dylib",
+                "usr/lib/libfoo.dylib",
+                "System/Library/Frameworks/CoreFoundation.framework/CoreFoundation.tbd",
+            ],
+        )?;
+        let lib = sdk_path.join("usr/lib");
+        let framework = sdk_path.join("System/Library/Frameworks/CoreFoundation.framework");
+
+        let sdk = SimpleSdk::from_directory(&sdk_path)?;
+
+        assert_eq!(
+            sdk.resolve_library("System"),
+            Some(SdkLibrary {
+                path: lib.join("libSystem.tbd"),
+                kind: SdkLibraryKind::TextStub
+            })
+        );
+        assert_eq!(
+            sdk.resolve_library("foo"),
+            Some(SdkLibrary {
+                path: lib.join("libfoo.dylib"),
+                kind: SdkLibraryKind::Dylib
+            })
+        );
+        assert_eq!(sdk.resolve_library("missing"), None);
+        assert_eq!(
+            sdk.resolve_framework("CoreFoundation"),
+            Some(SdkLibrary {
+                path: framework.join("CoreFoundation.tbd"),
+                kind: SdkLibraryKind::TextStub
+            })
+        );
+        assert_eq!(sdk.resolve_framework("Missing"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn libcxx() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let sdk_path = write_sdk(temp.path(), "MacOSX14.2.sdk", &[])?;
+
+        let sdk = SimpleSdk::from_directory(&sdk_path)?;
+        assert!(!sdk.has_libcxx_headers());
+        assert_eq!(sdk.libcxx_version()?, None);
+
+        let headers = sdk_path.join("usr/include/c++/v1");
+        std::fs::create_dir_all(&headers)?;
+        assert_eq!(sdk.libcxx_headers_path(), Some(headers.clone()));
+        assert_eq!(sdk.libcxx_version()?, None);
+
+        std::fs::write(
+            headers.join("__config"),
+            b"#ifndef _LIBCPP___CONFIG\n#  define _LIBCPP_VERSION 170006\n#endif\n",
+        )?;
+        assert_eq!(sdk.libcxx_version()?, Some(SdkVersion::from("17.0.6")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn libcpp_version() {
+        assert_eq!(
+            parse_libcpp_version("#define _LIBCPP_VERSION 160000"),
+            Some(SdkVersion::from("16.0.0"))
+        );
+        assert_eq!(
+            parse_libcpp_version("#define _LIBCPP_VERSION 15000"),
+            Some(SdkVersion::from("15.0.0"))
+        );
+        assert_eq!(
+            parse_libcpp_version("#  define _LIBCPP_VERSION 14006 // comment"),
+            Some(SdkVersion::from("14.0.6"))
+        );
+        assert_eq!(parse_libcpp_version("#define _LIBCPP_ABI_VERSION 1"), None);
+        assert_eq!(parse_libcpp_version("#define _LIBCPP_VERSION foo"), None);
+    }
+
+    #[test]
+    fn find_swift_module() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let sdk_path = write_sdk(
+            temp.path(),
+            "iPhoneSimulator17.2.sdk",
+            &[
+                "usr/lib/swift/Foundation.swiftmodule/arm64-apple-ios-simulator.swiftinterface",
+                "System/Library/Frameworks/SwiftUI.framework/Modules/SwiftUI.swiftmodule/x86_64.swiftinterface",
+            ],
+        )?;
+        let foundation = sdk_path.join("usr/lib/swift/Foundation.swiftmodule");
+        let swiftui = sdk_path
+            .join("System/Library/Frameworks/SwiftUI.framework/Modules/SwiftUI.swiftmodule");
+
+        let sdk = SimpleSdk::from_directory(&sdk_path)?;
+
+        assert_eq!(
+            sdk.find_swift_module("Foundation"),
+            Some(foundation.clone())
+        );
+        assert_eq!(sdk.find_swift_module("SwiftUI"), Some(swiftui.clone()));
+        assert!(!sdk.has_swift_module("Missing"));
+        assert_eq!(
+            sdk.find_swift_interface("Foundation", "arm64"),
+            Some(foundation.join("arm64-apple-ios-simulator.swiftinterface"))
+        );
+        assert_eq!(sdk.find_swift_interface("Foundation", "x86_64"), None);
+        assert_eq!(
+            sdk.find_swift_interface("SwiftUI", "x86_64"),
+            Some(swiftui.join("x86_64.swiftinterface"))
+        );
+        assert_eq!(sdk.find_swift_interface("Missing", "arm64"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let sdk_path = td.join("MacOSX14.0.sdk");
+        std::fs::create_dir_all(sdk_path.join("usr/include"))?;
+        std::fs::create_dir_all(sdk_path.join("usr/lib"))?;
+        std::fs::write(sdk_path.join("SDKSettings.plist"), b"")?;
+        std::fs::write(sdk_path.join("usr/lib/libSystem.tbd"), b"")?;
+
+        let sdk = SimpleSdk::from_directory(&sdk_path)?;
+        let problems = sdk.validate();
+
+        assert!(problems.contains(&SdkProblem::EmptyDirectory("usr/include".into())));
+        assert!(problems.contains(&SdkProblem::MissingDirectory(
+            "System/Library/Frameworks".into()
+        )));
+        assert!(!problems.contains(&SdkProblem::EmptyDirectory("usr/lib".into())));
+        assert!(!problems.contains(&SdkProblem::MissingSdkSettings));
+        #[cfg(feature = "parse")]
+        assert!(problems
+            .iter()
+            .any(|p| matches!(p, SdkProblem::InvalidSdkSettings(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn driverkit_sdk() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let sdk_path = write_sdk(
+            temp.path(),
+            "DriverKit19.0.sdk",
+            &["System/DriverKit/usr/include/stdint.h"],
+        )?;
+        let include = sdk_path.join("System/DriverKit/usr/include");
+
+        let sdk = SimpleSdk::from_directory(&sdk_path)?;
+
+        assert_eq!(sdk.content_path(), sdk_path.join("System/DriverKit"));
+        assert_eq!(sdk.find_header("stdint.h"), Some(include.join("stdint.h")));
+        assert!(sdk.validate().contains(&SdkProblem::MissingDirectory(
+            "System/DriverKit/usr/lib".into()
+        )));
+        assert_eq!(
+            sdk.compiler_flags("x86_64", &SdkVersion::from("19.0"))?,
+            vec![
+                OsString::from("-isysroot"),
+                sdk_path.clone().into_os_string(),
+                OsString::from("-target"),
+                OsString::from("x86_64-apple-driverkit19.0"),
+            ]
+        );
+        assert!(matches!(
+            sdk.swift_compiler_flags("x86_64", &SdkVersion::from("19.0")),
+            Err(Error::FunctionalityNotSupported(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn swift_compiler_flags() -> Result<(), Error> {
+        let sdk_path = Path::new("/sdks/iPhoneSimulator17.2.sdk");
+        let sdk = SimpleSdk::from_directory_with_filesystem(
+            sdk_path,
+            &MemoryFilesystem::new().sdk(sdk_path),
+        )?;
+
+        assert_eq!(
+            sdk.swift_compiler_flags("arm64", &SdkVersion::from("15.0"))?,
+            vec![
+                OsString::from("-sdk"),
+                sdk_path.as_os_str().to_os_string(),
+                OsString::from("-target"),
+                OsString::from("arm64-apple-ios15.0-simulator"),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "cc")]
+    #[test]
+    fn configure_cc_build() -> Result<(), Error> {
+        let sdk_path = Path::new("/sdks/MacOSX14.2.sdk");
+        let fs = MemoryFilesystem::new()
+            .sdk(sdk_path)
+            .sdk("/sdks/DriverKit19.0.sdk");
+        let sdk = SimpleSdk::from_directory_with_filesystem(sdk_path, &fs)?;
+
+        let mut build = cc::Build::new();
+        build
+            .compiler("clang")
+            .host("x86_64-unknown-linux-gnu")
+            .opt_level(0)
+            .debug(false)
+            .cargo_metadata(false);
+        sdk.configure_cc_build(&mut build, "arm64", &SdkVersion::from("11.0"))?;
+
+        let args = build
+            .try_get_compiler()
+            .unwrap()
+            .args()
+            .iter()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        let sysroot = args.iter().position(|arg| arg == "-isysroot").unwrap();
+        assert_eq!(args[sysroot + 1], sdk_path.to_string_lossy());
+        assert!(args.contains(&"--target=arm64-apple-darwin".to_string()));
+        assert!(args.contains(&"-mmacosx-version-min=11.0".to_string()));
+        assert!(!args.iter().any(|arg| arg == "-target"));
+
+        assert!(matches!(
+            SimpleSdk::from_directory_with_filesystem(Path::new("/sdks/DriverKit19.0.sdk"), &fs)?
+                .configure_cc_build(&mut cc::Build::new(), "x86_64", &SdkVersion::from("19.0")),
+            Err(Error::FunctionalityNotSupported(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_system_xcode_applications() -> Result<(), Error> {
+        let res = crate::find_system_xcode_applications()?;
+
+        if PathBuf::from(XCODE_APP_DEFAULT_PATH).exists() {
+            assert!(!res.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_system_xcode_developer_directories() -> Result<(), Error> {
+        let res = DeveloperDirectory::find_system_xcodes()?;
+
+        if PathBuf::from(XCODE_APP_DEFAULT_PATH).exists() {
+            assert!(!res.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_all_platform_directories() -> Result<(), Error> {
+        for dir in DeveloperDirectory::find_system_xcodes()? {
+            for platform in dir.platforms()? {
+                // Paths should agree.
+                assert_eq!(
+                    platform.path,
+                    dir.platforms_path().join(platform.directory_name())
+                );
+                assert_eq!(
+                    platform.path,
+                    platform.path_in_developer_directory(dir.path())
+                );
+
+                // Ensure we're able to parse all platform types in existence. We want
+                // this to fail when Apple introduces new platforms so we can implement
+                // support for the new platform!
+                assert!(!matches!(platform.platform, Platform::Unknown(_)));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn xcode_select_link() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        let developer = root
+            .join("Xcode.app")
+            .join(XCODE_APP_RELATIVE_PATH_DEVELOPER);
+        std::fs::create_dir_all(&developer)?;
+
+        let absolute = root.join("absolute_link");
+        let relative = root.join("relative_link");
+        let dangling = root.join("dangling_link");
+        std::os::unix::fs::symlink(&developer, &absolute)?;
+        std::os::unix::fs::symlink("Xcode.app/Contents/Developer", &relative)?;
+        std::os::unix::fs::symlink(root.join("missing"), &dangling)?;
+
+        assert_eq!(read_xcode_select_link(&absolute)?, Some(developer.clone()));
+        assert_eq!(
+            read_xcode_select_link(&relative)?,
+            Some(root.join("Xcode.app/Contents/Developer"))
+        );
+        assert_eq!(read_xcode_select_link(&dangling)?, None);
+        assert_eq!(read_xcode_select_link(&root.join("missing_link"))?, None);
+        // Not a symlink.
+        assert_eq!(read_xcode_select_link(&developer)?, None);
+
+        // Selecting replaces the link. Application paths resolve to their developer
+        // directory.
+        let other = root
+            .join("Other.app")
+            .join(XCODE_APP_RELATIVE_PATH_DEVELOPER);
+        std::fs::create_dir_all(other.join("usr/bin"))?;
+        std::fs::create_dir_all(developer.join("Platforms"))?;
+
+        let selected =
+            DeveloperDirectory::from(root.join("Other.app")).select_with_link(&absolute)?;
+        assert_eq!(selected.path(), other.canonicalize()?);
+        assert_eq!(read_xcode_select_link(&absolute)?, Some(selected.path));
+
+        let link = root.join("new_link");
+        DeveloperDirectory::from(&developer).select_with_link(&link)?;
+        assert_eq!(
+            read_xcode_select_link(&link)?,
+            Some(developer.canonicalize()?)
+        );
+        assert_eq!(std::fs::read_dir(root)?.count(), 6);
+
+        assert!(matches!(
+            DeveloperDirectory::from(root.join("missing")).select_with_link(&link),
+            Err(Error::PathNotDeveloper(_))
+        ));
+        assert!(matches!(
+            DeveloperDirectory::from(&developer).select_with_link(&root.join("missing/link")),
+            Err(Error::IoPath(_, _))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn apple_platform() -> Result<(), Error> {
+        assert_eq!(Platform::from_str("macosx")?, Platform::MacOsX);
+        assert_eq!(Platform::from_str("MacOSX")?, Platform::MacOsX);
+
+        Ok(())
+    }
+
+    #[test]
+    fn platform_all() -> Result<(), Error> {
+        assert_eq!(Platform::all().count(), Platform::ALL.len());
+
+        for platform in Platform::all() {
+            assert!(!matches!(platform, Platform::Unknown(_)));
+            assert_eq!(Platform::from_str(platform.filesystem_name())?, platform);
+        }
+
+        let names = Platform::all()
+            .map(|p| p.filesystem_name().to_string())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(names.len(), Platform::ALL.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn mach_o_platform() -> Result<(), Error> {
+        for platform in Platform::all() {
+            let value = platform.mach_o_platform().unwrap();
+            assert_eq!(Platform::from_mach_o_platform(value)?, platform);
+        }
+
+        assert_eq!(Platform::MacOsX.mach_o_platform(), Some(1));
+        assert_eq!(Platform::XrOsSimulator.mach_o_platform(), Some(12));
+        assert_eq!(Platform::from_mach_o_platform(6)?, Platform::IPhoneOs);
+        assert_eq!(Platform::Unknown("foo".into()).mach_o_platform(), None);
+        assert!(matches!(
+            Platform::from_mach_o_platform(5),
+            Err(Error::UnknownMachOPlatform(5))
+        ));
+        assert!(Platform::from_mach_o_platform(0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn platform_counterparts() {
+        for (device, simulator) in [
+            (Platform::AppleTvOs, Platform::AppleTvSimulator),
+            (Platform::IPhoneOs, Platform::IPhoneSimulator),
+            (Platform::WatchOs, Platform::WatchSimulator),
+            (Platform::XrOs, Platform::XrOsSimulator),
+        ] {
+            assert_eq!(device.simulator_counterpart(), Some(simulator.clone()));
+            assert_eq!(simulator.device_counterpart(), Some(device.clone()));
+            assert_eq!(device.device_counterpart(), None);
+            assert_eq!(simulator.simulator_counterpart(), None);
+        }
+
+        assert_eq!(Platform::MacOsX.simulator_counterpart(), None);
+        assert_eq!(Platform::MacOsX.device_counterpart(), None);
+    }
+
+    #[test]
+    fn platform_tools() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let path = td.join("iPhoneOS.platform");
+        std::fs::create_dir_all(path.join("Developer/usr/bin"))?;
+        std::fs::create_dir_all(path.join("usr/bin"))?;
+        std::fs::write(path.join("Developer/usr/bin/actool"), b"")?;
+        std::fs::write(path.join("usr/bin/actool"), b"")?;
+        std::fs::write(path.join("usr/bin/ld"), b"")?;
+
+        let platform = PlatformDirectory::from_path(&path)?;
+        assert_eq!(
+            platform.tool_directories(),
+            vec![path.join("Developer/usr/bin"), path.join("usr/bin")]
+        );
+        assert_eq!(platform.tools()?.len(), 3);
+        assert_eq!(
+            platform.find_tool("actool"),
+            Some(path.join("Developer/usr/bin/actool"))
+        );
+        assert_eq!(platform.find_tool("ld"), Some(path.join("usr/bin/ld")));
+        assert_eq!(platform.find_tool("ibtool"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn developer_directory_from_path() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let app = td.join("Xcode.app");
+        let developer = app.join(XCODE_APP_RELATIVE_PATH_DEVELOPER);
+        let clt = td.join("CommandLineTools");
+        std::fs::create_dir_all(developer.join("Platforms"))?;
+        std::fs::create_dir_all(clt.join("usr/bin"))?;
+
+        assert_eq!(DeveloperDirectory::from_path(&app)?.path(), developer);
+        assert_eq!(DeveloperDirectory::from_path(&developer)?.path(), developer);
+        assert_eq!(DeveloperDirectory::from_path(&clt)?.path(), clt);
+        assert!(matches!(
+            DeveloperDirectory::from_path(td),
+            Err(Error::PathNotDeveloper(p)) if p == td
+        ));
+        assert!(matches!(
+            DeveloperDirectory::from_path(td.join("missing")),
+            Err(Error::PathNotDeveloper(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn developer_directory_kind() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let xcode = td.join("Xcode.app/Contents/Developer");
+        let unbundled = td.join("Xcode");
+        let clt = td.join("CommandLineTools");
+        let tree = td.join("sdks");
+        std::fs::create_dir_all(xcode.join("Platforms"))?;
+        std::fs::create_dir_all(unbundled.join("usr/bin"))?;
+        std::fs::write(unbundled.join("usr/bin/xcodebuild"), b"")?;
+        std::fs::create_dir_all(clt.join("usr/bin"))?;
+        std::fs::create_dir_all(tree.join("Platforms"))?;
+
+        assert_eq!(
+            DeveloperDirectory::from(&xcode).kind(),
+            DeveloperDirectoryKind::Xcode
+        );
+        assert_eq!(
+            DeveloperDirectory::from(&unbundled).kind(),
+            DeveloperDirectoryKind::Xcode
+        );
+        assert_eq!(
+            DeveloperDirectory::from(&clt).kind(),
+            DeveloperDirectoryKind::CommandLineTools
+        );
+        assert_eq!(
+            DeveloperDirectory::from(&tree).kind(),
+            DeveloperDirectoryKind::SdkTree
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn sdk_environment() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let developer = td.join("Xcode.app/Contents/Developer");
+        let sdk_path = developer.join("Platforms/MacOSX.platform/Developer/SDKs/MacOSX14.2.sdk");
+        let clt = td.join("CommandLineTools");
+        let clt_sdk_path = clt.join("SDKs/MacOSX14.2.sdk");
+        let other_sdk_path = td.join("SDKs/MacOSX14.2.sdk");
+
+        for path in [&sdk_path, &clt_sdk_path, &other_sdk_path] {
+            write_sdk(path.parent().unwrap(), "MacOSX14.2.sdk", &[])?;
+        }
+        std::fs::create_dir_all(clt.join("usr/bin"))?;
+
+        let sdk = SimpleSdk::from_directory(&sdk_path)?;
+        assert_eq!(
+            sdk.developer_directory().as_ref().map(|d| d.path()),
+            Some(developer.as_path())
+        );
+        assert_eq!(
+            SimpleSdk::from_directory(&clt_sdk_path)?
+                .developer_directory()
+                .as_ref()
+                .map(|d| d.path()),
+            Some(clt.as_path())
+        );
+        assert!(SimpleSdk::from_directory(&other_sdk_path)?
+            .developer_directory()
+            .is_none());
+
+        let target = DeploymentTarget::new(Platform::MacOsX, "11.0")?;
+        let command = sdk.command("clang", Some(&target));
+        assert_eq!(command.get_program(), "clang");
+
+        let envs = command
+            .get_envs()
+            .map(|(k, v)| (k.to_os_string(), v.map(|v| v.to_os_string())))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            envs,
+            vec![
+                (
+                    "DEVELOPER_DIR".into(),
+                    Some(developer.clone().into_os_string())
+                ),
+                ("MACOSX_DEPLOYMENT_TARGET".into(), Some("11.0".into())),
+                ("SDKROOT".into(), Some(sdk_path.clone().into_os_string())),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn sdk_name() -> Result<(), Error> {
+        let name = SdkName::from_str("macosx")?;
+        assert_eq!(name.platform, Platform::MacOsX);
+        assert!(name.version.is_none());
+        assert_eq!(name.to_string(), "macosx");
+
+        let name = SdkName::from_str("iPhoneOS17.0")?;
+        assert_eq!(name.platform, Platform::IPhoneOs);
+        assert_eq!(name.version, Some(SdkVersion::from("17.0")));
+        assert_eq!(name.to_string(), "iphoneos17.0");
+
+        assert_eq!(
+            SdkName::from_str("iphonesimulator")?.platform,
+            Platform::IPhoneSimulator
+        );
+        assert!(matches!(
+            SdkName::from_str("linux"),
+            Err(Error::SdkNameParse(_))
+        ));
+        assert!(matches!(
+            SdkName::from_str("macosx.b"),
+            Err(Error::SdkNameParse(_))
+        ));
+
+        let sdk_path = Path::new("/sdks/MacOSX14.2.sdk");
+        let sdk = SimpleSdk::from_directory_with_filesystem(
+            sdk_path,
+            &MemoryFilesystem::new().sdk(sdk_path),
+        )?;
+        assert_eq!(sdk.canonical_name(), "macosx14.2");
+        assert_eq!(sdk.display_name(), "macOS 14.2");
+        assert_eq!(sdk.minimal_display_name(), "14.2");
+        assert!(SdkName::from_str("macosx")?.matches(&sdk));
+        assert!(SdkName::from_str("macosx14.2")?.matches(&sdk));
+        assert!(!SdkName::from_str("macosx14.1")?.matches(&sdk));
+        assert!(!SdkName::from_str("iphoneos14.2")?.matches(&sdk));
+
+        Ok(())
+    }
+
+    #[test]
+    fn platform_kinds() {
+        assert!(Platform::IPhoneSimulator.is_simulator());
+        assert!(!Platform::IPhoneSimulator.is_device());
+        assert!(Platform::IPhoneOs.is_device());
+        assert!(Platform::MacOsX.is_device());
+        assert!(!Platform::MacOsX.is_simulator());
+        assert!(!Platform::Unknown("Foo".into()).is_device());
+
+        assert_eq!(Platform::MacOsX.display_name(), "macOS");
+        assert_eq!(Platform::XrOsSimulator.to_string(), "visionOS Simulator");
+        assert_eq!(Platform::Unknown("Foo".into()).to_string(), "Foo");
+    }
+
+    #[test]
+    fn target_platform() -> Result<(), Error> {
         use Platform::*;
         fn test(target: &str, platform: Platform) {
             assert_eq!(Platform::from_target_triple(target).unwrap(), platform);
@@ -1077,6 +4161,7 @@ mod test {
         test("aarch64-apple-ios-macabi", IPhoneOs);
         test("aarch64-apple-ios-sim", IPhoneSimulator);
         test("aarch64-apple-tvos", AppleTvOs); // this can also can be simulator
+        test("aarch64-apple-tvos-sim", AppleTvSimulator);
         test("aarch64-apple-watchos-sim", WatchSimulator);
         test("arm64_32-apple-watchos", WatchOs);
         test("armv7-apple-ios", IPhoneOs);
@@ -1091,12 +4176,85 @@ mod test {
         test("x86_64-apple-watchos-sim", WatchSimulator);
         test("aarch64-apple-xros", XrOs);
         test("aarch64-apple-xros-sim", XrOsSimulator);
+        test("aarch64-apple-visionos", XrOs);
+        test("aarch64-apple-visionos-sim", XrOsSimulator);
 
         assert!(Platform::from_target_triple("x86_64-unknown-linux-gnu").is_err());
 
         Ok(())
     }
 
+    #[test]
+    fn deployment_target_environment_variable() {
+        assert_eq!(
+            Platform::MacOsX.deployment_target_environment_variable(),
+            Some("MACOSX_DEPLOYMENT_TARGET")
+        );
+        assert_eq!(
+            Platform::IPhoneSimulator.deployment_target_environment_variable(),
+            Platform::IPhoneOs.deployment_target_environment_variable()
+        );
+        assert_eq!(
+            Platform::XrOs.deployment_target_environment_variable(),
+            Some("XROS_DEPLOYMENT_TARGET")
+        );
+        assert_eq!(
+            Platform::Unknown("foo".into()).deployment_target_environment_variable(),
+            None
+        );
+    }
+
+    #[test]
+    fn llvm_target_triple() {
+        let version = SdkVersion::from("15.0");
+
+        assert_eq!(
+            Platform::IPhoneOs.llvm_target_triple("arm64", &version),
+            Some("arm64-apple-ios15.0".to_string())
+        );
+        assert_eq!(
+            Platform::IPhoneSimulator.llvm_target_triple("arm64", &version),
+            Some("arm64-apple-ios15.0-simulator".to_string())
+        );
+        assert_eq!(
+            Platform::MacOsX.llvm_target_triple("x86_64", &SdkVersion::from("12.3")),
+            Some("x86_64-apple-macosx12.3".to_string())
+        );
+        assert_eq!(
+            Platform::Unknown("foo".into()).llvm_target_triple("arm64", &version),
+            None
+        );
+    }
+
+    #[cfg(feature = "parse")]
+    #[test]
+    fn serde() -> Result<(), Error> {
+        assert_eq!(
+            serde_json::to_string(&Platform::IPhoneSimulator)?,
+            "\"iPhoneSimulator\""
+        );
+        assert_eq!(
+            serde_json::from_str::<Platform>("\"macosx\"")?,
+            Platform::MacOsX
+        );
+        assert_eq!(
+            serde_json::to_string(&SdkVersion::from("12.3"))?,
+            "\"12.3\""
+        );
+
+        let sdk = SdkPath::from_path("MacOSX12.3.sdk")?;
+        let value = serde_json::to_string(&sdk)?;
+        assert_eq!(
+            value,
+            r#"{"path":"MacOSX12.3.sdk","platform":"MacOSX","version":"12.3"}"#
+        );
+        let sdk2 = serde_json::from_str::<SdkPath>(&value)?;
+        assert_eq!(sdk2.platform, sdk.platform);
+        assert_eq!(sdk2.version, sdk.version);
+
+        Ok(())
+    }
+
     #[test]
     fn sdk_version() -> Result<(), Error> {
         let v = SdkVersion::from("foo");
@@ -1116,8 +4274,29 @@ mod test {
         assert_eq!(v.semantic_version()?, "12.3.1");
 
         let v = SdkVersion::from("12.3.1.2");
+        assert_eq!(v.normalized_version()?, (12, 3, 1));
+        assert_eq!(v.components(), vec![12, 3, 1, 2]);
+        assert_eq!(v.suffix(), "");
+
+        let v = SdkVersion::from("15.1.0.0.1.1700200546");
+        assert_eq!(v.components(), vec![15, 1, 0, 0, 1, 1700200546]);
+        assert_eq!(v.semantic_version()?, "15.1.0");
+
+        let v = SdkVersion::from("13.0b1");
+        assert_eq!(v.components(), vec![13, 0]);
+        assert_eq!(v.suffix(), "b1");
+        assert!(v.is_valid());
         assert!(v.normalized_version().is_err());
 
+        let v = SdkVersion::from("13.");
+        assert_eq!(v.components(), vec![13]);
+        assert_eq!(v.suffix(), ".");
+
+        let v = SdkVersion::from("foo");
+        assert!(v.components().is_empty());
+        assert_eq!(v.suffix(), "foo");
+        assert!(!v.is_valid());
+
         assert_eq!(
             SdkVersion::from("12").cmp(&SdkVersion::from("11")),
             Ordering::Greater
@@ -1130,6 +4309,65 @@ mod test {
             SdkVersion::from("12").cmp(&SdkVersion::from("13")),
             Ordering::Less
         );
+        assert_eq!(
+            SdkVersion::from("12.3").cmp(&SdkVersion::from("12.3.0")),
+            Ordering::Equal
+        );
+        assert_eq!(
+            SdkVersion::from("12.3.1.2").cmp(&SdkVersion::from("12.3.1")),
+            Ordering::Greater
+        );
+        assert_eq!(
+            SdkVersion::from("13.0b1").cmp(&SdkVersion::from("13.0")),
+            Ordering::Less
+        );
+        assert_eq!(
+            SdkVersion::from("13.0b1").cmp(&SdkVersion::from("13.0b2")),
+            Ordering::Less
+        );
+        assert!(SdkVersion::from("14.0b2") < SdkVersion::from("14.0b10"));
+        assert!(SdkVersion::from("14.0b10") < SdkVersion::from("14.0rc1"));
+        assert!(SdkVersion::from("14.0b2") < SdkVersion::from("14.0b2a"));
+        assert_ne!(SdkVersion::from("14.0b2"), SdkVersion::from("14.0b02"));
+        assert_eq!(
+            SdkVersion::from("foo").cmp(&SdkVersion::from("0.0")),
+            Ordering::Less
+        );
+        assert_eq!(
+            SdkVersion::from("bar").cmp(&SdkVersion::from("foo")),
+            Ordering::Less
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn sdk_version_traits() -> Result<(), Error> {
+        for s in ["12", "12.3", "12.3.1.2", "13.0b1"] {
+            let v = SdkVersion::from_str(s)?;
+            assert_eq!(v, SdkVersion::from(s));
+            assert_eq!(v.to_string(), s);
+        }
+
+        for s in ["", "foo", "12.", "12.3 beta", ".12"] {
+            assert!(matches!(
+                SdkVersion::from_str(s),
+                Err(Error::VersionParse(_))
+            ));
+        }
+
+        let mut versions = std::collections::HashSet::new();
+        versions.insert(SdkVersion::from("12.3"));
+        versions.insert(SdkVersion::from("12.3"));
+        versions.insert(SdkVersion::from("12.3.0"));
+        assert_eq!(versions.len(), 1);
+        assert!(versions.contains(&SdkVersion::from("12.3.0.0")));
+        assert!(!versions.contains(&SdkVersion::from("12.3.1")));
+        assert!(!versions.contains(&SdkVersion::from("12.3b1")));
+
+        assert_eq!(SdkVersion::from("12.3"), SdkVersion::from("12.3.0"));
+        assert_ne!(SdkVersion::from("12.3"), SdkVersion::from("12.30"));
+        assert_ne!(SdkVersion::from("foo"), SdkVersion::from("bar"));
 
         Ok(())
     }
@@ -1171,6 +4409,20 @@ mod test {
         let sdk = SdkPath::from_path("MacOSX12.3.sdk")?;
         assert_eq!(sdk.platform, Platform::MacOsX);
         assert_eq!(sdk.version, Some("12.3".to_string().into()));
+        assert_eq!(sdk.directory_name(), "MacOSX12.3.sdk");
+        assert_eq!(
+            sdk.to_string(),
+            "MacOSX (version: 12.3) SDK at MacOSX12.3.sdk"
+        );
+
+        for name in ["iPhoneSimulator17.0.sdk", "DriverKit.sdk"] {
+            let sdk = SdkPath::from_path(Path::new("/sdks").join(name))?;
+            assert_eq!(sdk.directory_name(), name);
+
+            let round_trip = SdkPath::from_path(sdk.directory_name())?;
+            assert_eq!(round_trip.platform, sdk.platform);
+            assert_eq!(round_trip.version, sdk.version);
+        }
 
         Ok(())
     }
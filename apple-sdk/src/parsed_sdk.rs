@@ -9,23 +9,178 @@
 //! Data structures in Apple SDKs.
 
 use {
-    crate::{AppleSdk, Error, Platform, SdkPath, SdkVersion, SimpleSdk},
-    serde::Deserialize,
+    crate::{
+        format_dt_xcode, AppleSdk, AppleToolchain, DeploymentTarget, DeveloperDirectory, Error,
+        Filesystem, Platform, PlatformDirectory, RealFilesystem, SdkPath, SdkVersion, SimpleSdk,
+    },
+    serde::{Deserialize, Serialize},
     std::{
         collections::HashMap,
+        ffi::OsString,
         path::{Path, PathBuf},
     },
 };
 
 /// Represents the DefaultProperties key in a SDKSettings.json file.
-#[derive(Debug, Deserialize)]
+///
+/// Commonly used properties are exposed as fields. All other properties are
+/// retained in [Self::other].
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub struct SdkSettingsJsonDefaultProperties {
+    /// The name of the platform. e.g. `macosx`.
     pub platform_name: String,
+
+    /// Identifier of the compiler to use by default.
+    ///
+    /// e.g. `com.apple.compilers.llvm.clang.1_0`.
+    pub default_compiler: Option<String>,
+
+    /// The name of the property holding the default deployment target.
+    ///
+    /// e.g. `MACOSX_DEPLOYMENT_TARGET`.
+    pub deployment_target_setting_name: Option<String>,
+
+    /// Deployment target versions suggested for this SDK.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deployment_target_suggested_values: Vec<String>,
+
+    /// All other properties.
+    #[serde(flatten)]
+    pub other: HashMap<String, serde_json::Value>,
+}
+
+impl SdkSettingsJsonDefaultProperties {
+    /// Obtain the string value of a property.
+    ///
+    /// Works for both the typed fields and [Self::other]. Returns [None] if the
+    /// property isn't defined or isn't a string.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        match name {
+            "PLATFORM_NAME" => Some(self.platform_name.as_str()),
+            "DEFAULT_COMPILER" => self.default_compiler.as_deref(),
+            "DEPLOYMENT_TARGET_SETTING_NAME" => self.deployment_target_setting_name.as_deref(),
+            _ => self.other.get(name).and_then(|v| v.as_str()),
+        }
+    }
+
+    /// Obtain all conditional properties.
+    ///
+    /// The return order is sorted by key and deterministic.
+    pub fn conditional_properties(&self) -> Vec<ConditionalProperty> {
+        let mut keys = self.other.keys().collect::<Vec<_>>();
+        keys.sort();
+
+        keys.into_iter()
+            .filter_map(|key| ConditionalProperty::from_key_value(key, self.other[key].clone()))
+            .collect::<Vec<_>>()
+    }
+
+    /// Obtain the string value of a property given build conditions.
+    ///
+    /// `conditions` are `(condition, value)` pairs describing the configuration
+    /// being built. e.g. `[("arch", "arm64e"), ("sdk", "iphoneos17.2")]`.
+    ///
+    /// The matching conditional property with the most conditions is used. If no
+    /// conditional property matches, this falls back to [Self::get()].
+    pub fn get_conditional(&self, name: &str, conditions: &[(&str, &str)]) -> Option<String> {
+        self.conditional_properties()
+            .into_iter()
+            .filter(|p| p.name == name && p.matches(conditions))
+            .max_by_key(|p| p.conditions.len())
+            .and_then(|p| p.value.as_str().map(|s| s.to_string()))
+            .or_else(|| self.get(name).map(|s| s.to_string()))
+    }
+}
+
+/// A property whose value only applies when certain conditions are met.
+///
+/// SDK settings can define properties with keys like
+/// `IPHONEOS_DEPLOYMENT_TARGET[arch=arm64e]` or `SUPPORTED_PLATFORMS[sdk=iphone*]`.
+/// These override the unconditional property of the same name when building for a
+/// configuration matching all conditions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConditionalProperty {
+    /// The name of the property. e.g. `IPHONEOS_DEPLOYMENT_TARGET`.
+    pub name: String,
+
+    /// The `(condition, pattern)` pairs that must all match. e.g. `(arch, arm64e)`.
+    ///
+    /// Patterns may contain `*` wildcards.
+    pub conditions: Vec<(String, String)>,
+
+    /// The value of the property.
+    pub value: serde_json::Value,
+}
+
+impl ConditionalProperty {
+    /// Attempt to parse a conditional property from a property key and value.
+    ///
+    /// Returns [None] if the key doesn't have any conditions or is malformed.
+    pub fn from_key_value(key: &str, value: serde_json::Value) -> Option<Self> {
+        let (name, mut remaining) = key.split_once('[')?;
+        let mut conditions = vec![];
+
+        loop {
+            let (condition, rest) = remaining.split_once(']')?;
+            let (condition, pattern) = condition.split_once('=')?;
+            conditions.push((condition.to_string(), pattern.to_string()));
+
+            if rest.is_empty() {
+                break;
+            }
+
+            remaining = rest.strip_prefix('[')?;
+        }
+
+        Some(Self {
+            name: name.to_string(),
+            conditions,
+            value,
+        })
+    }
+
+    /// Whether this property applies to the given `(condition, value)` pairs.
+    ///
+    /// Every condition of this property must be present in `conditions` with
+    /// a value matching its pattern.
+    pub fn matches(&self, conditions: &[(&str, &str)]) -> bool {
+        self.conditions.iter().all(|(condition, pattern)| {
+            conditions
+                .iter()
+                .any(|(k, v)| k == condition && wildcard_match(pattern, v))
+        })
+    }
+}
+
+/// Whether a value matches a pattern that may contain `*` wildcards.
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+
+    let Some(mut remaining) = value.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts = parts.collect::<Vec<_>>();
+
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcards.
+        return remaining.is_empty();
+    };
+
+    for part in middle {
+        match remaining.find(part) {
+            Some(pos) => remaining = &remaining[pos + part.len()..],
+            None => return false,
+        }
+    }
+
+    remaining.len() >= last.len() && remaining.ends_with(last)
 }
 
 /// Represents a SupportedTargets value in a SDKSettings.json file.
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct SupportedTarget {
     /// Names of machine architectures that can be targeted.
@@ -42,6 +197,24 @@ pub struct SupportedTarget {
     /// The name of the settings variant to use by default.
     pub default_variant: Option<String>,
 
+    /// The environment component of LLVM target triples for this target.
+    ///
+    /// e.g. `simulator` or `macabi`. Empty for targets without an environment.
+    #[serde(rename = "LLVMTargetTripleEnvironment")]
+    pub llvm_target_triple_environment: Option<String>,
+
+    /// The OS component of LLVM target triples for this target.
+    ///
+    /// e.g. `macosx` or `ios`.
+    #[serde(rename = "LLVMTargetTripleSys")]
+    pub llvm_target_triple_sys: Option<String>,
+
+    /// The vendor component of LLVM target triples for this target.
+    ///
+    /// e.g. `apple`.
+    #[serde(rename = "LLVMTargetTripleVendor")]
+    pub llvm_target_triple_vendor: Option<String>,
+
     /// The name of the toolchain setting that influences which deployment target version is used.
     ///
     /// e.g. on macOS this will be `MACOSX_DEPLOYMENT_TARGET`. This represents an
@@ -49,6 +222,12 @@ pub struct SupportedTarget {
     /// version to use.
     pub deployment_target_setting_name: Option<String>,
 
+    /// The highest version of a platform that this SDK can target.
+    ///
+    /// This is typically the SDK version with a `.99` patch component. e.g.
+    /// `11.3.99`.
+    pub maximum_deployment_target: Option<String>,
+
     /// The lowest version of a platform that this SDK can target.
     ///
     /// Using this SDK, it is possible to emit code that will support running
@@ -76,28 +255,273 @@ impl SupportedTarget {
             .map(SdkVersion::from)
             .collect::<Vec<_>>()
     }
+
+    /// Whether this target supports targeting the given OS version.
+    ///
+    /// If the target advertises a maximum deployment target, the version must be
+    /// within the inclusive range of the minimum and maximum deployment targets.
+    /// Otherwise, the version must be one of the valid deployment targets.
+    pub fn supports_deployment_target(&self, version: &SdkVersion) -> bool {
+        if let Some(maximum) = &self.maximum_deployment_target {
+            let minimum = SdkVersion::from(&self.minimum_deployment_target);
+            let maximum = SdkVersion::from(maximum);
+
+            version.normalized_version().is_ok() && version >= &minimum && version <= &maximum
+        } else {
+            self.deployment_targets_versions().contains(version)
+        }
+    }
+
+    /// Construct a versioned LLVM target triple for this target.
+    ///
+    /// `arch` is the Apple name of the machine architecture. e.g. `arm64`. The
+    /// triple components come from the `LLVMTargetTriple*` keys. The vendor
+    /// defaults to `apple` if not set. e.g. `arm64-apple-ios15.0-simulator`.
+    ///
+    /// Returns [None] if the OS component isn't defined or if the architecture
+    /// isn't listed in [Self::archs].
+    pub fn llvm_target_triple(&self, arch: &str, deployment_target: &SdkVersion) -> Option<String> {
+        if !self.archs.iter().any(|a| a == arch) {
+            return None;
+        }
+
+        let sys = self.llvm_target_triple_sys.as_deref()?;
+        let vendor = self.llvm_target_triple_vendor.as_deref().unwrap_or("apple");
+
+        Some(match self.llvm_target_triple_environment.as_deref() {
+            Some(env) if !env.is_empty() => {
+                format!("{arch}-{vendor}-{sys}{deployment_target}-{env}")
+            }
+            _ => format!("{arch}-{vendor}-{sys}{deployment_target}"),
+        })
+    }
+}
+
+/// An architecture of a [SupportedTarget] with its valid deployment target range.
+///
+/// See [ParsedSdk::supported_architectures()].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SupportedArchitecture {
+    /// The name of the architecture. e.g. `arm64`.
+    pub arch: String,
+
+    /// The lowest OS version that can be targeted for this architecture.
+    ///
+    /// This is the later of the target's minimum deployment target and the OS
+    /// release that introduced the architecture. e.g. `arm64` macOS starts at `11.0`.
+    pub minimum_deployment_target: SdkVersion,
+
+    /// The highest OS version that can be targeted for this architecture.
+    pub maximum_deployment_target: Option<SdkVersion>,
+}
+
+/// The OS release introducing an architecture on a target.
+///
+/// SDK settings don't record this, so it is maintained here. Architectures not
+/// listed are assumed valid for the target's entire deployment target range.
+fn architecture_introduced(target_name: &str, arch: &str) -> Option<&'static str> {
+    match (target_name, arch) {
+        ("macosx", "arm64" | "arm64e") => Some("11.0"),
+        ("iosmac", "arm64" | "arm64e") => Some("14.0"),
+        ("iphonesimulator" | "appletvsimulator", "arm64") => Some("14.0"),
+        ("watchsimulator", "arm64") => Some("7.0"),
+        ("watchos", "arm64_32") => Some("5.0"),
+        _ => None,
+    }
+}
+
+/// Represents a Variants entry in a SDKSettings.json file.
+///
+/// Variants are named sets of build settings applied when targeting a specific
+/// flavor of the SDK's platform. e.g. macOS SDKs have a `macos` variant and an
+/// `iosmac` variant for Mac Catalyst.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SdkVariant {
+    /// The name of this variant.
+    ///
+    /// e.g. `macos` or `iosmac`.
+    pub name: String,
+
+    /// Build settings defined by this variant.
+    ///
+    /// Values are typically strings, which may reference other settings via
+    /// `$(NAME)` syntax.
+    #[serde(default)]
+    pub build_settings: HashMap<String, serde_json::Value>,
+}
+
+impl SdkVariant {
+    /// Obtain the string value of a build setting.
+    ///
+    /// Returns [None] if the setting isn't defined or isn't a string.
+    pub fn build_setting(&self, name: &str) -> Option<&str> {
+        self.build_settings.get(name).and_then(|v| v.as_str())
+    }
+
+    /// Whether this is the Mac Catalyst variant.
+    pub fn is_mac_catalyst(&self) -> bool {
+        self.name == "iosmac"
+    }
+
+    /// The suffix to append to LLVM target triples when targeting this variant.
+    ///
+    /// e.g. `-macabi` for Mac Catalyst.
+    pub fn llvm_target_triple_suffix(&self) -> Option<&str> {
+        self.build_setting("LLVM_TARGET_TRIPLE_SUFFIX")
+    }
+}
+
+/// Represents a `SystemVersion.plist` file in an SDK directory.
+///
+/// This describes the OS release the SDK was derived from. Unlike the SDK version,
+/// the build version distinguishes point releases sharing a marketing version.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SdkProductVersion {
+    /// The build identifier of the OS release. e.g. `22E245`.
+    pub product_build_version: Option<String>,
+
+    /// The name of the product. e.g. `macOS` or `iPhone OS`.
+    pub product_name: Option<String>,
+
+    /// The marketing version of the product. e.g. `13.3`.
+    pub product_version: Option<String>,
+}
+
+impl SdkProductVersion {
+    /// Path within an SDK of the `SystemVersion.plist` file.
+    pub const SYSTEM_VERSION_PATH: &'static str = "System/Library/CoreServices/SystemVersion.plist";
+
+    /// Fallback path within an SDK of a `Version.plist` file.
+    pub const VERSION_PATH: &'static str = "System/Library/CoreServices/Version.plist";
+
+    /// Attempt to load product metadata from an SDK directory.
+    ///
+    /// Returns [None] if neither a `SystemVersion.plist` nor a `Version.plist` is
+    /// present.
+    pub fn from_sdk_directory(path: &Path) -> Result<Option<Self>, Error> {
+        Self::from_sdk_directory_with_filesystem(path, &RealFilesystem)
+    }
+
+    /// Load the product version from an SDK directory on the given [Filesystem].
+    pub fn from_sdk_directory_with_filesystem(
+        path: &Path,
+        fs: &dyn Filesystem,
+    ) -> Result<Option<Self>, Error> {
+        for relative in [Self::SYSTEM_VERSION_PATH, Self::VERSION_PATH] {
+            let plist_path = path.join(relative);
+
+            if fs.exists(&plist_path) {
+                let data = fs.read(&plist_path).map_err(Error::io_path(&plist_path))?;
+                return Ok(Some(plist::from_reader(std::io::Cursor::new(data))?));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// The full parsed document of an SDK settings file.
+///
+/// This allows reading keys [ParsedSdk] doesn't model. See
+/// [ParsedSdk::settings_document].
+#[derive(Clone, Debug)]
+pub enum SdkSettingsDocument {
+    /// Parsed from an `SDKSettings.json` file.
+    Json(serde_json::Value),
+    /// Parsed from an `SDKSettings.plist` file.
+    Plist(plist::Value),
+}
+
+impl SdkSettingsDocument {
+    /// Obtain the value of a top-level key, converted to JSON.
+    ///
+    /// e.g. `CanonicalName` or `CustomProperties`. Returns [None] if the key isn't
+    /// defined.
+    pub fn get(&self, key: &str) -> Option<serde_json::Value> {
+        match self {
+            Self::Json(value) => value.get(key).cloned(),
+            Self::Plist(value) => value
+                .as_dictionary()?
+                .get(key)
+                .and_then(|v| serde_json::to_value(v).ok()),
+        }
+    }
 }
 
 /// Used for deserializing a SDKSettings.json file in an SDK directory.
-#[derive(Debug, Deserialize)]
+///
+/// This can also be serialized to author `SDKSettings.json` files. e.g. for
+/// synthetic SDKs in tests. See [Self::write_to_sdk_directory()].
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct SdkSettingsJson {
     pub canonical_name: String,
     pub default_deployment_target: String,
     pub default_properties: SdkSettingsJsonDefaultProperties,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub default_variant: Option<String>,
     pub display_name: String,
     pub maximum_deployment_target: String,
     pub minimal_display_name: String,
     pub supported_targets: HashMap<String, SupportedTarget>,
+    #[serde(default)]
+    pub property_condition_fallback_names: Vec<String>,
+    #[serde(default)]
+    pub toolchains: Vec<String>,
+    #[serde(default)]
+    pub variants: Vec<SdkVariant>,
     pub version: String,
 }
 
+impl SdkSettingsJson {
+    /// Serialize to JSON in the format of `SDKSettings.json` files.
+    pub fn to_writer(&self, writer: impl std::io::Write) -> Result<(), Error> {
+        Ok(serde_json::to_writer_pretty(writer, self)?)
+    }
+
+    /// Write an `SDKSettings.json` file into an SDK directory.
+    ///
+    /// The directory must exist. An existing file is overwritten.
+    ///
+    /// Returns the path of the written file.
+    pub fn write_to_sdk_directory(&self, path: &Path) -> Result<PathBuf, Error> {
+        let json_path = path.join("SDKSettings.json");
+
+        let fh = std::fs::File::create(&json_path).map_err(Error::io_path(&json_path))?;
+        let mut writer = std::io::BufWriter::new(fh);
+        self.to_writer(&mut writer)?;
+        std::io::Write::flush(&mut writer).map_err(Error::io_path(&json_path))?;
+
+        Ok(json_path)
+    }
+}
+
+impl From<&ParsedSdk> for SdkSettingsJson {
+    fn from(sdk: &ParsedSdk) -> Self {
+        Self {
+            canonical_name: sdk.name.clone(),
+            default_deployment_target: sdk.default_deployment_target.clone(),
+            default_properties: sdk.default_properties.clone(),
+            default_variant: sdk.default_variant.clone(),
+            display_name: sdk.display_name.clone(),
+            maximum_deployment_target: sdk.maximum_deployment_target.clone(),
+            minimal_display_name: sdk.minimal_display_name.clone(),
+            supported_targets: sdk.supported_targets.clone(),
+            property_condition_fallback_names: sdk.property_condition_fallback_names.clone(),
+            toolchains: sdk.toolchains.clone(),
+            variants: sdk.variants.clone(),
+            version: sdk.version.to_string(),
+        }
+    }
+}
+
 /// An Apple SDK with parsed settings.
 ///
 /// Unlike [SimpleSdk], this type gives you access to rich metadata about the
 /// Apple SDK. This includes things like targeting capabilities.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ParsedSdk {
     /// Root directory of the SDK.
     path: PathBuf,
@@ -157,6 +581,48 @@ pub struct ParsedSdk {
     /// Example keys are `macosx` and `iosmac`. Use the [Self::default_variant]
     /// field to access the default target.
     pub supported_targets: HashMap<String, SupportedTarget>,
+
+    /// Properties that apply to all targets of this SDK by default.
+    ///
+    /// e.g. `PLATFORM_NAME` and `DEFAULT_COMPILER`.
+    pub default_properties: SdkSettingsJsonDefaultProperties,
+
+    /// Metadata about the OS release this SDK was derived from.
+    ///
+    /// Populated from `System/Library/CoreServices/SystemVersion.plist` when
+    /// constructed via [AppleSdk::from_directory()]. [None] otherwise or if the
+    /// file is missing.
+    #[serde(default)]
+    pub product_version: Option<SdkProductVersion>,
+
+    /// Alternate names to try when evaluating the `sdk` condition of conditional
+    /// properties.
+    ///
+    /// e.g. iOS SDKs define `embedded`. See [SdkSettingsJsonDefaultProperties::get_conditional()].
+    #[serde(default)]
+    pub property_condition_fallback_names: Vec<String>,
+
+    /// Identifiers of toolchains this SDK is meant to be used with.
+    ///
+    /// e.g. `com.apple.dt.toolchain.XcodeDefault`. Most SDKs don't define this,
+    /// in which case the default Xcode toolchain should be used.
+    #[serde(default)]
+    pub toolchains: Vec<String>,
+
+    /// Named build setting variants this SDK defines.
+    ///
+    /// e.g. macOS SDKs define `macos` and `iosmac` variants. Use
+    /// [Self::default_variant] to find the default variant.
+    #[serde(default)]
+    pub variants: Vec<SdkVariant>,
+
+    /// The full parsed SDK settings document.
+    ///
+    /// Populated when constructed via [AppleSdk::from_directory()] or
+    /// [Self::from_plist()]. [None] when constructed via [Self::from_json()], which
+    /// receives already typed settings. This isn't serialized.
+    #[serde(skip)]
+    pub settings_document: Option<SdkSettingsDocument>,
 }
 
 impl AsRef<Path> for ParsedSdk {
@@ -167,28 +633,42 @@ impl AsRef<Path> for ParsedSdk {
 
 impl AppleSdk for ParsedSdk {
     fn from_directory(path: &Path) -> Result<Self, Error> {
+        Self::from_directory_with_filesystem(path, &RealFilesystem)
+    }
+
+    fn from_directory_with_filesystem(path: &Path, fs: &dyn Filesystem) -> Result<Self, Error> {
         let sdk = SdkPath::from_path(path)?;
 
         // Need to call symlink_metadata so symlinks aren't followed.
-        let metadata = std::fs::symlink_metadata(path)?;
-
-        let is_symlink = metadata.file_type().is_symlink();
+        let is_symlink = fs
+            .symlink_metadata(path)
+            .map_err(Error::io_path(path))?
+            .is_symlink;
 
         let json_path = path.join("SDKSettings.json");
         let plist_path = path.join("SDKSettings.plist");
 
-        if json_path.exists() {
-            let fh = std::fs::File::open(&json_path)?;
-            let value: SdkSettingsJson = serde_json::from_reader(fh)?;
+        let mut res = if fs.exists(&json_path) {
+            let data = fs.read(&json_path).map_err(Error::io_path(&json_path))?;
+            let document: serde_json::Value = serde_json::from_slice(&data)?;
+            let value = SdkSettingsJson::deserialize(&document)?;
 
-            Self::from_json(path.to_path_buf(), is_symlink, sdk.platform, value)
-        } else if plist_path.exists() {
-            let value = plist::Value::from_file(&plist_path)?;
+            Self::from_json(path.to_path_buf(), is_symlink, sdk.platform, value).map(|mut sdk| {
+                sdk.settings_document = Some(SdkSettingsDocument::Json(document));
+                sdk
+            })
+        } else if fs.exists(&plist_path) {
+            let data = fs.read(&plist_path).map_err(Error::io_path(&plist_path))?;
+            let value = plist::Value::from_reader(std::io::Cursor::new(data))?;
 
             Self::from_plist(path.to_path_buf(), is_symlink, sdk.platform, value)
         } else {
             Err(Error::PathNotSdk(path.to_path_buf()))
-        }
+        }?;
+
+        res.product_version = SdkProductVersion::from_sdk_directory_with_filesystem(path, fs)?;
+
+        Ok(res)
     }
 
     fn is_symlink(&self) -> bool {
@@ -203,8 +683,27 @@ impl AppleSdk for ParsedSdk {
         Some(&self.version)
     }
 
+    /// The canonical name as recorded in the SDK settings.
+    fn canonical_name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// The `DisplayName` recorded in the SDK settings.
+    fn display_name(&self) -> String {
+        self.display_name.clone()
+    }
+
+    /// The `MinimalDisplayName` recorded in the SDK settings.
+    fn minimal_display_name(&self) -> String {
+        self.minimal_display_name.clone()
+    }
+
     /// Whether this SDK supports the given deployment target.
     ///
+    /// The version is checked against the minimum and maximum deployment targets
+    /// of the named target in `SupportedTargets`. See
+    /// [SupportedTarget::supports_deployment_target()].
+    ///
     /// This API does not work reliably on SDKs loaded from plists because the plist metadata
     /// lacks the required version constraint annotations.
     fn supports_deployment_target(
@@ -214,9 +713,7 @@ impl AppleSdk for ParsedSdk {
     ) -> Result<bool, Error> {
         Ok(
             if let Some(target) = self.supported_targets.get(target_name) {
-                target
-                    .deployment_targets_versions()
-                    .contains(target_version)
+                target.supports_deployment_target(target_version)
             } else {
                 false
             },
@@ -240,7 +737,7 @@ impl ParsedSdk {
             is_symlink,
             platform,
             version: value.version.into(),
-            platform_name: value.default_properties.platform_name,
+            platform_name: value.default_properties.platform_name.clone(),
             name: value.canonical_name,
             default_deployment_target: value.default_deployment_target,
             default_variant: value.default_variant,
@@ -248,6 +745,12 @@ impl ParsedSdk {
             maximum_deployment_target: value.maximum_deployment_target,
             minimal_display_name: value.minimal_display_name,
             supported_targets: value.supported_targets,
+            default_properties: value.default_properties,
+            product_version: None,
+            property_condition_fallback_names: value.property_condition_fallback_names,
+            toolchains: value.toolchains,
+            variants: value.variants,
+            settings_document: None,
         })
     }
 
@@ -262,6 +765,7 @@ impl ParsedSdk {
         platform: Platform,
         value: plist::Value,
     ) -> Result<Self, Error> {
+        let document = value.clone();
         let value = value.into_dictionary().ok_or(Error::PlistNotDictionary)?;
 
         let get_string = |dict: &plist::Dictionary, key: &str| -> Result<String, Error> {
@@ -328,6 +832,31 @@ impl ParsedSdk {
                 )?
             };
 
+        let default_properties = plist::from_value(
+            value
+                .get("DefaultProperties")
+                .ok_or_else(|| Error::PlistKeyMissing("DefaultProperties".to_string()))?,
+        )?;
+
+        let property_condition_fallback_names =
+            if let Some(names) = value.get("PropertyConditionFallbackNames") {
+                plist::from_value(names)?
+            } else {
+                vec![]
+            };
+
+        let toolchains = if let Some(toolchains) = value.get("Toolchains") {
+            plist::from_value(toolchains)?
+        } else {
+            vec![]
+        };
+
+        let variants = if let Some(variants) = value.get("Variants") {
+            plist::from_value(variants)?
+        } else {
+            vec![]
+        };
+
         Ok(Self {
             path,
             is_symlink,
@@ -341,8 +870,324 @@ impl ParsedSdk {
             maximum_deployment_target,
             minimal_display_name,
             supported_targets: HashMap::new(),
+            default_properties,
+            product_version: None,
+            property_condition_fallback_names,
+            toolchains,
+            variants,
+            settings_document: Some(SdkSettingsDocument::Plist(document)),
         })
     }
+
+    /// Obtain the architectures of a supported target with their deployment target ranges.
+    ///
+    /// `target_name` is a key in [Self::supported_targets]. e.g. `macosx`.
+    ///
+    /// Returns [None] if the target isn't supported.
+    pub fn supported_architectures(&self, target_name: &str) -> Option<Vec<SupportedArchitecture>> {
+        let target = self.supported_targets.get(target_name)?;
+        let minimum = SdkVersion::from(&target.minimum_deployment_target);
+        let maximum = target
+            .maximum_deployment_target
+            .as_ref()
+            .map(SdkVersion::from);
+
+        Some(
+            target
+                .archs
+                .iter()
+                .map(|arch| SupportedArchitecture {
+                    arch: arch.clone(),
+                    minimum_deployment_target: architecture_introduced(target_name, arch)
+                        .map(SdkVersion::from)
+                        .filter(|introduced| introduced > &minimum)
+                        .unwrap_or_else(|| minimum.clone()),
+                    maximum_deployment_target: maximum.clone(),
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Compute the minimum deployment target valid for all given architectures.
+    ///
+    /// This is the latest [SupportedArchitecture::minimum_deployment_target] of the
+    /// architectures. e.g. targeting `x86_64` and `arm64` on macOS yields `11.0`.
+    ///
+    /// Returns [None] if the target isn't supported, if any architecture isn't
+    /// supported by the target, or if no architectures are given.
+    pub fn minimum_deployment_target_for_archs(
+        &self,
+        target_name: &str,
+        archs: &[&str],
+    ) -> Option<SdkVersion> {
+        let supported = self.supported_architectures(target_name)?;
+
+        archs
+            .iter()
+            .map(|arch| {
+                supported
+                    .iter()
+                    .find(|a| a.arch == *arch)
+                    .map(|a| a.minimum_deployment_target.clone())
+            })
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .max()
+    }
+
+    /// Construct a versioned LLVM target triple for this SDK.
+    ///
+    /// `arch` is the Apple name of the machine architecture. e.g. `x86_64`.
+    /// `deployment_target` is the minimum OS version to target. If [None], the
+    /// default deployment target of the SDK is used. e.g. `x86_64-apple-macosx12.3`.
+    ///
+    /// The triple is derived from the supported target named by the SDK's
+    /// `PLATFORM_NAME`. See [SupportedTarget::llvm_target_triple()]. SDKs without
+    /// that supported target, such as SDKs loaded from plists, fall back to
+    /// [Platform::llvm_target_triple()].
+    ///
+    /// Returns [None] if the supported target doesn't list the architecture.
+    pub fn llvm_target_triple(
+        &self,
+        arch: &str,
+        deployment_target: Option<&SdkVersion>,
+    ) -> Option<String> {
+        let target = self
+            .supported_targets
+            .get(&self.default_properties.platform_name);
+
+        let version = match (deployment_target, target) {
+            (Some(v), _) => v.clone(),
+            (None, Some(target)) => SdkVersion::from(&target.default_deployment_target),
+            (None, None) => SdkVersion::from(&self.default_deployment_target),
+        };
+
+        match target {
+            Some(target) if target.llvm_target_triple_sys.is_some() => {
+                target.llvm_target_triple(arch, &version)
+            }
+            _ => self.platform.llvm_target_triple(arch, &version),
+        }
+    }
+
+    /// Obtain a named variant.
+    pub fn variant(&self, name: &str) -> Option<&SdkVariant> {
+        self.variants.iter().find(|v| v.name == name)
+    }
+
+    /// Obtain the string value of a default property for an architecture.
+    ///
+    /// Conditional properties with `arch` and `sdk` conditions are evaluated
+    /// against the given architecture and this SDK's canonical name. The names in
+    /// [Self::property_condition_fallback_names] are also accepted for the `sdk`
+    /// condition.
+    pub fn default_property_for_arch(&self, name: &str, arch: &str) -> Option<String> {
+        let mut conditions = vec![("arch", arch), ("sdk", self.name.as_str())];
+        conditions.extend(
+            self.property_condition_fallback_names
+                .iter()
+                .map(|v| ("sdk", v.as_str())),
+        );
+
+        self.default_properties.get_conditional(name, &conditions)
+    }
+
+    /// Find toolchains in a *developer directory* this SDK is meant to be used with.
+    ///
+    /// Toolchains are matched against [Self::toolchains] by identifier. If the SDK
+    /// doesn't define any toolchains, the default Xcode toolchain is matched instead.
+    ///
+    /// Returned toolchains are ordered by their position in [Self::toolchains].
+    pub fn find_toolchains(
+        &self,
+        developer_dir: &DeveloperDirectory,
+    ) -> Result<Vec<AppleToolchain>, Error> {
+        let toolchains = developer_dir.toolchains()?;
+
+        if self.toolchains.is_empty() {
+            return Ok(toolchains
+                .into_iter()
+                .filter(|t| t.is_xcode_default())
+                .collect::<Vec<_>>());
+        }
+
+        Ok(self
+            .toolchains
+            .iter()
+            .filter_map(|identifier| {
+                toolchains
+                    .iter()
+                    .find(|t| t.identifier.as_ref() == Some(identifier))
+                    .cloned()
+            })
+            .collect::<Vec<_>>())
+    }
+
+    /// Whether this SDK supports targeting Mac Catalyst.
+    ///
+    /// This is true if the SDK defines an `iosmac` supported target. SDKs without
+    /// supported targets are checked for an `iosmac` variant instead.
+    pub fn supports_mac_catalyst(&self) -> bool {
+        if self.supported_targets.is_empty() {
+            self.variants.iter().any(|v| v.is_mac_catalyst())
+        } else {
+            self.supported_targets.contains_key("iosmac")
+        }
+    }
+
+    /// The default iOS deployment target when targeting Mac Catalyst with this SDK.
+    ///
+    /// This comes from the `iosmac` supported target, falling back to the
+    /// `IPHONEOS_DEPLOYMENT_TARGET` build setting of the `iosmac` variant.
+    ///
+    /// Returns [None] if the SDK doesn't advertise Mac Catalyst support.
+    pub fn mac_catalyst_default_deployment_target(&self) -> Option<SdkVersion> {
+        if let Some(target) = self.supported_targets.get("iosmac") {
+            Some(SdkVersion::from(&target.default_deployment_target))
+        } else {
+            self.variant("iosmac")?
+                .build_setting("IPHONEOS_DEPLOYMENT_TARGET")
+                .map(SdkVersion::from)
+        }
+    }
+
+    /// Obtain an LLVM target triple for targeting Mac Catalyst with this SDK.
+    ///
+    /// `deployment_target` is the iOS version to target. If [None],
+    /// [Self::mac_catalyst_default_deployment_target()] is used. e.g.
+    /// `x86_64-apple-ios14.5-macabi`.
+    ///
+    /// The triple comes from [SupportedTarget::llvm_target_triple()] of the `iosmac`
+    /// supported target. SDKs without one derive it from the deployment target.
+    ///
+    /// Returns [None] if the SDK doesn't support Mac Catalyst or if the `iosmac`
+    /// supported target doesn't list the architecture.
+    pub fn mac_catalyst_llvm_target_triple(
+        &self,
+        arch: &str,
+        deployment_target: Option<&SdkVersion>,
+    ) -> Option<String> {
+        if !self.supports_mac_catalyst() {
+            return None;
+        }
+
+        let version = match deployment_target {
+            Some(v) => v.clone(),
+            None => self.mac_catalyst_default_deployment_target()?,
+        };
+
+        if let Some(target) = self.supported_targets.get("iosmac") {
+            target.llvm_target_triple(arch, &version)
+        } else {
+            DeploymentTarget::new(Platform::IPhoneOs, version)
+                .ok()?
+                .mac_catalyst_llvm_target_triple(arch)
+        }
+    }
+
+    /// Obtain `clang` arguments to compile for Mac Catalyst against this SDK.
+    ///
+    /// This is like [AppleSdk::compiler_flags()] except the target triple is from
+    /// [Self::mac_catalyst_llvm_target_triple()].
+    ///
+    /// Returns [Error::FunctionalityNotSupported] if a triple can't be derived.
+    pub fn mac_catalyst_compiler_flags(
+        &self,
+        arch: &str,
+        deployment_target: Option<&SdkVersion>,
+    ) -> Result<Vec<OsString>, Error> {
+        let triple = self
+            .mac_catalyst_llvm_target_triple(arch, deployment_target)
+            .ok_or(Error::FunctionalityNotSupported(
+                "Mac Catalyst with this SDK and architecture",
+            ))?;
+
+        Ok(vec![
+            "-isysroot".into(),
+            self.path.as_os_str().to_os_string(),
+            "-target".into(),
+            triple.into(),
+        ])
+    }
+
+    /// The build identifier of this SDK. e.g. `22E245`.
+    ///
+    /// This is the `ProductBuildVersion` of [Self::product_version], which Xcode
+    /// records as `DTSDKBuild`.
+    pub fn sdk_build(&self) -> Option<&str> {
+        self.product_version
+            .as_ref()
+            .and_then(|v| v.product_build_version.as_deref())
+    }
+
+    /// Resolve the platform directory containing this SDK.
+    ///
+    /// SDKs in Xcode live in `<platform>/Developer/SDKs`. Returns [None] for SDKs
+    /// elsewhere, such as in the Xcode Command Line Tools.
+    pub fn platform_directory(&self) -> Option<PlatformDirectory> {
+        let sdks = self.path.parent()?;
+        if sdks.file_name()? != "SDKs" {
+            return None;
+        }
+
+        PlatformDirectory::from_path(sdks.parent()?.parent()?).ok()
+    }
+
+    /// The `DT*` keys Xcode records in the `Info.plist` of bundles built against this SDK.
+    ///
+    /// Keys are returned in sorted order with their values. e.g. `("DTSDKName",
+    /// "macosx14.2")`. `DTPlatformBuild`, `DTSDKBuild`, `DTXcode`, and `DTXcodeBuild`
+    /// come from the `Info.plist` of `platform`, which is typically
+    /// [Self::platform_directory()]. `DTPlatformBuild`, `DTPlatformVersion`, and
+    /// `DTSDKBuild` fall back to the SDK's build and version. Keys whose value isn't
+    /// known are omitted.
+    pub fn info_plist_build_keys(
+        &self,
+        platform: Option<&PlatformDirectory>,
+    ) -> Vec<(&'static str, String)> {
+        let sdk_build = self.sdk_build().map(|s| s.to_string());
+
+        [
+            (
+                "DTCompiler",
+                self.default_properties.default_compiler.clone(),
+            ),
+            (
+                "DTPlatformBuild",
+                platform
+                    .and_then(|p| p.build.clone())
+                    .or_else(|| sdk_build.clone()),
+            ),
+            (
+                "DTPlatformName",
+                Some(self.default_properties.platform_name.clone()),
+            ),
+            (
+                "DTPlatformVersion",
+                Some(
+                    platform
+                        .and_then(|p| p.version.as_ref())
+                        .unwrap_or(&self.version)
+                        .to_string(),
+                ),
+            ),
+            (
+                "DTSDKBuild",
+                platform.and_then(|p| p.sdk_build.clone()).or(sdk_build),
+            ),
+            ("DTSDKName", Some(self.name.clone())),
+            (
+                "DTXcode",
+                platform
+                    .and_then(|p| p.xcode_version.as_ref())
+                    .and_then(format_dt_xcode),
+            ),
+            ("DTXcodeBuild", platform.and_then(|p| p.xcode_build.clone())),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| (key, value)))
+        .collect::<Vec<_>>()
+    }
 }
 
 impl TryFrom<SimpleSdk> for ParsedSdk {
@@ -353,6 +1198,14 @@ impl TryFrom<SimpleSdk> for ParsedSdk {
     }
 }
 
+impl TryFrom<&SimpleSdk> for ParsedSdk {
+    type Error = Error;
+
+    fn try_from(v: &SimpleSdk) -> Result<Self, Self::Error> {
+        Self::from_directory(v.path())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use {
@@ -462,6 +1315,30 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn from_simple_sdk() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let sdk_path = crate::FixtureSdk::new(Platform::IPhoneOs, "17.2")
+            .minimum_deployment_target("12.0")
+            .write_to_directory(temp.path())?;
+        let sdk = SimpleSdk::from_directory(&sdk_path)?;
+
+        let parsed = ParsedSdk::try_from(&sdk)?;
+        assert_eq!(parsed.path(), sdk.path());
+        assert_eq!(parsed.platform(), sdk.platform());
+        assert_eq!(parsed.version(), Some(&SdkVersion::from("17.2")));
+        assert_eq!(parsed.version(), sdk.version());
+
+        let parsed = sdk.clone().try_parse()?;
+        assert_eq!(parsed.path(), sdk.path());
+        assert_eq!(parsed.platform(), &Platform::IPhoneOs);
+
+        std::fs::remove_file(sdk_path.join("SDKSettings.json"))?;
+        assert!(ParsedSdk::try_from(&sdk).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn parse_test_sdks() -> Result<(), Error> {
         all_test_sdks()?;
@@ -469,6 +1346,449 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn variants() -> Result<(), Error> {
+        let sdk = macosx_11_3()?;
+
+        assert_eq!(
+            sdk.variants
+                .iter()
+                .map(|v| v.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["macos", "iosmac"]
+        );
+        assert!(sdk.supports_mac_catalyst());
+
+        let catalyst = sdk.variant("iosmac").unwrap();
+        assert!(catalyst.is_mac_catalyst());
+        assert_eq!(catalyst.llvm_target_triple_suffix(), Some("-macabi"));
+        assert_eq!(
+            catalyst.build_setting("IPHONEOS_DEPLOYMENT_TARGET"),
+            Some("14.5")
+        );
+        assert_eq!(
+            sdk.variant("macos").unwrap().llvm_target_triple_suffix(),
+            Some("")
+        );
+
+        assert_eq!(
+            sdk.mac_catalyst_default_deployment_target(),
+            Some(SdkVersion::from("14.5"))
+        );
+        assert_eq!(
+            sdk.mac_catalyst_llvm_target_triple("x86_64", None)
+                .as_deref(),
+            Some("x86_64-apple-ios14.5-macabi")
+        );
+        assert_eq!(
+            sdk.mac_catalyst_llvm_target_triple("arm64", Some(&SdkVersion::from("13.1")))
+                .as_deref(),
+            Some("arm64-apple-ios13.1-macabi")
+        );
+        assert_eq!(sdk.mac_catalyst_llvm_target_triple("armv7", None), None);
+        assert_eq!(
+            sdk.mac_catalyst_compiler_flags("arm64", None)?,
+            vec![
+                OsString::from("-isysroot"),
+                sdk.path.clone().into_os_string(),
+                OsString::from("-target"),
+                OsString::from("arm64-apple-ios14.5-macabi"),
+            ]
+        );
+
+        let mut variant_only = sdk.clone();
+        variant_only.supported_targets.clear();
+        assert!(variant_only.supports_mac_catalyst());
+        assert_eq!(
+            variant_only
+                .mac_catalyst_llvm_target_triple("x86_64", None)
+                .as_deref(),
+            Some("x86_64-apple-ios14.5-macabi")
+        );
+
+        let mut without_iosmac = sdk.clone();
+        without_iosmac.supported_targets.remove("iosmac");
+        assert!(!without_iosmac.supports_mac_catalyst());
+        assert_eq!(
+            without_iosmac.mac_catalyst_llvm_target_triple("x86_64", None),
+            None
+        );
+
+        let sdk = macosx_10_9()?;
+        assert!(sdk.variants.is_empty());
+        assert!(!sdk.supports_mac_catalyst());
+        assert_eq!(sdk.mac_catalyst_llvm_target_triple("x86_64", None), None);
+        assert!(matches!(
+            sdk.mac_catalyst_compiler_flags("x86_64", None),
+            Err(Error::FunctionalityNotSupported(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn llvm_target_triple() -> Result<(), Error> {
+        let sdk = macosx_11_3()?;
+
+        assert_eq!(
+            sdk.llvm_target_triple("arm64", None).as_deref(),
+            Some("arm64-apple-macosx11.3")
+        );
+        assert_eq!(
+            sdk.llvm_target_triple("x86_64", Some(&SdkVersion::from("10.15")))
+                .as_deref(),
+            Some("x86_64-apple-macosx10.15")
+        );
+        assert_eq!(sdk.llvm_target_triple("armv7", None), None);
+        assert_eq!(
+            sdk.supported_targets["iosmac"]
+                .llvm_target_triple("arm64", &SdkVersion::from("14.5"))
+                .as_deref(),
+            Some("arm64-apple-ios14.5-macabi")
+        );
+
+        let sdk = macosx_10_9()?;
+        assert_eq!(
+            sdk.llvm_target_triple("x86_64", None).as_deref(),
+            Some("x86_64-apple-macosx10.9")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn default_properties() -> Result<(), Error> {
+        for sdk in all_test_sdks()? {
+            assert_eq!(sdk.default_properties.platform_name, "macosx");
+            assert_eq!(sdk.default_properties.get("PLATFORM_NAME"), Some("macosx"));
+        }
+
+        let sdk = macosx_11_3()?;
+        let props = &sdk.default_properties;
+        assert_eq!(
+            props.default_compiler.as_deref(),
+            Some("com.apple.compilers.llvm.clang.1_0")
+        );
+        assert_eq!(props.get("MACOSX_DEPLOYMENT_TARGET"), Some("11.3"));
+        assert_eq!(
+            props.get("IOS_UNZIPPERED_TWIN_PREFIX_PATH"),
+            Some("/System/iOSSupport")
+        );
+        assert_eq!(props.deployment_target_suggested_values.len(), 11);
+        assert!(props.get("DEPLOYMENT_TARGET_SUGGESTED_VALUES").is_none());
+        assert!(props.get("DOES_NOT_EXIST").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn conditional_properties() -> Result<(), Error> {
+        let p = ConditionalProperty::from_key_value(
+            "IPHONEOS_DEPLOYMENT_TARGET[sdk=iphone*][arch=arm64e]",
+            serde_json::json!("14.0"),
+        )
+        .unwrap();
+        assert_eq!(p.name, "IPHONEOS_DEPLOYMENT_TARGET");
+        assert_eq!(
+            p.conditions,
+            vec![
+                ("sdk".to_string(), "iphone*".to_string()),
+                ("arch".to_string(), "arm64e".to_string())
+            ]
+        );
+        assert!(p.matches(&[("arch", "arm64e"), ("sdk", "iphoneos17.2")]));
+        assert!(!p.matches(&[("arch", "arm64"), ("sdk", "iphoneos17.2")]));
+        assert!(!p.matches(&[("arch", "arm64e")]));
+
+        assert!(ConditionalProperty::from_key_value("FOO", serde_json::json!("")).is_none());
+        assert!(ConditionalProperty::from_key_value("FOO[arch]", serde_json::json!("")).is_none());
+        assert!(
+            ConditionalProperty::from_key_value("FOO[arch=x]bar", serde_json::json!("")).is_none()
+        );
+
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("iphone*", "iphoneos"));
+        assert!(wildcard_match("*os*", "iphoneos17.2"));
+        assert!(!wildcard_match("iphone*", "macosx"));
+        assert!(!wildcard_match("arm64", "arm64e"));
+
+        let mut sdk = macosx_11_3()?;
+        assert!(sdk.default_properties.conditional_properties().is_empty());
+        assert!(sdk.property_condition_fallback_names.is_empty());
+
+        sdk.default_properties.other.insert(
+            "MACOSX_DEPLOYMENT_TARGET[arch=arm64*]".to_string(),
+            serde_json::json!("11.0"),
+        );
+        assert_eq!(
+            sdk.default_property_for_arch("MACOSX_DEPLOYMENT_TARGET", "arm64e")
+                .as_deref(),
+            Some("11.0")
+        );
+        assert_eq!(
+            sdk.default_property_for_arch("MACOSX_DEPLOYMENT_TARGET", "x86_64")
+                .as_deref(),
+            Some("11.3")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn product_version() -> Result<(), Error> {
+        const SYSTEM_VERSION_PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>ProductBuildVersion</key>
+	<string>22E245</string>
+	<key>ProductCopyright</key>
+	<string>1983-2023 Apple Inc.</string>
+	<key>ProductName</key>
+	<string>macOS</string>
+	<key>ProductUserVisibleVersion</key>
+	<string>13.3</string>
+	<key>ProductVersion</key>
+	<string>13.3</string>
+</dict>
+</plist>"#;
+
+        let value = plist::from_bytes::<SdkProductVersion>(SYSTEM_VERSION_PLIST.as_bytes())?;
+        assert_eq!(value.product_build_version.as_deref(), Some("22E245"));
+        assert_eq!(value.product_name.as_deref(), Some("macOS"));
+        assert_eq!(value.product_version.as_deref(), Some("13.3"));
+
+        assert!(SdkProductVersion::from_sdk_directory(Path::new("/does/not/exist"))?.is_none());
+        assert!(macosx_11_3()?.product_version.is_none());
+
+        let temp = tempfile::tempdir()?;
+        let sdk_path =
+            crate::FixtureSdk::new(Platform::MacOsX, "13.3").write_to_directory(temp.path())?;
+        assert!(ParsedSdk::from_directory(&sdk_path)?
+            .product_version
+            .is_none());
+
+        let plist_path = sdk_path.join(SdkProductVersion::SYSTEM_VERSION_PATH);
+        std::fs::create_dir_all(plist_path.parent().unwrap())?;
+        std::fs::write(&plist_path, SYSTEM_VERSION_PLIST)?;
+        assert_eq!(
+            ParsedSdk::from_directory(&sdk_path)?.product_version,
+            Some(value)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn info_plist_build_keys() -> Result<(), Error> {
+        let mut sdk = macosx_11_3()?;
+        sdk.product_version = Some(SdkProductVersion {
+            product_build_version: Some("20E214".to_string()),
+            product_name: None,
+            product_version: None,
+        });
+        assert_eq!(sdk.sdk_build(), Some("20E214"));
+        assert!(sdk.platform_directory().is_none());
+
+        let keys = sdk.info_plist_build_keys(None);
+        assert_eq!(
+            keys.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![
+                "DTCompiler",
+                "DTPlatformBuild",
+                "DTPlatformName",
+                "DTPlatformVersion",
+                "DTSDKBuild",
+                "DTSDKName"
+            ]
+        );
+        assert!(keys.contains(&("DTPlatformBuild", "20E214".to_string())));
+        assert!(keys.contains(&("DTSDKBuild", "20E214".to_string())));
+        assert!(keys.contains(&("DTSDKName", "macosx11.3".to_string())));
+
+        let mut platform = PlatformDirectory::from_path("/does/not/exist/MacOSX.platform")?;
+        platform.build = Some("20E215".to_string());
+        platform.xcode_version = Some(SdkVersion::from("12.5"));
+        platform.xcode_build = Some("12E262".to_string());
+
+        let keys = sdk.info_plist_build_keys(Some(&platform));
+        assert!(keys.contains(&("DTPlatformBuild", "20E215".to_string())));
+        assert!(keys.contains(&("DTSDKBuild", "20E214".to_string())));
+
+        platform.sdk_build = Some("20E216".to_string());
+        let keys = sdk.info_plist_build_keys(Some(&platform));
+        assert!(keys.contains(&("DTSDKBuild", "20E216".to_string())));
+        assert!(keys.contains(&("DTXcode", "1250".to_string())));
+        assert!(keys.contains(&("DTXcodeBuild", "12E262".to_string())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn canonical_name() -> Result<(), Error> {
+        assert_eq!(macosx_10_9()?.canonical_name(), "macosx10.9");
+        assert_eq!(macosx_11_3()?.canonical_name(), "macosx11.3");
+        assert_eq!(AppleSdk::display_name(&macosx_11_3()?), "macOS 11.3");
+        assert_eq!(AppleSdk::minimal_display_name(&macosx_11_3()?), "11.3");
+        assert_eq!(AppleSdk::display_name(&macosx_10_9()?), "OS X 10.9");
+
+        Ok(())
+    }
+
+    #[test]
+    fn settings_document() -> Result<(), Error> {
+        let sdk = macosx_10_9()?;
+        let document = sdk.settings_document.as_ref().unwrap();
+        assert!(matches!(document, SdkSettingsDocument::Plist(_)));
+        assert_eq!(
+            document.get("CanonicalName"),
+            Some(serde_json::Value::from("macosx10.9"))
+        );
+        assert_eq!(document.get("Missing"), None);
+
+        assert!(macosx_11_3()?.settings_document.is_none());
+
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let sdk_path = td.join("MacOSX11.3.sdk");
+        std::fs::create_dir_all(&sdk_path)?;
+        std::fs::write(sdk_path.join("SDKSettings.json"), MACOSX_11_3_SETTINGS_JSON)?;
+
+        let sdk = ParsedSdk::from_directory(&sdk_path)?;
+        let document = sdk.settings_document.as_ref().unwrap();
+        assert!(matches!(document, SdkSettingsDocument::Json(_)));
+        assert!(document
+            .get("CustomProperties")
+            .is_some_and(|v| v.is_object()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_settings_json() -> Result<(), Error> {
+        let settings = serde_json::from_slice::<SdkSettingsJson>(MACOSX_11_3_SETTINGS_JSON)?;
+
+        let mut data = vec![];
+        settings.to_writer(&mut data)?;
+        let round_trip = serde_json::from_slice::<SdkSettingsJson>(&data)?;
+        assert_eq!(round_trip.canonical_name, settings.canonical_name);
+        assert_eq!(round_trip.supported_targets.len(), 2);
+        assert_eq!(round_trip.variants.len(), 2);
+        assert_eq!(
+            round_trip.default_properties.other,
+            settings.default_properties.other
+        );
+
+        // Plist SDKs can be converted to JSON settings.
+        let settings = SdkSettingsJson::from(&macosx_10_9()?);
+        assert_eq!(settings.canonical_name, "macosx10.9");
+        assert_eq!(settings.version, "10.9");
+
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let sdk_path = td.join("MacOSX10.9.sdk");
+        std::fs::create_dir_all(&sdk_path)?;
+        assert_eq!(
+            settings.write_to_sdk_directory(&sdk_path)?,
+            sdk_path.join("SDKSettings.json")
+        );
+
+        let sdk = ParsedSdk::from_directory(&sdk_path)?;
+        assert_eq!(sdk.name, "macosx10.9");
+        assert_eq!(sdk.display_name, "OS X 10.9");
+        assert_eq!(sdk.version(), Some(&SdkVersion::from("10.9")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn toolchains() -> Result<(), Error> {
+        assert!(macosx_11_3()?.toolchains.is_empty());
+        assert!(macosx_10_9()?.toolchains.is_empty());
+
+        let mut value = serde_json::from_slice::<serde_json::Value>(MACOSX_11_3_SETTINGS_JSON)?;
+        value.as_object_mut().unwrap().insert(
+            "Toolchains".to_string(),
+            serde_json::json!([crate::XCODE_DEFAULT_TOOLCHAIN_IDENTIFIER]),
+        );
+
+        let sdk = ParsedSdk::from_json(
+            PathBuf::from("MacOSX11.3.sdk"),
+            false,
+            Platform::MacOsX,
+            serde_json::from_value(value)?,
+        )?;
+        assert_eq!(
+            sdk.toolchains,
+            vec![crate::XCODE_DEFAULT_TOOLCHAIN_IDENTIFIER.to_string()]
+        );
+
+        let temp = tempfile::tempdir()?;
+        let toolchains_dir = temp.path().join("Toolchains");
+        for (name, identifier) in [
+            ("XcodeDefault", crate::XCODE_DEFAULT_TOOLCHAIN_IDENTIFIER),
+            ("swift-5.9.2-RELEASE", "org.swift.59202311161a"),
+        ] {
+            let path = toolchains_dir.join(format!("{name}.xctoolchain"));
+            std::fs::create_dir_all(&path)?;
+            plist::to_file_xml(
+                path.join("ToolchainInfo.plist"),
+                &plist::Value::Dictionary(
+                    [("Identifier".to_string(), plist::Value::from(identifier))]
+                        .into_iter()
+                        .collect(),
+                ),
+            )?;
+        }
+        let dir = DeveloperDirectory::from(temp.path());
+
+        let toolchains = sdk.find_toolchains(&dir)?;
+        assert_eq!(toolchains.len(), 1);
+        assert!(toolchains[0].is_xcode_default());
+        assert_eq!(toolchains[0].name(), "XcodeDefault");
+        assert_eq!(macosx_11_3()?.find_toolchains(&dir)?, toolchains);
+
+        Ok(())
+    }
+
+    #[test]
+    fn supported_architectures() -> Result<(), Error> {
+        let sdk = macosx_11_3()?;
+
+        let archs = sdk.supported_architectures("macosx").unwrap();
+        assert_eq!(
+            archs.iter().map(|a| a.arch.as_str()).collect::<Vec<_>>(),
+            vec!["x86_64", "x86_64h", "arm64", "arm64e"]
+        );
+        assert_eq!(archs[0].minimum_deployment_target, SdkVersion::from("10.9"));
+        assert_eq!(archs[2].minimum_deployment_target, SdkVersion::from("11.0"));
+        assert_eq!(
+            archs[2].maximum_deployment_target,
+            Some(SdkVersion::from("11.3.99"))
+        );
+        assert!(sdk.supported_architectures("ios").is_none());
+
+        assert_eq!(
+            sdk.minimum_deployment_target_for_archs("macosx", &["x86_64"]),
+            Some(SdkVersion::from("10.9"))
+        );
+        assert_eq!(
+            sdk.minimum_deployment_target_for_archs("macosx", &["x86_64", "arm64"]),
+            Some(SdkVersion::from("11.0"))
+        );
+        assert_eq!(
+            sdk.minimum_deployment_target_for_archs("iosmac", &["x86_64", "arm64"]),
+            Some(SdkVersion::from("14.0"))
+        );
+        assert_eq!(
+            sdk.minimum_deployment_target_for_archs("macosx", &["armv7"]),
+            None
+        );
+        assert_eq!(sdk.minimum_deployment_target_for_archs("macosx", &[]), None);
+
+        Ok(())
+    }
+
     #[test]
     fn supports_deployment_target() -> Result<(), Error> {
         let sdk = macosx_10_15()?;
@@ -491,6 +1811,13 @@ mod test {
             assert!(sdk.supports_deployment_target("macosx", &SdkVersion::from(*version))?);
         }
 
+        // Versions within the supported range but not explicitly listed are supported.
+        assert!(sdk.supports_deployment_target("macosx", &SdkVersion::from("10.15.4"))?);
+        assert!(sdk.supports_deployment_target("macosx", &SdkVersion::from("11.3.1"))?);
+        assert!(!sdk.supports_deployment_target("macosx", &SdkVersion::from("11.4"))?);
+        assert!(!sdk.supports_deployment_target("macosx", &SdkVersion::from("foo"))?);
+        assert!(sdk.supports_deployment_target("iosmac", &SdkVersion::from("14.0"))?);
+
         // API doesn't work for plists.
         assert!(!macosx_10_9()?.supports_deployment_target("macosx", &SdkVersion::from("10.9"))?);
         assert!(!macosx_10_10()?.supports_deployment_target("macosx", &SdkVersion::from("10.9"))?);
@@ -8,17 +8,33 @@
 
 use {
     crate::{
-        command_line_tools_sdks_directory, AppleSdk, DeveloperDirectory, Error, Platform,
-        PlatformDirectory, SdkPath, SdkVersion,
+        command_line_tools_sdks_directory_with_filesystem, AppleSdk, DeploymentTarget,
+        DeveloperDirectory, DeveloperDirectoryKind, Error, Filesystem, Platform, PlatformDirectory,
+        RealFilesystem, SdkName, SdkPath, SdkVersion, SdkVersionReq, SimpleSdk, XcodeApplication,
     },
     std::{
+        any::{Any, TypeId},
         cmp::Ordering,
-        collections::HashSet,
+        collections::{HashMap, HashSet},
+        ffi::{OsStr, OsString},
         fmt::{Display, Formatter},
-        path::PathBuf,
+        path::{Path, PathBuf},
+        str::FromStr,
+        sync::{Arc, Mutex, PoisonError},
+        time::SystemTime,
     },
 };
 
+#[cfg(feature = "parse")]
+use crate::{SdkSearchConfig, XcodebuildSdk};
+
+/// Environment variable holding a list of additional directories containing SDKs.
+///
+/// The value is a list of paths delimited like the `PATH` environment variable.
+/// Each directory is searched after the registered locations of every [SdkSearch].
+/// See [SdkSearch::extra_sdks_dirs_from_env()].
+pub const EXTRA_SDKS_DIRS_ENV: &str = "APPLE_SDK_EXTRA_SDKS_DIRS";
+
 /// The search location that a [SdkSearchLocation] normalizes to.
 enum SdkSearchResolvedLocation {
     /// Nothing.
@@ -31,6 +47,9 @@ enum SdkSearchResolvedLocation {
     SdkDirectory(PathBuf),
     /// A specified directory with an SDK excluded from SDK filtering.
     SdkDirectoryUnfiltered(PathBuf),
+    /// Specific directories with SDKs, which may have been found by other locations.
+    #[cfg(feature = "parse")]
+    SdkDirectories(Vec<PathBuf>),
 }
 
 impl SdkSearchResolvedLocation {
@@ -40,12 +59,20 @@ impl SdkSearchResolvedLocation {
 }
 
 /// Represents a location to search for SDKs.
-#[derive(Clone, Debug)]
+///
+/// Some variants depend on crate features, so this enum is non-exhaustive.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum SdkSearchLocation {
     /// Use the path specified by the `SDKROOT` environment variable.
     ///
-    /// If this environment variable is defined and the path is not valid, an error
-    /// occurs.
+    /// The value can also be an SDK name as accepted by `xcrun --sdk`. e.g. `macosx`
+    /// or `iphoneos17.0`. In this case, the newest SDK for that platform (and version,
+    /// if specified) in the default *Developer Directory* or Xcode Command Line Tools
+    /// is used.
+    ///
+    /// If this environment variable is defined and the path is not valid or the name
+    /// does not resolve to an SDK, an error occurs.
     ///
     /// An SDK yielded from this location skips search filtering. This is because the
     /// semantic intent of the `SDKROOT` environment variable is to force usage of a
@@ -84,7 +111,11 @@ pub enum SdkSearchLocation {
     /// if available.
     CommandLineTools,
 
-    /// Invoke `xcode-select` to find a *Developer Directory* to search.
+    /// Use the *Developer Directory* selected via `xcode-select`.
+    ///
+    /// The selection is read from the `xcode-select` symlink if present. Otherwise
+    /// `xcode-select` is invoked to resolve it, which errors with
+    /// [Error::SubprocessDisabled] if [SdkSearch::allow_subprocess()] is disabled.
     ///
     /// This mechanism is intended as a fallback in case other (pure Rust) mechanisms for locating
     /// the default *Developer Directory* fail. If you find yourself needing this, it likely
@@ -104,6 +135,16 @@ pub enum SdkSearchLocation {
     /// of Xcode to be used.
     SystemXcodes,
 
+    /// Look for SDKs within all `Xcode` applications in the user's `~/Applications` directory.
+    ///
+    /// This effectively controls whether the paths resolved by
+    /// [DeveloperDirectory::find_user_xcodes()] will be searched, if present.
+    ///
+    /// Xcode is installed here by non-admin users and by version managers like
+    /// `xcodes`. See [SdkSearch::user_applications()] to register this location
+    /// alongside the system Xcode locations.
+    UserXcodes,
+
     /// Use an explicit *Developer Directory*.
     ///
     /// This can be used to point a search at a non-standard location holding a *Developer
@@ -120,6 +161,45 @@ pub enum SdkSearchLocation {
 
     /// Use an explicit directory holding an SDK.
     Sdk(PathBuf),
+
+    /// Look for SDKs within an [osxcross](https://github.com/tpoechtrager/osxcross) install.
+    ///
+    /// The path can be the osxcross checkout/install root or its `target` directory.
+    /// SDKs are looked for in the `SDK` directory under `target`. e.g.
+    /// `osxcross/target/SDK/MacOSX14.0.sdk`.
+    ///
+    /// osxcross is commonly used to cross-compile for macOS from Linux.
+    Osxcross(PathBuf),
+
+    /// Use the osxcross install defined by environment variables.
+    ///
+    /// If `OSXCROSS_SDK` is defined, it is used as the path to an SDK. Otherwise, if
+    /// `OSXCROSS_TARGET_DIR` is defined, it is treated like [Self::Osxcross]. These
+    /// variables are exported by osxcross's `osxcross-conf` tool.
+    ///
+    /// This location yields nothing if neither variable is defined or the path
+    /// doesn't exist.
+    ///
+    /// This location isn't part of [Self::default_locations()]. Register it explicitly
+    /// to honor osxcross installs.
+    OsxcrossEnv,
+
+    /// Use the SDKs reported by `xcodebuild -showsdks -json`.
+    ///
+    /// This runs `xcodebuild` from the *Developer Directory* named by `DEVELOPER_DIR`,
+    /// as read through [SdkSearch::environment()], or else the default one. See
+    /// [XcodebuildSdk::run_show_sdks()].
+    ///
+    /// This is intended as a fallback for when the filesystem heuristics of other
+    /// locations fail to find SDKs, such as in non-standard Xcode layouts. Consider
+    /// registering it last. SDKs already found by earlier locations are not yielded
+    /// again.
+    ///
+    /// If `xcodebuild` cannot be run or fails, this location yields nothing. If
+    /// running processes is disabled via [SdkSearch::allow_subprocess()], resolving it
+    /// errors with [Error::SubprocessDisabled].
+    #[cfg(feature = "parse")]
+    Xcodebuild,
 }
 
 impl Display for SdkSearchLocation {
@@ -131,29 +211,54 @@ impl Display for SdkSearchLocation {
             Self::CommandLineTools => f.write_str("Xcode Command Line Tools installation"),
             Self::XcodeSelect => f.write_str("xcode-select"),
             Self::SystemXcodes => f.write_str("All system-installed Xcode applications"),
+            Self::UserXcodes => f.write_str("All Xcode applications in ~/Applications"),
             Self::Developer(dir) => {
                 f.write_fmt(format_args!("Developer Directory {}", dir.path().display()))
             }
             Self::Sdks(path) => f.write_fmt(format_args!("SDKs directory {}", path.display())),
             Self::Sdk(path) => f.write_fmt(format_args!("SDK directory {}", path.display())),
+            Self::Osxcross(path) => {
+                f.write_fmt(format_args!("osxcross install {}", path.display()))
+            }
+            Self::OsxcrossEnv => f.write_str("osxcross environment variables"),
+            #[cfg(feature = "parse")]
+            Self::Xcodebuild => f.write_str("xcodebuild -showsdks"),
         }
     }
 }
 
 impl SdkSearchLocation {
+    /// The search locations used by [SdkSearch::default()], in search order.
+    pub fn default_locations() -> Vec<Self> {
+        vec![
+            Self::SdkRootEnv,
+            Self::DeveloperDirEnv,
+            Self::SystemXcode,
+            Self::CommandLineTools,
+        ]
+    }
+
     /// Whether this search location is terminal.
     fn is_terminal(&self) -> bool {
         matches!(self, Self::SdkRootEnv | Self::DeveloperDirEnv)
     }
 
-    fn resolve_location(&self) -> Result<SdkSearchResolvedLocation, Error> {
+    fn resolve_location(&self, search: &SdkSearch) -> Result<SdkSearchResolvedLocation, Error> {
+        let fs = &*search.filesystem;
+        let env = &*search.environment;
+
         match self {
             Self::SdkRootEnv => {
-                if let Some(path) = std::env::var_os("SDKROOT") {
+                if let Some(path) = env("SDKROOT") {
                     let path = PathBuf::from(path);
 
-                    if path.exists() {
+                    if fs.exists(&path) {
                         Ok(SdkSearchResolvedLocation::SdkDirectoryUnfiltered(path))
+                    } else if let Some(sdk) = path
+                        .to_str()
+                        .and_then(|name| resolve_sdk_name(name, search))
+                    {
+                        Ok(SdkSearchResolvedLocation::SdkDirectoryUnfiltered(sdk))
                     } else {
                         Err(Error::PathNotSdk(path))
                     }
@@ -162,58 +267,210 @@ impl SdkSearchLocation {
                 }
             }
             Self::DeveloperDirEnv => {
-                if let Some(dir) = DeveloperDirectory::from_env()? {
+                if let Some(value) = env("DEVELOPER_DIR") {
+                    let dir = DeveloperDirectory::from_path(PathBuf::from(value))?;
+
                     Ok(SdkSearchResolvedLocation::PlatformDirectories(
-                        dir.platforms()?,
+                        dir.platforms_with_filesystem(fs)?,
                     ))
                 } else {
                     Ok(SdkSearchResolvedLocation::None)
                 }
             }
             Self::SystemXcode => {
-                if let Some(dir) = DeveloperDirectory::default_xcode() {
+                if let Some(dir) = DeveloperDirectory::default_xcode_with_filesystem(fs) {
                     Ok(SdkSearchResolvedLocation::PlatformDirectories(
-                        dir.platforms()?,
+                        dir.platforms_with_filesystem(fs)?,
                     ))
                 } else {
                     Ok(SdkSearchResolvedLocation::None)
                 }
             }
             Self::CommandLineTools => {
-                if let Some(path) = command_line_tools_sdks_directory() {
+                if let Some(path) = command_line_tools_sdks_directory_with_filesystem(fs) {
                     Ok(SdkSearchResolvedLocation::SdksDirectory(path))
                 } else {
                     Ok(SdkSearchResolvedLocation::None)
                 }
             }
-            Self::XcodeSelect => Ok(SdkSearchResolvedLocation::PlatformDirectories(
-                DeveloperDirectory::from_xcode_select()?.platforms()?,
-            )),
+            Self::XcodeSelect => {
+                let dir = if let Some(dir) = DeveloperDirectory::from_xcode_select_link()? {
+                    dir
+                } else {
+                    search.ensure_subprocess_allowed("xcode-select")?;
+                    DeveloperDirectory::from_xcode_select()?
+                };
+
+                Ok(SdkSearchResolvedLocation::PlatformDirectories(
+                    dir.platforms_with_filesystem(fs)?,
+                ))
+            }
             Self::SystemXcodes => Ok(SdkSearchResolvedLocation::PlatformDirectories(
-                DeveloperDirectory::find_system_xcodes()?
+                DeveloperDirectory::find_system_xcodes_with_filesystem(fs)?
+                    .into_iter()
+                    .map(|dir| dir.platforms_with_filesystem(fs))
+                    .collect::<Result<Vec<_>, Error>>()?
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>(),
+            )),
+            Self::UserXcodes => Ok(SdkSearchResolvedLocation::PlatformDirectories(
+                DeveloperDirectory::find_user_xcodes_with_filesystem(fs)?
                     .into_iter()
-                    .map(|dir| dir.platforms())
+                    .map(|dir| dir.platforms_with_filesystem(fs))
                     .collect::<Result<Vec<_>, Error>>()?
                     .into_iter()
                     .flatten()
                     .collect::<Vec<_>>(),
             )),
             Self::Developer(dir) => Ok(SdkSearchResolvedLocation::PlatformDirectories(
-                dir.platforms()?,
+                dir.platforms_with_filesystem(fs)?,
             )),
             Self::Sdks(path) => Ok(SdkSearchResolvedLocation::SdksDirectory(path.clone())),
             Self::Sdk(path) => Ok(SdkSearchResolvedLocation::SdkDirectory(path.clone())),
+            Self::Osxcross(path) => Ok(SdkSearchResolvedLocation::SdksDirectory(
+                osxcross_sdks_directory(path, fs),
+            )),
+            Self::OsxcrossEnv => {
+                if let Some(path) = env("OSXCROSS_SDK") {
+                    let path = PathBuf::from(path);
+
+                    if fs.exists(&path) {
+                        return Ok(SdkSearchResolvedLocation::SdkDirectory(path));
+                    }
+                }
+
+                if let Some(path) = env("OSXCROSS_TARGET_DIR") {
+                    let path = osxcross_sdks_directory(Path::new(&path), fs);
+
+                    if fs.exists(&path) {
+                        return Ok(SdkSearchResolvedLocation::SdksDirectory(path));
+                    }
+                }
+
+                Ok(SdkSearchResolvedLocation::None)
+            }
+            #[cfg(feature = "parse")]
+            Self::Xcodebuild => {
+                match search
+                    .ensure_subprocess_allowed("xcodebuild")
+                    .and_then(|_| {
+                        let dir = env("DEVELOPER_DIR")
+                            .map(|value| DeveloperDirectory::from_path(PathBuf::from(value)))
+                            .transpose()?;
+
+                        XcodebuildSdk::run_show_sdks(dir.as_ref())
+                    }) {
+                    Ok(sdks) => Ok(SdkSearchResolvedLocation::SdkDirectories(
+                        sdks.into_iter().map(|sdk| sdk.sdk_path).collect(),
+                    )),
+                    Err(Error::XcodebuildRun(_) | Error::XcodebuildBadStatus(_)) => {
+                        Ok(SdkSearchResolvedLocation::None)
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        }
+    }
+}
+
+/// Canonicalize a path, falling back to the path itself if that fails.
+fn canonical_path(path: &Path, fs: &dyn Filesystem) -> PathBuf {
+    fs.canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Resolve the directory holding SDKs in an osxcross install.
+///
+/// `path` can be the install root or its `target` directory.
+/// Obtain [SdkSearchLocation::Sdks] locations for a [EXTRA_SDKS_DIRS_ENV] value.
+fn extra_sdks_dirs_locations(value: &OsStr) -> Vec<SdkSearchLocation> {
+    std::env::split_paths(value)
+        .filter(|path| !path.as_os_str().is_empty())
+        .map(SdkSearchLocation::Sdks)
+        .collect::<Vec<_>>()
+}
+
+/// Resolve the platform of the SDK to build a Rust target triple against.
+fn target_triple_sdk_platform(target: &str) -> Result<Platform, Error> {
+    if target.ends_with("-apple-ios-macabi") {
+        Ok(Platform::MacOsX)
+    } else {
+        Platform::from_target_triple(target)
+    }
+}
+
+fn osxcross_sdks_directory(path: &Path, fs: &dyn Filesystem) -> PathBuf {
+    let target = path.join("target");
+
+    if fs.is_dir(&target) {
+        target.join("SDK")
+    } else {
+        path.join("SDK")
+    }
+}
+
+/// Resolve an SDK name like `macosx` or `iphoneos17.0` to the path of an SDK.
+///
+/// SDKs are looked for in the default *Developer Directory* and the Xcode Command
+/// Line Tools. The newest matching SDK wins. The *Developer Directory* is resolved
+/// like [DeveloperDirectory::find_default()], reading `DEVELOPER_DIR` through the
+/// search's environment lookup and only running `xcode-select` if allowed.
+fn resolve_sdk_name(name: &str, search: &SdkSearch) -> Option<PathBuf> {
+    if name.is_empty() || name.contains(std::path::MAIN_SEPARATOR) {
+        return None;
+    }
+
+    let name = SdkName::from_str(name).ok()?;
+
+    let mut candidates = vec![];
+
+    let dir = if let Some(value) = (search.environment)("DEVELOPER_DIR") {
+        DeveloperDirectory::from_path(PathBuf::from(value)).ok()
+    } else if let Ok(Some(dir)) = DeveloperDirectory::from_xcode_select_pure() {
+        Some(dir)
+    } else if search.allow_subprocess {
+        DeveloperDirectory::from_xcode_select().ok()
+    } else {
+        None
+    };
+
+    let fs = &*search.filesystem;
+
+    if let Some(dir) = dir {
+        if let Ok(platforms) = dir.platforms_with_filesystem(fs) {
+            for platform_dir in platforms.iter().filter(|p| p.platform == name.platform) {
+                candidates.extend(
+                    SimpleSdk::find_in_directory_with_filesystem(&platform_dir.sdks_path(), fs)
+                        .unwrap_or_default(),
+                );
+            }
         }
     }
+    if let Some(path) = command_line_tools_sdks_directory_with_filesystem(fs) {
+        candidates
+            .extend(SimpleSdk::find_in_directory_with_filesystem(&path, fs).unwrap_or_default());
+    }
+
+    candidates
+        .into_iter()
+        .filter(|sdk| name.matches(sdk))
+        .max_by(|a, b| a.version().cmp(&b.version()))
+        .map(|sdk| sdk.path().to_path_buf())
 }
 
 /// Sorting strategy to apply to SDK searches.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "kebab-case")
+)]
 pub enum SdkSorting {
     /// Do not apply any sorting.
     ///
     /// This will return SDKs in the order they are discovered from the input
     /// paths.
+    #[default]
     None,
 
     /// Order SDKs by their version in descending order.
@@ -238,6 +495,11 @@ impl Display for SdkSorting {
 }
 
 impl SdkSorting {
+    /// Compare two SDK versions according to this sorting strategy.
+    ///
+    /// SDKs without a known version sort after SDKs with a version when sorting
+    /// by descending version and before them when sorting by ascending version.
+    /// In other words, unknown versions are treated as older than any known version.
     pub fn compare_version(&self, a: Option<&SdkVersion>, b: Option<&SdkVersion>) -> Ordering {
         match self {
             Self::None => Ordering::Equal,
@@ -255,29 +517,169 @@ impl SdkSorting {
             },
         }
     }
+
+    /// Sort SDKs according to this sorting strategy.
+    ///
+    /// The sort is stable: SDKs comparing equal retain their relative order.
+    pub fn sort<SDK: AppleSdk>(&self, sdks: &mut [SDK]) {
+        // Sorting should be stable with None variant. But we can avoid the
+        // overhead.
+        if *self != Self::None {
+            sdks.sort_by(|a, b| self.compare_version(a.version(), b.version()))
+        }
+    }
+}
+
+/// Find the newest SDK for a platform in the default search locations.
+///
+/// This is [SdkSearch::newest()] followed by [SdkSearch::find_first()].
+///
+/// Returns [Error::SdkNotFound] if no SDK for the platform is installed.
+pub fn find_newest_sdk<SDK: AppleSdk>(platform: Platform) -> Result<SDK, Error> {
+    SdkSearch::newest(platform).find_first()
+}
+
+/// How to treat SDKs coming from beta Xcode installs.
+///
+/// Beta status is determined by [XcodeApplication::is_beta()] on the Xcode
+/// application containing the SDK. SDKs not inside an Xcode application (e.g.
+/// the Command Line Tools) are never considered beta.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum XcodeBetaPreference {
+    /// SDKs from beta Xcodes are treated like any other SDK.
+    #[default]
+    Include,
+
+    /// SDKs from beta Xcodes are excluded from results.
+    Exclude,
+
+    /// SDKs from release Xcodes are ordered before SDKs from beta Xcodes.
+    ///
+    /// This is applied after [SdkSorting].
+    PreferRelease,
+
+    /// SDKs from beta Xcodes are ordered before SDKs from release Xcodes.
+    ///
+    /// This is applied after [SdkSorting].
+    PreferBeta,
+}
+
+impl Display for XcodeBetaPreference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Include => "include",
+            Self::Exclude => "exclude",
+            Self::PreferRelease => "prefer release",
+            Self::PreferBeta => "prefer beta",
+        })
+    }
+}
+
+/// Memoized beta status of Xcode applications, keyed by application path.
+///
+/// SDKs of the same Xcode share an entry, so each application's metadata is only
+/// read once.
+#[derive(Clone, Default)]
+struct XcodeBetaStatus(Arc<Mutex<HashMap<PathBuf, bool>>>);
+
+impl XcodeBetaStatus {
+    /// Whether an SDK path is within a beta Xcode application.
+    fn is_beta_sdk(&self, path: &Path) -> bool {
+        let Some(app) = path
+            .ancestors()
+            .find(|p| p.extension() == Some(OsStr::new("app")))
+        else {
+            return false;
+        };
+
+        if let Some(beta) = self
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(app)
+        {
+            return *beta;
+        }
+
+        let beta = XcodeApplication::containing(path)
+            .map(|app| app.is_beta())
+            .unwrap_or_default();
+
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(app.to_path_buf(), beta);
+
+        beta
+    }
+}
+
+/// Resolve the version of Xcode an SDK ships with.
+///
+/// The version of the containing Xcode application is preferred. Otherwise
+/// the `DTXcode` key of the containing platform directory is used.
+fn sdk_xcode_version(path: &Path) -> Option<SdkVersion> {
+    if let Some(version) = XcodeApplication::containing(path).and_then(|app| app.version) {
+        return Some(version);
+    }
+
+    // SDKs are in `<platform>/Developer/SDKs`.
+    let platform = path.parent()?.parent()?.parent()?;
+
+    PlatformDirectory::from_path(platform).ok()?.xcode_version
 }
 
 /// Describes an event during SDK discovery.
 ///
 /// This events are sent to the progress callback to allow monitoring and debugging
 /// of SDK searching activity.
+#[derive(Clone, Debug)]
 pub enum SdkSearchEvent {
     /// Beginning a search of a given location.
     SearchingLocation(SdkSearchLocation),
+    /// A Platform directory will be searched.
     PlatformDirectoryInclude(PathBuf),
+    /// A Platform directory will not be searched because it doesn't match the platform filter.
     PlatformDirectoryExclude(PathBuf),
+    /// Looking for SDKs in a directory holding SDKs.
+    SearchingDirectory(PathBuf),
+    /// An SDK directory could not be loaded, e.g. because its settings failed to
+    /// parse. The string describes the error.
+    SdkLoadFailure(PathBuf, String),
+    /// An SDK bypasses the search filter.
     SdkFilterSkip(SdkPath),
+    /// An SDK matches the search filter.
     SdkFilterMatch(SdkPath),
+    /// An SDK is excluded by the search filter. The string describes why.
     SdkFilterExclude(SdkPath, String),
+    /// An SDK is excluded because it resolves to the same directory as another SDK.
+    ///
+    /// The path is the canonical path of the SDK directory.
+    SdkDuplicateExclude(SdkPath, PathBuf),
+    /// No SDKs matched the preferred platforms. SDKs of a fallback platform are used.
+    PlatformFallback(Platform),
+    /// An SDK matching the search filter is excluded because SDKs of another platform
+    /// are preferred. See [SdkSearch::platform_fallbacks()].
+    SdkPlatformExclude(SdkPath),
+    /// An SDK matching the search filter is excluded because the result limit was
+    /// reached. See [SdkSearch::limit()].
+    SdkLimitExclude(SdkPath, usize),
+    /// Sorting found SDKs.
     Sorting(usize, SdkSorting),
+    /// Enough SDKs were found to satisfy the result limit. Remaining locations are
+    /// not searched.
+    LimitReached(usize),
 }
 
 impl Display for SdkSearchEvent {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::SearchingLocation(location) => {
-                f.write_fmt(format_args!("searching {location}"))
-            }
+            Self::SearchingLocation(location) => f.write_fmt(format_args!("searching {location}")),
             Self::PlatformDirectoryInclude(path) => f.write_fmt(format_args!(
                 "searching Platform directory {}",
                 path.display()
@@ -286,6 +688,12 @@ impl Display for SdkSearchEvent {
                 "excluding Platform directory {}",
                 path.display()
             )),
+            Self::SearchingDirectory(path) => {
+                f.write_fmt(format_args!("looking for SDKs in {}", path.display()))
+            }
+            Self::SdkLoadFailure(path, err) => {
+                f.write_fmt(format_args!("failed to load SDK {}: {err}", path.display()))
+            }
             Self::SdkFilterSkip(sdk) => f.write_fmt(format_args!("SDK {sdk} bypasses filter")),
             Self::SdkFilterMatch(sdk) => {
                 f.write_fmt(format_args!("SDK {sdk} matches search filter"))
@@ -293,6 +701,23 @@ impl Display for SdkSearchEvent {
             Self::SdkFilterExclude(sdk, reason) => {
                 f.write_fmt(format_args!("SDK {sdk} discarded because {reason}"))
             }
+            Self::SdkDuplicateExclude(sdk, path) => f.write_fmt(format_args!(
+                "SDK {sdk} discarded because it is a duplicate of {}",
+                path.display()
+            )),
+            Self::LimitReached(limit) => f.write_fmt(format_args!(
+                "found {limit} SDKs; not searching remaining locations"
+            )),
+            Self::PlatformFallback(platform) => f.write_fmt(format_args!(
+                "no SDKs found for preferred platforms; falling back to {}",
+                platform.filesystem_name()
+            )),
+            Self::SdkPlatformExclude(sdk) => f.write_fmt(format_args!(
+                "SDK {sdk} discarded because SDKs of another platform are preferred"
+            )),
+            Self::SdkLimitExclude(sdk, limit) => f.write_fmt(format_args!(
+                "SDK {sdk} discarded because the result limit of {limit} was reached"
+            )),
             Self::Sorting(count, sorting) => {
                 f.write_fmt(format_args!("sorting {count} SDKs by {sorting}"))
             }
@@ -300,8 +725,356 @@ impl Display for SdkSearchEvent {
     }
 }
 
+/// An SDK found by [SdkSearch::search_results()].
+#[derive(Clone, Debug)]
+pub struct SdkSearchResult<SDK> {
+    /// The found SDK.
+    pub sdk: SDK,
+
+    /// The search location that found the SDK.
+    pub location: SdkSearchLocation,
+
+    /// The *Developer Directory* the SDK was found in.
+    ///
+    /// For locations expanding to several installs, like [SdkSearchLocation::SystemXcodes],
+    /// this identifies the install the SDK belongs to. It is resolved from the location
+    /// when possible and from the SDK path otherwise. See
+    /// [AppleSdk::developer_directory()].
+    pub developer_directory: Option<DeveloperDirectory>,
+}
+
+impl<SDK: AppleSdk> SdkSearchResult<SDK> {
+    /// The kind of *Developer Directory* the SDK belongs to.
+    ///
+    /// This can be used to decide whether tools like `ibtool` or `actool` are
+    /// available alongside the SDK.
+    ///
+    /// Returns [None] if the SDK isn't in a *Developer Directory*.
+    pub fn developer_directory_kind(&self) -> Option<DeveloperDirectoryKind> {
+        self.developer_directory.as_ref().map(|dir| dir.kind())
+    }
+
+    /// The path to the Xcode application the SDK belongs to.
+    ///
+    /// Returns [None] unless [Self::developer_directory] is the `Contents/Developer`
+    /// directory of an `.app` bundle.
+    pub fn xcode_app_path(&self) -> Option<&Path> {
+        let dir = self.developer_directory.as_ref()?.path();
+
+        if dir.file_name()? != "Developer" {
+            return None;
+        }
+
+        let contents = dir.parent()?;
+
+        if contents.file_name()? != "Contents" {
+            return None;
+        }
+
+        let app = contents.parent()?;
+
+        if app.extension() == Some(OsStr::new("app")) {
+            Some(app)
+        } else {
+            None
+        }
+    }
+}
+
+/// A non-fatal problem encountered by [SdkSearch::search_lenient()].
+#[derive(Debug)]
+pub enum SdkSearchWarning {
+    /// A search location could not be resolved.
+    LocationFailure(SdkSearchLocation, Error),
+    /// A directory holding SDKs could not be read.
+    DirectoryUnreadable(PathBuf, Error),
+    /// An SDK could not be loaded. e.g. because its settings are malformed.
+    SdkLoadFailure(PathBuf, Error),
+}
+
+impl Display for SdkSearchWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LocationFailure(location, err) => {
+                f.write_fmt(format_args!("error resolving {location}: {err}"))
+            }
+            Self::DirectoryUnreadable(path, err) => f.write_fmt(format_args!(
+                "could not read SDKs directory {}: {err}",
+                path.display()
+            )),
+            Self::SdkLoadFailure(path, err) => {
+                f.write_fmt(format_args!("could not load SDK {}: {err}", path.display()))
+            }
+        }
+    }
+}
+
+impl SdkSearchWarning {
+    /// The error causing this warning.
+    pub fn error(&self) -> &Error {
+        match self {
+            Self::LocationFailure(_, err)
+            | Self::DirectoryUnreadable(_, err)
+            | Self::SdkLoadFailure(_, err) => err,
+        }
+    }
+
+    fn into_error(self) -> Error {
+        match self {
+            Self::LocationFailure(_, err)
+            | Self::DirectoryUnreadable(_, err)
+            | Self::SdkLoadFailure(_, err) => err,
+        }
+    }
+}
+
+/// The SDKs and warnings from [SdkSearch::search_lenient()].
+#[derive(Debug)]
+pub struct SdkSearchReport<SDK> {
+    /// The found SDKs, as [SdkSearch::search()] would return them.
+    pub sdks: Vec<SDK>,
+
+    /// Problems encountered during the search, in the order they occurred.
+    pub warnings: Vec<SdkSearchWarning>,
+}
+
+/// Why an SDK encountered by [SdkSearch::explain()] was not selected.
+#[derive(Debug)]
+pub enum SdkSearchRejection {
+    /// The SDK failed a search filter. The string describes why.
+    ///
+    /// e.g. its platform doesn't match the platform filter or its version is
+    /// older than the minimum version.
+    Filtered(String),
+    /// The SDK resolves to the same directory as a selected SDK.
+    ///
+    /// The path is the canonical path of the SDK directory.
+    Duplicate(PathBuf),
+    /// The SDK passed the filters, but SDKs of another platform were preferred.
+    ///
+    /// See [SdkSearch::platform_fallbacks()].
+    PlatformNotPreferred,
+    /// The SDK passed the filters, but enough SDKs were selected before it.
+    ///
+    /// See [SdkSearch::limit()].
+    LimitExceeded,
+    /// The SDK could not be loaded. e.g. because its settings are malformed.
+    LoadFailure(Error),
+}
+
+impl Display for SdkSearchRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Filtered(reason) => f.write_str(reason),
+            Self::Duplicate(path) => f.write_fmt(format_args!("duplicate of {}", path.display())),
+            Self::PlatformNotPreferred => f.write_str("SDKs of another platform are preferred"),
+            Self::LimitExceeded => f.write_str("search result limit reached"),
+            Self::LoadFailure(err) => f.write_fmt(format_args!("could not be loaded: {err}")),
+        }
+    }
+}
+
+/// An SDK encountered by [SdkSearch::explain()].
+#[derive(Debug)]
+pub struct SdkSearchCandidate {
+    /// The path of the SDK directory.
+    pub path: PathBuf,
+
+    /// Why the SDK was not selected, or [None] if the search selects it.
+    pub rejection: Option<SdkSearchRejection>,
+}
+
+impl SdkSearchCandidate {
+    /// Whether the search selects this SDK.
+    pub fn is_selected(&self) -> bool {
+        self.rejection.is_none()
+    }
+}
+
+/// The outcome of [SdkSearch::explain()].
+#[derive(Debug)]
+pub struct SdkSearchExplanation {
+    /// Every SDK the search encountered.
+    ///
+    /// SDKs are in the order they were encountered, followed by SDKs that could
+    /// not be loaded.
+    pub candidates: Vec<SdkSearchCandidate>,
+
+    /// Problems resolving search locations or reading directories holding SDKs.
+    pub warnings: Vec<SdkSearchWarning>,
+}
+
+impl SdkSearchExplanation {
+    /// The SDKs the search selects, in encounter order.
+    pub fn selected(&self) -> impl Iterator<Item = &SdkSearchCandidate> {
+        self.candidates.iter().filter(|c| c.is_selected())
+    }
+
+    /// The SDKs the search doesn't select, in encounter order.
+    pub fn rejected(&self) -> impl Iterator<Item = &SdkSearchCandidate> {
+        self.candidates.iter().filter(|c| !c.is_selected())
+    }
+}
+
+/// Handling of non-fatal errors during a search.
+///
+/// Strict searches fail on the first error. Lenient searches record the error
+/// as a warning and continue.
+struct SdkSearchWarnings(Option<Mutex<Vec<SdkSearchWarning>>>);
+
+impl SdkSearchWarnings {
+    fn strict() -> Self {
+        Self(None)
+    }
+
+    fn lenient() -> Self {
+        Self(Some(Mutex::new(vec![])))
+    }
+
+    /// Record a warning, or convert it to an error for strict searches.
+    fn record(&self, warning: SdkSearchWarning) -> Result<(), Error> {
+        if let Some(warnings) = &self.0 {
+            warn!("{warning}");
+
+            warnings
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(warning);
+
+            Ok(())
+        } else {
+            Err(warning.into_error())
+        }
+    }
+
+    fn into_vec(self) -> Vec<SdkSearchWarning> {
+        self.0
+            .map(|warnings| {
+                warnings
+                    .into_inner()
+                    .unwrap_or_else(PoisonError::into_inner)
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// A cached list of SDKs found in a directory.
+struct SdkSearchCacheEntry {
+    /// Modification time, size, and inode of the directory when it was read.
+    stamp: (SystemTime, u64, Option<u64>),
+    sdks: Box<dyn Any + Send + Sync>,
+}
+
+/// An in-memory cache of SDKs discovered by [SdkSearch::search_cached()].
+///
+/// Entries are keyed on the directory holding SDKs and the SDK type. An entry
+/// is reused for as long as the modification time, size, and inode of its
+/// directory are unchanged. Adding, removing, or renaming SDKs changes the
+/// modification time. Replacing the directory, e.g. by reinstalling Xcode, changes
+/// its inode even if the modification time is too coarse to tell. Changes within
+/// SDK directories (e.g. to `SDKSettings.json`) are not detected. Call
+/// [Self::invalidate()] or [Self::clear()] to discard entries if this matters.
+///
+/// Clones share the same entries, so a cache can be shared between threads.
+#[derive(Clone, Default)]
+pub struct SdkSearchCache {
+    entries: Arc<Mutex<HashMap<(TypeId, PathBuf), SdkSearchCacheEntry>>>,
+}
+
+impl SdkSearchCache {
+    /// Construct an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove the cache entries of a directory holding SDKs.
+    ///
+    /// The next search reads the directory again.
+    pub fn invalidate(&self, dir: impl AsRef<Path>) {
+        let dir = dir.as_ref();
+
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|(_, path), _| path != dir);
+    }
+
+    /// Remove all cache entries.
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// The number of cached directories.
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Whether the cache has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Find SDKs in a directory, consulting the cache first.
+    ///
+    /// Directories whose modification time can't be read are never cached.
+    fn find_in_directory<SDK: AppleSdk + Clone + Send + Sync + 'static>(
+        &self,
+        dir: &Path,
+        fs: &dyn Filesystem,
+    ) -> Result<Vec<SDK>, Error> {
+        let Some(stamp) = fs
+            .metadata(dir)
+            .ok()
+            .and_then(|m| Some((m.modified?, m.len, m.inode)))
+        else {
+            return SDK::find_in_directory_with_filesystem(dir, fs);
+        };
+
+        let key = (TypeId::of::<SDK>(), dir.to_path_buf());
+
+        if let Some(entry) = self
+            .entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+        {
+            if entry.stamp == stamp {
+                if let Some(sdks) = entry.sdks.downcast_ref::<Vec<SDK>>() {
+                    return Ok(sdks.clone());
+                }
+            }
+        }
+
+        let sdks = SDK::find_in_directory_with_filesystem(dir, fs)?;
+
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(
+                key,
+                SdkSearchCacheEntry {
+                    stamp,
+                    sdks: Box::new(sdks.clone()),
+                },
+            );
+
+        Ok(sdks)
+    }
+}
+
 /// A callable that receives progress during an SDK search.
-pub type SdkProgressCallback = fn(SdkSearchEvent);
+pub type SdkProgressCallback = Arc<dyn Fn(SdkSearchEvent) + Send + Sync>;
+
+/// A callable resolving an environment variable name to its value.
+///
+/// See [SdkSearch::environment()].
+pub type SdkEnvironmentLookup = Arc<dyn Fn(&str) -> Option<OsString> + Send + Sync>;
 
 /// Search parameters for locating an Apple SDK.
 ///
@@ -312,7 +1085,8 @@ pub type SdkProgressCallback = fn(SdkSearchEvent);
 ///
 /// 1. Iterate through each registered search location.
 /// 2. Discover candidate SDKs and filter.
-/// 3. Globally sort (if enabled).
+/// 3. Remove duplicate SDKs (if enabled).
+/// 4. Globally sort (if enabled).
 ///
 /// # Search Locations
 ///
@@ -326,13 +1100,16 @@ pub type SdkProgressCallback = fn(SdkSearchEvent);
 /// 4. Find SDKs within the system installed Xcode Command Line Tools.
 ///
 /// Simply call [Self::location()] to register a new location. If the default locations
-/// are not desirable, construct an empty instance via [Self::empty()] and register your
-/// explicit list of locations.
+/// are not desirable, call [Self::locations()] with an explicit ordered list of locations
+/// or construct an empty instance via [Self::empty()] and register them individually.
 ///
 /// An attempt is made to only search a given location at most once. This is done in
 /// order to avoid redundant work. If a location is specified multiple times - even via
 /// different [SdkSearchLocation] variants - subsequent searches of that location will
-/// yield no distinct results. Duplicate SDKs can occur in the returned list.
+/// yield no distinct results. Duplicate SDKs can occur in the returned list. e.g. SDK
+/// directories are often symlinks to other SDK directories (`MacOSX.sdk` ->
+/// `MacOSX13.1.sdk`). Call [Self::deduplicate()] to remove SDKs resolving to the
+/// same directory or [Self::prefer_versioned()] to only remove such symlinks.
 ///
 /// # Filtering
 ///
@@ -344,9 +1121,18 @@ pub type SdkProgressCallback = fn(SdkSearchEvent);
 /// The following functions control filtering:
 ///
 /// * [Self::platform()]
+/// * [Self::platform_fallbacks()]
 /// * [Self::minimum_version()]
 /// * [Self::maximum_version()]
+/// * [Self::version_req()]
 /// * [Self::deployment_target()]
+/// * [Self::deployment_target_from_env()]
+/// * [Self::exclude_symlinks()]
+/// * [Self::minimum_xcode_version()]
+///
+/// The platform filter and beta Xcode exclusion only need the SDK path. They are applied
+/// before SDKs are constructed, so searches for [ParsedSdk](crate::ParsedSdk) only parse
+/// the settings of SDKs passing them. See [Self::filter_sdk_path()].
 ///
 /// If you are looking for an SDK to use (e.g. for compilation), you should at least use a
 /// platform filter. Otherwise you may see SDKs for platforms you aren't targeting! It is
@@ -369,28 +1155,81 @@ pub type SdkProgressCallback = fn(SdkSearchEvent);
 pub struct SdkSearch {
     progress_callback: Option<SdkProgressCallback>,
     locations: Vec<SdkSearchLocation>,
-    platform: Option<Platform>,
+    platforms: Vec<Platform>,
+    platform_fallbacks: Vec<Platform>,
     minimum_version: Option<SdkVersion>,
     maximum_version: Option<SdkVersion>,
+    version_req: Option<SdkVersionReq>,
     deployment_target: Option<(String, SdkVersion)>,
+    deployment_target_strict: bool,
+    deployment_target_from_env: bool,
     sorting: SdkSorting,
+    beta_preference: XcodeBetaPreference,
+    deduplicate: bool,
+    prefer_versioned: bool,
+    exclude_symlinks: bool,
+    minimum_xcode_version: Option<SdkVersion>,
+    limit: Option<usize>,
+    extra_sdks_dirs_from_env: bool,
+    filesystem: Arc<dyn Filesystem>,
+    environment: SdkEnvironmentLookup,
+    allow_subprocess: bool,
+    xcode_beta_status: XcodeBetaStatus,
 }
 
-impl Default for SdkSearch {
-    fn default() -> Self {
+impl std::fmt::Debug for SdkSearch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SdkSearch")
+            .field("locations", &self.locations)
+            .field("platforms", &self.platforms)
+            .field("platform_fallbacks", &self.platform_fallbacks)
+            .field("minimum_version", &self.minimum_version)
+            .field("maximum_version", &self.maximum_version)
+            .field("version_req", &self.version_req)
+            .field("deployment_target", &self.deployment_target)
+            .field(
+                "deployment_target_from_env",
+                &self.deployment_target_from_env,
+            )
+            .field("sorting", &self.sorting)
+            .field("beta_preference", &self.beta_preference)
+            .field("deduplicate", &self.deduplicate)
+            .field("prefer_versioned", &self.prefer_versioned)
+            .field("exclude_symlinks", &self.exclude_symlinks)
+            .field("minimum_xcode_version", &self.minimum_xcode_version)
+            .field("limit", &self.limit)
+            .field("extra_sdks_dirs_from_env", &self.extra_sdks_dirs_from_env)
+            .field("allow_subprocess", &self.allow_subprocess)
+            .field("progress_callback", &self.progress_callback.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for SdkSearch {
+    fn default() -> Self {
         Self {
             progress_callback: None,
-            locations: vec![
-                SdkSearchLocation::SdkRootEnv,
-                SdkSearchLocation::DeveloperDirEnv,
-                SdkSearchLocation::SystemXcode,
-                SdkSearchLocation::CommandLineTools,
-            ],
-            platform: None,
+            locations: SdkSearchLocation::default_locations(),
+            platforms: vec![],
+            platform_fallbacks: vec![],
             minimum_version: None,
             maximum_version: None,
+            version_req: None,
             deployment_target: None,
+            deployment_target_strict: true,
+            deployment_target_from_env: false,
             sorting: SdkSorting::None,
+            beta_preference: XcodeBetaPreference::Include,
+            deduplicate: false,
+            prefer_versioned: false,
+            exclude_symlinks: false,
+            minimum_xcode_version: None,
+            limit: None,
+            extra_sdks_dirs_from_env: true,
+            filesystem: Arc::new(RealFilesystem),
+            environment: Arc::new(|name| std::env::var_os(name)),
+            allow_subprocess: true,
+            xcode_beta_status: XcodeBetaStatus::default(),
         }
     }
 }
@@ -406,12 +1245,276 @@ impl SdkSearch {
         s
     }
 
+    /// Obtain an instance with default search locations suitable for a Rust target triple.
+    ///
+    /// The argument should be a target triple of a Rust toolchain. e.g.
+    /// `aarch64-apple-ios-sim`. Build scripts can pass the value of the `TARGET`
+    /// environment variable.
+    ///
+    /// The platform filter is set via [Platform::from_target_triple()]. Mac Catalyst
+    /// targets (`*-apple-ios-macabi`) build against the macOS SDK, so the platform
+    /// filter is [Platform::MacOsX] for them.
+    ///
+    /// Will return [Error::UnknownTarget] if this does not appear to be a known
+    /// Apple target triple.
+    pub fn for_target_triple(target: &str) -> Result<Self, Error> {
+        Ok(Self::default().platform(target_triple_sdk_platform(target)?))
+    }
+
+    /// Obtain an instance with default search locations matching an SDK name.
+    ///
+    /// The argument is a name as accepted by `xcrun --sdk` and `xcodebuild -sdk`.
+    /// e.g. `macosx`, `iphoneos17.0`, or `iphonesimulator`. See [SdkName].
+    ///
+    /// The platform filter is set from the name. If the name has a version, both the
+    /// minimum and maximum version are set to it, so only SDKs of that version match.
+    /// Results are sorted by [SdkSorting::VersionDescending], so the first result is
+    /// usually the SDK `xcrun` would choose. Use [crate::xcrun_sdk_path()] to mirror
+    /// `xcrun` exactly, including its preference for unversioned symlinks.
+    ///
+    /// Will return [Error::SdkNameParse] if the name is not a known SDK name.
+    pub fn for_sdk_name(name: &str) -> Result<Self, Error> {
+        let name = SdkName::from_str(name)?;
+
+        let mut search = Self::default()
+            .platform(name.platform)
+            .sorting(SdkSorting::VersionDescending);
+
+        if let Some(version) = name.version {
+            search = search
+                .minimum_version(version.clone())
+                .maximum_version(version);
+        }
+
+        Ok(search)
+    }
+
+    /// Obtain an instance suited to finding a macOS SDK to compile against.
+    ///
+    /// This searches the locations a compiler toolchain would consult: `SDKROOT`,
+    /// `DEVELOPER_DIR`, the system Xcode, and the Xcode Command Line Tools. Only
+    /// macOS SDKs are returned, sorted by [SdkSorting::VersionDescending], with SDKs
+    /// from beta Xcodes ordered after release ones via
+    /// [XcodeBetaPreference::PreferRelease].
+    ///
+    /// The first result is the SDK to use.
+    pub fn macos_compiler_default() -> Self {
+        Self::empty()
+            .locations([
+                SdkSearchLocation::SdkRootEnv,
+                SdkSearchLocation::DeveloperDirEnv,
+                SdkSearchLocation::SystemXcode,
+                SdkSearchLocation::CommandLineTools,
+            ])
+            .platform(Platform::MacOsX)
+            .sorting(SdkSorting::VersionDescending)
+            .beta_preference(XcodeBetaPreference::PreferRelease)
+    }
+
+    /// Obtain an instance finding the newest SDK for a platform.
+    ///
+    /// This searches the default locations. See [SdkSearchLocation::default_locations()].
+    /// Results are sorted by [SdkSorting::VersionDescending], with SDKs from beta
+    /// Xcodes ordered after release ones via [XcodeBetaPreference::PreferRelease].
+    ///
+    /// No [Self::limit()] is set, as it would stop at the first location with
+    /// matches. Every location is searched so the newest SDK overall comes first.
+    ///
+    /// Use [Self::find_first()] or [find_newest_sdk()] to resolve the SDK.
+    pub fn newest(platform: Platform) -> Self {
+        Self::default()
+            .platform(platform)
+            .sorting(SdkSorting::VersionDescending)
+            .beta_preference(XcodeBetaPreference::PreferRelease)
+    }
+
+    /// Obtain an instance searching every location that can be found without configuration.
+    ///
+    /// This searches all system and user installed Xcode applications, the Xcode
+    /// Command Line Tools, and osxcross installs defined by environment variables.
+    /// SDKs reachable from multiple locations are only returned once and unversioned
+    /// symlinks are dropped in favor of the SDKs they point to. See
+    /// [Self::deduplicate()] and [Self::prefer_versioned()].
+    ///
+    /// This is useful for taking an inventory of installed SDKs. The terminal
+    /// [SdkSearchLocation::SdkRootEnv] and [SdkSearchLocation::DeveloperDirEnv]
+    /// locations are not registered, as they would end the search early. Neither
+    /// are the locations running external tools.
+    pub fn everything() -> Self {
+        Self::empty()
+            .locations([
+                SdkSearchLocation::SystemXcodes,
+                SdkSearchLocation::UserXcodes,
+                SdkSearchLocation::CommandLineTools,
+                SdkSearchLocation::OsxcrossEnv,
+            ])
+            .deduplicate(true)
+            .prefer_versioned(true)
+    }
+
+    /// Obtain an instance configured entirely from environment variables.
+    ///
+    /// This allows end users of tools built on this crate to control SDK resolution
+    /// without tool-specific configuration.
+    ///
+    /// The default search locations are used, which honor `SDKROOT` and
+    /// `DEVELOPER_DIR`. In addition:
+    ///
+    /// * If [SDK_SEARCH_CONFIG_ENV](crate::SDK_SEARCH_CONFIG_ENV) is set, the config
+    ///   file it designates is applied. Config files are otherwise not consulted.
+    ///   Requires the `parse` crate feature.
+    /// * If `TARGET` is an Apple target triple (as it is in Cargo build scripts), the
+    ///   platform filter is set as in [Self::for_target_triple()], replacing any
+    ///   platforms from the config file.
+    /// * Each directory in [EXTRA_SDKS_DIRS_ENV] is registered as a
+    ///   [SdkSearchLocation::Sdks] location.
+    /// * If a platform is known and its deployment target environment variable (e.g.
+    ///   `MACOSX_DEPLOYMENT_TARGET`) is set, a [Self::deployment_target()] filter is
+    ///   applied. Mac Catalyst targets use `IPHONEOS_DEPLOYMENT_TARGET` and the
+    ///   `iosmac` target.
+    ///
+    /// Unlike one set explicitly, the deployment target filter doesn't apply to SDKs
+    /// that can't evaluate deployment targets. So searches for `SimpleSdk` aren't
+    /// filtered by it.
+    ///
+    /// The returned search reads environment variables from the process environment.
+    pub fn from_env() -> Result<Self, Error> {
+        Self::from_env_with_lookup(|name| std::env::var_os(name))
+    }
+
+    /// Obtain an instance configured from an environment lookup.
+    ///
+    /// This is [Self::from_env()] with variables read through `lookup` instead of
+    /// the process environment. The lookup is retained for variables read at search
+    /// time. See [Self::environment()].
+    pub fn from_env_with_lookup(
+        lookup: impl Fn(&str) -> Option<OsString> + Send + Sync + 'static,
+    ) -> Result<Self, Error> {
+        let target = lookup("TARGET")
+            .and_then(|target| target.into_string().ok())
+            .filter(|target| target.contains("-apple-"));
+
+        let mut search = Self::default();
+
+        #[cfg(feature = "parse")]
+        if let Some(value) = lookup(crate::SDK_SEARCH_CONFIG_ENV).filter(|v| !v.is_empty()) {
+            if let Some(config) = SdkSearchConfig::from_env_value(Path::new(&value))? {
+                search = config.apply(search);
+            }
+        }
+
+        if let Some(target) = &target {
+            search.platforms = vec![target_triple_sdk_platform(target)?];
+        }
+
+        if let Some(value) = lookup(EXTRA_SDKS_DIRS_ENV) {
+            for location in extra_sdks_dirs_locations(&value) {
+                search = search.location(location);
+            }
+        }
+
+        let catalyst = target
+            .as_deref()
+            .map(|t| t.ends_with("-apple-ios-macabi"))
+            .unwrap_or_default();
+
+        let deployment_target = if catalyst {
+            DeploymentTarget::from_env_with_lookup(Platform::IPhoneOs, &lookup)?
+                .map(|target| ("iosmac".to_string(), target.version().clone()))
+        } else if let Some(platform) = search.platforms.first().cloned() {
+            DeploymentTarget::from_env_with_lookup(platform, &lookup)?
+                .map(|target| (target.target_name(), target.version().clone()))
+        } else {
+            None
+        };
+
+        if let Some((target, version)) = deployment_target {
+            search = search.deployment_target(target, version);
+            search.deployment_target_strict = false;
+        }
+
+        Ok(search.environment(lookup))
+    }
+
+    /// Obtain an instance with default search locations configured by a config file.
+    ///
+    /// See [SdkSearchConfig::from_path()] for how the file is loaded.
+    #[cfg(feature = "parse")]
+    pub fn from_config_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(SdkSearchConfig::from_path(path)?.apply(Self::default()))
+    }
+
     /// Define a function that will be called to provide updates on SDK search status.
-    pub fn progress_callback(mut self, callback: SdkProgressCallback) -> Self {
-        self.progress_callback = Some(callback);
+    ///
+    /// The function receives a [SdkSearchEvent] for each notable action taken during
+    /// the search, including why individual SDKs were excluded. Its [Display]
+    /// implementation provides a human readable description.
+    pub fn progress_callback(
+        mut self,
+        callback: impl Fn(SdkSearchEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Set the [Filesystem] that SDK search locations are read from.
+    ///
+    /// Default is [RealFilesystem]. Using a [MemoryFilesystem](crate::MemoryFilesystem)
+    /// allows testing search behavior against a fixed tree on any host.
+    ///
+    /// Locations derived from the running system, such as [SdkSearchLocation::SystemXcode]
+    /// and [SdkSearchLocation::SystemXcodes], are discovered on this filesystem too. Only
+    /// the `xcode-select` selection is still read from the real system.
+    pub fn filesystem(mut self, fs: impl Filesystem + 'static) -> Self {
+        self.filesystem = Arc::new(fs);
+        self
+    }
+
+    /// Set the function used to read environment variables.
+    ///
+    /// The function receives a variable name and returns its value, if set. Default
+    /// reads the process environment via [std::env::var_os()].
+    ///
+    /// The lookup is consulted for `SDKROOT`, `DEVELOPER_DIR`, the osxcross variables,
+    /// [EXTRA_SDKS_DIRS_ENV], and deployment target variables. Replacing it allows
+    /// controlling these without modifying the process environment.
+    pub fn environment(
+        mut self,
+        lookup: impl Fn(&str) -> Option<OsString> + Send + Sync + 'static,
+    ) -> Self {
+        self.environment = Arc::new(lookup);
+        self
+    }
+
+    /// Whether the search may run external programs.
+    ///
+    /// Some locations run programs to discover SDKs. e.g. `xcode-select` for
+    /// [SdkSearchLocation::XcodeSelect] when its symlink is missing and `xcodebuild`
+    /// for [SdkSearchLocation::Xcodebuild]. When disabled, resolving these locations
+    /// errors with [Error::SubprocessDisabled] instead. SDK names in `SDKROOT` are
+    /// then only resolved without running `xcode-select`. This is intended for
+    /// sandboxed build environments.
+    ///
+    /// This only affects the `xcode-select` and `xcodebuild` invocations made during
+    /// searches. Other functions of this crate still run programs regardless. e.g.
+    /// [DeveloperDirectory::find_default()] may run `xcode-select` and
+    /// [crate::find_command_line_tools_update_label()] runs `softwareupdate`.
+    ///
+    /// Default is `true`.
+    pub fn allow_subprocess(mut self, allowed: bool) -> Self {
+        self.allow_subprocess = allowed;
         self
     }
 
+    /// Error if running `program` is prevented by [Self::allow_subprocess()].
+    fn ensure_subprocess_allowed(&self, program: &'static str) -> Result<(), Error> {
+        if self.allow_subprocess {
+            Ok(())
+        } else {
+            Err(Error::SubprocessDisabled(program))
+        }
+    }
+
     /// Add a location to search.
     ///
     /// The location will be appended to the current search location list.
@@ -420,17 +1523,168 @@ impl SdkSearch {
         self
     }
 
+    /// Replace the list of locations to search.
+    ///
+    /// Locations are searched in the order given. Any previously registered locations,
+    /// including the defaults, are discarded.
+    pub fn locations(mut self, locations: impl IntoIterator<Item = SdkSearchLocation>) -> Self {
+        self.locations = locations.into_iter().collect::<Vec<_>>();
+        self
+    }
+
+    /// Whether to search Xcode applications in the user's `~/Applications` directory.
+    ///
+    /// When enabled, [SdkSearchLocation::UserXcodes] is registered right after the
+    /// last [SdkSearchLocation::SystemXcode] or [SdkSearchLocation::SystemXcodes]
+    /// location, so user installs are searched with the same priority relative to
+    /// other locations as system installs. If neither is registered, it is appended.
+    /// When disabled, [SdkSearchLocation::UserXcodes] is removed.
+    ///
+    /// Default is disabled.
+    pub fn user_applications(mut self, enabled: bool) -> Self {
+        let present = self.locations.contains(&SdkSearchLocation::UserXcodes);
+
+        if enabled && !present {
+            let index = self
+                .locations
+                .iter()
+                .rposition(|l| {
+                    matches!(
+                        l,
+                        SdkSearchLocation::SystemXcode | SdkSearchLocation::SystemXcodes
+                    )
+                })
+                .map(|i| i + 1)
+                .unwrap_or(self.locations.len());
+
+            self.locations.insert(index, SdkSearchLocation::UserXcodes);
+        } else if !enabled {
+            self.locations
+                .retain(|l| l != &SdkSearchLocation::UserXcodes);
+        }
+
+        self
+    }
+
+    /// The locations that will be searched, in search order.
+    pub fn search_locations(&self) -> &[SdkSearchLocation] {
+        &self.locations
+    }
+
     /// Set the SDK platform to search for.
     ///
     /// If you do not call this, SDKs for all platforms are returned.
     ///
+    /// This replaces any previously set platforms. Use [Self::add_platform()] to search
+    /// for SDKs of several platforms in a single pass.
+    ///
     /// If you are looking for a specific SDK to use, you probably want to call this.
     /// If you are searching for all available SDKs, you probably don't want to call this.
     pub fn platform(mut self, platform: Platform) -> Self {
-        self.platform = Some(platform);
+        self.platforms = vec![platform];
+        self
+    }
+
+    /// Add an SDK platform to search for.
+    ///
+    /// The platform is appended to the current platform filter. This allows searching
+    /// for SDKs of several platforms in a single pass. e.g. [Platform::IPhoneOs] and
+    /// [Platform::IPhoneSimulator].
+    pub fn add_platform(mut self, platform: Platform) -> Self {
+        if !self.platforms.contains(&platform) {
+            self.platforms.push(platform);
+        }
+        self
+    }
+
+    /// Replace the SDK platforms to search for.
+    ///
+    /// Any previously set platforms are discarded.
+    pub fn platforms(mut self, platforms: impl IntoIterator<Item = Platform>) -> Self {
+        self.platforms.clear();
+        for platform in platforms {
+            self = self.add_platform(platform);
+        }
+        self
+    }
+
+    /// Define platforms to fall back to if no SDKs match the platform filter.
+    ///
+    /// By default, the platform filter is strict: if no SDKs for the platforms
+    /// registered via [Self::platform()] exist, the search yields nothing. With
+    /// fallbacks, the platform filter becomes a preference. If no SDKs of the preferred
+    /// platforms are found, SDKs of the first fallback platform (in the order given)
+    /// having any SDKs are returned instead. e.g. prefer
+    /// [Platform::IPhoneSimulator] and fall back to [Platform::IPhoneOs].
+    ///
+    /// SDKs of at most one fallback platform are returned. All other filters still
+    /// apply. Fallbacks have no effect if no platform filter is registered, as SDKs
+    /// of all platforms are returned in that case.
+    ///
+    /// Calling this replaces previously registered fallbacks.
+    pub fn platform_fallbacks(mut self, platforms: impl IntoIterator<Item = Platform>) -> Self {
+        self.platform_fallbacks = vec![];
+
+        for platform in platforms {
+            if !self.platform_fallbacks.contains(&platform) {
+                self.platform_fallbacks.push(platform);
+            }
+        }
+
         self
     }
 
+    /// Whether a platform passes the platform filter.
+    ///
+    /// Fallback platforms pass the filter, as they may be needed.
+    fn platform_matches(&self, platform: &Platform) -> bool {
+        self.platforms.is_empty()
+            || self.platforms.contains(platform)
+            || self.platform_fallbacks.contains(platform)
+    }
+
+    /// Retain only SDKs of the most preferred platform having any SDKs.
+    ///
+    /// Preferred platforms all rank equally, followed by the fallbacks in order.
+    fn apply_platform_fallbacks<SDK: AppleSdk>(
+        &self,
+        sdks: Vec<SdkSearchResult<SDK>>,
+    ) -> Vec<SdkSearchResult<SDK>> {
+        if self.platforms.is_empty() || self.platform_fallbacks.is_empty() {
+            return sdks;
+        }
+
+        let retain = |sdks: Vec<SdkSearchResult<SDK>>, platforms: &[Platform]| {
+            sdks.into_iter()
+                .filter(|result| {
+                    if platforms.contains(result.sdk.platform()) {
+                        true
+                    } else {
+                        self.emit(|| SdkSearchEvent::SdkPlatformExclude(result.sdk.sdk_path()));
+                        false
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        if sdks
+            .iter()
+            .any(|result| self.platforms.contains(result.sdk.platform()))
+        {
+            return retain(sdks, &self.platforms);
+        }
+
+        for fallback in &self.platform_fallbacks {
+            if sdks.iter().any(|result| result.sdk.platform() == fallback) {
+                self.emit(|| SdkSearchEvent::PlatformFallback(fallback.clone()));
+
+                return retain(sdks, std::slice::from_ref(fallback));
+            }
+        }
+
+        retain(sdks, &[])
+    }
+
     /// Minimum SDK version to require.
     ///
     /// Effectively imposes a `>=` filter on found SDKs.
@@ -451,6 +1705,18 @@ impl SdkSearch {
         self
     }
 
+    /// Require SDK versions to satisfy a version requirement.
+    ///
+    /// This expresses version constraints like `>=13, <15` or `~14.2` in a single
+    /// value. See [SdkVersionReq]. It is applied in addition to [Self::minimum_version()]
+    /// and [Self::maximum_version()].
+    ///
+    /// SDKs without a version fail this filter.
+    pub fn version_req(mut self, req: SdkVersionReq) -> Self {
+        self.version_req = Some(req);
+        self
+    }
+
     /// Deployment target that the SDK must support.
     ///
     /// When set, only SDKs that support targeting the given target-version pair will
@@ -460,13 +1726,36 @@ impl SdkSearch {
     /// in a way that allows this filter to work.
     ///
     /// Attempting to use this filter on `SimpleSdk` will result in a run-time
-    /// error at search time since these SDKs do not parse `SDKSettings` files.
+    /// error at search time since these SDKs do not parse `SDKSettings` files. The
+    /// filter applied by [Self::from_env()] is the exception.
     pub fn deployment_target(
         mut self,
         target: impl ToString,
         version: impl Into<SdkVersion>,
     ) -> Self {
         self.deployment_target = Some((target.to_string(), version.into()));
+        self.deployment_target_strict = true;
+        self
+    }
+
+    /// Whether to filter SDKs by deployment target environment variables.
+    ///
+    /// When enabled, each SDK is checked against the deployment target in the
+    /// environment variable of its platform. e.g. `MACOSX_DEPLOYMENT_TARGET` for
+    /// macOS SDKs and `IPHONEOS_DEPLOYMENT_TARGET` for iOS and iOS Simulator SDKs.
+    /// See [Platform::deployment_target_environment_variable()]. SDKs that can't
+    /// target the version are excluded. SDKs whose variable isn't set aren't filtered.
+    ///
+    /// This matches how Apple's build tools treat these variables. The variables
+    /// are read at search time through [Self::environment()]. An invalid value fails
+    /// the search with [Error::VersionParse].
+    ///
+    /// SDKs that can't evaluate deployment targets, such as `SimpleSdk`, aren't
+    /// filtered.
+    ///
+    /// Default is `false`.
+    pub fn deployment_target_from_env(mut self, enabled: bool) -> Self {
+        self.deployment_target_from_env = enabled;
         self
     }
 
@@ -478,115 +1767,895 @@ impl SdkSearch {
         self
     }
 
+    /// Define how SDKs from beta Xcode installs are treated.
+    ///
+    /// Default is [XcodeBetaPreference::Include]. Machines carrying beta Xcodes
+    /// can use [XcodeBetaPreference::Exclude] or [XcodeBetaPreference::PreferRelease]
+    /// to avoid picking up pre-release SDKs.
+    ///
+    /// The beta status of each Xcode application is resolved once and remembered by
+    /// this search and its clones.
+    pub fn beta_preference(mut self, preference: XcodeBetaPreference) -> Self {
+        self.beta_preference = preference;
+        self
+    }
+
+    /// Minimum version of Xcode an SDK must ship with.
+    ///
+    /// The Xcode version is the version of the Xcode application containing the SDK.
+    /// If the SDK isn't within an Xcode application, the `DTXcode` key of its platform
+    /// directory is used. See [PlatformDirectory::xcode_version].
+    ///
+    /// SDKs with an unknown Xcode version fail this filter. This includes SDKs from
+    /// the Xcode Command Line Tools. Xcode versions are read from plist files, so this
+    /// filter excludes all SDKs without the `parse` crate feature.
+    pub fn minimum_xcode_version(mut self, version: impl Into<SdkVersion>) -> Self {
+        self.minimum_xcode_version = Some(version.into());
+        self
+    }
+
+    /// Whether to remove SDKs resolving to the same directory.
+    ///
+    /// When enabled, SDK paths are canonicalized and only the first SDK for each
+    /// canonical path is retained. If one of the duplicates is not a symlink, it is
+    /// retained in place of the symlink, as its directory name typically carries the
+    /// SDK version.
+    ///
+    /// Default is `false`.
+    pub fn deduplicate(mut self, enabled: bool) -> Self {
+        self.deduplicate = enabled;
+        self
+    }
+
+    /// Whether to drop symlinked SDKs resolving to another SDK in the results.
+    ///
+    /// SDK directories often contain symlinks like `MacOSX.sdk` or `MacOSX14.sdk`
+    /// pointing at `MacOSX14.2.sdk`. When enabled, a symlinked SDK is removed if the
+    /// results hold another SDK resolving to the same directory that is not a symlink
+    /// or whose directory name carries a version. So only the versioned,
+    /// non-symlink entry remains.
+    ///
+    /// Unlike [Self::deduplicate()], non-symlink SDKs are never removed and which
+    /// entry is retained doesn't depend on the search order. Unlike
+    /// [Self::exclude_symlinks()], symlinks are retained if they are the only way
+    /// to reach an SDK.
+    ///
+    /// Default is `false`.
+    pub fn prefer_versioned(mut self, enabled: bool) -> Self {
+        self.prefer_versioned = enabled;
+        self
+    }
+
+    /// Whether to search the directories listed in [EXTRA_SDKS_DIRS_ENV].
+    ///
+    /// When enabled, each directory in the environment variable is searched as a
+    /// [SdkSearchLocation::Sdks] location after the registered locations. Directories
+    /// that are already registered are not searched twice. The variable is read at
+    /// search time.
+    ///
+    /// This lets end users point tools built on this crate at custom SDK
+    /// directories without tool-specific configuration.
+    ///
+    /// Default is `true`.
+    pub fn extra_sdks_dirs_from_env(mut self, enabled: bool) -> Self {
+        self.extra_sdks_dirs_from_env = enabled;
+        self
+    }
+
+    /// Whether to exclude SDKs whose directory is a symlink.
+    ///
+    /// SDK directories like `MacOSX.sdk` are often unversioned symlinks to a
+    /// versioned SDK directory in the same location. See [AppleSdk::is_symlink()].
+    /// Excluding them is a cheaper alternative to [Self::deduplicate()] when only
+    /// the versioned SDKs are of interest.
+    ///
+    /// Default is `false`.
+    pub fn exclude_symlinks(mut self, enabled: bool) -> Self {
+        self.exclude_symlinks = enabled;
+        self
+    }
+
+    /// Stop searching once this many matching SDKs have been found.
+    ///
+    /// Locations are searched in order. Once the SDKs found so far reach the limit,
+    /// remaining locations are skipped. At most `limit` SDKs are returned. This avoids
+    /// scanning every Xcode install when only one SDK is needed.
+    ///
+    /// Sorting only considers SDKs found before the search stopped. e.g. with a limit
+    /// of 1 and [SdkSorting::VersionDescending], the newest SDK of the first location
+    /// with matches is returned, not the newest SDK of all locations.
+    ///
+    /// When deduplicating, SDKs resolving to the same directory count once. When
+    /// platform fallbacks are defined, only SDKs of the preferred platforms count.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
     /// Perform a search, yielding found SDKs sorted by the search's preferences.
     ///
     /// May return an empty vector.
     pub fn search<SDK: AppleSdk>(&self) -> Result<Vec<SDK>, Error> {
-        let mut sdks = vec![];
+        Ok(self
+            .search_results::<SDK>()?
+            .into_iter()
+            .map(|result| result.sdk)
+            .collect())
+    }
 
-        // Track searched locations to avoid redundant work.
-        let mut searched_platform_dirs = HashSet::new();
-        let mut searched_sdks_dirs = HashSet::new();
+    /// Perform a search, yielding found SDKs with the location that found them.
+    ///
+    /// This is like [Self::search()] except each SDK is paired with the
+    /// [SdkSearchLocation] it was found in. This allows tools to explain why an SDK
+    /// was chosen.
+    pub fn search_results<SDK: AppleSdk>(&self) -> Result<Vec<SdkSearchResult<SDK>>, Error> {
+        let warnings = SdkSearchWarnings::strict();
 
-        for location in &self.locations {
-            if let Some(cb) = &self.progress_callback {
-                cb(SdkSearchEvent::SearchingLocation(location.clone()));
-            }
+        self.search_with(&warnings, |dirs| {
+            Ok(dirs
+                .iter()
+                .map(|dir| self.find_in_directory::<SDK>(dir, &warnings))
+                .collect::<Result<Vec<_>, Error>>()?
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>())
+        })
+    }
 
-            // Expand each location to SDKs.
-            let resolved = location.resolve_location()?;
+    /// Perform a search, collecting non-fatal errors as warnings.
+    ///
+    /// This is like [Self::search()] except errors resolving search locations, reading
+    /// directories holding SDKs, and loading individual SDKs don't fail the search.
+    /// Instead, they are recorded in [SdkSearchReport::warnings] and the search
+    /// continues. e.g. a directory without read permission or an SDK with malformed
+    /// settings is skipped.
+    ///
+    /// Errors applying search filters still fail the search, as they indicate a
+    /// search that can't be satisfied.
+    pub fn search_lenient<SDK: AppleSdk>(&self) -> Result<SdkSearchReport<SDK>, Error> {
+        let warnings = SdkSearchWarnings::lenient();
 
-            let candidate_sdks = match &resolved {
-                SdkSearchResolvedLocation::None => {
-                    vec![]
-                }
-                SdkSearchResolvedLocation::PlatformDirectories(dirs) => dirs
-                    .iter()
-                    // Apply platform filter.
-                    .filter(|dir| {
-                        if let Some(wanted_platform) = &self.platform {
-                            if &dir.platform == wanted_platform {
-                                if let Some(cb) = &self.progress_callback {
-                                    cb(SdkSearchEvent::PlatformDirectoryInclude(dir.path.clone()));
-                                }
+        let sdks = self.search_with(&warnings, |dirs| {
+            Ok(dirs
+                .iter()
+                .map(|dir| self.find_in_directory::<SDK>(dir, &warnings))
+                .collect::<Result<Vec<_>, Error>>()?
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>())
+        })?;
 
-                                true
-                            } else {
-                                if let Some(cb) = &self.progress_callback {
-                                    cb(SdkSearchEvent::PlatformDirectoryExclude(dir.path.clone()));
-                                }
+        Ok(SdkSearchReport {
+            sdks: sdks.into_iter().map(|result| result.sdk).collect(),
+            warnings: warnings.into_vec(),
+        })
+    }
 
-                                false
-                            }
-                        } else {
-                            if let Some(cb) = &self.progress_callback {
-                                cb(SdkSearchEvent::PlatformDirectoryInclude(dir.path.clone()));
-                            }
+    /// Perform a search, explaining why each encountered SDK was or wasn't selected.
+    ///
+    /// This is a dry run of [Self::search_lenient()] that reports every SDK the search
+    /// looked at instead of only the matching ones. Each [SdkSearchCandidate] carries
+    /// the [SdkSearchRejection] excluding it, if any. This powers diagnostics like
+    /// "why wasn't my SDK picked?".
+    ///
+    /// SDKs excluded by the platform filter before being loaded are included.
+    /// SDKs in platform directories excluded by the platform filter are not, as
+    /// these directories aren't read. Errors loading SDKs are reported as
+    /// [SdkSearchRejection::LoadFailure] instead of failing the search.
+    ///
+    /// The progress callback still receives all events.
+    pub fn explain<SDK: AppleSdk>(&self) -> Result<SdkSearchExplanation, Error> {
+        let events = Arc::new(Mutex::new(vec![]));
 
-                            true
-                        }
-                    })
-                    // Apply duplicate search filter.
-                    .filter(|dir| {
-                        if searched_platform_dirs.contains(dir.path()) {
-                            false
-                        } else {
-                            searched_platform_dirs.insert(dir.path().to_path_buf());
-                            true
-                        }
-                    })
-                    .map(|dir| dir.find_sdks::<SDK>())
-                    .collect::<Result<Vec<_>, Error>>()?
-                    .into_iter()
-                    .flatten()
-                    .collect::<Vec<_>>(),
-                SdkSearchResolvedLocation::SdksDirectory(path) => {
-                    if searched_sdks_dirs.contains(path) {
-                        vec![]
-                    } else {
-                        searched_sdks_dirs.insert(path.clone());
-                        SDK::find_in_directory(path)?
-                    }
-                }
-                SdkSearchResolvedLocation::SdkDirectory(path)
-                | SdkSearchResolvedLocation::SdkDirectoryUnfiltered(path) => {
-                    vec![SDK::from_directory(path)?]
-                }
-            };
+        let mut search = self.clone();
+        let inner = self.progress_callback.clone();
+        let captured = events.clone();
+        search.progress_callback = Some(Arc::new(move |event: SdkSearchEvent| {
+            captured
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(event.clone());
 
-            let mut added_count = 0;
+            if let Some(cb) = &inner {
+                cb(event);
+            }
+        }));
 
-            for sdk in candidate_sdks {
-                let include = if resolved.apply_sdk_filter() {
-                    self.filter_sdk(&sdk)?
-                } else {
-                    if let Some(cb) = &self.progress_callback {
-                        cb(SdkSearchEvent::SdkFilterSkip(sdk.sdk_path()));
-                    }
+        let report = search.search_lenient::<SDK>()?;
+        let events = std::mem::take(&mut *events.lock().unwrap_or_else(PoisonError::into_inner));
 
-                    true
-                };
+        let mut candidates: Vec<(SdkSearchCandidate, bool)> = vec![];
 
-                if include {
-                    sdks.push(sdk);
-                    added_count += 1;
+        for event in events {
+            // Rejections after filtering apply to the first matched SDK with the path
+            // not rejected yet.
+            let mut reject = |path: &Path, rejection: SdkSearchRejection| {
+                if let Some((candidate, _)) = candidates
+                    .iter_mut()
+                    .find(|(c, matched)| *matched && c.rejection.is_none() && c.path == path)
+                {
+                    candidate.rejection = Some(rejection);
                 }
-            }
+            };
 
-            if location.is_terminal() && added_count > 0 {
-                break;
-            }
+            match event {
+                SdkSearchEvent::SdkDuplicateExclude(sdk, canonical) => {
+                    reject(&sdk.path, SdkSearchRejection::Duplicate(canonical));
+                }
+                SdkSearchEvent::SdkPlatformExclude(sdk) => {
+                    reject(&sdk.path, SdkSearchRejection::PlatformNotPreferred);
+                }
+                SdkSearchEvent::SdkLimitExclude(sdk, _) => {
+                    reject(&sdk.path, SdkSearchRejection::LimitExceeded);
+                }
+                SdkSearchEvent::SdkFilterMatch(sdk) | SdkSearchEvent::SdkFilterSkip(sdk) => {
+                    candidates.push((
+                        SdkSearchCandidate {
+                            path: sdk.path,
+                            rejection: None,
+                        },
+                        true,
+                    ));
+                }
+                SdkSearchEvent::SdkFilterExclude(sdk, reason) => {
+                    candidates.push((
+                        SdkSearchCandidate {
+                            path: sdk.path,
+                            rejection: Some(SdkSearchRejection::Filtered(reason)),
+                        },
+                        false,
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        let mut candidates = candidates
+            .into_iter()
+            .map(|(candidate, _)| candidate)
+            .collect::<Vec<_>>();
+        let mut warnings = vec![];
+
+        for warning in report.warnings {
+            match warning {
+                SdkSearchWarning::SdkLoadFailure(path, err) => {
+                    candidates.push(SdkSearchCandidate {
+                        path,
+                        rejection: Some(SdkSearchRejection::LoadFailure(err)),
+                    });
+                }
+                warning => warnings.push(warning),
+            }
+        }
+
+        Ok(SdkSearchExplanation {
+            candidates,
+            warnings,
+        })
+    }
+
+    /// Perform a search, reusing SDKs previously found in unchanged directories.
+    ///
+    /// This is like [Self::search()] except directories holding SDKs are looked up
+    /// in the given cache first. See [SdkSearchCache] for when cache entries are
+    /// reused. Locations are still resolved and all filters are applied, so the
+    /// results reflect this search's configuration.
+    ///
+    /// This is intended for long-lived processes performing many searches.
+    pub fn search_cached<SDK: AppleSdk + Clone + Send + Sync + 'static>(
+        &self,
+        cache: &SdkSearchCache,
+    ) -> Result<Vec<SDK>, Error> {
+        let results = self.search_with(&SdkSearchWarnings::strict(), |dirs| {
+            Ok(dirs
+                .iter()
+                .map(|dir| cache.find_in_directory::<SDK>(dir, &*self.filesystem))
+                .collect::<Result<Vec<_>, Error>>()?
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>())
+        })?;
+
+        Ok(results.into_iter().map(|result| result.sdk).collect())
+    }
+
+    /// Perform a search, scanning directories in parallel.
+    ///
+    /// This is like [Self::search()] except SDK directories within each search location
+    /// are scanned and parsed in parallel using a rayon thread pool. This can be
+    /// significantly faster when many Xcode installs are searched or when SDK settings
+    /// files are parsed.
+    ///
+    /// Search locations are still processed in order and results are identical to
+    /// [Self::search()].
+    ///
+    /// Requires the `rayon` crate feature.
+    #[cfg(feature = "rayon")]
+    pub fn search_parallel<SDK: AppleSdk + Send>(&self) -> Result<Vec<SDK>, Error> {
+        use rayon::prelude::*;
+
+        let warnings = SdkSearchWarnings::strict();
+
+        let results = self.search_with(&warnings, |dirs| {
+            Ok(dirs
+                .par_iter()
+                .map(|dir| self.find_in_directory::<SDK>(dir, &warnings))
+                .collect::<Result<Vec<_>, Error>>()?
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>())
+        })?;
+
+        Ok(results.into_iter().map(|result| result.sdk).collect())
+    }
+
+    /// Perform a search and render the results as JSON.
+    ///
+    /// The returned value is an array with an object for each found SDK, in search
+    /// order. Each object has the following keys:
+    ///
+    /// * `path` - The filesystem path of the SDK.
+    /// * `platform` - The filesystem name of the SDK's platform. e.g. `MacOSX`.
+    /// * `version` - The SDK version string, or `null` if unknown.
+    /// * `is_symlink` - Whether the SDK path is a symlink.
+    /// * `sdk` - The serialized SDK value. For [ParsedSdk](crate::ParsedSdk), this includes
+    ///   parsed SDK settings.
+    ///
+    /// This is intended for consumption by tools not written in Rust.
+    ///
+    /// Requires the `parse` crate feature.
+    #[cfg(feature = "parse")]
+    pub fn search_json<SDK: AppleSdk + serde::Serialize>(
+        &self,
+    ) -> Result<serde_json::Value, Error> {
+        Ok(serde_json::Value::Array(
+            self.search::<SDK>()?
+                .iter()
+                .map(|sdk| {
+                    Ok(serde_json::json!({
+                        "path": sdk.path(),
+                        "platform": sdk.platform(),
+                        "version": sdk.version(),
+                        "is_symlink": sdk.is_symlink(),
+                        "sdk": serde_json::to_value(sdk)?,
+                    }))
+                })
+                .collect::<Result<Vec<_>, Error>>()?,
+        ))
+    }
+
+    /// Report a search event to the progress callback.
+    ///
+    /// With the `log` feature, the event is also logged. The event is only
+    /// constructed if something consumes it, as describing it can be costly.
+    fn emit(&self, event: impl FnOnce() -> SdkSearchEvent) {
+        #[cfg(feature = "log")]
+        let log = log::log_enabled!(log::Level::Debug);
+        #[cfg(not(feature = "log"))]
+        let log = false;
+
+        if !log && self.progress_callback.is_none() {
+            return;
+        }
+
+        let event = event();
+
+        if log {
+            debug!("{event}");
+        }
+
+        if let Some(cb) = &self.progress_callback {
+            cb(event);
+        }
+    }
+
+    /// Report an SDK that failed to load and record it as a warning.
+    fn record_load_failure(
+        &self,
+        warnings: &SdkSearchWarnings,
+        path: PathBuf,
+        err: Error,
+    ) -> Result<(), Error> {
+        self.emit(|| SdkSearchEvent::SdkLoadFailure(path.clone(), err.to_string()));
+
+        warnings.record(SdkSearchWarning::SdkLoadFailure(path, err))
+    }
+
+    /// Verify the configuration of this search can match SDKs.
+    ///
+    /// This is called by all search functions before searching. It detects
+    /// contradictory configurations that would otherwise silently yield no SDKs:
+    ///
+    /// * A minimum version greater than the maximum version.
+    /// * A [SdkSearchLocation::Sdk] location or a [SdkSearchLocation::Sdks] location
+    ///   within a platform directory whose platform doesn't pass the platform filter.
+    /// * A deployment target for a platform not in the platform filter.
+    ///
+    /// Returns [Error::SdkSearchInvalid] describing the first problem found.
+    pub fn validate(&self) -> Result<(), Error> {
+        if let (Some(minimum), Some(maximum)) = (&self.minimum_version, &self.maximum_version) {
+            if minimum > maximum {
+                return Err(Error::SdkSearchInvalid(format!(
+                    "minimum version {minimum} is greater than maximum version {maximum}"
+                )));
+            }
+        }
+
+        for location in &self.locations {
+            let platform = match location {
+                SdkSearchLocation::Sdk(path) => SdkPath::from_path(path).ok().map(|p| p.platform),
+                SdkSearchLocation::Sdks(path) => path
+                    .parent()
+                    .and_then(|p| p.parent())
+                    .filter(|p| p.extension() == Some(OsStr::new("platform")))
+                    .and_then(|p| Platform::from_platform_path(p).ok()),
+                _ => None,
+            };
+
+            if let Some(platform) = platform {
+                if !self.platform_matches(&platform) {
+                    return Err(Error::SdkSearchInvalid(format!(
+                        "location {location} only holds {} SDKs, which don't match platform filter {}",
+                        platform.filesystem_name(),
+                        self.platform_names()
+                    )));
+                }
+            }
+        }
+
+        if let Some((target, _)) = &self.deployment_target {
+            let catalyst = target == "iosmac" && self.platform_matches(&Platform::MacOsX);
+
+            if !self.platforms.is_empty()
+                && !catalyst
+                && !self
+                    .platforms
+                    .iter()
+                    .chain(self.platform_fallbacks.iter())
+                    .any(|p| p.filesystem_name().eq_ignore_ascii_case(target))
+            {
+                return Err(Error::SdkSearchInvalid(format!(
+                    "deployment target {target} doesn't match platform filter {}",
+                    self.platform_names()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Perform a search using a function to find SDKs in directories holding SDKs.
+    fn search_with<SDK: AppleSdk>(
+        &self,
+        warnings: &SdkSearchWarnings,
+        find_sdks_impl: impl Fn(&[PathBuf]) -> Result<Vec<SDK>, Error>,
+    ) -> Result<Vec<SdkSearchResult<SDK>>, Error> {
+        self.validate()?;
+        let find_sdks = |dirs: &[PathBuf]| {
+            for dir in dirs {
+                self.emit(|| SdkSearchEvent::SearchingDirectory(dir.clone()));
+            }
+
+            find_sdks_impl(dirs)
+        };
+
+        let mut sdks: Vec<SdkSearchResult<SDK>> = vec![];
+
+        // Track searched locations to avoid redundant work.
+        let mut searched_platform_dirs = HashSet::new();
+        let mut searched_sdks_dirs = HashSet::new();
+
+        // SDKs counting toward the result limit.
+        let mut limit_count = 0;
+        let mut limit_paths = HashSet::new();
+
+        for location in &self.effective_locations() {
+            if let Some(limit) = self.limit {
+                if limit_count >= limit {
+                    self.emit(|| SdkSearchEvent::LimitReached(limit));
+                    break;
+                }
+            }
+
+            self.emit(|| SdkSearchEvent::SearchingLocation(location.clone()));
+
+            // Expand each location to SDKs.
+            let resolved = match location.resolve_location(self) {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!("error resolving SDK search location {location}: {e}");
+
+                    warnings.record(SdkSearchWarning::LocationFailure(location.clone(), e))?;
+                    continue;
+                }
+            };
+
+            let candidate_sdks = match &resolved {
+                SdkSearchResolvedLocation::None => {
+                    vec![]
+                }
+                SdkSearchResolvedLocation::PlatformDirectories(dirs) => {
+                    let sdks_dirs = dirs
+                        .iter()
+                        // Apply platform filter.
+                        .filter(|dir| {
+                            // Xcode 11 and 12 ship DriverKit SDKs in the macOS platform.
+                            if self.platform_matches(&dir.platform)
+                                || (dir.platform == Platform::MacOsX
+                                    && self.platform_matches(&Platform::DriverKit))
+                            {
+                                self.emit(|| {
+                                    SdkSearchEvent::PlatformDirectoryInclude(dir.path.clone())
+                                });
+
+                                true
+                            } else {
+                                self.emit(|| {
+                                    SdkSearchEvent::PlatformDirectoryExclude(dir.path.clone())
+                                });
+
+                                false
+                            }
+                        })
+                        // Apply duplicate search filter.
+                        .filter(|dir| {
+                            if searched_platform_dirs.contains(dir.path()) {
+                                false
+                            } else {
+                                searched_platform_dirs.insert(dir.path().to_path_buf());
+                                true
+                            }
+                        })
+                        .map(|dir| dir.sdks_path())
+                        .collect::<Vec<_>>();
+
+                    find_sdks(&sdks_dirs)?
+                }
+                SdkSearchResolvedLocation::SdksDirectory(path) => {
+                    if searched_sdks_dirs.contains(path) {
+                        vec![]
+                    } else {
+                        searched_sdks_dirs.insert(path.clone());
+                        find_sdks(std::slice::from_ref(path))?
+                    }
+                }
+                SdkSearchResolvedLocation::SdkDirectory(path)
+                | SdkSearchResolvedLocation::SdkDirectoryUnfiltered(path) => {
+                    match SDK::from_directory_with_filesystem(path, &*self.filesystem) {
+                        Ok(sdk) => vec![sdk],
+                        Err(e) => {
+                            self.record_load_failure(warnings, path.clone(), e)?;
+                            vec![]
+                        }
+                    }
+                }
+                #[cfg(feature = "parse")]
+                SdkSearchResolvedLocation::SdkDirectories(paths) => {
+                    // Merge with SDKs found by previous locations.
+                    let found = sdks
+                        .iter()
+                        .map(|result| canonical_path(result.sdk.as_ref(), &*self.filesystem))
+                        .collect::<HashSet<_>>();
+
+                    let mut res = vec![];
+
+                    for path in paths {
+                        if found.contains(&canonical_path(path, &*self.filesystem)) {
+                            continue;
+                        }
+
+                        match SDK::from_directory_with_filesystem(path, &*self.filesystem) {
+                            Ok(sdk) => res.push(sdk),
+                            Err(Error::PathNotSdk(_)) => {}
+                            Err(e) => {
+                                self.record_load_failure(warnings, path.clone(), e)?;
+                            }
+                        }
+                    }
+
+                    res
+                }
+            };
+
+            let mut added_count = 0;
+
+            for sdk in candidate_sdks {
+                let include = if resolved.apply_sdk_filter() {
+                    self.filter_sdk(&sdk)?
+                } else {
+                    self.emit(|| SdkSearchEvent::SdkFilterSkip(sdk.sdk_path()));
+
+                    true
+                };
+
+                if include {
+                    if self.limit.is_some()
+                        && (self.platform_fallbacks.is_empty()
+                            || self.platforms.is_empty()
+                            || self.platforms.contains(sdk.platform()))
+                        && (!(self.deduplicate || self.prefer_versioned)
+                            || limit_paths.insert(canonical_path(sdk.as_ref(), &*self.filesystem)))
+                    {
+                        limit_count += 1;
+                    }
+
+                    let developer_directory = match &resolved {
+                        SdkSearchResolvedLocation::PlatformDirectories(dirs) => dirs
+                            .iter()
+                            .find(|dir| sdk.path().parent() == Some(dir.sdks_path().as_path()))
+                            .and_then(|dir| dir.path().parent()?.parent())
+                            .map(DeveloperDirectory::from),
+                        SdkSearchResolvedLocation::SdksDirectory(path)
+                            if location == &SdkSearchLocation::CommandLineTools =>
+                        {
+                            path.parent().map(DeveloperDirectory::from)
+                        }
+                        _ => None,
+                    }
+                    .or_else(|| sdk.developer_directory());
+
+                    sdks.push(SdkSearchResult {
+                        sdk,
+                        location: location.clone(),
+                        developer_directory,
+                    });
+                    added_count += 1;
+                }
+            }
+
+            if location.is_terminal() && added_count > 0 {
+                break;
+            }
+        }
+
+        sdks = self.apply_platform_fallbacks(sdks);
+
+        if self.prefer_versioned {
+            sdks = self.prefer_versioned_sdks(sdks);
+        }
+
+        if self.deduplicate {
+            sdks = self.deduplicate_sdks(sdks);
+        }
+
+        if self.sorting != SdkSorting::None {
+            self.emit(|| SdkSearchEvent::Sorting(sdks.len(), self.sorting));
         }
 
-        // Sorting should be stable with None variant. But we can avoid the
-        // overhead.
         if self.sorting != SdkSorting::None {
-            sdks.sort_by(|a, b| self.sorting.compare_version(a.version(), b.version()))
+            sdks.sort_by(|a, b| {
+                self.sorting
+                    .compare_version(a.sdk.version(), b.sdk.version())
+            });
+        }
+
+        match self.beta_preference {
+            XcodeBetaPreference::PreferRelease => {
+                sdks.sort_by_cached_key(|result| {
+                    self.xcode_beta_status.is_beta_sdk(result.sdk.as_ref())
+                });
+            }
+            XcodeBetaPreference::PreferBeta => {
+                sdks.sort_by_cached_key(|result| {
+                    !self.xcode_beta_status.is_beta_sdk(result.sdk.as_ref())
+                });
+            }
+            XcodeBetaPreference::Include | XcodeBetaPreference::Exclude => {}
+        }
+
+        if let Some(limit) = self.limit {
+            for result in sdks.iter().skip(limit) {
+                self.emit(|| SdkSearchEvent::SdkLimitExclude(result.sdk.sdk_path(), limit));
+            }
+
+            sdks.truncate(limit);
         }
 
         Ok(sdks)
     }
 
+    /// Perform a search, yielding the highest priority SDK.
+    ///
+    /// This is [Self::search()] but only returns the first SDK. Configure
+    /// [Self::sorting()] to control which SDK is first. e.g. use
+    /// [SdkSorting::VersionDescending] to obtain the newest SDK.
+    ///
+    /// Returns [Error::SdkNotFound] describing the search if no SDK matches.
+    pub fn find_first<SDK: AppleSdk>(&self) -> Result<SDK, Error> {
+        self.search::<SDK>()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::SdkNotFound(self.describe()))
+    }
+
+    /// The names of platforms in the platform filter.
+    fn platform_names(&self) -> String {
+        self.platforms
+            .iter()
+            .map(|p| p.filesystem_name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The registered locations followed by the locations from [EXTRA_SDKS_DIRS_ENV].
+    fn effective_locations(&self) -> Vec<SdkSearchLocation> {
+        let mut locations = self.locations.clone();
+
+        if self.extra_sdks_dirs_from_env {
+            if let Some(value) = (self.environment)(EXTRA_SDKS_DIRS_ENV) {
+                for location in extra_sdks_dirs_locations(&value) {
+                    if !locations.contains(&location) {
+                        locations.push(location);
+                    }
+                }
+            }
+        }
+
+        locations
+    }
+
+    /// Describe this search in human readable form.
+    ///
+    /// The description lists the locations in search order, followed by the filters
+    /// and ordering preferences that are set. e.g. `searched SDKs directory /sdks;
+    /// platform MacOSX; minimum version 11.0; sorted by descending version`. Locations
+    /// from [EXTRA_SDKS_DIRS_ENV] are included.
+    ///
+    /// This is intended for diagnostics, such as explaining why no SDK was found.
+    /// [Error::SdkNotFound] carries this description. The format is not stable.
+    pub fn describe(&self) -> String {
+        let locations = self.effective_locations();
+
+        let mut parts = vec![format!(
+            "searched {}",
+            if locations.is_empty() {
+                "no locations".to_string()
+            } else {
+                locations
+                    .iter()
+                    .map(|l| l.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        )];
+
+        if !self.platforms.is_empty() {
+            parts.push(format!("platform {}", self.platform_names()));
+        }
+        if !self.platforms.is_empty() && !self.platform_fallbacks.is_empty() {
+            parts.push(format!(
+                "fallback platforms {}",
+                self.platform_fallbacks
+                    .iter()
+                    .map(|p| p.filesystem_name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if let Some(version) = &self.minimum_version {
+            parts.push(format!("minimum version {version}"));
+        }
+        if let Some(version) = &self.maximum_version {
+            parts.push(format!("maximum version {version}"));
+        }
+        if let Some(req) = &self.version_req {
+            parts.push(format!("version {req}"));
+        }
+        if let Some((target, version)) = &self.deployment_target {
+            parts.push(format!("deployment target {target}:{version}"));
+        }
+        if self.beta_preference == XcodeBetaPreference::Exclude {
+            parts.push("excluding beta Xcodes".to_string());
+        }
+        if self.deployment_target_from_env {
+            parts.push("deployment target from environment".to_string());
+        }
+        if self.exclude_symlinks {
+            parts.push("excluding symlinked SDKs".to_string());
+        }
+        if let Some(version) = &self.minimum_xcode_version {
+            parts.push(format!("minimum Xcode version {version}"));
+        }
+        if self.sorting != SdkSorting::None {
+            parts.push(format!("sorted by {}", self.sorting));
+        }
+        if matches!(
+            self.beta_preference,
+            XcodeBetaPreference::PreferRelease | XcodeBetaPreference::PreferBeta
+        ) {
+            parts.push(format!("{} Xcodes", self.beta_preference));
+        }
+        if self.prefer_versioned {
+            parts.push("preferring versioned SDK directories".to_string());
+        }
+        if self.deduplicate {
+            parts.push("deduplicating".to_string());
+        }
+        if let Some(limit) = self.limit {
+            parts.push(format!("limit {limit}"));
+        }
+
+        parts.join("; ")
+    }
+
+    /// Remove SDKs resolving to the same canonical path.
+    fn deduplicate_sdks<SDK: AppleSdk>(
+        &self,
+        sdks: Vec<SdkSearchResult<SDK>>,
+    ) -> Vec<SdkSearchResult<SDK>> {
+        let mut res: Vec<(PathBuf, SdkSearchResult<SDK>)> = vec![];
+
+        for result in sdks {
+            let canonical = canonical_path(result.sdk.as_ref(), &*self.filesystem);
+
+            if let Some(pos) = res.iter().position(|(path, _)| path == &canonical) {
+                let (discarded, path) = if res[pos].1.sdk.is_symlink() && !result.sdk.is_symlink() {
+                    (std::mem::replace(&mut res[pos].1, result), &res[pos].0)
+                } else {
+                    (result, &res[pos].0)
+                };
+
+                self.emit(|| {
+                    SdkSearchEvent::SdkDuplicateExclude(discarded.sdk.sdk_path(), path.clone())
+                });
+            } else {
+                res.push((canonical, result));
+            }
+        }
+
+        res.into_iter()
+            .map(|(_, result)| result)
+            .collect::<Vec<_>>()
+    }
+
+    /// Remove symlinked SDKs resolving to a preferred SDK in the results.
+    fn prefer_versioned_sdks<SDK: AppleSdk>(
+        &self,
+        sdks: Vec<SdkSearchResult<SDK>>,
+    ) -> Vec<SdkSearchResult<SDK>> {
+        // Directories that aren't symlinks are preferred, followed by directory
+        // names carrying a version.
+        let ranked = sdks
+            .iter()
+            .map(|result| {
+                let path = result.sdk.as_ref();
+                let versioned = SdkPath::from_path(path)
+                    .map(|p| p.version.is_some())
+                    .unwrap_or_default();
+
+                (
+                    canonical_path(path, &*self.filesystem),
+                    (!result.sdk.is_symlink(), versioned),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        sdks.into_iter()
+            .enumerate()
+            .filter_map(|(i, result)| {
+                let (canonical, rank) = &ranked[i];
+
+                let superseded = result.sdk.is_symlink()
+                    && ranked.iter().enumerate().any(|(j, (other, other_rank))| {
+                        j != i && other == canonical && other_rank > rank
+                    });
+
+                if superseded {
+                    self.emit(|| {
+                        SdkSearchEvent::SdkDuplicateExclude(
+                            result.sdk.sdk_path(),
+                            canonical.clone(),
+                        )
+                    });
+
+                    None
+                } else {
+                    Some(result)
+                }
+            })
+            .collect::<Vec<_>>()
+    }
+
     /// Whether an SDK matches our search filter.
     ///
     /// This is exposed as a convenience method to allow custom implementations of
@@ -594,47 +2663,66 @@ impl SdkSearch {
     pub fn filter_sdk<SDK: AppleSdk>(&self, sdk: &SDK) -> Result<bool, Error> {
         let sdk_path = sdk.sdk_path();
 
-        if let Some(wanted_platform) = &self.platform {
-            if sdk.platform() != wanted_platform {
-                if let Some(cb) = &self.progress_callback {
-                    cb(SdkSearchEvent::SdkFilterExclude(
+        if !self.filter_sdk_path(&sdk_path) {
+            return Ok(false);
+        }
+
+        if let Some(min_version) = &self.minimum_xcode_version {
+            match sdk_xcode_version(sdk.path()) {
+                Some(version) if &version < min_version => {
+                    self.emit(|| {
+                        SdkSearchEvent::SdkFilterExclude(
+                            sdk_path,
+                            format!(
+                                "Xcode version {version} < minimum Xcode version {min_version}"
+                            ),
+                        )
+                    });
+
+                    return Ok(false);
+                }
+                Some(_) => {}
+                None => {
+                    self.emit(|| SdkSearchEvent::SdkFilterExclude(
                         sdk_path,
                         format!(
-                            "platform {} != {}",
-                            sdk.platform().filesystem_name(),
-                            wanted_platform.filesystem_name()
+                            "Unknown Xcode version fails to meet minimum Xcode version {min_version}"
                         ),
                     ));
-                }
 
-                return Ok(false);
+                    return Ok(false);
+                }
             }
         }
 
+        if self.exclude_symlinks && sdk.is_symlink() {
+            self.emit(|| {
+                SdkSearchEvent::SdkFilterExclude(sdk_path, "SDK is a symlink".to_string())
+            });
+
+            return Ok(false);
+        }
+
         if let Some(min_version) = &self.minimum_version {
             if let Some(sdk_version) = sdk.version() {
                 if sdk_version < min_version {
-                    if let Some(cb) = &self.progress_callback {
-                        cb(SdkSearchEvent::SdkFilterExclude(
+                    self.emit(|| {
+                        SdkSearchEvent::SdkFilterExclude(
                             sdk_path,
-                            format!(
-                                "SDK version {sdk_version} < minimum version {min_version}"
-                            ),
-                        ));
-                    }
+                            format!("SDK version {sdk_version} < minimum version {min_version}"),
+                        )
+                    });
 
                     return Ok(false);
                 }
             } else {
                 // SDKs without a version always fail.
-                if let Some(cb) = &self.progress_callback {
-                    cb(SdkSearchEvent::SdkFilterExclude(
+                self.emit(|| {
+                    SdkSearchEvent::SdkFilterExclude(
                         sdk_path,
-                        format!(
-                            "Unknown SDK version fails to meet minimum version {min_version}"
-                        ),
-                    ));
-                }
+                        format!("Unknown SDK version fails to meet minimum version {min_version}"),
+                    )
+                });
 
                 return Ok(false);
             }
@@ -643,50 +2731,1533 @@ impl SdkSearch {
         if let Some(max_version) = &self.maximum_version {
             if let Some(sdk_version) = sdk.version() {
                 if sdk_version > max_version {
-                    if let Some(cb) = &self.progress_callback {
-                        cb(SdkSearchEvent::SdkFilterExclude(
+                    self.emit(|| {
+                        SdkSearchEvent::SdkFilterExclude(
                             sdk_path,
-                            format!(
-                                "SDK version {sdk_version} > maximum version {max_version}"
-                            ),
-                        ));
-                    }
+                            format!("SDK version {sdk_version} > maximum version {max_version}"),
+                        )
+                    });
 
                     return Ok(false);
                 }
             } else {
                 // SDKs without a version always fail.
 
-                if let Some(cb) = &self.progress_callback {
-                    cb(SdkSearchEvent::SdkFilterExclude(
+                self.emit(|| {
+                    SdkSearchEvent::SdkFilterExclude(
                         sdk_path,
-                        format!(
-                            "Unknown SDK version fails to meet maximum version {max_version}"
-                        ),
-                    ));
+                        format!("Unknown SDK version fails to meet maximum version {max_version}"),
+                    )
+                });
+
+                return Ok(false);
+            }
+        }
+
+        if let Some(req) = &self.version_req {
+            if let Some(sdk_version) = sdk.version() {
+                if !req.matches(sdk_version) {
+                    self.emit(|| {
+                        SdkSearchEvent::SdkFilterExclude(
+                            sdk_path,
+                            format!("SDK version {sdk_version} does not satisfy {req}"),
+                        )
+                    });
+
+                    return Ok(false);
                 }
+            } else {
+                self.emit(|| {
+                    SdkSearchEvent::SdkFilterExclude(
+                        sdk_path,
+                        format!("Unknown SDK version fails to satisfy {req}"),
+                    )
+                });
 
                 return Ok(false);
             }
         }
 
         if let Some((target, version)) = &self.deployment_target {
-            if !sdk.supports_deployment_target(target, version)? {
-                if let Some(cb) = &self.progress_callback {
-                    cb(SdkSearchEvent::SdkFilterExclude(
+            let supported = match sdk.supports_deployment_target(target, version) {
+                Err(Error::FunctionalityNotSupported(_)) if !self.deployment_target_strict => true,
+                res => res?,
+            };
+
+            if !supported {
+                self.emit(|| {
+                    SdkSearchEvent::SdkFilterExclude(
                         sdk_path,
                         format!("does not support deployment target {target}:{version}"),
-                    ));
-                }
+                    )
+                });
 
                 return Ok(false);
             }
         }
 
-        if let Some(cb) = &self.progress_callback {
-            cb(SdkSearchEvent::SdkFilterMatch(sdk_path));
+        if self.deployment_target_from_env {
+            if let Some(target) =
+                DeploymentTarget::from_env_with_lookup(sdk.platform().clone(), &*self.environment)?
+            {
+                let supported = match target.supported_by(sdk) {
+                    Err(Error::FunctionalityNotSupported(_)) => true,
+                    res => res?,
+                };
+
+                if !supported {
+                    self.emit(|| {
+                        SdkSearchEvent::SdkFilterExclude(
+                            sdk_path,
+                            format!(
+                                "does not support deployment target {target} from {}",
+                                target.environment_variable().unwrap_or_default()
+                            ),
+                        )
+                    });
+
+                    return Ok(false);
+                }
+            }
         }
 
+        self.emit(|| SdkSearchEvent::SdkFilterMatch(sdk_path));
+
         Ok(true)
     }
+
+    /// Whether an SDK path matches the filters that don't require SDK metadata.
+    ///
+    /// This applies the platform filter and the exclusion of beta Xcode SDKs. It is
+    /// a subset of [Self::filter_sdk()], which calls it. Searches use it to avoid
+    /// constructing (and potentially parsing) SDKs that can't match.
+    pub fn filter_sdk_path(&self, sdk_path: &SdkPath) -> bool {
+        if !self.platform_matches(&sdk_path.platform) {
+            self.emit(|| {
+                SdkSearchEvent::SdkFilterExclude(
+                    sdk_path.clone(),
+                    if self.platforms.len() == 1 {
+                        format!(
+                            "platform {} != {}",
+                            sdk_path.platform.filesystem_name(),
+                            self.platform_names()
+                        )
+                    } else {
+                        format!(
+                            "platform {} not one of {}",
+                            sdk_path.platform.filesystem_name(),
+                            self.platform_names()
+                        )
+                    },
+                )
+            });
+
+            return false;
+        }
+
+        if self.beta_preference == XcodeBetaPreference::Exclude
+            && self.xcode_beta_status.is_beta_sdk(&sdk_path.path)
+        {
+            self.emit(|| {
+                SdkSearchEvent::SdkFilterExclude(
+                    sdk_path.clone(),
+                    "SDK is from a beta Xcode".to_string(),
+                )
+            });
+
+            return false;
+        }
+
+        true
+    }
+
+    /// Find SDKs in a directory, only constructing those passing [Self::filter_sdk_path()].
+    ///
+    /// This is [AppleSdk::find_in_directory()] with path based filtering applied
+    /// first, so SDK settings are only parsed for SDKs which may match.
+    fn find_in_directory<SDK: AppleSdk>(
+        &self,
+        root: &Path,
+        warnings: &SdkSearchWarnings,
+    ) -> Result<Vec<SDK>, Error> {
+        let dir = match self.filesystem.read_dir(root) {
+            Ok(v) => v,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => {
+                warnings.record(SdkSearchWarning::DirectoryUnreadable(
+                    root.to_path_buf(),
+                    Error::IoPath(root.to_path_buf(), e),
+                ))?;
+
+                return Ok(vec![]);
+            }
+        };
+
+        let mut res = vec![];
+
+        for path in dir {
+            match SdkPath::from_path(&path) {
+                Ok(sdk_path) if !self.filter_sdk_path(&sdk_path) => continue,
+                Ok(_) => {}
+                Err(Error::PathNotSdk(_)) => continue,
+                Err(err) => return Err(err),
+            }
+
+            match SDK::from_directory_with_filesystem(&path, &*self.filesystem) {
+                Ok(sdk) => res.push(sdk),
+                Err(Error::PathNotSdk(_)) => {}
+                Err(err) => self.record_load_failure(warnings, path, err)?,
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, crate::MemoryFilesystem};
+
+    #[cfg(feature = "parse")]
+    use crate::FixtureSdk;
+
+    #[test]
+    fn deduplicate() -> Result<(), Error> {
+        let sdks_dir = PathBuf::from("/SDKs");
+        let fs = MemoryFilesystem::new()
+            .sdk("/SDKs/MacOSX13.1.sdk")
+            .symlink("/SDKs/MacOSX.sdk", "MacOSX13.1.sdk");
+
+        let search = SdkSearch::empty()
+            .filesystem(fs)
+            .location(SdkSearchLocation::Sdks(sdks_dir.clone()));
+
+        let sdks = search.search::<SimpleSdk>()?;
+        assert_eq!(sdks.len(), 2);
+
+        let events = Arc::new(std::sync::Mutex::new(vec![]));
+        let events_cb = events.clone();
+
+        let sdks = search
+            .location(SdkSearchLocation::Sdk(sdks_dir.join("MacOSX.sdk")))
+            .deduplicate(true)
+            .progress_callback(move |event| events_cb.lock().unwrap().push(event))
+            .search::<SimpleSdk>()?;
+        assert_eq!(sdks.len(), 1);
+        assert!(!sdks[0].is_symlink());
+        assert_eq!(sdks[0].version(), Some(&SdkVersion::from("13.1")));
+        assert_eq!(
+            events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|e| matches!(e, SdkSearchEvent::SdkDuplicateExclude(..)))
+                .count(),
+            2
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn deduplicate_memory_filesystem() -> Result<(), Error> {
+        let fs = MemoryFilesystem::new()
+            .sdk("/real/MacOSX14.2.sdk")
+            .symlink("/linked", "/real");
+
+        let sdks = SdkSearch::empty()
+            .filesystem(fs)
+            .location(SdkSearchLocation::Sdks("/real".into()))
+            .location(SdkSearchLocation::Sdks("/linked".into()))
+            .deduplicate(true)
+            .search::<SimpleSdk>()?;
+        assert_eq!(sdks.len(), 1);
+        assert_eq!(sdks[0].path(), Path::new("/real/MacOSX14.2.sdk"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn prefer_versioned() -> Result<(), Error> {
+        let fs = MemoryFilesystem::new()
+            .sdk("/sdks/MacOSX13.1.sdk")
+            .symlink("/sdks/MacOSX.sdk", "MacOSX14.sdk")
+            .symlink("/sdks/other/MacOSX.sdk", "../MacOSX13.1.sdk");
+
+        let search = SdkSearch::empty()
+            .filesystem(
+                fs.clone()
+                    .sdk("/sdks/MacOSX14.2.sdk")
+                    .symlink("/sdks/MacOSX14.sdk", "MacOSX14.2.sdk"),
+            )
+            .location(SdkSearchLocation::Sdks("/sdks".into()));
+        assert_eq!(search.search::<SimpleSdk>()?.len(), 4);
+
+        let names = |search: SdkSearch| -> Result<Vec<String>, Error> {
+            Ok(search
+                .search::<SimpleSdk>()?
+                .iter()
+                .map(|sdk| sdk.sdk_path().directory_name())
+                .collect::<Vec<_>>())
+        };
+
+        let search = search
+            .prefer_versioned(true)
+            .sorting(SdkSorting::VersionAscending);
+        assert_eq!(
+            names(search.clone())?,
+            vec!["MacOSX13.1.sdk".to_string(), "MacOSX14.2.sdk".to_string()]
+        );
+        assert!(search
+            .describe()
+            .contains("; preferring versioned SDK directories"));
+
+        // Symlinks are retained when they are the only entry for an SDK.
+        let sdks = SdkSearch::empty()
+            .filesystem(fs.clone())
+            .location(SdkSearchLocation::Sdks("/sdks/other".into()))
+            .prefer_versioned(true)
+            .search::<SimpleSdk>()?;
+        assert_eq!(sdks.len(), 1);
+        assert!(sdks[0].is_symlink());
+
+        // The versioned symlink wins over the unversioned one.
+        let search = search.filesystem(
+            fs.sdk("/sdks/real/MacOSX14.2.sdk")
+                .symlink("/sdks/MacOSX14.sdk", "real/MacOSX14.2.sdk"),
+        );
+        assert_eq!(
+            names(search)?,
+            vec!["MacOSX13.1.sdk".to_string(), "MacOSX14.sdk".to_string()]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn exclude_symlinks() -> Result<(), Error> {
+        let fs = MemoryFilesystem::new()
+            .sdk("/sdks/MacOSX13.1.sdk")
+            .symlink("/sdks/MacOSX.sdk", "MacOSX13.1.sdk");
+
+        let search = SdkSearch::empty()
+            .filesystem(fs)
+            .location(SdkSearchLocation::Sdks("/sdks".into()));
+        assert_eq!(search.search::<SimpleSdk>()?.len(), 2);
+
+        let sdks = search.exclude_symlinks(true).search::<SimpleSdk>()?;
+        assert_eq!(sdks.len(), 1);
+        assert_eq!(sdks[0].path(), Path::new("/sdks/MacOSX13.1.sdk"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn platform_fallbacks() -> Result<(), Error> {
+        let fs = MemoryFilesystem::new()
+            .sdk("/sdks/iPhoneOS17.0.sdk")
+            .sdk("/sdks/MacOSX14.0.sdk");
+
+        let search = SdkSearch::empty()
+            .filesystem(fs.clone())
+            .location(SdkSearchLocation::Sdks("/sdks".into()))
+            .platform(Platform::IPhoneSimulator);
+        assert!(search.search::<SimpleSdk>()?.is_empty());
+
+        let search = search.platform_fallbacks([Platform::AppleTvOs, Platform::IPhoneOs]);
+        let sdks = search.search::<SimpleSdk>()?;
+        assert_eq!(sdks.len(), 1);
+        assert_eq!(sdks[0].platform(), &Platform::IPhoneOs);
+        assert!(search
+            .describe()
+            .contains("fallback platforms AppleTVOS, iPhoneOS"));
+
+        // SDKs of the preferred platform win over fallbacks.
+        let sdks = search
+            .filesystem(fs.sdk("/sdks/iPhoneSimulator17.0.sdk"))
+            .search::<SimpleSdk>()?;
+        assert_eq!(sdks.len(), 1);
+        assert_eq!(sdks[0].platform(), &Platform::IPhoneSimulator);
+
+        Ok(())
+    }
+
+    #[test]
+    fn driverkit_in_macos_platform() -> Result<(), Error> {
+        let sdks = Path::new("/xcode/Platforms/MacOSX.platform/Developer/SDKs");
+        let fs = MemoryFilesystem::new()
+            .sdk(sdks.join("DriverKit19.0.sdk"))
+            .sdk(sdks.join("MacOSX10.15.sdk"));
+
+        let found = SdkSearch::empty()
+            .filesystem(fs)
+            .location(SdkSearchLocation::Developer(DeveloperDirectory::from(
+                Path::new("/xcode"),
+            )))
+            .platform(Platform::DriverKit)
+            .search::<SimpleSdk>()?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path(), sdks.join("DriverKit19.0.sdk"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn memory_filesystem() -> Result<(), Error> {
+        let sdks = "/xcode/Platforms/MacOSX.platform/Developer/SDKs";
+        let mut fs = MemoryFilesystem::new()
+            .directory("/xcode/Platforms/iPhoneOS.platform/Developer/SDKs")
+            .symlink(format!("{sdks}/MacOSX.sdk"), "MacOSX14.2.sdk");
+        for version in ["13.3", "14.2", "12.1"] {
+            fs = fs.file(format!("{sdks}/MacOSX{version}.sdk/SDKSettings.json"), "{}");
+        }
+        fs = fs.file(
+            "/xcode/Platforms/iPhoneOS.platform/Developer/SDKs/iPhoneOS17.2.sdk/SDKSettings.plist",
+            "",
+        );
+
+        let search = SdkSearch::empty()
+            .filesystem(fs)
+            .location(SdkSearchLocation::Developer(DeveloperDirectory::from(
+                Path::new("/xcode"),
+            )));
+
+        assert_eq!(search.clone().search::<SimpleSdk>()?.len(), 5);
+
+        let found = search
+            .clone()
+            .platform(Platform::MacOsX)
+            .minimum_version("13.0")
+            .sorting(SdkSorting::VersionDescending)
+            .search::<SimpleSdk>()?;
+        let names = found
+            .iter()
+            .map(|sdk| (sdk.sdk_path().directory_name(), sdk.is_symlink()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("MacOSX14.2.sdk".to_string(), false),
+                ("MacOSX13.3.sdk".to_string(), false)
+            ]
+        );
+
+        let found = search
+            .platform(Platform::MacOsX)
+            .exclude_symlinks(true)
+            .sorting(SdkSorting::VersionAscending)
+            .search::<SimpleSdk>()?;
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].version(), Some(&SdkVersion::from("12.1")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn system_xcodes_memory_filesystem() -> Result<(), Error> {
+        let fs = MemoryFilesystem::new()
+            .sdk(
+                "/Applications/Xcode.app/Contents/Developer/Platforms/MacOSX.platform/Developer/SDKs/MacOSX14.2.sdk",
+            )
+            .sdk(
+                "/Applications/Xcode_15.0.app/Contents/Developer/Platforms/MacOSX.platform/Developer/SDKs/MacOSX14.0.sdk",
+            );
+
+        let search = SdkSearch::empty().filesystem(fs);
+
+        let found = search
+            .clone()
+            .location(SdkSearchLocation::SystemXcode)
+            .search::<SimpleSdk>()?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].version(), Some(&SdkVersion::from("14.2")));
+
+        let found = search
+            .location(SdkSearchLocation::SystemXcodes)
+            .sorting(SdkSorting::VersionAscending)
+            .search::<SimpleSdk>()?;
+        assert_eq!(
+            found
+                .iter()
+                .map(|sdk| sdk.version().unwrap().to_string())
+                .collect::<Vec<_>>(),
+            vec!["14.0", "14.2"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn limit() -> Result<(), Error> {
+        let mut fs = MemoryFilesystem::new();
+        for (dir, version) in [("a", "13.0"), ("a", "14.0"), ("b", "15.0"), ("c", "16.0")] {
+            fs = fs.file(format!("/{dir}/MacOSX{version}.sdk/SDKSettings.json"), "{}");
+        }
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let events_clone = events.clone();
+
+        let search = SdkSearch::empty()
+            .filesystem(fs)
+            .location(SdkSearchLocation::Sdks("/a".into()))
+            .location(SdkSearchLocation::Sdks("/b".into()))
+            .location(SdkSearchLocation::Sdks("/c".into()))
+            .sorting(SdkSorting::VersionDescending)
+            .progress_callback(move |event| {
+                events_clone.lock().unwrap().push(event.to_string());
+            });
+
+        let found = search.clone().limit(1).search::<SimpleSdk>()?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].version(), Some(&SdkVersion::from("14.0")));
+        assert!(events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|e| e == "found 1 SDKs; not searching remaining locations"));
+
+        let found = search.clone().limit(3).search::<SimpleSdk>()?;
+        assert_eq!(
+            found
+                .iter()
+                .map(|sdk| sdk.version().unwrap().to_string())
+                .collect::<Vec<_>>(),
+            vec!["15.0", "14.0", "13.0"]
+        );
+
+        assert_eq!(search.clone().limit(10).search::<SimpleSdk>()?.len(), 4);
+        assert!(search.limit(0).search::<SimpleSdk>()?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate() -> Result<(), Error> {
+        SdkSearch::default().validate()?;
+        SdkSearch::default()
+            .platform(Platform::MacOsX)
+            .minimum_version("14.0")
+            .maximum_version("14.0")
+            .deployment_target("iosmac", "15.0")
+            .location(SdkSearchLocation::Sdk("/sdks/MacOSX14.0.sdk".into()))
+            .validate()?;
+
+        let err = SdkSearch::empty()
+            .minimum_version("14.0")
+            .maximum_version("13.0")
+            .search::<SimpleSdk>()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid SDK search: minimum version 14.0 is greater than maximum version 13.0"
+        );
+
+        assert!(matches!(
+            SdkSearch::empty()
+                .platform(Platform::IPhoneOs)
+                .location(SdkSearchLocation::Sdk("/sdks/MacOSX14.0.sdk".into()))
+                .validate(),
+            Err(Error::SdkSearchInvalid(_))
+        ));
+        assert!(matches!(
+            SdkSearch::empty()
+                .platform(Platform::IPhoneOs)
+                .location(SdkSearchLocation::Sdks(
+                    "/Platforms/MacOSX.platform/Developer/SDKs".into()
+                ))
+                .validate(),
+            Err(Error::SdkSearchInvalid(_))
+        ));
+        assert!(matches!(
+            SdkSearch::empty()
+                .platform(Platform::IPhoneOs)
+                .deployment_target("macosx", "14.0")
+                .validate(),
+            Err(Error::SdkSearchInvalid(_))
+        ));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parse")]
+    #[test]
+    fn deployment_target_from_env() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path().to_path_buf();
+        let sdk = td.join("MacOSX11.3.sdk");
+        std::fs::create_dir_all(&sdk)?;
+        std::fs::copy(
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/src/testfiles/macosx11.3-settings.json"
+            ),
+            sdk.join("SDKSettings.json"),
+        )?;
+
+        let search = |value: Option<&'static str>| {
+            SdkSearch::empty()
+                .location(SdkSearchLocation::Sdks(td.clone()))
+                .deployment_target_from_env(true)
+                .environment(move |name| {
+                    value
+                        .filter(|_| name == "MACOSX_DEPLOYMENT_TARGET")
+                        .map(OsString::from)
+                })
+        };
+
+        assert_eq!(search(None).search::<crate::ParsedSdk>()?.len(), 1);
+        assert_eq!(search(Some("10.15")).search::<crate::ParsedSdk>()?.len(), 1);
+        assert!(search(Some("12.0"))
+            .search::<crate::ParsedSdk>()?
+            .is_empty());
+        assert!(matches!(
+            search(Some("foo")).search::<crate::ParsedSdk>(),
+            Err(Error::VersionParse(_))
+        ));
+
+        // SDKs that can't evaluate deployment targets aren't filtered.
+        assert_eq!(search(Some("12.0")).search::<SimpleSdk>()?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn osxcross() -> Result<(), Error> {
+        let td = PathBuf::from("/osxcross");
+        let sdk = td.join("target/SDK/MacOSX14.0.sdk");
+        let fs = MemoryFilesystem::new().sdk(&sdk);
+
+        for path in [td.clone(), td.join("target")] {
+            let sdks = SdkSearch::empty()
+                .filesystem(fs.clone())
+                .location(SdkSearchLocation::Osxcross(path))
+                .search::<SimpleSdk>()?;
+            assert_eq!(sdks.len(), 1);
+            assert_eq!(sdks[0].path(), sdk);
+        }
+
+        assert!(!SdkSearchLocation::default_locations().contains(&SdkSearchLocation::OsxcrossEnv));
+
+        let target = td.join("target");
+        let sdks = SdkSearch::empty()
+            .filesystem(fs)
+            .location(SdkSearchLocation::OsxcrossEnv)
+            .environment(move |name| {
+                (name == "OSXCROSS_TARGET_DIR").then(|| target.clone().into_os_string())
+            })
+            .search::<SimpleSdk>()?;
+        assert_eq!(sdks.len(), 1);
+        assert_eq!(sdks[0].path(), sdk);
+
+        Ok(())
+    }
+
+    #[test]
+    fn extra_sdks_dirs() {
+        let value = std::env::join_paths(["/extra/one", "", "/extra/two"]).unwrap();
+        assert_eq!(
+            extra_sdks_dirs_locations(&value),
+            vec![
+                SdkSearchLocation::Sdks("/extra/one".into()),
+                SdkSearchLocation::Sdks("/extra/two".into()),
+            ]
+        );
+
+        let search = SdkSearch::empty()
+            .location(SdkSearchLocation::Sdks("/sdks".into()))
+            .extra_sdks_dirs_from_env(false);
+        assert_eq!(
+            search.effective_locations(),
+            vec![SdkSearchLocation::Sdks("/sdks".into())]
+        );
+    }
+
+    #[test]
+    fn presets() {
+        let search = SdkSearch::macos_compiler_default();
+        assert_eq!(
+            search.search_locations(),
+            &[
+                SdkSearchLocation::SdkRootEnv,
+                SdkSearchLocation::DeveloperDirEnv,
+                SdkSearchLocation::SystemXcode,
+                SdkSearchLocation::CommandLineTools,
+            ]
+        );
+        assert_eq!(search.platforms, vec![Platform::MacOsX]);
+        assert_eq!(search.sorting, SdkSorting::VersionDescending);
+        assert_eq!(search.beta_preference, XcodeBetaPreference::PreferRelease);
+
+        let search = SdkSearch::newest(Platform::IPhoneSimulator);
+        assert_eq!(
+            search.search_locations(),
+            SdkSearchLocation::default_locations()
+        );
+        assert_eq!(search.platforms, vec![Platform::IPhoneSimulator]);
+        assert_eq!(search.sorting, SdkSorting::VersionDescending);
+        assert_eq!(search.beta_preference, XcodeBetaPreference::PreferRelease);
+        assert_eq!(search.limit, None);
+
+        let search = SdkSearch::everything();
+        assert!(search
+            .search_locations()
+            .contains(&SdkSearchLocation::UserXcodes));
+        assert!(!search.search_locations().iter().any(|l| l.is_terminal()));
+        assert!(search.platforms.is_empty());
+        assert!(search.deduplicate);
+        assert!(search.prefer_versioned);
+    }
+
+    /// An environment lookup backed by a fixed set of variables.
+    fn environment(
+        vars: &[(&str, OsString)],
+    ) -> impl Fn(&str) -> Option<OsString> + Send + Sync + 'static {
+        let vars = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect::<HashMap<_, _>>();
+
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn from_env() -> Result<(), Error> {
+        let search = SdkSearch::from_env_with_lookup(environment(&[
+            ("TARGET", "aarch64-apple-tvos".into()),
+            ("TVOS_DEPLOYMENT_TARGET", "15.0".into()),
+            (
+                EXTRA_SDKS_DIRS_ENV,
+                std::env::join_paths(["/extra/one", "/extra/two"]).unwrap(),
+            ),
+        ]))?;
+
+        assert_eq!(search.platforms, vec![Platform::AppleTvOs]);
+        assert_eq!(
+            search.deployment_target,
+            Some(("appletvos".to_string(), SdkVersion::from("15.0")))
+        );
+        assert_eq!(
+            &search.search_locations()[SdkSearchLocation::default_locations().len()..],
+            &[
+                SdkSearchLocation::Sdks(PathBuf::from("/extra/one")),
+                SdkSearchLocation::Sdks(PathBuf::from("/extra/two")),
+            ]
+        );
+
+        let sdk = Path::new("/extra/MacOSX14.2.sdk");
+        let search = SdkSearch::from_env_with_lookup(environment(&[
+            ("TARGET", "aarch64-apple-darwin".into()),
+            ("MACOSX_DEPLOYMENT_TARGET", "11.0".into()),
+            (EXTRA_SDKS_DIRS_ENV, "/extra".into()),
+        ]))?
+        .filesystem(MemoryFilesystem::new().sdk(sdk));
+        assert_eq!(
+            search.deployment_target,
+            Some(("macosx".to_string(), SdkVersion::from("11.0")))
+        );
+        assert!(search
+            .search::<SimpleSdk>()?
+            .iter()
+            .any(|found| found.path() == sdk));
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_first() -> Result<(), Error> {
+        let search = SdkSearch::empty()
+            .location(SdkSearchLocation::Sdks(PathBuf::from("/does/not/exist")))
+            .platform(Platform::MacOsX)
+            .minimum_version("11.0");
+
+        let err = search.find_first::<SimpleSdk>().unwrap_err();
+        assert!(matches!(&err, Error::SdkNotFound(_)));
+        assert_eq!(
+            err.to_string(),
+            "no SDK found: searched SDKs directory /does/not/exist; platform MacOSX; minimum version 11.0"
+        );
+
+        let search = search
+            .sorting(SdkSorting::VersionDescending)
+            .beta_preference(XcodeBetaPreference::PreferRelease)
+            .limit(1);
+        assert_eq!(
+            search.describe(),
+            "searched SDKs directory /does/not/exist; platform MacOSX; minimum version 11.0; \
+            sorted by descending version; prefer release Xcodes; limit 1"
+        );
+        let debug = format!("{search:?}");
+        assert!(debug.starts_with("SdkSearch { locations: [Sdks(\"/does/not/exist\")]"));
+        assert!(debug.contains("progress_callback: false"));
+
+        let fs = MemoryFilesystem::new()
+            .sdk("/sdks/MacOSX13.3.sdk")
+            .sdk("/sdks/MacOSX14.2.sdk")
+            .sdk("/sdks/iPhoneOS17.2.sdk");
+        let search = SdkSearch::empty()
+            .filesystem(fs)
+            .location(SdkSearchLocation::Sdks("/sdks".into()))
+            .platform(Platform::MacOsX)
+            .sorting(SdkSorting::VersionDescending);
+        assert_eq!(
+            search.find_first::<SimpleSdk>()?.path(),
+            search.search::<SimpleSdk>()?[0].path()
+        );
+        assert_eq!(
+            search.find_first::<SimpleSdk>()?.path(),
+            Path::new("/sdks/MacOSX14.2.sdk")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn multiple_platforms() -> Result<(), Error> {
+        let search = SdkSearch::default()
+            .platform(Platform::MacOsX)
+            .platform(Platform::IPhoneOs)
+            .add_platform(Platform::IPhoneSimulator)
+            .add_platform(Platform::IPhoneOs);
+        assert_eq!(
+            search.platforms,
+            vec![Platform::IPhoneOs, Platform::IPhoneSimulator]
+        );
+        assert_eq!(
+            search
+                .clone()
+                .platforms([Platform::WatchOs, Platform::WatchOs])
+                .platforms,
+            vec![Platform::WatchOs]
+        );
+        assert!(search.platform_matches(&Platform::IPhoneSimulator));
+        assert!(!search.platform_matches(&Platform::MacOsX));
+        assert!(SdkSearch::default().platform_matches(&Platform::MacOsX));
+
+        for sdk in search.search::<SimpleSdk>()? {
+            assert!(matches!(
+                sdk.platform(),
+                Platform::IPhoneOs | Platform::IPhoneSimulator
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn locations() {
+        assert_eq!(
+            SdkSearch::default().search_locations(),
+            SdkSearchLocation::default_locations().as_slice()
+        );
+        assert!(SdkSearch::empty().search_locations().is_empty());
+
+        let search = SdkSearch::default()
+            .locations([
+                SdkSearchLocation::CommandLineTools,
+                SdkSearchLocation::SystemXcodes,
+            ])
+            .location(SdkSearchLocation::XcodeSelect);
+        assert_eq!(
+            search.search_locations(),
+            &[
+                SdkSearchLocation::CommandLineTools,
+                SdkSearchLocation::SystemXcodes,
+                SdkSearchLocation::XcodeSelect
+            ]
+        );
+    }
+
+    #[test]
+    fn user_applications() {
+        let search = SdkSearch::default().user_applications(true);
+        assert_eq!(
+            search.search_locations(),
+            &[
+                SdkSearchLocation::SdkRootEnv,
+                SdkSearchLocation::DeveloperDirEnv,
+                SdkSearchLocation::SystemXcode,
+                SdkSearchLocation::UserXcodes,
+                SdkSearchLocation::CommandLineTools,
+            ]
+        );
+        assert_eq!(
+            search
+                .clone()
+                .user_applications(true)
+                .search_locations()
+                .len(),
+            5
+        );
+        assert_eq!(
+            search.user_applications(false).search_locations(),
+            SdkSearchLocation::default_locations().as_slice()
+        );
+
+        assert_eq!(
+            SdkSearch::empty()
+                .location(SdkSearchLocation::CommandLineTools)
+                .user_applications(true)
+                .search_locations(),
+            &[
+                SdkSearchLocation::CommandLineTools,
+                SdkSearchLocation::UserXcodes
+            ]
+        );
+    }
+
+    #[test]
+    fn for_target_triple() -> Result<(), Error> {
+        assert_eq!(
+            SdkSearch::for_target_triple("aarch64-apple-ios-sim")?.platforms,
+            vec![Platform::IPhoneSimulator]
+        );
+        assert_eq!(
+            SdkSearch::for_target_triple("x86_64-apple-ios-macabi")?.platforms,
+            vec![Platform::MacOsX]
+        );
+        assert!(matches!(
+            SdkSearch::for_target_triple("x86_64-unknown-linux-gnu"),
+            Err(Error::UnknownTarget(_))
+        ));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parse")]
+    #[test]
+    fn xcodebuild() -> Result<(), Error> {
+        // Failure to run xcodebuild yields no SDKs instead of an error.
+        let sdks = SdkSearch::empty()
+            .location(SdkSearchLocation::Xcodebuild)
+            .search::<SimpleSdk>()?;
+
+        if !cfg!(target_os = "macos") {
+            assert!(sdks.is_empty());
+        }
+
+        // DEVELOPER_DIR is read through the environment lookup.
+        assert!(matches!(
+            SdkSearch::empty()
+                .location(SdkSearchLocation::Xcodebuild)
+                .environment(|name| (name == "DEVELOPER_DIR").then(|| "/does/not/exist".into()))
+                .search::<SimpleSdk>(),
+            Err(Error::PathNotDeveloper(_))
+        ));
+
+        // SDKs found by prior locations aren't yielded again.
+        let all = SdkSearch::default().search::<SimpleSdk>()?;
+        let merged = SdkSearch::default()
+            .location(SdkSearchLocation::Xcodebuild)
+            .search::<SimpleSdk>()?;
+        assert!(merged.len() >= all.len());
+        assert!(merged.iter().all(|sdk| merged
+            .iter()
+            .filter(|other| other.path() == sdk.path())
+            .count()
+            == 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn allow_subprocess() -> Result<(), Error> {
+        #[cfg(feature = "parse")]
+        assert!(matches!(
+            SdkSearch::empty()
+                .location(SdkSearchLocation::Xcodebuild)
+                .allow_subprocess(false)
+                .search::<SimpleSdk>(),
+            Err(Error::SubprocessDisabled("xcodebuild"))
+        ));
+
+        if DeveloperDirectory::from_xcode_select_link()?.is_none() {
+            assert!(matches!(
+                SdkSearch::empty()
+                    .location(SdkSearchLocation::XcodeSelect)
+                    .allow_subprocess(false)
+                    .search::<SimpleSdk>(),
+                Err(Error::SubprocessDisabled("xcode-select"))
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn for_sdk_name() -> Result<(), Error> {
+        let search = SdkSearch::for_sdk_name("iphoneos17.0")?;
+        assert_eq!(search.platforms, vec![Platform::IPhoneOs]);
+        assert_eq!(search.minimum_version, Some(SdkVersion::from("17.0")));
+        assert_eq!(search.maximum_version, Some(SdkVersion::from("17.0")));
+        assert_eq!(search.sorting, SdkSorting::VersionDescending);
+
+        let search = SdkSearch::for_sdk_name("macosx")?;
+        assert_eq!(search.platforms, vec![Platform::MacOsX]);
+        assert!(search.minimum_version.is_none());
+
+        assert!(matches!(
+            SdkSearch::for_sdk_name("linux"),
+            Err(Error::SdkNameParse(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn progress_callback() -> Result<(), Error> {
+        let events = Arc::new(std::sync::Mutex::new(vec![]));
+
+        let events_cb = events.clone();
+        SdkSearch::empty()
+            .location(SdkSearchLocation::Sdks(PathBuf::from("/does/not/exist")))
+            .progress_callback(move |event| events_cb.lock().unwrap().push(event.to_string()))
+            .sorting(SdkSorting::VersionDescending)
+            .search::<SimpleSdk>()?;
+
+        assert_eq!(
+            events.lock().unwrap().as_slice(),
+            &[
+                "searching SDKs directory /does/not/exist",
+                "looking for SDKs in /does/not/exist",
+                "sorting 0 SDKs by descending version"
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parse")]
+    #[test]
+    fn search_json() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let dir = crate::DeveloperDirectoryFixture::new(temp.path())
+            .sdk(FixtureSdk::new(Platform::MacOsX, "14.2"))
+            .sdk(FixtureSdk::new(Platform::IPhoneOs, "17.2"))
+            .build()?;
+        let search = SdkSearch::empty().location(SdkSearchLocation::Developer(dir));
+
+        let sdks = search.search::<crate::ParsedSdk>()?;
+        let value = search.search_json::<crate::ParsedSdk>()?;
+
+        let entries = value.as_array().expect("value should be an array");
+        assert_eq!(sdks.len(), 2);
+        assert_eq!(entries.len(), sdks.len());
+
+        for (entry, sdk) in entries.iter().zip(sdks.iter()) {
+            assert_eq!(entry["path"], serde_json::json!(sdk.path()));
+            assert_eq!(
+                entry["platform"],
+                serde_json::json!(sdk.platform().filesystem_name())
+            );
+            assert_eq!(
+                entry["version"],
+                serde_json::json!(sdk.version().map(|v| v.to_string()))
+            );
+            assert_eq!(entry["is_symlink"], serde_json::json!(sdk.is_symlink()));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(all(feature = "rayon", feature = "parse"))]
+    #[test]
+    fn search_parallel() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let xcode = crate::DeveloperDirectoryFixture::new(temp.path().join("xcode"))
+            .sdk(FixtureSdk::new(Platform::MacOsX, "13.3"))
+            .sdk(FixtureSdk::new(Platform::MacOsX, "14.2"))
+            .sdk(FixtureSdk::new(Platform::IPhoneOs, "17.2"))
+            .sdk(FixtureSdk::new(Platform::IPhoneSimulator, "17.2"))
+            .build()?;
+        let beta = crate::DeveloperDirectoryFixture::new(temp.path().join("beta"))
+            .sdk(FixtureSdk::new(Platform::MacOsX, "15.0"))
+            .sdk(FixtureSdk::new(Platform::WatchOs, "11.0"))
+            .build()?;
+
+        let search = SdkSearch::empty()
+            .location(SdkSearchLocation::Developer(xcode))
+            .location(SdkSearchLocation::Developer(beta));
+
+        let serial = search
+            .search::<crate::ParsedSdk>()?
+            .into_iter()
+            .map(|sdk| sdk.path().to_path_buf())
+            .collect::<Vec<_>>();
+        let parallel = search
+            .search_parallel::<crate::ParsedSdk>()?
+            .into_iter()
+            .map(|sdk| sdk.path().to_path_buf())
+            .collect::<Vec<_>>();
+
+        assert_eq!(serial.len(), 6);
+        assert_eq!(serial, parallel);
+
+        let parallel = search
+            .clone()
+            .platform(Platform::MacOsX)
+            .search_parallel::<crate::ParsedSdk>()?;
+        assert_eq!(parallel.len(), 3);
+
+        Ok(())
+    }
+
+    /// A [MemoryFilesystem] whose content and directory stamps can change between
+    /// searches.
+    #[derive(Clone, Default)]
+    struct MutableFilesystem {
+        fs: Arc<Mutex<MemoryFilesystem>>,
+        stamp: Arc<Mutex<(u64, u64)>>,
+    }
+
+    impl MutableFilesystem {
+        fn set(&self, fs: MemoryFilesystem) {
+            *self.fs.lock().unwrap() = fs;
+        }
+
+        /// Set the modification time (in seconds) and inode reported for all paths.
+        fn set_stamp(&self, modified: u64, inode: u64) {
+            *self.stamp.lock().unwrap() = (modified, inode);
+        }
+    }
+
+    impl Filesystem for MutableFilesystem {
+        fn read_dir(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+            self.fs.lock().unwrap().read_dir(path)
+        }
+
+        fn metadata(&self, path: &Path) -> std::io::Result<crate::FileMetadata> {
+            let (modified, inode) = *self.stamp.lock().unwrap();
+
+            Ok(crate::FileMetadata {
+                modified: Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(modified)),
+                inode: Some(inode),
+                ..self.fs.lock().unwrap().metadata(path)?
+            })
+        }
+
+        fn symlink_metadata(&self, path: &Path) -> std::io::Result<crate::FileMetadata> {
+            self.fs.lock().unwrap().symlink_metadata(path)
+        }
+
+        fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+            self.fs.lock().unwrap().read(path)
+        }
+
+        fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+            self.fs.lock().unwrap().canonicalize(path)
+        }
+    }
+
+    #[test]
+    fn search_cached() -> Result<(), Error> {
+        let sdks_fs = |names: &[&str]| {
+            names.iter().fold(MemoryFilesystem::new(), |fs, name| {
+                fs.sdk(format!("/sdks/{name}"))
+            })
+        };
+
+        let fs = MutableFilesystem::default();
+        fs.set(sdks_fs(&["MacOSX14.0.sdk"]));
+        fs.set_stamp(1, 1);
+
+        let cache = SdkSearchCache::new();
+        let search = SdkSearch::empty()
+            .filesystem(fs.clone())
+            .location(SdkSearchLocation::Sdks("/sdks".into()));
+
+        assert_eq!(search.search_cached::<SimpleSdk>(&cache)?.len(), 1);
+        assert_eq!(cache.len(), 1);
+
+        // Changes within an SDK aren't detected.
+        fs.set(MemoryFilesystem::new().directory("/sdks/MacOSX14.0.sdk"));
+        assert_eq!(search.search_cached::<SimpleSdk>(&cache)?.len(), 1);
+        assert!(search.search::<SimpleSdk>()?.is_empty());
+
+        // Unless the directory is invalidated explicitly.
+        cache.invalidate("/sdks");
+        assert!(cache.is_empty());
+        assert!(search.search_cached::<SimpleSdk>(&cache)?.is_empty());
+        fs.set(sdks_fs(&["MacOSX14.0.sdk"]));
+        cache.invalidate("/sdks");
+
+        // Filters are applied to cached SDKs.
+        assert!(search
+            .clone()
+            .platform(Platform::IPhoneOs)
+            .search_cached::<SimpleSdk>(&cache)?
+            .is_empty());
+
+        // A new modification time, e.g. from adding an SDK, invalidates the entry.
+        fs.set(sdks_fs(&["MacOSX14.0.sdk", "MacOSX14.2.sdk"]));
+        assert_eq!(search.search_cached::<SimpleSdk>(&cache)?.len(), 1);
+        fs.set_stamp(2, 1);
+        assert_eq!(search.search_cached::<SimpleSdk>(&cache)?.len(), 2);
+
+        // Replacing the directory invalidates the entry, even with an unchanged
+        // modification time.
+        fs.set(sdks_fs(&["MacOSX15.0.sdk"]));
+        fs.set_stamp(2, 2);
+        let sdks = search.search_cached::<SimpleSdk>(&cache)?;
+        assert_eq!(sdks.len(), 1);
+        assert_eq!(sdks[0].version(), Some(&SdkVersion::from("15.0")));
+
+        cache.clear();
+        assert!(cache.is_empty());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parse")]
+    #[test]
+    fn path_filter_before_parse() -> Result<(), Error> {
+        let fs =
+            MemoryFilesystem::new().file("/sdks/iPhoneOS17.0.sdk/SDKSettings.json", "malformed");
+
+        let search = SdkSearch::empty()
+            .filesystem(fs)
+            .location(SdkSearchLocation::Sdks("/sdks".into()));
+
+        // The malformed SDK is never parsed when filtered out by platform.
+        assert!(search
+            .clone()
+            .platform(Platform::MacOsX)
+            .search::<crate::ParsedSdk>()?
+            .is_empty());
+        assert!(search.search::<crate::ParsedSdk>().is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parse")]
+    #[test]
+    fn search_lenient() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path().to_path_buf();
+        let bad = td.join("MacOSX13.0.sdk");
+        std::fs::create_dir_all(&bad)?;
+        std::fs::write(bad.join("SDKSettings.json"), b"malformed")?;
+        let good = FixtureSdk::new(Platform::MacOsX, "14.0").write_to_directory(&td)?;
+
+        let missing = td.join("missing.sdk");
+        let search = SdkSearch::empty()
+            .location(SdkSearchLocation::Sdks(td.clone()))
+            .location(SdkSearchLocation::Sdk(missing.clone()));
+        assert!(search.search::<crate::ParsedSdk>().is_err());
+
+        let report = search.search_lenient::<crate::ParsedSdk>()?;
+        assert_eq!(report.sdks.len(), 1);
+        assert_eq!(report.sdks[0].path(), good);
+        assert_eq!(report.warnings.len(), 2);
+        assert!(matches!(
+            &report.warnings[0],
+            SdkSearchWarning::SdkLoadFailure(p, Error::SerdeJson(_)) if p == &bad
+        ));
+        assert!(matches!(
+            &report.warnings[1],
+            SdkSearchWarning::SdkLoadFailure(p, _) if p == &missing
+        ));
+
+        let events = Arc::new(std::sync::Mutex::new(vec![]));
+        let events_cb = events.clone();
+        search
+            .progress_callback(move |event| events_cb.lock().unwrap().push(event))
+            .search_lenient::<crate::ParsedSdk>()?;
+        let failures = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                SdkSearchEvent::SdkLoadFailure(path, _) => Some(path.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(failures, vec![bad, missing]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn version_req() -> Result<(), Error> {
+        let fs = MemoryFilesystem::new()
+            .sdk("/sdks/MacOSX12.3.sdk")
+            .sdk("/sdks/MacOSX14.2.sdk")
+            .sdk("/sdks/MacOSX14.5.sdk")
+            .sdk("/sdks/MacOSX15.0.sdk");
+
+        let search = SdkSearch::empty()
+            .filesystem(fs)
+            .location(SdkSearchLocation::Sdks("/sdks".into()))
+            .version_req(">=13, <15".parse()?)
+            .sorting(SdkSorting::VersionAscending);
+        assert_eq!(
+            search
+                .search::<SimpleSdk>()?
+                .iter()
+                .map(|sdk| sdk.version().unwrap().to_string())
+                .collect::<Vec<_>>(),
+            vec!["14.2", "14.5"]
+        );
+        assert!(search.describe().contains("version >=13, <15"));
+
+        let search = search.version_req("~14.2".parse()?);
+        assert_eq!(search.search::<SimpleSdk>()?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn explain() -> Result<(), Error> {
+        let td = PathBuf::from("/sdks");
+        let fs = MemoryFilesystem::new()
+            .sdk(td.join("MacOSX10.15.sdk"))
+            .sdk(td.join("MacOSX14.0.sdk"))
+            .sdk(td.join("iPhoneOS17.0.sdk"));
+
+        let missing = td.join("missing").join("MacOSX15.0.sdk");
+        let search = SdkSearch::empty()
+            .filesystem(fs.clone())
+            .location(SdkSearchLocation::Sdks(td.clone()))
+            .location(SdkSearchLocation::Sdk(missing.clone()))
+            .platform(Platform::MacOsX)
+            .minimum_version("11.0");
+
+        let explanation = search.explain::<SimpleSdk>()?;
+        assert_eq!(explanation.candidates.len(), 4);
+        assert!(explanation.warnings.is_empty());
+
+        let rejection = |name: &str| {
+            explanation
+                .candidates
+                .iter()
+                .find(|c| c.path == td.join(name))
+                .and_then(|c| c.rejection.as_ref())
+                .map(|r| r.to_string())
+        };
+        assert_eq!(rejection("MacOSX14.0.sdk"), None);
+        assert_eq!(
+            rejection("MacOSX10.15.sdk"),
+            Some("SDK version 10.15 < minimum version 11.0".to_string())
+        );
+        assert_eq!(
+            rejection("iPhoneOS17.0.sdk"),
+            Some("platform iPhoneOS != MacOSX".to_string())
+        );
+        assert!(matches!(
+            explanation.candidates.last(),
+            Some(SdkSearchCandidate {
+                rejection: Some(SdkSearchRejection::LoadFailure(_)),
+                path,
+            }) if path == &missing
+        ));
+        assert_eq!(
+            explanation
+                .selected()
+                .map(|c| c.path.clone())
+                .collect::<Vec<_>>(),
+            vec![td.join("MacOSX14.0.sdk")]
+        );
+
+        let search = SdkSearch::empty()
+            .filesystem(fs.clone())
+            .location(SdkSearchLocation::Sdks(td.clone()))
+            .platform(Platform::AppleTvOs)
+            .platform_fallbacks([Platform::IPhoneOs, Platform::MacOsX])
+            .limit(1);
+        let explanation = search.explain::<SimpleSdk>()?;
+        assert_eq!(explanation.selected().count(), 1);
+        assert!(explanation
+            .rejected()
+            .all(|c| matches!(c.rejection, Some(SdkSearchRejection::PlatformNotPreferred))));
+
+        let explanation = SdkSearch::empty()
+            .filesystem(fs)
+            .location(SdkSearchLocation::Sdks(td.clone()))
+            .platform(Platform::MacOsX)
+            .sorting(SdkSorting::VersionDescending)
+            .limit(1)
+            .explain::<SimpleSdk>()?;
+        let rejection = |name: &str| {
+            explanation
+                .candidates
+                .iter()
+                .find(|c| c.path == td.join(name))
+                .and_then(|c| c.rejection.as_ref())
+        };
+        assert!(rejection("MacOSX14.0.sdk").is_none());
+        assert!(matches!(
+            rejection("MacOSX10.15.sdk"),
+            Some(SdkSearchRejection::LimitExceeded)
+        ));
+        assert!(matches!(
+            rejection("iPhoneOS17.0.sdk"),
+            Some(SdkSearchRejection::Filtered(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn search_results() -> Result<(), Error> {
+        let first = PathBuf::from("/first");
+        let second = PathBuf::from("/second");
+        let fs = MemoryFilesystem::new()
+            .sdk(first.join("MacOSX14.0.sdk"))
+            .sdk(second.join("MacOSX14.0.sdk"));
+
+        let results = SdkSearch::empty()
+            .filesystem(fs)
+            .location(SdkSearchLocation::Sdks(first.clone()))
+            .location(SdkSearchLocation::Sdks(second.clone()))
+            .search_results::<SimpleSdk>()?;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].location, SdkSearchLocation::Sdks(first.clone()));
+        assert!(results[0].sdk.path().starts_with(&first));
+        assert_eq!(results[1].location, SdkSearchLocation::Sdks(second));
+        assert_eq!(results[0].developer_directory_kind(), None);
+
+        // Developer directory kinds are detected on the real filesystem.
+        let temp = tempfile::tempdir()?;
+        let clt = temp.path().join("CommandLineTools");
+        crate::test::write_sdk(&clt.join("SDKs"), "MacOSX14.0.sdk", &[])?;
+        std::fs::create_dir_all(clt.join("usr/bin"))?;
+
+        let results = SdkSearch::empty()
+            .location(SdkSearchLocation::Sdks(clt.join("SDKs")))
+            .search_results::<SimpleSdk>()?;
+        assert_eq!(
+            results[0].developer_directory_kind(),
+            Some(DeveloperDirectoryKind::CommandLineTools)
+        );
+        assert_eq!(results[0].xcode_app_path(), None);
+
+        let fs = MemoryFilesystem::new()
+            .sdk(
+                "/Applications/Xcode.app/Contents/Developer/Platforms/MacOSX.platform/Developer/SDKs/MacOSX14.2.sdk",
+            )
+            .sdk(
+                "/Applications/Xcode_15.0.app/Contents/Developer/Platforms/MacOSX.platform/Developer/SDKs/MacOSX14.0.sdk",
+            );
+
+        let results = SdkSearch::empty()
+            .filesystem(fs)
+            .location(SdkSearchLocation::SystemXcodes)
+            .sorting(SdkSorting::VersionAscending)
+            .search_results::<SimpleSdk>()?;
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| result.xcode_app_path())
+                .collect::<Vec<_>>(),
+            vec![
+                Some(Path::new("/Applications/Xcode_15.0.app")),
+                Some(Path::new("/Applications/Xcode.app"))
+            ]
+        );
+        assert_eq!(
+            results[0]
+                .developer_directory
+                .as_ref()
+                .map(|dir| dir.path()),
+            Some(Path::new("/Applications/Xcode_15.0.app/Contents/Developer"))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn xcode_beta_status() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let app = temp.path().join("Xcode-beta.app");
+        let sdks = app.join("Contents/Developer/Platforms/MacOSX.platform/Developer/SDKs");
+        let sdk = crate::test::write_sdk(&sdks, "MacOSX15.0.sdk", &[])?;
+        let other = crate::test::write_sdk(&sdks, "MacOSX.sdk", &[])?;
+
+        let status = XcodeBetaStatus::default();
+        assert!(status.is_beta_sdk(&sdk));
+        assert!(!status.is_beta_sdk(temp.path()));
+
+        // The application is only inspected once.
+        std::fs::remove_dir_all(&app)?;
+        assert!(status.is_beta_sdk(&other));
+        assert!(!XcodeBetaStatus::default().is_beta_sdk(&other));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parse")]
+    #[test]
+    fn minimum_xcode_version() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let app = td.join("Xcode.app");
+        let platform = app.join("Contents/Developer/Platforms/MacOSX.platform");
+        let other_platform = td.join("Platforms/MacOSX.platform");
+
+        let sdk_path = FixtureSdk::new(Platform::MacOsX, "14.2")
+            .write_to_directory(&platform.join("Developer/SDKs"))?;
+        let other_sdk_path = FixtureSdk::new(Platform::MacOsX, "13.0")
+            .write_to_directory(&other_platform.join("Developer/SDKs"))?;
+        let unknown_sdk_path =
+            FixtureSdk::new(Platform::MacOsX, "12.0").write_to_directory(&td.join("SDKs"))?;
+
+        plist::Value::Dictionary(plist::Dictionary::from_iter([(
+            "CFBundleShortVersionString".to_string(),
+            plist::Value::from("15.2"),
+        )]))
+        .to_file_xml(app.join("Contents/version.plist"))?;
+        plist::Value::Dictionary(plist::Dictionary::from_iter([(
+            "DTXcode".to_string(),
+            plist::Value::from("1430"),
+        )]))
+        .to_file_xml(other_platform.join("Info.plist"))?;
+
+        assert_eq!(sdk_xcode_version(&sdk_path), Some(SdkVersion::from("15.2")));
+        assert_eq!(
+            sdk_xcode_version(&other_sdk_path),
+            Some(SdkVersion::from("14.3"))
+        );
+
+        let search = SdkSearch::empty()
+            .location(SdkSearchLocation::Sdk(sdk_path.clone()))
+            .location(SdkSearchLocation::Sdk(other_sdk_path.clone()))
+            .location(SdkSearchLocation::Sdk(unknown_sdk_path.clone()));
+        assert_eq!(search.search::<SimpleSdk>()?.len(), 3);
+        assert_eq!(
+            search
+                .clone()
+                .minimum_xcode_version("14.0")
+                .search::<SimpleSdk>()?
+                .len(),
+            2
+        );
+
+        let sdks = search.minimum_xcode_version("15.0").search::<SimpleSdk>()?;
+        assert_eq!(sdks.len(), 1);
+        assert_eq!(sdks[0].path(), sdk_path);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parse")]
+    #[test]
+    fn newest_across_locations() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let first = temp.path().join("first");
+        let second = temp.path().join("second");
+
+        FixtureSdk::new(Platform::MacOsX, "13.3").write_to_directory(&first)?;
+        FixtureSdk::new(Platform::MacOsX, "14.2").write_to_directory(&second)?;
+        FixtureSdk::new(Platform::IPhoneOs, "17.2").write_to_directory(&second)?;
+
+        let sdk = SdkSearch::newest(Platform::MacOsX)
+            .locations([
+                SdkSearchLocation::Sdks(first),
+                SdkSearchLocation::Sdks(second.clone()),
+            ])
+            .extra_sdks_dirs_from_env(false)
+            .find_first::<SimpleSdk>()?;
+        assert_eq!(sdk.path(), second.join("MacOSX14.2.sdk"));
+
+        Ok(())
+    }
 }
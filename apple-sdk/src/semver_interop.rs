@@ -0,0 +1,270 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Conversions between our version types and those of the `semver` crate.
+//!
+//! A `semver::Version` converts to the [SdkVersion] holding its string form,
+//! so converting back yields an identical value. Converting an [SdkVersion]
+//! requires at most 3 components. Missing components are filled with `0`.
+//! A suffix becomes the pre-release, with a leading `-` being optional. e.g.
+//! `13.0b1` converts to `13.0.0-b1`.
+//!
+//! Converting an [SdkVersion] is therefore normalizing: the component count
+//! isn't preserved. `14.2` converts to `14.2.0`, which converts back to the
+//! [SdkVersion] `14.2.0`. The two [SdkVersion] values compare equal, as
+//! trailing zero components are ignored, but their string forms differ.
+//!
+//! Version requirements convert comparator by comparator. Partial versions
+//! (e.g. the `14` in `>=14`) are preserved in both directions.
+
+use {
+    crate::{Error, SdkVersion, SdkVersionComparator, SdkVersionOp, SdkVersionReq},
+    semver::{BuildMetadata, Comparator, Op, Prerelease, Version, VersionReq},
+};
+
+/// Split a version suffix into pre-release and build metadata.
+fn split_suffix(suffix: &str) -> (&str, &str) {
+    let suffix = suffix.strip_prefix('-').unwrap_or(suffix);
+
+    suffix.split_once('+').unwrap_or((suffix, ""))
+}
+
+impl From<&Version> for SdkVersion {
+    fn from(v: &Version) -> Self {
+        Self::from(v.to_string())
+    }
+}
+
+impl From<Version> for SdkVersion {
+    fn from(v: Version) -> Self {
+        Self::from(&v)
+    }
+}
+
+impl TryFrom<&SdkVersion> for Version {
+    type Error = Error;
+
+    /// Convert to a semantic version.
+    ///
+    /// Missing components are filled with `0`. e.g. `14.2` becomes `14.2.0`.
+    ///
+    /// Errors with [Error::VersionParse] if the version has more than 3 components
+    /// or its suffix isn't a valid pre-release and build metadata.
+    fn try_from(v: &SdkVersion) -> Result<Self, Self::Error> {
+        let err = || Error::VersionParse(v.to_string());
+
+        let components = v.components();
+        if components.is_empty() || components.len() > 3 {
+            return Err(err());
+        }
+
+        let (pre, build) = split_suffix(v.suffix());
+
+        Ok(Self {
+            major: components[0],
+            minor: components.get(1).copied().unwrap_or_default(),
+            patch: components.get(2).copied().unwrap_or_default(),
+            pre: Prerelease::new(pre).map_err(|_| err())?,
+            build: BuildMetadata::new(build).map_err(|_| err())?,
+        })
+    }
+}
+
+impl TryFrom<SdkVersion> for Version {
+    type Error = Error;
+
+    fn try_from(v: SdkVersion) -> Result<Self, Self::Error> {
+        Self::try_from(&v)
+    }
+}
+
+impl TryFrom<&Comparator> for SdkVersionComparator {
+    type Error = Error;
+
+    /// Convert a `semver` comparator.
+    ///
+    /// `semver` wildcards like `14.*` become [SdkVersionOp::Exact]. Errors with
+    /// [Error::VersionReqParse] for operators unknown to us.
+    fn try_from(c: &Comparator) -> Result<Self, Self::Error> {
+        let op = match c.op {
+            Op::Exact | Op::Wildcard => SdkVersionOp::Exact,
+            Op::Greater => SdkVersionOp::Greater,
+            Op::GreaterEq => SdkVersionOp::GreaterEq,
+            Op::Less => SdkVersionOp::Less,
+            Op::LessEq => SdkVersionOp::LessEq,
+            Op::Tilde => SdkVersionOp::Tilde,
+            Op::Caret => SdkVersionOp::Caret,
+            _ => return Err(Error::VersionReqParse(c.to_string())),
+        };
+
+        let mut version = [Some(c.major), c.minor, c.patch]
+            .into_iter()
+            .flatten()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(".");
+
+        if !c.pre.is_empty() {
+            version.push('-');
+            version.push_str(c.pre.as_str());
+        }
+
+        Ok(Self {
+            op,
+            version: SdkVersion::from(version),
+        })
+    }
+}
+
+impl TryFrom<&SdkVersionComparator> for Comparator {
+    type Error = Error;
+
+    /// Convert to a `semver` comparator.
+    ///
+    /// Errors with [Error::VersionReqParse] for [SdkVersionOp::Wildcard], versions
+    /// with more than 3 components, and suffixes that aren't a valid pre-release.
+    /// `semver` only allows pre-releases on versions with 3 components.
+    fn try_from(c: &SdkVersionComparator) -> Result<Self, Self::Error> {
+        let err = || Error::VersionReqParse(c.to_string());
+
+        let op = match c.op {
+            SdkVersionOp::Exact => Op::Exact,
+            SdkVersionOp::Greater => Op::Greater,
+            SdkVersionOp::GreaterEq => Op::GreaterEq,
+            SdkVersionOp::Less => Op::Less,
+            SdkVersionOp::LessEq => Op::LessEq,
+            SdkVersionOp::Tilde => Op::Tilde,
+            SdkVersionOp::Caret => Op::Caret,
+            SdkVersionOp::Wildcard => return Err(err()),
+        };
+
+        let components = c.version.components();
+        if components.is_empty() || components.len() > 3 {
+            return Err(err());
+        }
+
+        let (pre, build) = split_suffix(c.version.suffix());
+        if !build.is_empty() || (!pre.is_empty() && components.len() != 3) {
+            return Err(err());
+        }
+
+        Ok(Self {
+            op,
+            major: components[0],
+            minor: components.get(1).copied(),
+            patch: components.get(2).copied(),
+            pre: Prerelease::new(pre).map_err(|_| err())?,
+        })
+    }
+}
+
+impl TryFrom<&VersionReq> for SdkVersionReq {
+    type Error = Error;
+
+    /// Convert a `semver` requirement.
+    ///
+    /// A requirement without comparators (`*`) becomes [SdkVersionReq::any()].
+    fn try_from(req: &VersionReq) -> Result<Self, Self::Error> {
+        if req.comparators.is_empty() {
+            return Ok(Self::any());
+        }
+
+        req.comparators
+            .iter()
+            .map(SdkVersionComparator::try_from)
+            .collect::<Result<Self, Error>>()
+    }
+}
+
+impl TryFrom<VersionReq> for SdkVersionReq {
+    type Error = Error;
+
+    fn try_from(req: VersionReq) -> Result<Self, Self::Error> {
+        Self::try_from(&req)
+    }
+}
+
+impl TryFrom<&SdkVersionReq> for VersionReq {
+    type Error = Error;
+
+    /// Convert to a `semver` requirement.
+    ///
+    /// [SdkVersionOp::Wildcard] comparators match everything, so they are dropped.
+    /// Errors if a comparator can't be represented. See the [Comparator] conversion.
+    fn try_from(req: &SdkVersionReq) -> Result<Self, Self::Error> {
+        Ok(Self {
+            comparators: req
+                .comparators()
+                .iter()
+                .filter(|c| c.op != SdkVersionOp::Wildcard)
+                .map(Comparator::try_from)
+                .collect::<Result<Vec<_>, Error>>()?,
+        })
+    }
+}
+
+impl TryFrom<SdkVersionReq> for VersionReq {
+    type Error = Error;
+
+    fn try_from(req: SdkVersionReq) -> Result<Self, Self::Error> {
+        Self::try_from(&req)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, std::str::FromStr};
+
+    #[test]
+    fn version() -> Result<(), Error> {
+        for s in ["14.2.0", "13.0.0-beta.1", "1.2.3-rc1+build.5"] {
+            let v = Version::parse(s).unwrap();
+            let sdk = SdkVersion::from(&v);
+            assert_eq!(sdk.to_string(), s);
+            assert_eq!(Version::try_from(&sdk)?, v);
+        }
+
+        assert_eq!(
+            Version::try_from(SdkVersion::from("14.2"))?,
+            Version::new(14, 2, 0)
+        );
+
+        // The conversion normalizes to 3 components.
+        let round_trip = SdkVersion::from(Version::try_from(SdkVersion::from("14.2"))?);
+        assert_eq!(round_trip.to_string(), "14.2.0");
+        assert_eq!(round_trip, SdkVersion::from("14.2"));
+        assert_eq!(
+            Version::try_from(SdkVersion::from("13.0b1"))?,
+            Version::parse("13.0.0-b1").unwrap()
+        );
+        assert!(Version::try_from(SdkVersion::from("12.3.1.2")).is_err());
+        assert!(Version::try_from(SdkVersion::from("foo")).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn version_req() -> Result<(), Error> {
+        for s in [">=13, <15", "~14.2", "^14.2.1", "=14", "<=13.0.0-beta.2"] {
+            let req = VersionReq::parse(s).unwrap();
+            let sdk = SdkVersionReq::try_from(&req)?;
+            assert_eq!(VersionReq::try_from(&sdk)?, req);
+        }
+
+        let req = SdkVersionReq::try_from(VersionReq::parse("14.*").unwrap())?;
+        assert_eq!(req.to_string(), "=14");
+
+        let req = SdkVersionReq::try_from(VersionReq::STAR)?;
+        assert_eq!(req, SdkVersionReq::any());
+        assert_eq!(VersionReq::try_from(req)?, VersionReq::STAR);
+
+        assert!(VersionReq::try_from(SdkVersionReq::from_str(">=12.3.1.2")?).is_err());
+
+        Ok(())
+    }
+}
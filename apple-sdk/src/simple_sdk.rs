@@ -7,7 +7,7 @@
 // except according to those terms.
 
 use {
-    crate::{AppleSdk, Error, Platform, SdkPath, SdkVersion},
+    crate::{AppleSdk, Error, Filesystem, Platform, RealFilesystem, SdkPath, SdkVersion},
     std::path::{Path, PathBuf},
 };
 
@@ -16,6 +16,7 @@ use crate::parsed_sdk::ParsedSdk;
 
 /// A directory purported to hold an Apple SDK.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct SimpleSdk {
     /// Root directory of the SDK.
     path: PathBuf,
@@ -34,17 +35,22 @@ impl AsRef<Path> for SimpleSdk {
 
 impl AppleSdk for SimpleSdk {
     fn from_directory(path: &Path) -> Result<Self, Error> {
+        Self::from_directory_with_filesystem(path, &RealFilesystem)
+    }
+
+    fn from_directory_with_filesystem(path: &Path, fs: &dyn Filesystem) -> Result<Self, Error> {
         let sdk = SdkPath::from_path(path)?;
 
         // Need to call symlink_metadata so symlinks aren't followed.
-        let metadata = std::fs::symlink_metadata(path)?;
-
-        let is_symlink = metadata.file_type().is_symlink();
+        let is_symlink = fs
+            .symlink_metadata(path)
+            .map_err(Error::io_path(path))?
+            .is_symlink;
 
         let json_path = path.join("SDKSettings.json");
         let plist_path = path.join("SDKSettings.plist");
 
-        if json_path.exists() || plist_path.exists() {
+        if fs.exists(&json_path) || fs.exists(&plist_path) {
             Ok(Self {
                 path: path.to_path_buf(),
                 is_symlink,
@@ -81,6 +87,13 @@ impl AppleSdk for SimpleSdk {
 impl SimpleSdk {
     #[cfg(feature = "parse")]
     /// Attempt to convert into an [AppleSdk] by parsing an `SDKSettings.*` file.
+    ///
+    /// Searching for [SimpleSdk] is cheap since only filesystem paths are examined.
+    /// A common pattern is to search for [SimpleSdk], pick a candidate, then call this
+    /// to obtain rich metadata for just that SDK.
+    ///
+    /// `ParsedSdk` also implements `TryFrom<&SimpleSdk>` for converting without
+    /// consuming the instance.
     pub fn try_parse(self) -> Result<ParsedSdk, Error> {
         self.try_into()
     }
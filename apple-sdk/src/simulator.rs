@@ -0,0 +1,445 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Simulator devices.
+//!
+//! CoreSimulator keeps a *device set* directory, typically
+//! `~/Library/Developer/CoreSimulator/Devices`. Each device is a subdirectory
+//! named after its UDID holding a `device.plist` describing it. A
+//! `device_set.plist` file records the default device for each runtime and
+//! device type.
+
+use {
+    crate::{Error, Platform, SdkVersion},
+    std::{
+        collections::BTreeMap,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Relative path under the user's home directory of the default device set.
+pub const SIMULATOR_DEVICE_SET_RELATIVE_PATH: &str = "Library/Developer/CoreSimulator/Devices";
+
+const RUNTIME_IDENTIFIER_PREFIX: &str = "com.apple.CoreSimulator.SimRuntime.";
+const DEVICE_TYPE_IDENTIFIER_PREFIX: &str = "com.apple.CoreSimulator.SimDeviceType.";
+
+/// The state of a simulator device.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SimulatorDeviceState {
+    /// The device is being created.
+    Creating,
+    /// The device is shut down.
+    Shutdown,
+    /// The device is booting.
+    Booting,
+    /// The device is booted and running.
+    Booted,
+    /// The device is shutting down.
+    ShuttingDown,
+    /// A state value not known to this crate.
+    Unknown(u64),
+}
+
+impl From<u64> for SimulatorDeviceState {
+    fn from(v: u64) -> Self {
+        match v {
+            0 => Self::Creating,
+            1 => Self::Shutdown,
+            2 => Self::Booting,
+            3 => Self::Booted,
+            4 => Self::ShuttingDown,
+            _ => Self::Unknown(v),
+        }
+    }
+}
+
+/// A simulator device defined in a CoreSimulator device set.
+///
+/// Instances are constructed from a device directory holding a `device.plist`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SimulatorDevice {
+    /// The filesystem path to the device directory.
+    path: PathBuf,
+
+    /// The unique identifier of the device.
+    pub udid: String,
+
+    /// The human readable name of the device. e.g. `iPhone 15`.
+    pub name: String,
+
+    /// The identifier of the runtime the device uses.
+    ///
+    /// e.g. `com.apple.CoreSimulator.SimRuntime.iOS-17-2`.
+    pub runtime: String,
+
+    /// The identifier of the device type.
+    ///
+    /// e.g. `com.apple.CoreSimulator.SimDeviceType.iPhone-15`.
+    pub device_type: String,
+
+    /// The last recorded state of the device.
+    pub state: Option<SimulatorDeviceState>,
+}
+
+impl SimulatorDevice {
+    /// Attempt to construct an instance from a device directory.
+    ///
+    /// Returns [Error::PathNotSimulatorDevice] if the directory doesn't contain
+    /// a `device.plist`.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let plist_path = path.join("device.plist");
+
+        if !plist_path.is_file() {
+            return Err(Error::PathNotSimulatorDevice(path));
+        }
+
+        Self::from_plist(path, plist::Value::from_file(&plist_path)?)
+    }
+
+    /// Construct an instance from a device directory path and its parsed `device.plist`.
+    pub fn from_plist(path: PathBuf, value: plist::Value) -> Result<Self, Error> {
+        let value = value.into_dictionary().ok_or(Error::PlistNotDictionary)?;
+
+        let get_string = |key: &str| -> Result<String, Error> {
+            value
+                .get(key)
+                .ok_or_else(|| Error::PlistKeyMissing(key.to_string()))?
+                .as_string()
+                .ok_or_else(|| Error::PlistKeyNotString(key.to_string()))
+                .map(|s| s.to_string())
+        };
+
+        let state = value
+            .get("state")
+            .and_then(|v| v.as_unsigned_integer())
+            .map(SimulatorDeviceState::from);
+
+        Ok(Self {
+            udid: get_string("UDID")?,
+            name: get_string("name")?,
+            runtime: get_string("runtime")?,
+            device_type: get_string("deviceType")?,
+            state,
+            path,
+        })
+    }
+
+    /// The filesystem path of this device's directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The short name of the runtime. e.g. `iOS-17-2`.
+    pub fn runtime_name(&self) -> &str {
+        self.runtime
+            .strip_prefix(RUNTIME_IDENTIFIER_PREFIX)
+            .unwrap_or(&self.runtime)
+    }
+
+    /// The short name of the device type. e.g. `iPhone-15`.
+    pub fn device_type_name(&self) -> &str {
+        self.device_type
+            .strip_prefix(DEVICE_TYPE_IDENTIFIER_PREFIX)
+            .unwrap_or(&self.device_type)
+    }
+
+    /// The simulator platform of this device's runtime.
+    ///
+    /// Returns [None] if the runtime's OS isn't recognized.
+    pub fn platform(&self) -> Option<Platform> {
+        let (os, _) = self.runtime_name().split_once('-')?;
+
+        match os {
+            "iOS" => Some(Platform::IPhoneSimulator),
+            "tvOS" => Some(Platform::AppleTvSimulator),
+            "watchOS" => Some(Platform::WatchSimulator),
+            "xrOS" | "visionOS" => Some(Platform::XrOsSimulator),
+            _ => None,
+        }
+    }
+
+    /// The OS version of this device's runtime. e.g. `17.2`.
+    pub fn runtime_version(&self) -> Option<SdkVersion> {
+        let (_, version) = self.runtime_name().split_once('-')?;
+
+        Some(SdkVersion::from(version.replace('-', ".")))
+    }
+
+    /// Whether the device was booted when its state was last recorded.
+    pub fn is_booted(&self) -> bool {
+        self.state == Some(SimulatorDeviceState::Booted)
+    }
+}
+
+/// A CoreSimulator device set.
+///
+/// This holds all simulator devices in a device set directory plus the default
+/// device assignments from its `device_set.plist`.
+#[derive(Clone, Debug)]
+pub struct SimulatorDeviceSet {
+    /// The filesystem path to the device set directory.
+    path: PathBuf,
+
+    /// Devices in this set, sorted by name.
+    pub devices: Vec<SimulatorDevice>,
+
+    /// Default devices, keyed by runtime identifier, then device type identifier.
+    ///
+    /// Values are device UDIDs.
+    pub default_devices: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl SimulatorDeviceSet {
+    /// Load a device set from a directory.
+    ///
+    /// A missing directory is treated as an empty device set. Subdirectories
+    /// not holding a `device.plist` are ignored. Devices whose `device.plist` can't
+    /// be read or parsed are skipped, so one broken device doesn't hide the others.
+    pub fn from_directory(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+
+        let dir = match std::fs::read_dir(&path) {
+            Ok(v) => Some(v),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(Error::IoPath(path, e)),
+        };
+
+        let mut devices = vec![];
+
+        for entry in dir.into_iter().flatten() {
+            let entry = entry.map_err(Error::io_path(&path))?;
+
+            match SimulatorDevice::from_path(entry.path()) {
+                Ok(device) => devices.push(device),
+                Err(Error::PathNotSimulatorDevice(_)) => {}
+                Err(e) => {
+                    warn!(
+                        "ignoring invalid simulator device {}: {e}",
+                        entry.path().display()
+                    );
+                }
+            }
+        }
+
+        // Make deterministic.
+        devices.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.udid.cmp(&b.udid)));
+
+        let mut res = Self {
+            path,
+            devices,
+            default_devices: BTreeMap::new(),
+        };
+
+        let set_path = res.path.join("device_set.plist");
+        if set_path.is_file() {
+            res.load_plist(plist::Value::from_file(&set_path)?)?;
+        }
+
+        Ok(res)
+    }
+
+    /// Load the current user's default device set.
+    ///
+    /// This is `~/Library/Developer/CoreSimulator/Devices`. Returns [None] if the
+    /// home directory can't be resolved.
+    pub fn from_user() -> Result<Option<Self>, Error> {
+        match std::env::var_os("HOME") {
+            Some(home) => Ok(Some(Self::from_directory(
+                PathBuf::from(home).join(SIMULATOR_DEVICE_SET_RELATIVE_PATH),
+            )?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Populate default devices from a parsed `device_set.plist`.
+    pub fn load_plist(&mut self, value: plist::Value) -> Result<(), Error> {
+        let value = value.into_dictionary().ok_or(Error::PlistNotDictionary)?;
+
+        let Some(defaults) = value.get("DefaultDevices") else {
+            return Ok(());
+        };
+        let defaults = defaults
+            .as_dictionary()
+            .ok_or_else(|| Error::PlistKeyNotDictionary("DefaultDevices".to_string()))?;
+
+        for (runtime, device_types) in defaults {
+            // The dictionary also holds non-runtime keys, such as `version`.
+            let Some(device_types) = device_types.as_dictionary() else {
+                continue;
+            };
+
+            let entry = self.default_devices.entry(runtime.clone()).or_default();
+
+            for (device_type, udid) in device_types {
+                if let Some(udid) = udid.as_string() {
+                    entry.insert(device_type.clone(), udid.to_string());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The filesystem path of this device set.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Find a device by UDID or name.
+    pub fn find_device(&self, udid_or_name: &str) -> Option<&SimulatorDevice> {
+        self.devices
+            .iter()
+            .find(|d| d.udid == udid_or_name)
+            .or_else(|| self.devices.iter().find(|d| d.name == udid_or_name))
+    }
+
+    /// Obtain devices for a simulator platform.
+    ///
+    /// Devices are ordered by runtime version, newest first.
+    pub fn devices_for_platform(&self, platform: &Platform) -> Vec<&SimulatorDevice> {
+        let mut res = self
+            .devices
+            .iter()
+            .filter(|d| d.platform().as_ref() == Some(platform))
+            .collect::<Vec<_>>();
+
+        res.sort_by_key(|d| std::cmp::Reverse(d.runtime_version()));
+
+        res
+    }
+
+    /// Whether a device is a default device for its runtime and device type.
+    pub fn is_default_device(&self, device: &SimulatorDevice) -> bool {
+        self.default_devices
+            .get(&device.runtime)
+            .and_then(|types| types.get(&device.device_type))
+            .map(|udid| udid == &device.udid)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DEVICE_PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>UDID</key>
+	<string>0D7F4F2A-6E0B-4C3B-8D8E-2F7E32A5C9B1</string>
+	<key>deviceType</key>
+	<string>com.apple.CoreSimulator.SimDeviceType.iPhone-15</string>
+	<key>isDeleted</key>
+	<false/>
+	<key>name</key>
+	<string>iPhone 15</string>
+	<key>runtime</key>
+	<string>com.apple.CoreSimulator.SimRuntime.iOS-17-2</string>
+	<key>runtimePolicy</key>
+	<string>latest</string>
+	<key>state</key>
+	<integer>3</integer>
+</dict>
+</plist>"#;
+
+    const DEVICE_SET_PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>DefaultDevices</key>
+	<dict>
+		<key>com.apple.CoreSimulator.SimRuntime.iOS-17-2</key>
+		<dict>
+			<key>com.apple.CoreSimulator.SimDeviceType.iPhone-15</key>
+			<string>0D7F4F2A-6E0B-4C3B-8D8E-2F7E32A5C9B1</string>
+		</dict>
+		<key>version</key>
+		<integer>0</integer>
+	</dict>
+	<key>DevicePairs</key>
+	<dict/>
+	<key>Version</key>
+	<integer>2</integer>
+</dict>
+</plist>"#;
+
+    #[test]
+    fn parse_device() -> Result<(), Error> {
+        let device = SimulatorDevice::from_plist(
+            PathBuf::from("/devices/0D7F4F2A-6E0B-4C3B-8D8E-2F7E32A5C9B1"),
+            plist::Value::from_reader_xml(DEVICE_PLIST.as_bytes())?,
+        )?;
+
+        assert_eq!(device.name, "iPhone 15");
+        assert_eq!(device.runtime_name(), "iOS-17-2");
+        assert_eq!(device.device_type_name(), "iPhone-15");
+        assert_eq!(device.platform(), Some(Platform::IPhoneSimulator));
+        assert_eq!(device.runtime_version(), Some(SdkVersion::from("17.2")));
+        assert!(device.is_booted());
+
+        let mut set = SimulatorDeviceSet {
+            path: PathBuf::from("/devices"),
+            devices: vec![device],
+            default_devices: BTreeMap::new(),
+        };
+        set.load_plist(plist::Value::from_reader_xml(DEVICE_SET_PLIST.as_bytes())?)?;
+
+        assert_eq!(set.default_devices.len(), 1);
+        assert!(set.is_default_device(&set.devices[0]));
+        assert!(set.find_device("iPhone 15").is_some());
+        assert!(set
+            .find_device("0D7F4F2A-6E0B-4C3B-8D8E-2F7E32A5C9B1")
+            .is_some());
+        assert_eq!(
+            set.devices_for_platform(&Platform::IPhoneSimulator).len(),
+            1
+        );
+        assert!(set
+            .devices_for_platform(&Platform::WatchSimulator)
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_directory() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+
+        let set = SimulatorDeviceSet::from_directory(td.join("missing"))?;
+        assert!(set.devices.is_empty());
+
+        let valid = td.join("0D7F4F2A-6E0B-4C3B-8D8E-2F7E32A5C9B1");
+        std::fs::create_dir_all(&valid)?;
+        std::fs::write(valid.join("device.plist"), DEVICE_PLIST)?;
+        let invalid = td.join("8C2E6B1D-0000-0000-0000-000000000000");
+        std::fs::create_dir_all(&invalid)?;
+        std::fs::write(invalid.join("device.plist"), b"not a plist")?;
+        std::fs::create_dir_all(td.join(".delete"))?;
+        std::fs::write(td.join("device_set.plist"), DEVICE_SET_PLIST)?;
+
+        let set = SimulatorDeviceSet::from_directory(td)?;
+        assert_eq!(set.path(), td);
+        assert_eq!(set.devices.len(), 1);
+        assert_eq!(set.devices[0].path(), valid);
+        assert!(set.is_default_device(&set.devices[0]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_user() -> Result<(), Error> {
+        if let Some(set) = SimulatorDeviceSet::from_user()? {
+            assert!(set.path().ends_with(SIMULATOR_DEVICE_SET_RELATIVE_PATH));
+        }
+
+        Ok(())
+    }
+}
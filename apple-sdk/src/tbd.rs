@@ -0,0 +1,656 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Text-based stub libraries.
+//!
+//! SDKs don't ship actual dynamic libraries. Instead, they ship `.tbd`
+//! *text stubs* describing the install name and exported symbols of each
+//! library. The linker consumes these in place of the real library.
+//!
+//! Up to version 4, a `.tbd` file is one or more YAML documents. The first
+//! document describes the library itself. Subsequent documents describe
+//! re-exported libraries inlined into the stub. Version 5 files are a JSON
+//! object holding the same information under `main_library` and `libraries`.
+
+use {
+    crate::{AppleSdk, Error},
+    serde::Deserialize,
+    std::path::{Path, PathBuf},
+};
+
+/// Obtain the path to the `.tbd` stub for a library install name within an SDK.
+///
+/// e.g. `/usr/lib/libz.1.dylib` resolves to `<sdk>/usr/lib/libz.1.tbd` and
+/// `/System/Library/Frameworks/Foo.framework/Versions/A/Foo` resolves to
+/// `<sdk>/System/Library/Frameworks/Foo.framework/Versions/A/Foo.tbd`.
+///
+/// The returned path is not validated to exist.
+pub fn tbd_stub_path(sdk_path: &Path, install_name: &str) -> PathBuf {
+    sdk_path
+        .join(install_name.trim_start_matches('/'))
+        .with_extension("tbd")
+}
+
+/// A set of symbols in a `.tbd` document that applies to specific targets.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TbdSymbols {
+    /// Targets these symbols apply to. e.g. `arm64-macos`.
+    pub targets: Vec<String>,
+
+    /// Regular exported symbols.
+    pub symbols: Vec<String>,
+
+    /// Objective-C class names.
+    pub objc_classes: Vec<String>,
+
+    /// Weakly defined symbols.
+    pub weak_symbols: Vec<String>,
+
+    /// Thread local symbols.
+    pub thread_local_symbols: Vec<String>,
+}
+
+impl TbdSymbols {
+    /// Whether these symbols apply to a target.
+    pub fn applies_to(&self, target: &str) -> bool {
+        self.targets.iter().any(|t| t == target)
+    }
+
+    /// Whether a symbol is in this set.
+    ///
+    /// Objective-C class symbols (`_OBJC_CLASS_$_` and `_OBJC_METACLASS_$_`
+    /// prefixed) are resolved against [Self::objc_classes].
+    pub fn contains(&self, symbol: &str) -> bool {
+        if self
+            .symbols
+            .iter()
+            .chain(self.weak_symbols.iter())
+            .chain(self.thread_local_symbols.iter())
+            .any(|s| s == symbol)
+        {
+            return true;
+        }
+
+        let class = symbol
+            .strip_prefix("_OBJC_CLASS_$_")
+            .or_else(|| symbol.strip_prefix("_OBJC_METACLASS_$_"));
+
+        if let Some(class) = class {
+            // Older formats record classes with a leading underscore.
+            self.objc_classes
+                .iter()
+                .any(|c| c.trim_start_matches('_') == class.trim_start_matches('_'))
+        } else {
+            false
+        }
+    }
+
+    /// Iterate over all symbol names, excluding Objective-C classes.
+    pub fn iter_symbols(&self) -> impl Iterator<Item = &str> {
+        self.symbols
+            .iter()
+            .chain(self.weak_symbols.iter())
+            .chain(self.thread_local_symbols.iter())
+            .map(|s| s.as_str())
+    }
+}
+
+/// A single library described in a `.tbd` file.
+///
+/// All versions of the format are normalized to the same representation. For
+/// formats predating version 4, targets are derived from the architectures and
+/// platform of the document. e.g. `x86_64` and `macosx` become `x86_64-macos`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TbdDocument {
+    /// The value of `tbd-version` or `tapi_tbd_version`.
+    ///
+    /// Only present in version 4 and newer documents.
+    pub tbd_version: Option<u64>,
+
+    /// The install name of the library. e.g. `/usr/lib/libz.1.dylib`.
+    pub install_name: String,
+
+    /// Targets the library supports. e.g. `arm64-macos`.
+    pub targets: Vec<String>,
+
+    /// The current version of the library.
+    ///
+    /// This is the version as written in the file. e.g. `1.10` stays `1.10`.
+    pub current_version: Option<String>,
+
+    /// The compatibility version of the library.
+    ///
+    /// This is the version as written in the file.
+    pub compatibility_version: Option<String>,
+
+    /// Symbols exported by the library.
+    pub exports: Vec<TbdSymbols>,
+
+    /// Symbols re-exported by the library.
+    pub reexports: Vec<TbdSymbols>,
+}
+
+impl TbdDocument {
+    /// The architectures the library supports.
+    ///
+    /// These are derived from [Self::targets] and are deduplicated. e.g. `arm64`.
+    pub fn architectures(&self) -> Vec<&str> {
+        let mut res = vec![];
+
+        for target in &self.targets {
+            let arch = target.split_once('-').map(|(a, _)| a).unwrap_or(target);
+
+            if !res.contains(&arch) {
+                res.push(arch);
+            }
+        }
+
+        res
+    }
+
+    /// Whether the library supports a target. e.g. `arm64-macos`.
+    pub fn supports_target(&self, target: &str) -> bool {
+        self.targets.iter().any(|t| t == target)
+    }
+
+    /// Whether the library exports a symbol.
+    ///
+    /// If `target` is defined, only symbols applying to that target are considered.
+    pub fn exports_symbol(&self, symbol: &str, target: Option<&str>) -> bool {
+        self.exports
+            .iter()
+            .chain(self.reexports.iter())
+            .filter(|s| target.map(|t| s.applies_to(t)).unwrap_or(true))
+            .any(|s| s.contains(symbol))
+    }
+
+    /// Iterate over exported symbol names, excluding Objective-C classes.
+    ///
+    /// If `target` is defined, only symbols applying to that target are returned.
+    pub fn exported_symbols<'a>(
+        &'a self,
+        target: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a str> + 'a {
+        self.exports
+            .iter()
+            .chain(self.reexports.iter())
+            .filter(move |s| target.map(|t| s.applies_to(t)).unwrap_or(true))
+            .flat_map(|s| s.iter_symbols())
+    }
+}
+
+/// A parsed `.tbd` file.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TbdFile {
+    /// Documents in the file.
+    ///
+    /// The first document is the library itself. Others are inlined libraries.
+    pub documents: Vec<TbdDocument>,
+}
+
+impl TbdFile {
+    /// Parse the contents of a `.tbd` file.
+    ///
+    /// Contents starting with `{` are parsed as a version 5 JSON stub. Everything
+    /// else is parsed as YAML.
+    pub fn parse(data: &str) -> Result<Self, Error> {
+        if data.trim_start().starts_with('{') {
+            return Ok(serde_json::from_str::<RawV5File>(data)?.into());
+        }
+
+        let documents = serde_yaml::Deserializer::from_str(data)
+            .map(|d| Ok(RawDocument::deserialize(d)?.into()))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(Self { documents })
+    }
+
+    /// Parse a `.tbd` file from a filesystem path.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path).map_err(Error::io_path(path))?;
+
+        Self::parse(&data)
+    }
+
+    /// Find and parse the `.tbd` stub for a library install name in an SDK.
+    ///
+    /// See [tbd_stub_path()] for how install names map to paths.
+    ///
+    /// Returns [None] if the SDK doesn't have a stub for the library.
+    pub fn from_sdk(sdk: &impl AppleSdk, install_name: &str) -> Result<Option<Self>, Error> {
+        let path = tbd_stub_path(sdk.path(), install_name);
+
+        if path.is_file() {
+            Ok(Some(Self::from_path(path)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The document describing the library itself.
+    pub fn library(&self) -> Option<&TbdDocument> {
+        self.documents.first()
+    }
+
+    /// Find a document by install name.
+    pub fn document(&self, install_name: &str) -> Option<&TbdDocument> {
+        self.documents
+            .iter()
+            .find(|d| d.install_name == install_name)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawSymbols {
+    #[serde(default)]
+    targets: Vec<String>,
+    #[serde(default)]
+    archs: Vec<String>,
+    #[serde(default)]
+    symbols: Vec<String>,
+    #[serde(default)]
+    objc_classes: Vec<String>,
+    #[serde(default, alias = "weak-def-symbols")]
+    weak_symbols: Vec<String>,
+    #[serde(default)]
+    thread_local_symbols: Vec<String>,
+}
+
+impl RawSymbols {
+    fn into_symbols(self, platform: Option<&str>) -> TbdSymbols {
+        let targets = if self.targets.is_empty() {
+            legacy_targets(&self.archs, platform)
+        } else {
+            self.targets
+        };
+
+        TbdSymbols {
+            targets,
+            symbols: self.symbols,
+            objc_classes: self.objc_classes,
+            weak_symbols: self.weak_symbols,
+            thread_local_symbols: self.thread_local_symbols,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawDocument {
+    tbd_version: Option<u64>,
+    #[serde(default)]
+    targets: Vec<String>,
+    #[serde(default)]
+    archs: Vec<String>,
+    platform: Option<String>,
+    install_name: String,
+    // Deserializing plain scalars as strings preserves their source text. Going
+    // through a number would turn `1.10` into `1.1`.
+    current_version: Option<String>,
+    compatibility_version: Option<String>,
+    #[serde(default)]
+    exports: Vec<RawSymbols>,
+    #[serde(default)]
+    reexports: Vec<RawSymbols>,
+}
+
+impl From<RawDocument> for TbdDocument {
+    fn from(raw: RawDocument) -> Self {
+        let platform = raw.platform.as_deref();
+
+        let targets = if raw.targets.is_empty() {
+            legacy_targets(&raw.archs, platform)
+        } else {
+            raw.targets
+        };
+
+        Self {
+            tbd_version: raw.tbd_version,
+            install_name: raw.install_name,
+            targets,
+            current_version: raw.current_version,
+            compatibility_version: raw.compatibility_version,
+            exports: raw
+                .exports
+                .into_iter()
+                .map(|s| s.into_symbols(platform))
+                .collect(),
+            reexports: raw
+                .reexports
+                .into_iter()
+                .map(|s| s.into_symbols(platform))
+                .collect(),
+        }
+    }
+}
+
+/// Derive version 4 style targets from pre version 4 architectures and platform.
+fn legacy_targets(archs: &[String], platform: Option<&str>) -> Vec<String> {
+    let platform = match platform {
+        Some("macosx") => "macos",
+        Some("iosmac") => "maccatalyst",
+        Some(p) => p,
+        None => return archs.to_vec(),
+    };
+
+    archs
+        .iter()
+        .map(|arch| format!("{arch}-{platform}"))
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct RawV5File {
+    tapi_tbd_version: u64,
+    main_library: RawV5Library,
+    #[serde(default)]
+    libraries: Vec<RawV5Library>,
+}
+
+impl From<RawV5File> for TbdFile {
+    fn from(raw: RawV5File) -> Self {
+        let version = raw.tapi_tbd_version;
+
+        Self {
+            documents: std::iter::once(raw.main_library)
+                .chain(raw.libraries)
+                .map(|library| library.into_document(version))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawV5Library {
+    #[serde(default)]
+    target_info: Vec<RawV5Target>,
+    #[serde(default)]
+    install_names: Vec<RawV5InstallName>,
+    #[serde(default)]
+    current_versions: Vec<RawV5Version>,
+    #[serde(default)]
+    compatibility_versions: Vec<RawV5Version>,
+    #[serde(default)]
+    exported_symbols: Vec<RawV5Symbols>,
+    #[serde(default)]
+    reexported_symbols: Vec<RawV5Symbols>,
+}
+
+impl RawV5Library {
+    fn into_document(self, version: u64) -> TbdDocument {
+        let targets = self
+            .target_info
+            .into_iter()
+            .map(|t| t.target)
+            .collect::<Vec<_>>();
+
+        let symbols = |sections: Vec<RawV5Symbols>| {
+            sections
+                .into_iter()
+                .map(|s| s.into_symbols(&targets))
+                .collect::<Vec<_>>()
+        };
+
+        TbdDocument {
+            tbd_version: Some(version),
+            install_name: self
+                .install_names
+                .into_iter()
+                .next()
+                .map(|n| n.name)
+                .unwrap_or_default(),
+            current_version: self.current_versions.into_iter().next().map(|v| v.version),
+            compatibility_version: self
+                .compatibility_versions
+                .into_iter()
+                .next()
+                .map(|v| v.version),
+            exports: symbols(self.exported_symbols),
+            reexports: symbols(self.reexported_symbols),
+            targets,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawV5Target {
+    target: String,
+}
+
+#[derive(Deserialize)]
+struct RawV5InstallName {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RawV5Version {
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct RawV5Symbols {
+    targets: Option<Vec<String>>,
+    #[serde(default)]
+    data: RawV5SymbolNames,
+    #[serde(default)]
+    text: RawV5SymbolNames,
+}
+
+impl RawV5Symbols {
+    /// Sections without targets apply to all targets of the library.
+    fn into_symbols(self, library_targets: &[String]) -> TbdSymbols {
+        let mut names = self.data;
+        names.extend(self.text);
+
+        TbdSymbols {
+            targets: self.targets.unwrap_or_else(|| library_targets.to_vec()),
+            symbols: names.global,
+            objc_classes: names.objc_class,
+            weak_symbols: names.weak,
+            thread_local_symbols: names.thread_local,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+struct RawV5SymbolNames {
+    #[serde(default)]
+    global: Vec<String>,
+    #[serde(default)]
+    objc_class: Vec<String>,
+    #[serde(default)]
+    weak: Vec<String>,
+    #[serde(default)]
+    thread_local: Vec<String>,
+}
+
+impl RawV5SymbolNames {
+    fn extend(&mut self, other: Self) {
+        self.global.extend(other.global);
+        self.objc_class.extend(other.objc_class);
+        self.weak.extend(other.weak);
+        self.thread_local.extend(other.thread_local);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TBD_V4: &str = "--- !tapi-tbd
+tbd-version:     4
+targets:         [ x86_64-macos, arm64-macos, arm64e-macos ]
+install-name:    '/usr/lib/libz.1.dylib'
+current-version: 1.2.12
+compatibility-version: 1
+exports:
+  - targets:         [ x86_64-macos, arm64-macos, arm64e-macos ]
+    symbols:         [ _adler32, _crc32, _deflate, _inflate,
+                       _zlibVersion ]
+  - targets:         [ arm64-macos, arm64e-macos ]
+    symbols:         [ _crc32_z ]
+    objc-classes:    [ ZStream ]
+...
+--- !tapi-tbd
+tbd-version:     4
+targets:         [ arm64-macos ]
+install-name:    '/usr/lib/system/libinner.dylib'
+exports:
+  - targets:         [ arm64-macos ]
+    symbols:         [ _inner ]
+...
+";
+
+    const TBD_V3: &str = "--- !tapi-tbd-v3
+archs:           [ i386, x86_64 ]
+platform:        macosx
+install-name:    /usr/lib/libfoo.dylib
+current-version: 2
+exports:
+  - archs:           [ x86_64 ]
+    symbols:         [ _foo ]
+    objc-classes:    [ _Foo ]
+    weak-def-symbols: [ _weak_foo ]
+...
+";
+
+    #[test]
+    fn parse_v4() -> Result<(), Error> {
+        let tbd = TbdFile::parse(TBD_V4)?;
+
+        assert_eq!(tbd.documents.len(), 2);
+
+        let lib = tbd.library().unwrap();
+        assert_eq!(lib.tbd_version, Some(4));
+        assert_eq!(lib.install_name, "/usr/lib/libz.1.dylib");
+        assert_eq!(lib.current_version.as_deref(), Some("1.2.12"));
+        assert_eq!(lib.compatibility_version.as_deref(), Some("1"));
+        assert_eq!(lib.architectures(), vec!["x86_64", "arm64", "arm64e"]);
+        assert!(lib.supports_target("arm64-macos"));
+        assert!(!lib.supports_target("arm64-ios"));
+
+        assert!(lib.exports_symbol("_crc32", None));
+        assert!(lib.exports_symbol("_crc32_z", Some("arm64-macos")));
+        assert!(!lib.exports_symbol("_crc32_z", Some("x86_64-macos")));
+        assert!(lib.exports_symbol("_OBJC_CLASS_$_ZStream", None));
+        assert!(!lib.exports_symbol("_missing", None));
+        assert_eq!(lib.exported_symbols(Some("x86_64-macos")).count(), 5);
+
+        assert!(tbd.document("/usr/lib/system/libinner.dylib").is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_v3() -> Result<(), Error> {
+        let tbd = TbdFile::parse(TBD_V3)?;
+        let lib = tbd.library().unwrap();
+
+        assert_eq!(lib.tbd_version, None);
+        assert_eq!(lib.targets, vec!["i386-macos", "x86_64-macos"]);
+        assert_eq!(lib.current_version.as_deref(), Some("2"));
+        assert!(lib.exports_symbol("_foo", Some("x86_64-macos")));
+        assert!(lib.exports_symbol("_weak_foo", None));
+        assert!(lib.exports_symbol("_OBJC_METACLASS_$_Foo", None));
+        assert!(!lib.exports_symbol("_foo", Some("i386-macos")));
+
+        Ok(())
+    }
+
+    const TBD_V5: &str = r#"{
+  "tapi_tbd_version": 5,
+  "main_library": {
+    "target_info": [
+      { "target": "x86_64-macos", "min_deployment": "10.13" },
+      { "target": "arm64-macos", "min_deployment": "11" }
+    ],
+    "install_names": [{ "name": "/usr/lib/libbar.dylib" }],
+    "current_versions": [{ "version": "1.10" }],
+    "exported_symbols": [
+      {
+        "data": { "global": ["_bar_data"], "objc_class": ["Bar"] },
+        "text": { "global": ["_bar"], "weak": ["_weak_bar"] }
+      },
+      {
+        "targets": ["arm64-macos"],
+        "text": { "global": ["_bar_arm64"] }
+      }
+    ]
+  },
+  "libraries": [
+    {
+      "target_info": [{ "target": "arm64-macos" }],
+      "install_names": [{ "name": "/usr/lib/system/libinner.dylib" }],
+      "exported_symbols": [{ "text": { "global": ["_inner"] } }]
+    }
+  ]
+}"#;
+
+    #[test]
+    fn version_source_text() -> Result<(), Error> {
+        let tbd = TbdFile::parse(
+            "--- !tapi-tbd
+tbd-version:     4
+targets:         [ arm64-macos ]
+install-name:    /usr/lib/libfoo.dylib
+current-version: 1.10
+compatibility-version: 1.0
+...
+",
+        )?;
+        let lib = tbd.library().unwrap();
+
+        assert_eq!(lib.current_version.as_deref(), Some("1.10"));
+        assert_eq!(lib.compatibility_version.as_deref(), Some("1.0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_v5() -> Result<(), Error> {
+        let tbd = TbdFile::parse(TBD_V5)?;
+
+        assert_eq!(tbd.documents.len(), 2);
+
+        let lib = tbd.library().unwrap();
+        assert_eq!(lib.tbd_version, Some(5));
+        assert_eq!(lib.install_name, "/usr/lib/libbar.dylib");
+        assert_eq!(lib.targets, vec!["x86_64-macos", "arm64-macos"]);
+        assert_eq!(lib.current_version.as_deref(), Some("1.10"));
+        assert_eq!(lib.compatibility_version, None);
+        assert_eq!(lib.exports[0].targets, lib.targets);
+        assert!(lib.exports_symbol("_bar", Some("x86_64-macos")));
+        assert!(lib.exports_symbol("_bar_data", None));
+        assert!(lib.exports_symbol("_weak_bar", None));
+        assert!(lib.exports_symbol("_OBJC_CLASS_$_Bar", None));
+        assert!(lib.exports_symbol("_bar_arm64", Some("arm64-macos")));
+        assert!(!lib.exports_symbol("_bar_arm64", Some("x86_64-macos")));
+
+        let inner = tbd.document("/usr/lib/system/libinner.dylib").unwrap();
+        assert!(inner.exports_symbol("_inner", Some("arm64-macos")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn stub_path() {
+        assert_eq!(
+            tbd_stub_path(Path::new("/sdk"), "/usr/lib/libz.1.dylib"),
+            PathBuf::from("/sdk/usr/lib/libz.1.tbd")
+        );
+        assert_eq!(
+            tbd_stub_path(
+                Path::new("/sdk"),
+                "/System/Library/Frameworks/Foo.framework/Versions/A/Foo"
+            ),
+            PathBuf::from("/sdk/System/Library/Frameworks/Foo.framework/Versions/A/Foo.tbd")
+        );
+    }
+}
@@ -0,0 +1,507 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Apple toolchains.
+
+use {
+    crate::{Error, SdkVersion},
+    std::{
+        cmp::Ordering,
+        path::{Path, PathBuf},
+        process::{Command, Stdio},
+    },
+};
+
+/// Identifier of the default toolchain shipped with Xcode.
+pub const XCODE_DEFAULT_TOOLCHAIN_IDENTIFIER: &str = "com.apple.dt.toolchain.XcodeDefault";
+
+/// Represents an Apple toolchain directory.
+///
+/// Toolchains are `*.xctoolchain` directories, typically located in the
+/// `Toolchains` directory of a *developer directory*. e.g.
+/// `/Applications/Xcode.app/Contents/Developer/Toolchains/XcodeDefault.xctoolchain`.
+///
+/// Toolchains hold compilers, linkers, and other tools used to build software for
+/// Apple platforms.
+///
+/// Metadata is parsed from the `ToolchainInfo.plist` or `Info.plist` file in the
+/// toolchain directory. This requires the `parse` crate feature. Without it,
+/// only the path derived fields are available.
+///
+/// Equivalence and sorting are implemented in terms of the path only.
+#[derive(Clone, Debug)]
+pub struct AppleToolchain {
+    /// The filesystem path to this toolchain.
+    path: PathBuf,
+
+    /// The name of the toolchain as derived from its directory name.
+    ///
+    /// e.g. `XcodeDefault`.
+    name: String,
+
+    /// The identifier of this toolchain.
+    ///
+    /// e.g. `com.apple.dt.toolchain.XcodeDefault`.
+    pub identifier: Option<String>,
+
+    /// The version of this toolchain.
+    ///
+    /// The default Xcode toolchain does not advertise a version. Toolchains
+    /// from swift.org do.
+    pub version: Option<String>,
+
+    /// Human friendly name of this toolchain.
+    pub display_name: Option<String>,
+
+    /// Alternate names this toolchain can be referred to by.
+    ///
+    /// e.g. `xcode` for the default Xcode toolchain. These are the names
+    /// accepted by `xcrun --toolchain`.
+    pub aliases: Vec<String>,
+}
+
+impl AppleToolchain {
+    /// Attempt to construct an instance from a filesystem path.
+    ///
+    /// The path must be a `*.xctoolchain` directory. Will return
+    /// [Error::PathNotToolchain] if not.
+    ///
+    /// If the `parse` feature is enabled and the directory contains a
+    /// `ToolchainInfo.plist` or `Info.plist` file, its metadata is loaded.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+
+        let name = path
+            .file_name()
+            .map(|s| s.to_string_lossy())
+            .and_then(|s| s.strip_suffix(".xctoolchain").map(|s| s.to_string()))
+            .ok_or_else(|| Error::PathNotToolchain(path.clone()))?;
+
+        if !path.is_dir() {
+            return Err(Error::PathNotToolchain(path));
+        }
+
+        #[allow(unused_mut)]
+        let mut res = Self {
+            path,
+            name,
+            identifier: None,
+            version: None,
+            display_name: None,
+            aliases: vec![],
+        };
+
+        #[cfg(feature = "parse")]
+        for filename in ["ToolchainInfo.plist", "Info.plist"] {
+            let plist_path = res.path.join(filename);
+
+            if plist_path.exists() {
+                res.load_plist(plist::Value::from_file(&plist_path)?)?;
+                break;
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Populate metadata fields from a parsed `ToolchainInfo.plist` or `Info.plist`.
+    #[cfg(feature = "parse")]
+    pub fn load_plist(&mut self, value: plist::Value) -> Result<(), Error> {
+        let value = value.into_dictionary().ok_or(Error::PlistNotDictionary)?;
+
+        let get_string = |key: &str| -> Result<Option<String>, Error> {
+            match value.get(key) {
+                Some(v) => Ok(Some(
+                    v.as_string()
+                        .ok_or_else(|| Error::PlistKeyNotString(key.to_string()))?
+                        .to_string(),
+                )),
+                None => Ok(None),
+            }
+        };
+
+        self.identifier = get_string("Identifier")?.or(get_string("CFBundleIdentifier")?);
+        self.version = get_string("Version")?;
+        self.display_name = get_string("DisplayName")?;
+        self.aliases = value
+            .get("Aliases")
+            .and_then(|v| v.as_array())
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|v| v.as_string().map(|s| s.to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        Ok(())
+    }
+
+    /// The filesystem path of this toolchain.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The name of this toolchain, as derived from its directory name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether this is the default toolchain shipped with Xcode.
+    pub fn is_xcode_default(&self) -> bool {
+        if let Some(identifier) = &self.identifier {
+            identifier == XCODE_DEFAULT_TOOLCHAIN_IDENTIFIER
+        } else {
+            self.name == "XcodeDefault"
+        }
+    }
+
+    /// The path to the `usr` directory within this toolchain.
+    ///
+    /// The returned path is not validated to exist.
+    pub fn usr_path(&self) -> PathBuf {
+        self.path.join("usr")
+    }
+
+    /// The path to the directory holding executables in this toolchain.
+    ///
+    /// Compilers like `clang` and `swiftc` are located in this directory.
+    ///
+    /// The returned path is not validated to exist.
+    pub fn bin_path(&self) -> PathBuf {
+        self.usr_path().join("bin")
+    }
+}
+
+/// Swift compiler tools resolved within a toolchain.
+///
+/// See [Self::from_toolchain()] and
+/// [DeveloperDirectory::swift_toolchain()](crate::DeveloperDirectory::swift_toolchain).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SwiftToolchain {
+    /// The filesystem path to `swiftc`.
+    pub swiftc: PathBuf,
+
+    /// The filesystem path to `swift-frontend`, if present.
+    ///
+    /// Older toolchains lack it, as `swiftc` was the frontend.
+    pub swift_frontend: Option<PathBuf>,
+
+    /// The Swift resource directory, if present.
+    ///
+    /// This is `usr/lib/swift` and holds the runtime and standard library modules
+    /// for each platform. It is the value of `swiftc -resource-dir`.
+    pub resource_dir: Option<PathBuf>,
+}
+
+impl SwiftToolchain {
+    /// Resolve the Swift tools within a toolchain.
+    ///
+    /// Returns [None] if the toolchain doesn't have `usr/bin/swiftc`.
+    pub fn from_toolchain(toolchain: &AppleToolchain) -> Option<Self> {
+        Self::from_usr_directory(&toolchain.usr_path())
+    }
+
+    /// Resolve the Swift tools within a `usr` directory.
+    ///
+    /// Returns [None] if the directory doesn't have `bin/swiftc`.
+    pub fn from_usr_directory(usr: &Path) -> Option<Self> {
+        let bin = usr.join("bin");
+
+        let swiftc = bin.join("swiftc");
+        if !swiftc.exists() {
+            return None;
+        }
+
+        let swift_frontend = Some(bin.join("swift-frontend")).filter(|p| p.exists());
+        let resource_dir = Some(usr.join("lib").join("swift")).filter(|p| p.is_dir());
+
+        Some(Self {
+            swiftc,
+            swift_frontend,
+            resource_dir,
+        })
+    }
+
+    /// Resolve the version of the Swift compiler by running `swiftc --version`.
+    pub fn version(&self) -> Result<SdkVersion, Error> {
+        let output = Command::new(&self.swiftc)
+            .arg("--version")
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .map_err(Error::SwiftcRun)?;
+
+        if !output.status.success() {
+            return Err(Error::SwiftcBadStatus(output.status));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        parse_swift_version(&stdout).ok_or_else(|| Error::VersionParse(stdout.trim().to_string()))
+    }
+}
+
+/// Parse the Swift version from `swiftc --version` output.
+///
+/// e.g. `Apple Swift version 5.9.2 (swiftlang-5.9.2.2.56 clang-1500.1.0.2.5)` yields
+/// `5.9.2`. Toolchains from swift.org print `Swift version 5.10 (swift-5.10-RELEASE)`.
+fn parse_swift_version(output: &str) -> Option<SdkVersion> {
+    let (_, rest) = output.split_once("Swift version ")?;
+    let version = rest.split_whitespace().next()?;
+
+    if version.starts_with(|c: char| c.is_ascii_digit()) {
+        Some(SdkVersion::from(version))
+    } else {
+        None
+    }
+}
+
+impl AsRef<Path> for AppleToolchain {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl PartialEq for AppleToolchain {
+    fn eq(&self, other: &Self) -> bool {
+        self.path.eq(&other.path)
+    }
+}
+
+impl Eq for AppleToolchain {}
+
+impl PartialOrd for AppleToolchain {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AppleToolchain {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.path.cmp(&other.path)
+    }
+}
+
+/// Find toolchains in a directory holding `*.xctoolchain` directories.
+///
+/// Entries that are not toolchains are ignored. A missing directory yields
+/// an empty result. Toolchains whose metadata can't be read or parsed are
+/// skipped, so one broken toolchain doesn't hide the others.
+///
+/// The return order is sorted and deterministic.
+pub fn find_toolchains_in_directory(toolchains_dir: &Path) -> Result<Vec<AppleToolchain>, Error> {
+    let dir = match std::fs::read_dir(toolchains_dir) {
+        Ok(v) => Ok(v),
+        Err(e) => {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                return Ok(vec![]);
+            } else {
+                Err(Error::IoPath(toolchains_dir.to_path_buf(), e))
+            }
+        }
+    }?;
+
+    let mut res = vec![];
+
+    for entry in dir {
+        let entry = entry.map_err(Error::io_path(toolchains_dir))?;
+
+        match AppleToolchain::from_path(entry.path()) {
+            Ok(toolchain) => {
+                res.push(toolchain);
+            }
+            Err(Error::PathNotToolchain(_)) => {}
+            Err(e) => {
+                warn!("ignoring invalid toolchain {}: {e}", entry.path().display());
+            }
+        }
+    }
+
+    // Make deterministic.
+    res.sort();
+
+    Ok(res)
+}
+
+/// Find toolchains within a *developer directory*.
+///
+/// This is a convenience method for [find_toolchains_in_directory()] looking
+/// in the `Toolchains` directory under the given *developer directory*.
+pub fn find_toolchains_in_developer_directory(
+    developer_dir: &Path,
+) -> Result<Vec<AppleToolchain>, Error> {
+    find_toolchains_in_directory(&developer_dir.join("Toolchains"))
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, crate::DeveloperDirectory};
+
+    #[test]
+    fn find_developer_directory_toolchains() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let dev = temp.path().join("Developer");
+        for name in [
+            "swift-5.9.2-RELEASE.xctoolchain",
+            "XcodeDefault.xctoolchain",
+            "README",
+        ] {
+            std::fs::create_dir_all(dev.join("Toolchains").join(name))?;
+        }
+
+        let toolchains = DeveloperDirectory::from(&dev).toolchains()?;
+        assert_eq!(
+            toolchains.iter().map(|t| t.name()).collect::<Vec<_>>(),
+            vec!["XcodeDefault", "swift-5.9.2-RELEASE"]
+        );
+        assert!(toolchains[0].is_xcode_default());
+        assert!(!toolchains[1].is_xcode_default());
+
+        assert!(DeveloperDirectory::from(temp.path())
+            .toolchains()?
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parse")]
+    #[test]
+    fn find_skips_invalid_plists() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let toolchains = temp.path().join("Toolchains");
+        std::fs::create_dir_all(toolchains.join("XcodeDefault.xctoolchain"))?;
+        std::fs::create_dir_all(toolchains.join("broken.xctoolchain"))?;
+        std::fs::write(
+            toolchains.join("broken.xctoolchain/Info.plist"),
+            b"malformed",
+        )?;
+
+        assert!(AppleToolchain::from_path(toolchains.join("broken.xctoolchain")).is_err());
+
+        let found = find_toolchains_in_directory(&toolchains)?;
+        assert_eq!(
+            found.iter().map(|t| t.name()).collect::<Vec<_>>(),
+            vec!["XcodeDefault"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn swift_toolchain() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let dev = td.join("Developer");
+        let usr = dev.join("Toolchains/XcodeDefault.xctoolchain/usr");
+        std::fs::create_dir_all(usr.join("bin"))?;
+        std::fs::create_dir_all(usr.join("lib/swift/macosx"))?;
+        std::fs::write(usr.join("bin/swiftc"), b"")?;
+        std::fs::write(usr.join("bin/swift-frontend"), b"")?;
+
+        let toolchain = AppleToolchain::from_path(dev.join("Toolchains/XcodeDefault.xctoolchain"))?;
+        let expected = SwiftToolchain {
+            swiftc: usr.join("bin/swiftc"),
+            swift_frontend: Some(usr.join("bin/swift-frontend")),
+            resource_dir: Some(usr.join("lib/swift")),
+        };
+        assert_eq!(
+            SwiftToolchain::from_toolchain(&toolchain),
+            Some(expected.clone())
+        );
+        assert_eq!(
+            DeveloperDirectory::from(&dev).swift_toolchain()?,
+            Some(expected)
+        );
+
+        // Command Line Tools layout.
+        let clt = td.join("CommandLineTools");
+        std::fs::create_dir_all(clt.join("usr/bin"))?;
+        assert_eq!(DeveloperDirectory::from(&clt).swift_toolchain()?, None);
+        std::fs::write(clt.join("usr/bin/swiftc"), b"")?;
+        let swift = DeveloperDirectory::from(&clt).swift_toolchain()?.unwrap();
+        assert_eq!(swift.swiftc, clt.join("usr/bin/swiftc"));
+        assert_eq!(swift.swift_frontend, None);
+        assert_eq!(swift.resource_dir, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn swift_version() {
+        assert_eq!(
+            parse_swift_version(
+                "swift-driver version: 1.87.3 Apple Swift version 5.9.2 (swiftlang-5.9.2.2.56 clang-1500.1.0.2.5)\nTarget: arm64-apple-macosx14.0\n"
+            ),
+            Some(SdkVersion::from("5.9.2"))
+        );
+        assert_eq!(
+            parse_swift_version("Swift version 5.10 (swift-5.10-RELEASE)\n"),
+            Some(SdkVersion::from("5.10"))
+        );
+        assert_eq!(parse_swift_version("clang version 15.0.0"), None);
+    }
+
+    #[test]
+    fn toolchain_path_validation() {
+        assert!(matches!(
+            AppleToolchain::from_path("foo"),
+            Err(Error::PathNotToolchain(_))
+        ));
+        assert!(matches!(
+            AppleToolchain::from_path("/does/not/exist/XcodeDefault.xctoolchain"),
+            Err(Error::PathNotToolchain(_))
+        ));
+    }
+
+    #[cfg(feature = "parse")]
+    #[test]
+    fn parse_toolchain_plist() -> Result<(), Error> {
+        const SWIFT_INFO_PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>Aliases</key>
+	<array>
+		<string>swift</string>
+	</array>
+	<key>CFBundleIdentifier</key>
+	<string>org.swift.59202311161a</string>
+	<key>DisplayName</key>
+	<string>Swift 5.9.2 Release 2023-11-16 (a)</string>
+	<key>Version</key>
+	<string>5.9.2.2.56</string>
+</dict>
+</plist>"#;
+
+        let mut toolchain = AppleToolchain {
+            path: PathBuf::from("swift-5.9.2-RELEASE.xctoolchain"),
+            name: "swift-5.9.2-RELEASE".to_string(),
+            identifier: None,
+            version: None,
+            display_name: None,
+            aliases: vec![],
+        };
+
+        toolchain.load_plist(plist::Value::from_reader_xml(SWIFT_INFO_PLIST.as_bytes())?)?;
+
+        assert_eq!(
+            toolchain.identifier.as_deref(),
+            Some("org.swift.59202311161a")
+        );
+        assert_eq!(toolchain.version.as_deref(), Some("5.9.2.2.56"));
+        assert_eq!(toolchain.aliases, vec!["swift".to_string()]);
+        assert!(!toolchain.is_xcode_default());
+        assert_eq!(
+            toolchain.bin_path(),
+            PathBuf::from("swift-5.9.2-RELEASE.xctoolchain/usr/bin")
+        );
+
+        Ok(())
+    }
+}
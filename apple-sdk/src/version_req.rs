@@ -0,0 +1,383 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! SDK version requirements.
+
+use {
+    crate::{Error, SdkVersion},
+    std::{
+        fmt::{Display, Formatter},
+        str::FromStr,
+    },
+};
+
+/// An operator in a [SdkVersionComparator].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SdkVersionOp {
+    /// `=`. Versions starting with the given components.
+    ///
+    /// e.g. `=14` matches `14.0` and `14.5` and `=14.2` matches `14.2` and `14.2.1`.
+    Exact,
+    /// `>`. Versions newer than all versions matching [Self::Exact].
+    ///
+    /// e.g. `>14` matches `15.0` but not `14.5`.
+    Greater,
+    /// `>=`. Versions not older than the given version.
+    GreaterEq,
+    /// `<`. Versions older than the given version.
+    Less,
+    /// `<=`. Versions older than the given version or matching [Self::Exact].
+    ///
+    /// e.g. `<=14` matches `14.5` but not `15.0`.
+    LessEq,
+    /// `~`. Versions not older than the given version with the same major and
+    /// minor components.
+    ///
+    /// e.g. `~14.2` matches `14.2` and `14.2.1` but not `14.3`. `~14` matches any
+    /// `14.x` version.
+    Tilde,
+    /// `^`. Versions not older than the given version with the same leftmost
+    /// non-zero component.
+    ///
+    /// e.g. `^14.2` matches `14.2` and `14.5` but not `15.0`.
+    Caret,
+    /// `*`. All versions.
+    Wildcard,
+}
+
+impl SdkVersionOp {
+    /// The textual form of this operator.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Exact => "=",
+            Self::Greater => ">",
+            Self::GreaterEq => ">=",
+            Self::Less => "<",
+            Self::LessEq => "<=",
+            Self::Tilde => "~",
+            Self::Caret => "^",
+            Self::Wildcard => "*",
+        }
+    }
+}
+
+/// A single version constraint in a [SdkVersionReq]. e.g. `>=13`.
+///
+/// The version of a [SdkVersionOp::Wildcard] comparator is ignored.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SdkVersionComparator {
+    /// The comparison operator.
+    pub op: SdkVersionOp,
+
+    /// The version compared against.
+    pub version: SdkVersion,
+}
+
+impl Display for SdkVersionComparator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.op == SdkVersionOp::Wildcard {
+            f.write_str("*")
+        } else {
+            f.write_fmt(format_args!("{}{}", self.op.as_str(), self.version))
+        }
+    }
+}
+
+impl FromStr for SdkVersionComparator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if s == "*" {
+            return Ok(Self {
+                op: SdkVersionOp::Wildcard,
+                version: SdkVersion::from("0"),
+            });
+        }
+
+        let (op, rest) = [
+            (">=", SdkVersionOp::GreaterEq),
+            ("<=", SdkVersionOp::LessEq),
+            (">", SdkVersionOp::Greater),
+            ("<", SdkVersionOp::Less),
+            ("=", SdkVersionOp::Exact),
+            ("~", SdkVersionOp::Tilde),
+            ("^", SdkVersionOp::Caret),
+        ]
+        .into_iter()
+        .find_map(|(prefix, op)| s.strip_prefix(prefix).map(|rest| (Some(op), rest)))
+        .unwrap_or((None, s));
+
+        let mut version = rest.trim();
+        let mut wildcard = false;
+
+        // `14.*` and `14.x` are equivalent to `=14`.
+        while let Some(v) = ["*", "x", "X"]
+            .into_iter()
+            .find_map(|w| version.strip_suffix(w).and_then(|v| v.strip_suffix('.')))
+        {
+            version = v;
+            wildcard = true;
+        }
+
+        let op = match (op, wildcard) {
+            (None, true) => SdkVersionOp::Exact,
+            (Some(op), true) if op != SdkVersionOp::Exact => {
+                return Err(Error::VersionReqParse(s.to_string()));
+            }
+            (Some(op), _) => op,
+            (None, false) => SdkVersionOp::Caret,
+        };
+
+        let version =
+            SdkVersion::from_str(version).map_err(|_| Error::VersionReqParse(s.to_string()))?;
+
+        Ok(Self { op, version })
+    }
+}
+
+impl SdkVersionComparator {
+    /// Whether the leading components of a version equal the first `count` components
+    /// of our version.
+    fn prefix_matches(&self, components: &[u64], count: usize) -> bool {
+        let ours = self.version.components();
+
+        (0..count.min(ours.len()))
+            .all(|i| components.get(i).copied().unwrap_or_default() == ours[i])
+    }
+
+    /// Whether a version satisfies this comparator.
+    pub fn matches(&self, version: &SdkVersion) -> bool {
+        if self.op == SdkVersionOp::Wildcard {
+            return true;
+        }
+
+        if !version.is_valid() {
+            return false;
+        }
+
+        let components = version.components();
+        let count = self.version.components().len();
+        let exact_suffix = !self.version.suffix().is_empty();
+
+        match self.op {
+            SdkVersionOp::Exact if exact_suffix => version == &self.version,
+            SdkVersionOp::Exact => {
+                version >= &self.version && self.prefix_matches(&components, count)
+            }
+            SdkVersionOp::Greater if exact_suffix => version > &self.version,
+            SdkVersionOp::Greater => {
+                version > &self.version && !self.prefix_matches(&components, count)
+            }
+            SdkVersionOp::GreaterEq => version >= &self.version,
+            SdkVersionOp::Less => version < &self.version,
+            SdkVersionOp::LessEq if exact_suffix => version <= &self.version,
+            SdkVersionOp::LessEq => {
+                version <= &self.version || self.prefix_matches(&components, count)
+            }
+            SdkVersionOp::Tilde => {
+                version >= &self.version && self.prefix_matches(&components, count.min(2))
+            }
+            SdkVersionOp::Caret => {
+                let significant = self
+                    .version
+                    .components()
+                    .iter()
+                    .position(|c| *c != 0)
+                    .map(|i| i + 1)
+                    .unwrap_or(count);
+
+                version >= &self.version && self.prefix_matches(&components, significant)
+            }
+            SdkVersionOp::Wildcard => true,
+        }
+    }
+}
+
+/// A requirement that SDK versions must satisfy. e.g. `>=13, <15` or `~14.2`.
+///
+/// A requirement is a comma delimited list of comparators, all of which must
+/// match. Comparators use the operators of [SdkVersionOp]. A version without an
+/// operator is treated like `^`, as in Cargo. `14.*` and `14.x` are equivalent to
+/// `=14`.
+///
+/// Versions are compared via the ordering of [SdkVersion], which considers any
+/// number of components. Versions with a suffix, like `13.0b1`, sort before the
+/// same version without a suffix. So `>=13` doesn't match `13.0b1`. Invalid
+/// versions only match `*`.
+///
+/// Requirements are constructed via [FromStr].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SdkVersionReq {
+    comparators: Vec<SdkVersionComparator>,
+}
+
+impl Display for SdkVersionReq {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(
+            &self
+                .comparators
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+}
+
+impl FromStr for SdkVersionReq {
+    type Err = Error;
+
+    /// Parse a version requirement.
+    ///
+    /// Errors with [Error::VersionReqParse] if the string is empty or a comparator
+    /// is malformed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Err(Error::VersionReqParse(s.to_string()));
+        }
+
+        Ok(Self {
+            comparators: s
+                .split(',')
+                .map(SdkVersionComparator::from_str)
+                .collect::<Result<Vec<_>, Error>>()?,
+        })
+    }
+}
+
+impl From<SdkVersionComparator> for SdkVersionReq {
+    fn from(comparator: SdkVersionComparator) -> Self {
+        Self {
+            comparators: vec![comparator],
+        }
+    }
+}
+
+impl FromIterator<SdkVersionComparator> for SdkVersionReq {
+    fn from_iter<T: IntoIterator<Item = SdkVersionComparator>>(iter: T) -> Self {
+        Self {
+            comparators: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SdkVersionReq {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SdkVersionReq {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+
+        Self::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl SdkVersionReq {
+    /// A requirement matching all versions.
+    pub fn any() -> Self {
+        Self {
+            comparators: vec![SdkVersionComparator {
+                op: SdkVersionOp::Wildcard,
+                version: SdkVersion::from("0"),
+            }],
+        }
+    }
+
+    /// The comparators of this requirement.
+    pub fn comparators(&self) -> &[SdkVersionComparator] {
+        &self.comparators
+    }
+
+    /// Whether a version satisfies all comparators of this requirement.
+    pub fn matches(&self, version: &SdkVersion) -> bool {
+        self.comparators.iter().all(|c| c.matches(version))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn matches(req: &str, version: &str) -> bool {
+        SdkVersionReq::from_str(req)
+            .unwrap()
+            .matches(&SdkVersion::from(version))
+    }
+
+    #[test]
+    fn parse() -> Result<(), Error> {
+        let req = SdkVersionReq::from_str(">=13, <15")?;
+        assert_eq!(
+            req.comparators(),
+            &[
+                SdkVersionComparator {
+                    op: SdkVersionOp::GreaterEq,
+                    version: SdkVersion::from("13"),
+                },
+                SdkVersionComparator {
+                    op: SdkVersionOp::Less,
+                    version: SdkVersion::from("15"),
+                },
+            ]
+        );
+        assert_eq!(req.to_string(), ">=13, <15");
+
+        assert_eq!(SdkVersionReq::from_str("14.2")?.to_string(), "^14.2");
+        assert_eq!(SdkVersionReq::from_str("14.*")?.to_string(), "=14");
+        assert_eq!(SdkVersionReq::from_str("*")?, SdkVersionReq::any());
+
+        for bad in ["", ">=", "foo", ">=13,", "~14.*", "14.2 beta"] {
+            assert!(
+                matches!(SdkVersionReq::from_str(bad), Err(Error::VersionReqParse(_))),
+                "{bad}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn matching() {
+        assert!(matches(">=13, <15", "13.0"));
+        assert!(matches(">=13, <15", "14.5.1"));
+        assert!(!matches(">=13, <15", "15.0"));
+        assert!(!matches(">=13, <15", "12.3"));
+        assert!(!matches(">=13", "13.0b1"));
+
+        assert!(matches("~14.2", "14.2"));
+        assert!(matches("~14.2", "14.2.1"));
+        assert!(!matches("~14.2", "14.3"));
+        assert!(!matches("~14.2", "14.1"));
+        assert!(matches("~14", "14.5"));
+
+        assert!(matches("^14.2", "14.5"));
+        assert!(!matches("^14.2", "15.0"));
+        assert!(!matches("^14.2", "14.1"));
+
+        assert!(matches("=14", "14.5"));
+        assert!(matches("=14.2", "14.2.0"));
+        assert!(!matches("=14.2", "14.2b1"));
+        assert!(matches("=13.0b1", "13.0b1"));
+        assert!(!matches("=13.0b1", "13.0"));
+
+        assert!(matches(">14", "15.0"));
+        assert!(!matches(">14", "14.5"));
+        assert!(matches("<=14", "14.5"));
+        assert!(!matches("<=14", "15.0"));
+
+        assert!(matches("*", "foo"));
+        assert!(!matches(">=0", "foo"));
+    }
+}
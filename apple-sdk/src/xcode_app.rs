@@ -0,0 +1,618 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Xcode applications.
+
+use {
+    crate::{
+        os_str_to_bytes, user_applications_directory, DeveloperDirectory, Error, Filesystem,
+        RealFilesystem, SdkVersion, XCODE_APP_RELATIVE_PATH_DEVELOPER,
+    },
+    std::{
+        cmp::Ordering,
+        ffi::OsStr,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Represents an installed Xcode application.
+///
+/// This is a reference to an `Xcode*.app` directory plus metadata about the
+/// application.
+///
+/// Version metadata is parsed from the `Contents/version.plist` file, falling back
+/// to `Contents/Info.plist`. This requires the `parse` crate feature. If the version
+/// isn't available from these files, it is derived from a version suffixed
+/// directory name like `Xcode-15.2.0.app` or `Xcode_15.2.app`, if present.
+///
+/// Beta and release candidate installs can be detected via [Self::is_beta()].
+///
+/// Sorting is by version, then by path. Applications without a known version sort
+/// before applications with a version.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct XcodeApplication {
+    /// The filesystem path to the `.app` directory.
+    path: PathBuf,
+
+    /// The marketing version of Xcode. e.g. `15.2`.
+    pub version: Option<SdkVersion>,
+
+    /// The build identifier of Xcode. e.g. `15C500b`.
+    pub build: Option<String>,
+}
+
+impl XcodeApplication {
+    /// Attempt to construct an instance from a filesystem path.
+    ///
+    /// The path should be a `*.app` directory. e.g. `/Applications/Xcode.app`.
+    ///
+    /// Returns [Error::PathNotXcodeApplication] if the path is not an existing
+    /// `.app` directory.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+
+        if path.extension() != Some(OsStr::new("app")) || !path.is_dir() {
+            return Err(Error::PathNotXcodeApplication(path));
+        }
+
+        #[allow(unused_mut)]
+        let mut res = Self {
+            path,
+            version: None,
+            build: None,
+        };
+
+        #[cfg(feature = "parse")]
+        for filename in ["version.plist", "Info.plist"] {
+            let plist_path = res.path.join("Contents").join(filename);
+
+            if plist_path.exists() {
+                res.load_plist(plist::Value::from_file(&plist_path)?)?;
+                break;
+            }
+        }
+
+        if res.version.is_none() {
+            res.version = res
+                .path
+                .file_name()
+                .and_then(|s| version_from_app_name(&s.to_string_lossy()));
+        }
+
+        Ok(res)
+    }
+
+    /// Resolve the Xcode application containing a path.
+    ///
+    /// e.g. given an SDK path like
+    /// `/Applications/Xcode.app/Contents/Developer/Platforms/MacOSX.platform/Developer/SDKs/MacOSX.sdk`,
+    /// this resolves `/Applications/Xcode.app`.
+    ///
+    /// Returns [None] if the path is not within an Xcode application.
+    pub fn containing(path: &Path) -> Option<Self> {
+        path.ancestors()
+            .filter(|p| p.extension() == Some(OsStr::new("app")))
+            .find_map(|p| Self::from_path(p).ok())
+    }
+
+    /// Find Xcode applications in an `Applications` directory.
+    ///
+    /// This is a wrapper around [XcodeAppDiscovery::find()] that resolves each found
+    /// path to an instance.
+    ///
+    /// The results are sorted by version, newest first.
+    pub fn find_in_directory(applications_dir: &Path) -> Result<Vec<Self>, Error> {
+        Self::find_in_directory_with(applications_dir, &XcodeAppDiscovery::default())
+    }
+
+    /// Find Xcode applications in a directory using custom discovery options.
+    ///
+    /// This is like [Self::find_in_directory()] except applications are found via
+    /// [XcodeAppDiscovery::find()].
+    ///
+    /// Applications whose version metadata can't be read or parsed are skipped, so
+    /// one broken install doesn't hide the others.
+    pub fn find_in_directory_with(
+        applications_dir: &Path,
+        discovery: &XcodeAppDiscovery,
+    ) -> Result<Vec<Self>, Error> {
+        let mut res = vec![];
+
+        for path in discovery.find(applications_dir)? {
+            match Self::from_path(&path) {
+                Ok(app) => res.push(app),
+                Err(e) => {
+                    warn!("ignoring invalid Xcode application {}: {e}", path.display());
+                }
+            }
+        }
+
+        res.sort_by(|a, b| b.cmp(a));
+
+        Ok(res)
+    }
+
+    /// Find all system installed Xcode applications.
+    ///
+    /// This is a convenience method for [Self::find_in_directory()] looking under
+    /// `/Applications`.
+    pub fn find_system() -> Result<Vec<Self>, Error> {
+        Self::find_in_directory(Path::new("/Applications"))
+    }
+
+    /// Find all Xcode applications in the current user's `Applications` directory.
+    ///
+    /// This is a convenience method for [Self::find_in_directory()] looking under
+    /// [user_applications_directory()].
+    pub fn find_user() -> Result<Vec<Self>, Error> {
+        if let Some(dir) = user_applications_directory() {
+            Self::find_in_directory(&dir)
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    /// Populate metadata fields from a parsed `version.plist` or `Info.plist`.
+    #[cfg(feature = "parse")]
+    pub fn load_plist(&mut self, value: plist::Value) -> Result<(), Error> {
+        let value = value.into_dictionary().ok_or(Error::PlistNotDictionary)?;
+
+        let get_string = |key: &str| -> Option<String> {
+            value
+                .get(key)
+                .and_then(|v| v.as_string())
+                .map(|s| s.to_string())
+        };
+
+        self.version = get_string("CFBundleShortVersionString").map(SdkVersion::from);
+        self.build = get_string("ProductBuildVersion").or_else(|| get_string("DTXcodeBuild"));
+
+        Ok(())
+    }
+
+    /// The filesystem path of this application.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether this appears to be a beta or release candidate Xcode.
+    ///
+    /// An application is considered a beta if its directory name contains `beta`
+    /// (e.g. `Xcode-beta.app`) or if its build identifier is a pre-release build.
+    /// Apple's pre-release builds have a 4 digit build number starting with `5`
+    /// after the train letter. e.g. `15E5178i`.
+    ///
+    /// Release candidates usually carry the same build identifier as the final
+    /// release and are only detected if their directory name marks them as such.
+    /// e.g. `Xcode_15.3_RC.app`.
+    ///
+    /// This is a best-effort heuristic. Xcode records no explicit pre-release
+    /// marker, so renamed installs and unconventional build identifiers can be
+    /// misclassified.
+    pub fn is_beta(&self) -> bool {
+        let name = self
+            .path
+            .file_name()
+            .map(|s| s.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        name.contains("beta")
+            || name.contains("_rc")
+            || name.contains("-rc")
+            || self.build.as_deref().map(is_beta_build).unwrap_or_default()
+    }
+
+    /// The *developer directory* within this application.
+    ///
+    /// The returned path is not validated to exist.
+    pub fn developer_directory(&self) -> DeveloperDirectory {
+        DeveloperDirectory::from(self.path.join(XCODE_APP_RELATIVE_PATH_DEVELOPER))
+    }
+}
+
+impl AsRef<Path> for XcodeApplication {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl PartialOrd for XcodeApplication {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for XcodeApplication {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.version
+            .cmp(&other.version)
+            .then_with(|| self.path.cmp(&other.path))
+    }
+}
+
+/// Whether an Xcode build identifier represents a pre-release build.
+fn is_beta_build(build: &str) -> bool {
+    let Some(train) = build.find(|c: char| c.is_ascii_alphabetic()) else {
+        return false;
+    };
+
+    let number = build[train + 1..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>();
+
+    number.len() >= 4 && number.starts_with('5')
+}
+
+/// Options controlling how Xcode applications are found in a directory.
+///
+/// By default, direct children of the directory matching `Xcode*.app` are found.
+/// This is the behavior of [find_xcode_apps()](crate::find_xcode_apps).
+///
+/// Name patterns are matched against the application's directory name. `*` matches
+/// any sequence of characters and `?` matches a single character. Patterns must
+/// match the entire name.
+///
+/// ```
+/// use apple_sdk::XcodeAppDiscovery;
+///
+/// // Find `Developer/Xcode_15.app` and `Xcode.app` in an `Applications` directory.
+/// let discovery = XcodeAppDiscovery::default().max_depth(2);
+/// assert!(discovery.matches_name("Xcode_15.app".as_ref()));
+///
+/// // Also find custom names like `IDE-15.app`.
+/// let discovery = discovery.pattern("IDE-*.app");
+/// assert!(discovery.matches_name("IDE-15.app".as_ref()));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct XcodeAppDiscovery {
+    patterns: Vec<String>,
+    max_depth: usize,
+}
+
+impl Default for XcodeAppDiscovery {
+    fn default() -> Self {
+        Self {
+            patterns: vec![Self::DEFAULT_PATTERN.to_string()],
+            max_depth: 1,
+        }
+    }
+}
+
+impl XcodeAppDiscovery {
+    /// The default application name pattern.
+    pub const DEFAULT_PATTERN: &'static str = "Xcode*.app";
+
+    /// Add a name pattern to match.
+    ///
+    /// Patterns are added to the existing patterns, which include
+    /// [Self::DEFAULT_PATTERN] by default.
+    pub fn pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.patterns.push(pattern.into());
+        self
+    }
+
+    /// Replace the name patterns to match.
+    pub fn patterns(mut self, patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.patterns = patterns.into_iter().map(|p| p.into()).collect();
+        self
+    }
+
+    /// Set how many directory levels to search.
+    ///
+    /// `1` only considers direct children of the searched directory. `2` also
+    /// considers children of its subdirectories, and so on. Directories matching a
+    /// pattern and other `.app` bundles are never descended into.
+    ///
+    /// Default is `1`.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Whether a directory name matches one of the name patterns.
+    pub fn matches_name(&self, name: &OsStr) -> bool {
+        let name = os_str_to_bytes(name);
+
+        self.patterns
+            .iter()
+            .any(|pattern| glob_matches(pattern.as_bytes(), &name))
+    }
+
+    /// Find Xcode applications in a directory.
+    ///
+    /// A missing directory yields no results. The results are sorted by path.
+    /// However, an application named `Xcode.app` always sorts first so the default
+    /// application name is always preferred.
+    pub fn find(&self, applications_dir: &Path) -> Result<Vec<PathBuf>, Error> {
+        self.find_with_filesystem(applications_dir, &RealFilesystem)
+    }
+
+    /// Find Xcode applications in a directory on the given [Filesystem].
+    ///
+    /// This is like [Self::find()] except all reads go through `fs`.
+    pub fn find_with_filesystem(
+        &self,
+        applications_dir: &Path,
+        fs: &dyn Filesystem,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let mut res = vec![];
+        self.find_recursive(applications_dir, 1, fs, &mut res)?;
+
+        // Make deterministic.
+        res.sort_by(|a, b| match (a.file_name(), b.file_name()) {
+            (Some(x), _) if x == "Xcode.app" => Ordering::Less,
+            (_, Some(x)) if x == "Xcode.app" => Ordering::Greater,
+            (_, _) => a.cmp(b),
+        });
+
+        Ok(res)
+    }
+
+    fn find_recursive(
+        &self,
+        dir: &Path,
+        depth: usize,
+        fs: &dyn Filesystem,
+        res: &mut Vec<PathBuf>,
+    ) -> Result<(), Error> {
+        if depth > self.max_depth {
+            return Ok(());
+        }
+
+        let entries = match fs.read_dir(dir) {
+            Ok(v) => v,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(Error::IoPath(dir.to_path_buf(), e)),
+        };
+
+        for path in entries {
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+
+            if self.matches_name(name) {
+                res.push(path);
+            } else if path.extension() != Some(OsStr::new("app")) && fs.is_dir(&path) {
+                self.find_recursive(&path, depth + 1, fs, res)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether a name matches a pattern with `*` and `?` wildcards.
+fn glob_matches(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|i| glob_matches(rest, &name[i..])),
+        Some((b'?', rest)) => !name.is_empty() && glob_matches(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && glob_matches(rest, &name[1..]),
+    }
+}
+
+/// Derive a version from a version suffixed Xcode application name.
+///
+/// e.g. `Xcode-15.2.0.app` yields `15.2.0` and `Xcode_15.3_RC.app` yields `15.3`.
+fn version_from_app_name(name: &str) -> Option<SdkVersion> {
+    let suffix = name
+        .strip_prefix("Xcode")?
+        .strip_suffix(".app")?
+        .trim_start_matches(['-', '_', ' ']);
+
+    let version = suffix
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()?
+        .trim_end_matches('.');
+
+    if version.starts_with(|c: char| c.is_ascii_digit()) {
+        Some(SdkVersion::from(version))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn glob() {
+        assert!(glob_matches(b"Xcode*.app", b"Xcode.app"));
+        assert!(glob_matches(b"Xcode*.app", b"Xcode_15.2.app"));
+        assert!(!glob_matches(b"Xcode*.app", b"Xcode.app.bak"));
+        assert!(!glob_matches(b"Xcode*.app", b"MyXcode.app"));
+        assert!(glob_matches(b"Xcode?.app", b"Xcode5.app"));
+        assert!(!glob_matches(b"Xcode?.app", b"Xcode.app"));
+        assert!(glob_matches(b"*", b""));
+    }
+
+    #[test]
+    fn discovery() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        for name in [
+            "Xcode.app",
+            "Xcode-beta.app/Contents/Xcode-nested.app",
+            "Developer/Xcode_15.app",
+            "Developer/Deeper/Xcode_14.app",
+            "IDE-16.app",
+        ] {
+            std::fs::create_dir_all(td.join(name))?;
+        }
+
+        assert_eq!(
+            crate::find_xcode_apps(td)?,
+            vec![td.join("Xcode.app"), td.join("Xcode-beta.app")]
+        );
+
+        let discovery = XcodeAppDiscovery::default().max_depth(2);
+        assert_eq!(
+            discovery.find(td)?,
+            vec![
+                td.join("Xcode.app"),
+                td.join("Developer/Xcode_15.app"),
+                td.join("Xcode-beta.app")
+            ]
+        );
+
+        let discovery = XcodeAppDiscovery::default()
+            .patterns(["IDE-*.app"])
+            .max_depth(3);
+        assert_eq!(discovery.find(td)?, vec![td.join("IDE-16.app")]);
+
+        let apps = XcodeApplication::find_in_directory_with(
+            td,
+            &XcodeAppDiscovery::default()
+                .pattern("IDE-*.app")
+                .max_depth(3),
+        )?;
+        assert_eq!(apps.len(), 5);
+
+        assert!(XcodeAppDiscovery::default()
+            .find(&td.join("missing"))?
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parse")]
+    #[test]
+    fn find_skips_invalid_plists() -> Result<(), Error> {
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        std::fs::create_dir_all(td.join("Xcode.app/Contents"))?;
+        std::fs::create_dir_all(td.join("Xcode-beta.app/Contents"))?;
+        std::fs::write(
+            td.join("Xcode-beta.app/Contents/version.plist"),
+            b"malformed",
+        )?;
+
+        assert!(XcodeApplication::from_path(td.join("Xcode-beta.app")).is_err());
+
+        let apps = XcodeApplication::find_in_directory(td)?;
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0].path(), td.join("Xcode.app"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_system() -> Result<(), Error> {
+        let apps = XcodeApplication::find_system()?;
+
+        assert_eq!(apps.len(), crate::find_system_xcode_applications()?.len());
+
+        #[cfg(feature = "parse")]
+        for app in apps {
+            assert!(app.version.is_some());
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parse")]
+    #[test]
+    fn parse_version_plist() -> Result<(), Error> {
+        const VERSION_PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>BuildAliasOf</key>
+	<string>IDEApplication</string>
+	<key>BuildVersion</key>
+	<string>3</string>
+	<key>CFBundleShortVersionString</key>
+	<string>15.2</string>
+	<key>CFBundleVersion</key>
+	<string>22503</string>
+	<key>ProductBuildVersion</key>
+	<string>15C500b</string>
+	<key>ProjectName</key>
+	<string>IDEApplication</string>
+	<key>SourceVersion</key>
+	<string>22503000000000000</string>
+</dict>
+</plist>"#;
+
+        let mut app = XcodeApplication {
+            path: PathBuf::from("/Applications/Xcode_15.2.app"),
+            version: None,
+            build: None,
+        };
+        app.load_plist(plist::Value::from_reader_xml(VERSION_PLIST.as_bytes())?)?;
+
+        assert_eq!(app.version, Some(SdkVersion::from("15.2")));
+        assert_eq!(app.build.as_deref(), Some("15C500b"));
+        assert_eq!(
+            app.developer_directory().path(),
+            Path::new("/Applications/Xcode_15.2.app/Contents/Developer")
+        );
+
+        let older = XcodeApplication {
+            path: PathBuf::from("/Applications/Xcode_14.3.app"),
+            version: Some(SdkVersion::from("14.3")),
+            build: None,
+        };
+        assert!(older < app);
+        assert!(!app.is_beta());
+
+        Ok(())
+    }
+
+    #[test]
+    fn version_from_name() -> Result<(), Error> {
+        assert_eq!(
+            version_from_app_name("Xcode-15.2.0.app"),
+            Some(SdkVersion::from("15.2.0"))
+        );
+        assert_eq!(
+            version_from_app_name("Xcode_15.3_RC.app"),
+            Some(SdkVersion::from("15.3"))
+        );
+        assert_eq!(
+            version_from_app_name("Xcode-16.0.0-Beta.app"),
+            Some(SdkVersion::from("16.0.0"))
+        );
+        assert_eq!(version_from_app_name("Xcode.app"), None);
+        assert_eq!(version_from_app_name("Xcode-beta.app"), None);
+
+        let temp = tempfile::tempdir()?;
+        let td = temp.path();
+        let path = td.join("Xcode-15.2.0.app");
+        std::fs::create_dir_all(&path)?;
+
+        let apps = XcodeApplication::find_in_directory(td)?;
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0].version, Some(SdkVersion::from("15.2.0")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn beta_detection() {
+        assert!(is_beta_build("15E5178i"));
+        assert!(is_beta_build("16A5171c"));
+        assert!(!is_beta_build("15C500b"));
+        assert!(!is_beta_build("15A240d"));
+        assert!(!is_beta_build("foo"));
+
+        let app = |path: &str, build: Option<&str>| XcodeApplication {
+            path: PathBuf::from(path),
+            version: None,
+            build: build.map(|s| s.to_string()),
+        };
+
+        assert!(app("/Applications/Xcode-beta.app", None).is_beta());
+        assert!(app("/Applications/Xcode_15.3_RC.app", None).is_beta());
+        assert!(app("/Applications/Xcode.app", Some("15E5178i")).is_beta());
+        assert!(!app("/Applications/Xcode.app", Some("15C500b")).is_beta());
+        assert!(!app("/Applications/Xcode_15.2.app", None).is_beta());
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! SDK discovery via `xcodebuild -showsdks`.
+
+use {
+    crate::{DeveloperDirectory, Error, Platform, SdkVersion},
+    serde::{Deserialize, Serialize},
+    std::{
+        path::PathBuf,
+        process::{Command, Stdio},
+        str::FromStr,
+    },
+};
+
+/// An SDK as reported by `xcodebuild -showsdks -json`.
+///
+/// Only the fields that are consistently present across Xcode versions are
+/// modeled.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XcodebuildSdk {
+    /// The canonical name of the SDK. e.g. `macosx14.2`.
+    pub canonical_name: String,
+
+    /// Human readable name of the SDK. e.g. `macOS 14.2`.
+    pub display_name: String,
+
+    /// Lowercase name of the platform. e.g. `macosx`.
+    pub platform: String,
+
+    /// Path to the `*.platform` directory holding this SDK.
+    pub platform_path: Option<PathBuf>,
+
+    /// Version of the platform.
+    pub platform_version: Option<String>,
+
+    /// Version of the product the SDK targets. e.g. `14.2`.
+    pub product_version: Option<String>,
+
+    /// Path to the SDK directory.
+    pub sdk_path: PathBuf,
+
+    /// Version of the SDK.
+    pub sdk_version: Option<String>,
+}
+
+impl XcodebuildSdk {
+    /// Parse the output of `xcodebuild -showsdks -json`.
+    pub fn parse_show_sdks(data: &[u8]) -> Result<Vec<Self>, Error> {
+        Ok(serde_json::from_slice(data)?)
+    }
+
+    /// Run `xcodebuild -showsdks -json` and parse its output.
+    ///
+    /// If a *Developer Directory* is given, it is passed to `xcodebuild` via the
+    /// `DEVELOPER_DIR` environment variable. Otherwise `xcodebuild` uses its default
+    /// resolution.
+    pub fn run_show_sdks(developer_dir: Option<&DeveloperDirectory>) -> Result<Vec<Self>, Error> {
+        let mut command = Command::new("xcodebuild");
+        command.args(["-showsdks", "-json"]).stderr(Stdio::null());
+
+        if let Some(dir) = developer_dir {
+            command.env("DEVELOPER_DIR", dir.path());
+        }
+
+        let output = command.output().map_err(Error::XcodebuildRun)?;
+
+        if output.status.success() {
+            Self::parse_show_sdks(&output.stdout)
+        } else {
+            Err(Error::XcodebuildBadStatus(output.status))
+        }
+    }
+
+    /// The platform this SDK belongs to.
+    ///
+    /// Unrecognized platform names resolve to [Platform::Unknown].
+    pub fn platform(&self) -> Platform {
+        Platform::from_str(&self.platform).expect("platform parsing is infallible")
+    }
+
+    /// The version of this SDK.
+    pub fn version(&self) -> Option<SdkVersion> {
+        self.sdk_version.as_deref().map(SdkVersion::from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SHOW_SDKS: &str = r#"[
+  {
+    "buildID" : "D1C5C383-0BF2-4A51-A87F-7CF7AE3F6BB0",
+    "canonicalName" : "driverkit23.2",
+    "displayName" : "DriverKit 23.2",
+    "isBaseSdk" : true,
+    "platform" : "driverkit",
+    "platformPath" : "/Applications/Xcode.app/Contents/Developer/Platforms/DriverKit.platform",
+    "platformVersion" : "23.2",
+    "productBuildVersion" : "23C53",
+    "sdkPath" : "/Applications/Xcode.app/Contents/Developer/Platforms/DriverKit.platform/Developer/SDKs/DriverKit23.2.sdk",
+    "sdkVersion" : "23.2"
+  },
+  {
+    "buildID" : "1B2CEA3E-6F1C-11EE-9E16-0E4A3D8A4C0B",
+    "canonicalName" : "macosx14.2",
+    "displayName" : "macOS 14.2",
+    "isBaseSdk" : true,
+    "platform" : "macosx",
+    "platformPath" : "/Applications/Xcode.app/Contents/Developer/Platforms/MacOSX.platform",
+    "platformVersion" : "14.2",
+    "productBuildVersion" : "23C53",
+    "productCopyright" : "1983-2023 Apple Inc.",
+    "productName" : "macOS",
+    "productUserVisibleVersion" : "14.2",
+    "productVersion" : "14.2",
+    "sdkPath" : "/Applications/Xcode.app/Contents/Developer/Platforms/MacOSX.platform/Developer/SDKs/MacOSX14.2.sdk",
+    "sdkVersion" : "14.2"
+  }
+]"#;
+
+    #[test]
+    fn parse_show_sdks() -> Result<(), Error> {
+        let sdks = XcodebuildSdk::parse_show_sdks(SHOW_SDKS.as_bytes())?;
+        assert_eq!(sdks.len(), 2);
+
+        assert_eq!(sdks[0].canonical_name, "driverkit23.2");
+        assert_eq!(sdks[0].platform(), Platform::DriverKit);
+        assert!(sdks[0].product_version.is_none());
+
+        assert_eq!(sdks[1].display_name, "macOS 14.2");
+        assert_eq!(sdks[1].platform(), Platform::MacOsX);
+        assert_eq!(sdks[1].version(), Some(SdkVersion::from("14.2")));
+        assert_eq!(
+            sdks[1].sdk_path,
+            PathBuf::from("/Applications/Xcode.app/Contents/Developer/Platforms/MacOSX.platform/Developer/SDKs/MacOSX14.2.sdk")
+        );
+
+        assert!(XcodebuildSdk::parse_show_sdks(b"not json").is_err());
+
+        Ok(())
+    }
+}
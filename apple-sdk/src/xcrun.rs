@@ -0,0 +1,231 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! SDK selection compatible with `xcrun`.
+//!
+//! `xcrun --sdk <name> --show-sdk-path` resolves an SDK as follows:
+//!
+//! 1. The SDK is the `--sdk` argument. If absent, the value of `SDKROOT`. If
+//!    that is absent too, `macosx`.
+//! 2. If the SDK is a filesystem path, it is used as is.
+//! 3. Otherwise it is an SDK name. e.g. `macosx` or `iphoneos17.0`. The name is
+//!    resolved in a single *Developer Directory*: `DEVELOPER_DIR` if defined,
+//!    otherwise the `xcode-select` selection. Other Xcode installs aren't
+//!    consulted.
+//! 4. A name without a version resolves to the newest SDK of the platform. If an
+//!    unversioned symlink like `MacOSX.sdk` points to that SDK, the symlink path
+//!    is reported instead of its target.
+//! 5. A name with a version resolves to the SDK having that version, preferring
+//!    the versioned directory over symlinks.
+//!
+//! [xcrun_sdk_path()] implements this. [SdkSearch](crate::SdkSearch) instead
+//! considers every registered location and ignores symlink preferences. So the
+//! two can disagree.
+
+use {
+    crate::{
+        AppleSdk, DeveloperDirectory, Error, Filesystem, RealFilesystem, SdkName, SdkPath,
+        SdkVersion, SimpleSdk,
+    },
+    std::{
+        ffi::OsString,
+        path::{Path, PathBuf},
+        str::FromStr,
+    },
+};
+
+/// The SDK name `xcrun` uses when neither `--sdk` nor `SDKROOT` is given.
+pub const XCRUN_DEFAULT_SDK: &str = "macosx";
+
+/// Resolve the SDK path `xcrun --sdk <sdk> --show-sdk-path` would print.
+///
+/// `sdk` is the value of the `--sdk` argument. If [None], `SDKROOT` is consulted,
+/// falling back to [XCRUN_DEFAULT_SDK]. See the module documentation for the full
+/// algorithm.
+///
+/// Paths are returned without validation if they exist. Errors with
+/// [Error::PathNotSdk] if they don't. Errors with [Error::SdkNameParse] for
+/// unknown SDK names, [Error::DeveloperDirectoryNotFound] if no *Developer
+/// Directory* is found, and [Error::SdkNotFound] if the name doesn't resolve.
+pub fn xcrun_sdk_path(sdk: Option<&str>) -> Result<PathBuf, Error> {
+    let sdk = sdk
+        .map(OsString::from)
+        .or_else(|| std::env::var_os("SDKROOT").filter(|v| !v.is_empty()))
+        .unwrap_or_else(|| OsString::from(XCRUN_DEFAULT_SDK));
+
+    let path = PathBuf::from(&sdk);
+    if path.components().count() > 1 || path.is_absolute() {
+        return if path.exists() {
+            Ok(path)
+        } else {
+            Err(Error::PathNotSdk(path))
+        };
+    }
+
+    let name = sdk
+        .to_str()
+        .ok_or_else(|| Error::SdkNameParse(sdk.to_string_lossy().to_string()))?;
+    let name = SdkName::from_str(name)?;
+
+    xcrun_select_sdk(&xcrun_developer_directory()?, &name)
+}
+
+/// Resolve the *Developer Directory* `xcrun` would use.
+///
+/// This is `DEVELOPER_DIR` if defined, otherwise the `xcode-select` selection as
+/// resolved by [DeveloperDirectory::from_xcode_select_pure()].
+///
+/// Errors if `DEVELOPER_DIR` isn't a valid *Developer Directory* or with
+/// [Error::DeveloperDirectoryNotFound] if nothing is selected.
+pub fn xcrun_developer_directory() -> Result<DeveloperDirectory, Error> {
+    if let Some(dir) = DeveloperDirectory::from_env()? {
+        Ok(dir)
+    } else {
+        DeveloperDirectory::from_xcode_select_pure()?.ok_or(Error::DeveloperDirectoryNotFound)
+    }
+}
+
+/// Resolve an SDK name within a *Developer Directory* like `xcrun` does.
+///
+/// SDKs are looked for in the platform directories and, for the Xcode Command
+/// Line Tools, the `SDKs` directory. See the module documentation for how an
+/// SDK is chosen.
+///
+/// Errors with [Error::SdkNotFound] if no SDK matches the name.
+pub fn xcrun_select_sdk(dir: &DeveloperDirectory, name: &SdkName) -> Result<PathBuf, Error> {
+    xcrun_select_sdk_with_filesystem(dir, name, &RealFilesystem)
+}
+
+/// Resolve an SDK name within a *Developer Directory* on the given [Filesystem].
+///
+/// This is like [xcrun_select_sdk()] except all reads, including resolving
+/// unversioned symlinks, go through `fs`.
+pub fn xcrun_select_sdk_with_filesystem(
+    dir: &DeveloperDirectory,
+    name: &SdkName,
+    fs: &dyn Filesystem,
+) -> Result<PathBuf, Error> {
+    let mut sdks = vec![];
+    for platform in dir.platforms_with_filesystem(fs)? {
+        sdks.extend(SimpleSdk::find_in_directory_with_filesystem(
+            &platform.sdks_path(),
+            fs,
+        )?);
+    }
+
+    let sdks_dir = dir.path().join("SDKs");
+    if fs.is_dir(&sdks_dir) {
+        sdks.extend(SimpleSdk::find_in_directory_with_filesystem(&sdks_dir, fs)?);
+    }
+
+    let candidates = sdks
+        .into_iter()
+        .filter(|sdk| sdk.platform() == &name.platform)
+        .filter_map(|sdk| effective_version(&sdk, fs).map(|version| (sdk, version)));
+
+    let selected = if let Some(wanted) = &name.version {
+        candidates
+            .filter(|(_, version)| version.cmp(wanted).is_eq())
+            .max_by_key(|(sdk, _)| (sdk.version().is_some(), !sdk.is_symlink()))
+    } else {
+        candidates.max_by(|(a, a_version), (b, b_version)| {
+            a_version
+                .cmp(b_version)
+                .then_with(|| a.version().is_none().cmp(&b.version().is_none()))
+                .then_with(|| b.is_symlink().cmp(&a.is_symlink()))
+        })
+    };
+
+    selected
+        .map(|(sdk, _)| sdk.path().to_path_buf())
+        .ok_or_else(|| {
+            Error::SdkNotFound(format!(
+                "xcrun SDK {name} in Developer Directory {}",
+                dir.path().display()
+            ))
+        })
+}
+
+/// The version of an SDK, looking through unversioned symlinks.
+fn effective_version(sdk: &SimpleSdk, fs: &dyn Filesystem) -> Option<SdkVersion> {
+    if let Some(version) = sdk.version() {
+        return Some(version.clone());
+    }
+
+    if !sdk.is_symlink() {
+        return None;
+    }
+
+    let target = fs.canonicalize(sdk.path()).ok()?;
+
+    SdkPath::from_path(Path::new(target.file_name()?))
+        .ok()?
+        .version
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use {super::*, crate::MemoryFilesystem};
+
+    #[test]
+    fn select_sdk() -> Result<(), Error> {
+        let sdks = Path::new("/xcode/Platforms/MacOSX.platform/Developer/SDKs");
+        let link = sdks.join("MacOSX.sdk");
+        let fs = MemoryFilesystem::new()
+            .sdk(sdks.join("MacOSX13.3.sdk"))
+            .sdk(sdks.join("MacOSX14.2.sdk"))
+            .sdk("/clt/SDKs/MacOSX14.sdk");
+
+        let dir = DeveloperDirectory::from(Path::new("/xcode"));
+        let select = |fs: &MemoryFilesystem, name: &str| {
+            xcrun_select_sdk_with_filesystem(&dir, &SdkName::from_str(name).unwrap(), fs)
+        };
+
+        let linked = fs.clone().symlink(&link, "MacOSX14.2.sdk");
+        assert_eq!(select(&linked, "macosx")?, link);
+        assert_eq!(select(&linked, "macosx14.2")?, sdks.join("MacOSX14.2.sdk"));
+        assert_eq!(select(&linked, "macosx13.3")?, sdks.join("MacOSX13.3.sdk"));
+        assert!(matches!(
+            select(&linked, "macosx12.0"),
+            Err(Error::SdkNotFound(_))
+        ));
+        assert!(matches!(
+            select(&linked, "iphoneos"),
+            Err(Error::SdkNotFound(_))
+        ));
+
+        assert_eq!(select(&fs, "macosx")?, sdks.join("MacOSX14.2.sdk"));
+
+        assert_eq!(
+            xcrun_select_sdk_with_filesystem(
+                &DeveloperDirectory::from(Path::new("/clt")),
+                &SdkName::from_str("macosx")?,
+                &fs
+            )?,
+            Path::new("/clt/SDKs/MacOSX14.sdk")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn sdk_path_argument() -> Result<(), Error> {
+        let td = std::env::temp_dir();
+        assert_eq!(xcrun_sdk_path(td.to_str())?, td);
+        assert!(matches!(
+            xcrun_sdk_path(Some("/does/not/exist.sdk")),
+            Err(Error::PathNotSdk(_))
+        ));
+        assert!(matches!(
+            xcrun_sdk_path(Some("bogus")),
+            Err(Error::SdkNameParse(_))
+        ));
+
+        Ok(())
+    }
+}
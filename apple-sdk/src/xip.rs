@@ -0,0 +1,411 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Xcode `.xip` archives.
+//!
+//! Xcode is distributed as a `.xip` file. This is a signed XAR archive holding a
+//! `Content` file. `Content` is a *pbzx* stream: a sequence of (usually xz
+//! compressed) chunks which, once concatenated, form a cpio archive of the
+//! Xcode application.
+
+use {
+    crate::{Error, Platform, SdkPath, SdkVersion},
+    std::{
+        ffi::OsStr,
+        io::{Read, Seek, SeekFrom},
+        path::{Path, PathBuf},
+        str::FromStr,
+    },
+};
+
+const PBZX_MAGIC: &[u8; 4] = b"pbzx";
+const XZ_MAGIC: &[u8; 6] = b"\xfd7zXZ\0";
+
+/// Flag set on a pbzx chunk header when another chunk follows.
+const PBZX_FLAG_MORE_CHUNKS: u64 = 1 << 24;
+
+/// Reads the decompressed content of a pbzx stream.
+struct PbzxReader<R: Read> {
+    inner: R,
+    more_chunks: bool,
+    chunk: Vec<u8>,
+    offset: usize,
+}
+
+impl<R: Read> PbzxReader<R> {
+    fn new(mut inner: R) -> std::io::Result<Self> {
+        let mut magic = [0u8; 4];
+        inner.read_exact(&mut magic)?;
+
+        if &magic != PBZX_MAGIC {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "not a pbzx stream",
+            ));
+        }
+
+        let flags = read_u64(&mut inner)?;
+
+        Ok(Self {
+            inner,
+            more_chunks: flags & PBZX_FLAG_MORE_CHUNKS != 0,
+            chunk: vec![],
+            offset: 0,
+        })
+    }
+
+    /// Read the next chunk into the buffer.
+    ///
+    /// Returns false if there are no more chunks.
+    fn next_chunk(&mut self) -> std::io::Result<bool> {
+        if !self.more_chunks {
+            return Ok(false);
+        }
+
+        let flags = read_u64(&mut self.inner)?;
+        let length = read_u64(&mut self.inner)?;
+        self.more_chunks = flags & PBZX_FLAG_MORE_CHUNKS != 0;
+
+        let mut data = vec![];
+        (&mut self.inner).take(length).read_to_end(&mut data)?;
+
+        if data.len() as u64 != length {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+
+        // Chunks that don't compress are stored raw.
+        self.chunk = if data.starts_with(XZ_MAGIC) {
+            let mut chunk = vec![];
+            xz2::read::XzDecoder::new(data.as_slice()).read_to_end(&mut chunk)?;
+            chunk
+        } else {
+            data
+        };
+        self.offset = 0;
+
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for PbzxReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.offset == self.chunk.len() {
+            if !self.next_chunk()? {
+                return Ok(0);
+            }
+        }
+
+        let count = buf.len().min(self.chunk.len() - self.offset);
+        buf[..count].copy_from_slice(&self.chunk[self.offset..self.offset + count]);
+        self.offset += count;
+
+        Ok(count)
+    }
+}
+
+fn read_u64(reader: &mut impl Read) -> std::io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+
+    Ok(u64::from_be_bytes(buf))
+}
+
+/// Platforms and SDKs contained in an Xcode `.xip` archive.
+///
+/// SDK and platform paths are relative to the root of the archive. e.g.
+/// `Xcode.app/Contents/Developer/Platforms/MacOSX.platform/Developer/SDKs/MacOSX14.2.sdk`.
+#[derive(Clone, Debug, Default)]
+pub struct XcodeXipContents {
+    /// The directory name of the Xcode application. e.g. `Xcode.app`.
+    pub application_name: Option<String>,
+
+    /// The marketing version of Xcode. e.g. `15.2`.
+    ///
+    /// Requires the `parse` crate feature. Always [None] without it.
+    pub version: Option<SdkVersion>,
+
+    /// The build identifier of Xcode. e.g. `15C500b`.
+    ///
+    /// Requires the `parse` crate feature. Always [None] without it.
+    pub build: Option<String>,
+
+    /// Platforms in the Xcode application.
+    pub platforms: Vec<Platform>,
+
+    /// SDKs in the Xcode application.
+    ///
+    /// Symlinked SDKs (e.g. `MacOSX.sdk`) are not reported.
+    pub sdks: Vec<SdkPath>,
+}
+
+impl XcodeXipContents {
+    /// Obtain the SDKs for a given platform.
+    pub fn sdks_for_platform(&self, platform: &Platform) -> Vec<&SdkPath> {
+        self.sdks
+            .iter()
+            .filter(|sdk| &sdk.platform == platform)
+            .collect()
+    }
+
+    /// Record a cpio archive entry.
+    fn process_entry(
+        &mut self,
+        name: &str,
+        data: impl FnOnce() -> Result<Vec<u8>, Error>,
+    ) -> Result<(), Error> {
+        let path = Path::new(name.trim_start_matches("./"));
+        let components = path
+            .components()
+            .filter_map(|c| c.as_os_str().to_str())
+            .collect::<Vec<_>>();
+
+        let Some(app) = components.first().filter(|c| c.ends_with(".app")) else {
+            return Ok(());
+        };
+
+        if self.application_name.is_none() {
+            self.application_name = Some(app.to_string());
+        }
+
+        match &components[1..] {
+            ["Contents", "version.plist"] => {
+                #[cfg(feature = "parse")]
+                {
+                    let value = plist::Value::from_reader(std::io::Cursor::new(data()?))?;
+                    let value = value.into_dictionary().ok_or(Error::PlistNotDictionary)?;
+
+                    let get_string = |key: &str| -> Option<String> {
+                        value
+                            .get(key)
+                            .and_then(|v| v.as_string())
+                            .map(|s| s.to_string())
+                    };
+
+                    self.version = get_string("CFBundleShortVersionString").map(SdkVersion::from);
+                    self.build = get_string("ProductBuildVersion");
+                }
+
+                #[cfg(not(feature = "parse"))]
+                let _ = data;
+            }
+            ["Contents", "Developer", "Platforms", platform, rest @ ..] => {
+                if let Some(name) = platform.strip_suffix(".platform") {
+                    let platform = Platform::from_str(name)?;
+
+                    if !self.platforms.contains(&platform) {
+                        self.platforms.push(platform);
+                    }
+                }
+
+                if let ["Developer", "SDKs", sdk, settings] = rest {
+                    if sdk.ends_with(".sdk")
+                        && matches!(*settings, "SDKSettings.json" | "SDKSettings.plist")
+                    {
+                        let sdk_path = path.parent().expect("path has parent").to_path_buf();
+
+                        if !self.sdks.iter().any(|s| s.path == sdk_path) {
+                            self.sdks.push(SdkPath::from_path(sdk_path)?);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Populate an instance from the cpio archive in the decompressed `Content` stream.
+    fn from_cpio(reader: impl Read + 'static) -> Result<Self, Error> {
+        let mut res = Self::default();
+        let mut archive = cpio_archive::reader(reader)?;
+
+        while let Some(header) = archive.read_next()? {
+            // Symlinks record aliases, such as `MacOSX.sdk`.
+            if header.mode() & 0o170000 == 0o120000 {
+                continue;
+            }
+
+            res.process_entry(header.name(), || {
+                let mut data = vec![];
+                archive.read_to_end(&mut data)?;
+
+                Ok(data)
+            })?;
+        }
+
+        // Make deterministic.
+        res.sdks.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(res)
+    }
+}
+
+/// An Xcode `.xip` archive.
+///
+/// This can be used to determine which platforms and SDKs an Xcode distribution
+/// provides without installing it. e.g. to let CI provisioning tools decide
+/// whether an Xcode release satisfies requirements before extracting it.
+#[derive(Clone, Debug)]
+pub struct XcodeXip {
+    path: PathBuf,
+}
+
+impl XcodeXip {
+    /// Construct an instance from a path to a `.xip` file.
+    ///
+    /// Returns [Error::PathNotXip] if the path isn't an existing `.xip` file.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+
+        if path.extension() == Some(OsStr::new("xip")) && path.is_file() {
+            Ok(Self { path })
+        } else {
+            Err(Error::PathNotXip(path))
+        }
+    }
+
+    /// The filesystem path of this archive.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Enumerate the platforms and SDKs in this archive.
+    ///
+    /// This decompresses the archive's content in memory without writing
+    /// anything to disk. It is still expensive, as the entire content is
+    /// read and decompressed. But it is much cheaper than installing Xcode.
+    pub fn contents(&self) -> Result<XcodeXipContents, Error> {
+        let fh = std::fs::File::open(&self.path).map_err(Error::io_path(&self.path))?;
+
+        let xar = apple_xar::reader::XarReader::new(fh)?;
+
+        let file = xar
+            .find_file("Content")?
+            .ok_or_else(|| Error::ArchiveMalformed("xip archive lacks Content".to_string()))?;
+        let data = file
+            .data
+            .ok_or_else(|| Error::ArchiveMalformed("xip Content has no data".to_string()))?;
+
+        if data.encoding.style != "application/octet-stream" {
+            return Err(Error::ArchiveMalformed(format!(
+                "unsupported xip Content encoding: {}",
+                data.encoding.style
+            )));
+        }
+
+        let heap_start = xar.heap_start_offset();
+        let mut fh = xar.into_inner();
+        fh.seek(SeekFrom::Start(heap_start + data.offset))
+            .map_err(Error::io_path(&self.path))?;
+
+        let content = PbzxReader::new(std::io::BufReader::new(fh).take(data.length))
+            .map_err(Error::io_path(&self.path))?;
+
+        XcodeXipContents::from_cpio(content)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, std::io::Write};
+
+    /// Construct a pbzx stream from chunks, compressing them with xz.
+    fn pbzx(chunks: &[&[u8]]) -> Vec<u8> {
+        let mut res = PBZX_MAGIC.to_vec();
+        res.extend(PBZX_FLAG_MORE_CHUNKS.to_be_bytes());
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            let mut encoder = xz2::write::XzEncoder::new(vec![], 6);
+            encoder.write_all(chunk).unwrap();
+            let compressed = encoder.finish().unwrap();
+
+            let flags = if i + 1 < chunks.len() {
+                PBZX_FLAG_MORE_CHUNKS
+            } else {
+                0
+            };
+            res.extend(flags.to_be_bytes());
+            res.extend((compressed.len() as u64).to_be_bytes());
+            res.extend(compressed);
+        }
+
+        res
+    }
+
+    #[test]
+    fn pbzx_reader() -> Result<(), Error> {
+        let mut reader = PbzxReader::new(std::io::Cursor::new(pbzx(&[b"hello, ", b"world"])))?;
+
+        let mut data = String::new();
+        reader.read_to_string(&mut data)?;
+        assert_eq!(data, "hello, world");
+
+        assert!(PbzxReader::new(std::io::Cursor::new(b"nope".to_vec())).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn contents() -> Result<(), Error> {
+        let sdks = "Xcode.app/Contents/Developer/Platforms/MacOSX.platform/Developer/SDKs";
+
+        let mut builder = cpio_archive::odc::OdcBuilder::new(vec![]);
+        builder.append_file_from_data(
+            "Xcode.app/Contents/version.plist",
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+	<key>CFBundleShortVersionString</key>
+	<string>15.2</string>
+	<key>ProductBuildVersion</key>
+	<string>15C500b</string>
+</dict>
+</plist>"#,
+            0o100644,
+        )?;
+        builder.append_file_from_data(
+            format!("{sdks}/MacOSX14.2.sdk/SDKSettings.json"),
+            b"{}",
+            0o100644,
+        )?;
+        builder.append_file_from_data(
+            "Xcode.app/Contents/Developer/Platforms/iPhoneOS.platform/Info.plist",
+            b"",
+            0o100644,
+        )?;
+        builder.append_file_from_data(format!("{sdks}/MacOSX.sdk"), b"MacOSX14.2.sdk", 0o120755)?;
+        builder.finish()?;
+
+        let content = pbzx(&[&builder.into_inner()?]);
+        let contents =
+            XcodeXipContents::from_cpio(PbzxReader::new(std::io::Cursor::new(content))?)?;
+
+        assert_eq!(contents.application_name.as_deref(), Some("Xcode.app"));
+        assert_eq!(
+            contents.platforms,
+            vec![Platform::MacOsX, Platform::IPhoneOs]
+        );
+        assert_eq!(contents.sdks.len(), 1);
+        assert_eq!(
+            contents.sdks[0].path,
+            PathBuf::from(format!("{sdks}/MacOSX14.2.sdk"))
+        );
+        assert_eq!(contents.sdks[0].version, Some(SdkVersion::from("14.2")));
+        assert_eq!(contents.sdks_for_platform(&Platform::MacOsX).len(), 1);
+        assert!(contents.sdks_for_platform(&Platform::IPhoneOs).is_empty());
+
+        #[cfg(feature = "parse")]
+        {
+            assert_eq!(contents.version, Some(SdkVersion::from("15.2")));
+            assert_eq!(contents.build.as_deref(), Some("15C500b"));
+        }
+
+        Ok(())
+    }
+}